@@ -1,14 +1,11 @@
-#[path = "../deep_print_schema.rs"]
-mod deep_print_schema;
-#[path = "../renderer.rs"]
-mod renderer;
-
-use crate::deep_print_schema::*;
-use crate::renderer::DeepPrintRenderer;
+use deepprint_core::deep_print_schema::*;
+use deepprint_core::output::{self, PdfOptions};
+use deepprint_core::renderer::{DeepPrintRenderer, LumaWeights, RenderOptions};
 use serde_json::json;
 use skia_safe::{surfaces, Color, EncodedImageFormat};
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // -------------------------------------------------------------------------
@@ -148,11 +145,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // -------------------------------------------------------------------------
     // 4. 执行渲染
     // -------------------------------------------------------------------------
-    let renderer = DeepPrintRenderer::new();
+    let mut renderer = DeepPrintRenderer::new();
+    // ./fonts 目录下的字体会被注册并嵌入到 PDF 中
+    let font_count = renderer.register_font_dir(Path::new("fonts"));
+    if font_count > 0 {
+        println!("🔤 已注册自定义字体: {} 个", font_count);
+    }
+    // 传入 --grayscale 时以灰度模式渲染 (模拟黑白打印机输出)
+    let options = RenderOptions {
+        grayscale: std::env::args()
+            .any(|a| a == "--grayscale")
+            .then(LumaWeights::default),
+        ..Default::default()
+    };
     println!("🚀 开始渲染...");
     
     // 直接传入 surface.canvas()，避免中间变量导致类型推断为不可变借用
-    match renderer.render(surface.canvas(), &template, &data) {
+    match renderer.render_with_options(surface.canvas(), &template, &data, &options) {
         Ok(_) => println!("✅ 渲染完成！"),
         Err(e) => {
             eprintln!("❌ 渲染错误: {}", e);
@@ -174,5 +183,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         eprintln!("❌ 图像编码失败");
     }
 
+    // 同时输出 PDF (传入 --embed-fonts 时强制嵌入系统字体)
+    let pdf_options = PdfOptions {
+        embed_system_fonts: std::env::args().any(|a| a == "--embed-fonts"),
+    };
+    let pdf_name = "output_receipt.pdf";
+    match output::render_pdf(&renderer, &template, &data, &options, &pdf_options) {
+        Ok(bytes) => {
+            File::create(pdf_name)?.write_all(&bytes)?;
+            println!("💾 PDF 已保存至: ./{}", pdf_name);
+        }
+        Err(e) => eprintln!("❌ PDF 生成失败: {}", e),
+    }
+
     Ok(())
 }
\ No newline at end of file
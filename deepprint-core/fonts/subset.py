@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+"""生成后备字体 (Noto Sans SC/TC/JP 子集)，输出到本目录并随仓库提交。
+
+以 --features cjk-fonts 编译时字体通过 include_bytes! 编译进二进制；
+未启用时也可将生成的文件放到程序目录下的 fonts 目录，由 Agent 在运行时加载。
+
+依赖 fonttools (pip install fonttools)，源字体从 Google Fonts / notofonts 下载
+(NotoSansSC-Regular.otf、NotoSansTC-Regular.otf、NotoSansJP-Regular.otf) 放到本目录后运行：
+
+    python3 subset.py
+
+字符集：
+- SC: GB2312 全部汉字 (一级 + 二级，6763 字)
+- TC: Big5 常用字 (5401 字)
+- JP: JIS X 0208 第一水准汉字、平假名、片假名
+三者都包含 ASCII、全角字符与 CJK 标点。
+"""
+
+from pathlib import Path
+import subprocess
+
+HERE = Path(__file__).resolve().parent
+
+# ASCII、CJK 标点、全角/半角字符、常用符号 (¥ ° × ÷ ※ 等)
+COMMON = "U+0020-007E,U+00A5,U+00B0,U+00B7,U+00D7,U+00F7,U+2010-2027,U+2030-203B,U+2103,U+2116,U+2190-2193,U+2460-2473,U+3000-303F,U+FF01-FF9F,U+FFE0-FFE6"
+
+
+def decode_range(encoding, rows, cols):
+    chars = set()
+    for hi in rows:
+        for lo in cols:
+            try:
+                chars.add(bytes([hi, lo]).decode(encoding))
+            except UnicodeDecodeError:
+                pass
+    return chars
+
+
+def gb2312():
+    return decode_range("gb2312", range(0xB0, 0xF8), range(0xA1, 0xFF))
+
+
+def big5_common():
+    return decode_range("big5", range(0xA4, 0xC7), list(range(0x40, 0x7F)) + list(range(0xA1, 0xFF)))
+
+
+def jis_level1():
+    kanji = decode_range("euc_jp", range(0xB0, 0xD0), range(0xA1, 0xFF))
+    kana = {chr(c) for c in range(0x3041, 0x3097)} | {chr(c) for c in range(0x30A1, 0x30FF)}
+    return kanji | kana
+
+
+SUBSETS = [
+    ("NotoSansSC-Regular.otf", "NotoSansSC-Subset.otf", gb2312),
+    ("NotoSansTC-Regular.otf", "NotoSansTC-Subset.otf", big5_common),
+    ("NotoSansJP-Regular.otf", "NotoSansJP-Subset.otf", jis_level1),
+]
+
+
+def main():
+    for source, target, charset in SUBSETS:
+        text = "".join(sorted(charset()))
+        subprocess.run(
+            [
+                "pyftsubset",
+                str(HERE / source),
+                f"--unicodes={COMMON}",
+                f"--text={text}",
+                f"--output-file={HERE / target}",
+                "--layout-features=*",
+                "--no-hinting",
+                "--desubroutinize",
+                "--name-IDs=*",
+            ],
+            check=True,
+        )
+        print(f"{target}: {len(text)} chars, {(HERE / target).stat().st_size // 1024} KB")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,63 @@
+use crate::error::TemplateError;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 图片元素引用资源池的前缀，如 src: "asset:logo"
+pub const ASSET_PREFIX: &str = "asset:";
+/// 资源池中引用宿主已注册资源的前缀，如 "sha256:9f86d0..."
+pub const HASH_PREFIX: &str = "sha256:";
+
+/// 编译后的图片来源
+#[derive(Debug, Clone)]
+pub(crate) enum ImageSource {
+    /// 模板资源池中内嵌的图片数据 (base64 解码后)
+    Embedded(Arc<Vec<u8>>),
+    /// 按内容哈希引用、由宿主通过 DeepPrintRenderer::register_asset 提供的图片
+    Registered(String),
+}
+
+/// 解析图片元素的 src：非 "asset:" 引用或引用了未下载的远程 URL 时返回 None；
+/// 资源不存在或不是合法的 base64 时返回错误，模板因此可以在渲染前发现悬空引用
+pub(crate) fn resolve_image(
+    element_id: &str,
+    src: &str,
+    assets: Option<&HashMap<String, String>>,
+) -> Result<Option<ImageSource>, TemplateError> {
+    let Some(name) = src.strip_prefix(ASSET_PREFIX) else {
+        return Ok(None);
+    };
+    let value = assets
+        .and_then(|assets| assets.get(name))
+        .ok_or_else(|| TemplateError::UnknownAsset {
+            element_id: element_id.to_string(),
+            asset: name.to_string(),
+        })?;
+
+    if let Some(hash) = value.strip_prefix(HASH_PREFIX) {
+        return Ok(Some(ImageSource::Registered(hash.to_ascii_lowercase())));
+    }
+    // 未能由宿主下载的远程图片，按元素的 fallback 处理
+    if value.starts_with("http://") || value.starts_with("https://") {
+        return Ok(None);
+    }
+    decode(value)
+        .map(|bytes| Some(ImageSource::Embedded(Arc::new(bytes))))
+        .map_err(|message| TemplateError::Parse {
+            path: format!("assets.{}", name),
+            message,
+        })
+}
+
+/// 解码资源池中的 base64 数据，支持 data URI ("data:image/png;base64,...")
+pub(crate) fn decode(value: &str) -> Result<Vec<u8>, String> {
+    let data = match value.split_once(";base64,") {
+        Some((scheme, data)) if scheme.starts_with("data:") => data,
+        _ => value,
+    };
+    let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    STANDARD
+        .decode(data)
+        .map_err(|e| format!("Invalid base64 asset: {}", e))
+}
@@ -0,0 +1,438 @@
+use crate::assets::{self, ImageSource};
+use crate::deep_print_schema::*;
+use crate::error::{RenderError, TemplateError};
+use crate::partials;
+use crate::renderer::{parse_color, Interpolator};
+use regex::Regex;
+use serde_json::Value;
+use skia_safe::Color;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// 全局缓存保留的已编译模板数
+const CACHE_CAPACITY: usize = 64;
+
+/// 预编译的模板：拓扑排序、合并后的样式、插值表达式与表格列宽只计算一次，
+/// 之后每条数据的渲染直接复用
+#[derive(Debug)]
+pub struct CompiledTemplate {
+    pub template: DeepPrintTemplate,
+    /// 按 linkedTo 依赖排序后的元素下标
+    pub(crate) order: Vec<usize>,
+    /// 与 template.canvas.elements 一一对应
+    pub(crate) elements: Vec<CompiledElement>,
+    /// 多页模板各页的编译结果 (共享元素 + 页面元素合并为单页模板)，单页模板为空
+    pub(crate) pages: Vec<CompiledTemplate>,
+}
+
+/// 单个元素的预计算结果
+#[derive(Debug, Default)]
+pub(crate) struct CompiledElement {
+    /// 文本内容、条码/二维码值、复选框勾选状态的插值表达式
+    pub content: Option<Interpolation>,
+    /// 文本样式 (已合并全局样式)
+    pub text_style: Option<ResolvedTextStyle>,
+    /// 表格各列的实际宽度 (pt)
+    pub column_widths: Vec<f64>,
+    /// 图片元素引用的资源池图片
+    pub image: Option<ImageSource>,
+    /// switch 元素的各分支
+    pub switch: Option<CompiledSwitch>,
+}
+
+/// 一组元素的编译结果 (switch 的分支)
+#[derive(Debug)]
+pub(crate) struct CompiledGroup {
+    pub elements: Vec<Element>,
+    /// 按 linkedTo 依赖排序后的元素下标
+    pub order: Vec<usize>,
+    /// 与 elements 一一对应
+    pub compiled: Vec<CompiledElement>,
+}
+
+#[derive(Debug)]
+pub(crate) struct CompiledSwitch {
+    pub value: Interpolation,
+    pub cases: Vec<(String, CompiledGroup)>,
+    pub fallback: Option<CompiledGroup>,
+}
+
+impl CompiledSwitch {
+    /// 与 value 插值结果匹配的分支
+    pub fn branch(&self, value: &str) -> Option<&CompiledGroup> {
+        self.cases
+            .iter()
+            .find(|(when, _)| when.trim() == value)
+            .map(|(_, group)| group)
+            .or(self.fallback.as_ref())
+    }
+}
+
+/// 合并元素与全局样式后的文本样式
+#[derive(Debug)]
+pub(crate) struct ResolvedTextStyle {
+    pub font_size: f64,
+    /// 原始颜色，输出前的颜色变换 (如灰度化) 在渲染时进行
+    pub color: Color,
+    pub font_family: Option<String>,
+}
+
+/// 解析后的插值表达式，如 "订单号: {{orderNo}}"
+#[derive(Debug)]
+pub(crate) struct Interpolation(Vec<Segment>);
+
+#[derive(Debug)]
+enum Segment {
+    Literal(String),
+    Field(String),
+}
+
+impl Interpolation {
+    fn parse(template: &str) -> Self {
+        static RE: OnceLock<Regex> = OnceLock::new();
+        let re = RE.get_or_init(|| Regex::new(r"\{\{\s*([a-zA-Z0-9_.]+)\s*\}\}").unwrap());
+
+        let mut segments = Vec::new();
+        let mut last = 0;
+        for caps in re.captures_iter(template) {
+            let whole = caps.get(0).unwrap();
+            if whole.start() > last {
+                segments.push(Segment::Literal(template[last..whole.start()].to_string()));
+            }
+            segments.push(Segment::Field(caps[1].to_string()));
+            last = whole.end();
+        }
+        if last < template.len() {
+            segments.push(Segment::Literal(template[last..].to_string()));
+        }
+        Self(segments)
+    }
+
+    /// 数据中找不到 (或为空) 的字段
+    pub fn missing_fields<'a>(&'a self, data: &'a Value) -> impl Iterator<Item = &'a str> + 'a {
+        self.0.iter().filter_map(move |segment| match segment {
+            Segment::Field(path) if Interpolator::get_value_from_obj(data, path).is_empty() => {
+                Some(path.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    /// 用数据替换表达式中的字段，缺失的字段替换为空串；数据中的控制字符被去除
+    pub fn render(&self, data: &Value) -> String {
+        self.render_with(data, Interpolator::get_value_from_obj)
+    }
+
+    /// 同 render，但保留数据中的控制字符 (二维码内容)
+    pub fn render_raw(&self, data: &Value) -> String {
+        self.render_with(data, Interpolator::get_raw_value_from_obj)
+    }
+
+    fn render_with(&self, data: &Value, value: fn(&Value, &str) -> String) -> String {
+        let mut out = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field(path) => out.push_str(&value(data, path)),
+            }
+        }
+        out
+    }
+}
+
+impl CompiledTemplate {
+    /// 编译模板：展开模板内定义的片段 (include)、解码引用的资源池图片，
+    /// linkedTo 存在循环依赖、引用的资源不存在或 copies 展开后超出元素数上限时返回错误
+    pub fn compile(template: DeepPrintTemplate) -> Result<Self, RenderError> {
+        let template = partials::expand(&template, &|_| None)?.unwrap_or(template);
+        if let Some(pages) = &template.canvas.pages {
+            let pages = pages
+                .iter()
+                .map(|page| Self::compile(single_page(&template, page)))
+                .collect::<Result<_, _>>()?;
+            return Ok(Self {
+                template,
+                order: Vec::new(),
+                elements: Vec::new(),
+                pages,
+            });
+        }
+        let order = topological_sort(&template.canvas.elements)?;
+        let styles = template.canvas.styles.as_ref();
+        let assets = template.assets.as_ref();
+        let elements = template
+            .canvas
+            .elements
+            .iter()
+            .map(|element| compile_element(element, styles, assets))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            template,
+            order,
+            elements,
+            pages: Vec::new(),
+        })
+    }
+
+    /// 按输出顺序排列的各页，单页模板只有自身
+    pub fn pages(&self) -> &[CompiledTemplate] {
+        if self.pages.is_empty() {
+            std::slice::from_ref(self)
+        } else {
+            &self.pages
+        }
+    }
+}
+
+/// 多页模板中的一页：共享元素在前、页面元素在后，组成单页模板
+fn single_page(template: &DeepPrintTemplate, page: &CanvasPage) -> DeepPrintTemplate {
+    let mut single = template.clone();
+    single.canvas.pages = None;
+    single.canvas.elements.extend(page.elements.iter().cloned());
+    single
+}
+
+fn compile_element(
+    element: &Element,
+    styles: Option<&GlobalStyles>,
+    assets: Option<&HashMap<String, String>>,
+) -> Result<CompiledElement, TemplateError> {
+    let compiled = match &element.data {
+        ElementData::Text(props) => CompiledElement {
+            content: Some(Interpolation::parse(&props.content)),
+            text_style: Some(ResolvedTextStyle {
+                font_size: props
+                    .font_size
+                    .or(styles.and_then(|s| s.font_size))
+                    .unwrap_or(12.0),
+                color: parse_color(
+                    props
+                        .font_color
+                        .as_deref()
+                        .or(styles.and_then(|s| s.font_color.as_deref()))
+                        .unwrap_or("#000000"),
+                ),
+                font_family: props
+                    .font_family
+                    .clone()
+                    .or_else(|| styles.and_then(|s| s.font_family.clone())),
+            }),
+            ..Default::default()
+        },
+        ElementData::Barcode(props) => CompiledElement {
+            content: Some(Interpolation::parse(&props.value)),
+            ..Default::default()
+        },
+        ElementData::Qrcode(props) => CompiledElement {
+            content: Some(Interpolation::parse(&props.value)),
+            ..Default::default()
+        },
+        ElementData::Checkbox(props) => CompiledElement {
+            content: Some(Interpolation::parse(&props.checked)),
+            ..Default::default()
+        },
+        ElementData::Table(props) => CompiledElement {
+            column_widths: column_widths(element.w, &props.columns),
+            ..Default::default()
+        },
+        ElementData::Image(props) => {
+            let mut image = assets::resolve_image(&element.id, &props.src, assets)?;
+            if image.is_none() {
+                if let Some(fallback) = &props.fallback {
+                    image = assets::resolve_image(&element.id, fallback, assets)?;
+                }
+            }
+            CompiledElement {
+                image,
+                ..Default::default()
+            }
+        }
+        ElementData::Switch(props) => CompiledElement {
+            switch: Some(CompiledSwitch {
+                value: Interpolation::parse(&props.value),
+                cases: props
+                    .cases
+                    .iter()
+                    .map(|case| Ok((case.when.clone(), compile_group(&case.elements, styles, assets)?)))
+                    .collect::<Result<_, TemplateError>>()?,
+                fallback: props
+                    .fallback
+                    .as_deref()
+                    .map(|elements| compile_group(elements, styles, assets))
+                    .transpose()?,
+            }),
+            ..Default::default()
+        },
+        _ => CompiledElement::default(),
+    };
+    Ok(compiled)
+}
+
+fn compile_group(
+    elements: &[Element],
+    styles: Option<&GlobalStyles>,
+    assets: Option<&HashMap<String, String>>,
+) -> Result<CompiledGroup, TemplateError> {
+    Ok(CompiledGroup {
+        elements: elements.to_vec(),
+        order: topological_sort(elements)?,
+        compiled: elements
+            .iter()
+            .map(|element| compile_element(element, styles, assets))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// 计算表格列宽：固定宽度优先，百分比按剩余宽度计算，未指定的列平分剩余宽度
+pub(crate) fn column_widths(total_width: f64, columns: &[TableColumn]) -> Vec<f64> {
+    let mut col_widths = Vec::new();
+    let mut fixed_used = 0.0;
+
+    for col in columns {
+        match &col.width {
+            Some(TableColumnWidth::Fixed(w)) => {
+                col_widths.push(*w);
+                fixed_used += w;
+            }
+            Some(TableColumnWidth::Percentage(s)) => {
+                let p = s.trim_end_matches('%').parse::<f64>().unwrap_or(0.0);
+                col_widths.push(-p); // 负数标记
+            }
+            None => col_widths.push(0.0),
+        }
+    }
+
+    let remaining = (total_width - fixed_used).max(0.0);
+    let auto_cols_count = col_widths.iter().filter(|&&w| w == 0.0).count();
+
+    for w in &mut col_widths {
+        if *w < 0.0 {
+            *w = remaining * (w.abs() / 100.0);
+        } else if *w == 0.0 && auto_cols_count > 0 {
+            *w = remaining / auto_cols_count as f64;
+        }
+    }
+    col_widths
+}
+
+/// 按 linkedTo 依赖排序，被依赖的元素先渲染
+fn topological_sort(elements: &[Element]) -> Result<Vec<usize>, TemplateError> {
+    let mut result = Vec::with_capacity(elements.len());
+    let mut visited = HashSet::new();
+    let mut temp_mark = HashSet::new();
+    let index: HashMap<&str, usize> = elements
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.id.as_str(), i))
+        .collect();
+
+    fn visit<'a>(
+        id: &'a str,
+        elements: &'a [Element],
+        index: &HashMap<&'a str, usize>,
+        result: &mut Vec<usize>,
+        visited: &mut HashSet<&'a str>,
+        temp_mark: &mut HashSet<&'a str>,
+    ) -> Result<(), TemplateError> {
+        if visited.contains(id) { return Ok(()); }
+        if temp_mark.contains(id) {
+            return Err(TemplateError::CircularDependency { element_id: id.to_string() });
+        }
+
+        temp_mark.insert(id);
+        if let Some(&i) = index.get(id) {
+            if let Some(target_id) = &elements[i].linked_to {
+                visit(target_id, elements, index, result, visited, temp_mark)?;
+            }
+            temp_mark.remove(id);
+            visited.insert(id);
+            result.push(i);
+        }
+        Ok(())
+    }
+
+    for elem in elements {
+        if !visited.contains(elem.id.as_str()) {
+            visit(&elem.id, elements, &index, &mut result, &mut visited, &mut temp_mark)?;
+        }
+    }
+    Ok(result)
+}
+
+/// 按模板内容哈希缓存的已编译模板，重复打印同一模板时跳过解析与预计算
+pub struct TemplateCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<u64, Arc<CompiledTemplate>>,
+    /// 插入顺序，超出容量时淘汰最早的模板
+    order: VecDeque<u64>,
+}
+
+impl TemplateCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    /// 进程内共享的缓存，渲染器默认使用
+    pub fn global() -> &'static TemplateCache {
+        static CACHE: OnceLock<TemplateCache> = OnceLock::new();
+        CACHE.get_or_init(|| TemplateCache::new(CACHE_CAPACITY))
+    }
+
+    /// 取出已编译的模板，未命中时编译并缓存
+    pub fn get_or_compile(&self, template: &DeepPrintTemplate) -> Result<Arc<CompiledTemplate>, RenderError> {
+        // 缓存中保存的是展开片段后的模板，按展开结果查找
+        let expanded = partials::expand(template, &|_| None)?;
+        let template = expanded.as_ref().unwrap_or(template);
+        let key = content_hash(template);
+        if let Some(compiled) = self.inner.lock().unwrap().entries.get(&key) {
+            // 哈希相同时再比较内容，避免碰撞
+            if compiled.template == *template {
+                return Ok(compiled.clone());
+            }
+        }
+
+        let compiled = Arc::new(CompiledTemplate::compile(template.clone())?);
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.insert(key, compiled.clone()).is_none() {
+            inner.order.push_back(key);
+        }
+        while inner.order.len() > self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+        Ok(compiled)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.order.clear();
+    }
+}
+
+fn content_hash(template: &DeepPrintTemplate) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_vec(template)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,92 @@
+use crate::deep_print_schema::*;
+use crate::error::RenderError;
+use crate::limits::RenderLimits;
+use std::collections::HashMap;
+
+/// 展开元素的 copies：同一元素向下重复盖印多份
+///
+/// - 第 k 份 (k ≥ 2) 的 ID 为 "{id}#k"，linkedTo 指向上一份，y 为 copyGap，
+///   因此高度自适应的元素 (文本、表格) 也不会重叠；
+/// - 其他元素 linkedTo 指向该元素时，改为指向最后一份。
+///
+/// 展开后的元素数先与元素数上限比较，超出时直接报错而不复制；
+/// 没有设置 copies 的元素时返回 None
+pub(crate) fn expand_elements(elements: &[Element]) -> Result<Option<Vec<Element>>, RenderError> {
+    if !elements.iter().any(|e| e.copies.is_some() || e.copy_gap.is_some()) {
+        return Ok(None);
+    }
+    let total = elements
+        .iter()
+        .fold(0usize, |total, e| total.saturating_add(copies(e.copies) as usize));
+    RenderLimits::global().check_elements(total)?;
+
+    // 原元素 ID → 最后一份的 ID
+    let last: HashMap<&str, String> = elements
+        .iter()
+        .filter(|e| copies(e.copies) > 1)
+        .map(|e| (e.id.as_str(), copy_id(&e.id, copies(e.copies))))
+        .collect();
+
+    let mut result = Vec::with_capacity(elements.len());
+    for element in elements {
+        let mut original = element.clone();
+        original.copies = None;
+        original.copy_gap = None;
+        if let Some(target) = original.linked_to.as_ref().and_then(|t| last.get(t.as_str())) {
+            original.linked_to = Some(target.clone());
+        }
+
+        let mut previous = original.id.clone();
+        let repeated = (2..=copies(element.copies))
+            .map(|k| {
+                let mut copy = original.clone();
+                copy.id = copy_id(&element.id, k);
+                copy.y = element.copy_gap.unwrap_or(0.0);
+                copy.linked_to = Some(std::mem::replace(&mut previous, copy.id.clone()));
+                copy
+            })
+            .collect::<Vec<_>>();
+        result.push(original);
+        result.extend(repeated);
+    }
+    Ok(Some(result))
+}
+
+/// 展开画布的 copies：整个版面向下重复盖印多份，每份间隔 copyGap，画布高度随之增加
+/// 第 k 份元素的 ID 为 "{id}#k"，份内的 linkedTo 指向同一份中的元素；
+/// 展开后的元素数或画布高度超过上限时报错
+pub(crate) fn expand_canvas(canvas: &mut Canvas) -> Result<(), RenderError> {
+    let n = copies(canvas.copies);
+    canvas.copies = None;
+    let gap = canvas.copy_gap.take().unwrap_or(0.0);
+    if n <= 1 {
+        return Ok(());
+    }
+    let block = canvas.height + gap;
+    let limits = RenderLimits::global();
+    limits.check_elements(canvas.elements.len().saturating_mul(n as usize))?;
+    limits.check_canvas(canvas.width, block * f64::from(n) - gap)?;
+    let originals = canvas.elements.clone();
+    for k in 2..=n {
+        canvas.elements.extend(originals.iter().map(|element| {
+            let mut copy = element.clone();
+            copy.id = copy_id(&element.id, k);
+            match &element.linked_to {
+                Some(target) => copy.linked_to = Some(copy_id(target, k)),
+                None => copy.y += block * f64::from(k - 1),
+            }
+            copy
+        }));
+    }
+    canvas.height = block * f64::from(n) - gap;
+    Ok(())
+}
+
+/// 份数：未设置时为 1
+fn copies(value: Option<u32>) -> u32 {
+    value.unwrap_or(1).max(1)
+}
+
+fn copy_id(id: &str, k: u32) -> String {
+    format!("{}#{}", id, k)
+}
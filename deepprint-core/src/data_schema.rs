@@ -0,0 +1,249 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value};
+
+/// 数据不符合 dataSchema 的一处问题
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaViolation {
+    /// 数据中的路径，如 "items[2].price"，根为 ""
+    pub path: String,
+    pub message: String,
+}
+
+/// 读取 dataSchema：JSON Schema 对象，或旧版的字符串形式
+/// 字符串为空时视为未定义；是 JSON 文本时按 Schema 解析；其他文本作为 description 保留
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => None,
+        Some(Value::String(text)) => {
+            let text = text.trim();
+            if text.is_empty() {
+                None
+            } else {
+                match serde_json::from_str::<Value>(text) {
+                    Ok(schema @ Value::Object(_)) => Some(schema),
+                    _ => Some(json!({ "description": text })),
+                }
+            }
+        }
+        Some(schema) => Some(schema),
+    })
+}
+
+/// 按 JSON Schema 校验数据
+///
+/// 支持打印数据常用的子集：type、properties、required、items、enum、const、
+/// minimum/maximum、minLength/maxLength、minItems/maxItems；其余关键字忽略
+pub fn validate(schema: &Value, data: &Value) -> Vec<SchemaViolation> {
+    let mut out = Vec::new();
+    check(schema, data, "", &mut out);
+    out
+}
+
+fn check(schema: &Value, data: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    let Value::Object(schema) = schema else {
+        return;
+    };
+    let mut violation = |message: String| {
+        out.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(t, data)) {
+            violation(format!("Expected {}, got {}", types.join(" or "), type_name(data)));
+            return;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(data) {
+            violation(format!("Value {} is not one of {}", data, Value::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != data {
+            violation(format!("Expected {}", expected));
+        }
+    }
+
+    match data {
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    violation(format!("{} is less than minimum {}", n, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    violation(format!("{} is greater than maximum {}", n, max));
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    violation(format!("Length {} is shorter than minLength {}", len, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if len > max {
+                    violation(format!("Length {} is longer than maxLength {}", len, max));
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if len < min {
+                    violation(format!("{} items, expected at least {}", len, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if len > max {
+                    violation(format!("{} items, expected at most {}", len, max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{}[{}]", path, i), out);
+                }
+            }
+        }
+        Value::Object(fields) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !fields.contains_key(name) {
+                        out.push(SchemaViolation {
+                            path: join(path, name),
+                            message: "Required field is missing".to_string(),
+                        });
+                    }
+                }
+            }
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (name, property) in properties {
+                    if let Some(value) = fields.get(name) {
+                        check(property, value, &join(path, name), out);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches_type(expected: &str, data: &Value) -> bool {
+    match expected {
+        "string" => data.is_string(),
+        "number" => data.is_number(),
+        "integer" => data.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => data.is_boolean(),
+        "array" => data.is_array(),
+        "object" => data.is_object(),
+        "null" => data.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(data: &Value) -> &'static str {
+    match data {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// 按 Schema 生成示例数据，供没有真实数据时预览模板
+///
+/// 优先使用 examples / default / const / enum 的第一个值，否则按类型生成占位值：
+/// 字符串为字段名 (或 title)，数字为 0，数组包含一个示例元素
+pub fn sample(schema: &Value) -> Value {
+    sample_named(schema, "")
+}
+
+fn sample_named(schema: &Value, name: &str) -> Value {
+    let Value::Object(schema) = schema else {
+        return Value::Null;
+    };
+    if let Some(Value::Array(examples)) = schema.get("examples") {
+        if let Some(example) = examples.first() {
+            return example.clone();
+        }
+    }
+    for key in ["default", "const"] {
+        if let Some(value) = schema.get(key) {
+            return value.clone();
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if let Some(first) = options.first() {
+            return first.clone();
+        }
+    }
+
+    let declared = match schema.get("type") {
+        Some(Value::String(t)) => Some(t.as_str()),
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).find(|t| *t != "null"),
+        _ => None,
+    };
+    // 未声明类型时按其他关键字推断
+    let kind = declared.unwrap_or(if schema.contains_key("properties") {
+        "object"
+    } else if schema.contains_key("items") {
+        "array"
+    } else {
+        "string"
+    });
+
+    match kind {
+        "object" => {
+            let mut fields = Map::new();
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (field, property) in properties {
+                    fields.insert(field.clone(), sample_named(property, field));
+                }
+            }
+            Value::Object(fields)
+        }
+        "array" => {
+            let item = schema
+                .get("items")
+                .map(|items| sample_named(items, name))
+                .unwrap_or(Value::Null);
+            Value::Array(vec![item])
+        }
+        "number" | "integer" => {
+            let min = schema.get("minimum").cloned();
+            min.unwrap_or(json!(0))
+        }
+        "boolean" => Value::Bool(false),
+        "null" => Value::Null,
+        _ => {
+            let title = schema.get("title").and_then(Value::as_str).unwrap_or(name);
+            Value::String(title.to_string())
+        }
+    }
+}
@@ -0,0 +1,649 @@
+use crate::migration::{self, MigrationWarning};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// DeepPrint 协议顶层结构 (v6.1)
+/// 反序列化时先将旧版本 (v5.x) 模板迁移为当前版本，见 [`crate::migration`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepPrintTemplate {
+    pub meta: Meta,
+    /// 数据契约：描述模板预期数据结构的 JSON Schema (可选)，用于校验打印数据与生成预览示例数据。
+    /// 兼容旧版的字符串形式：JSON 文本按 Schema 解析，其他文本作为 description 保留
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_schema: Option<Value>,
+    /// 资源池 (可选)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<HashMap<String, String>>,
+    /// 可复用片段 (可选)，由 include 元素按名称引用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partials: Option<HashMap<String, Partial>>,
+    pub canvas: Canvas,
+    /// 旧版本模板迁移时的提示 (不序列化)
+    #[serde(skip)]
+    pub migration_warnings: Vec<MigrationWarning>,
+}
+
+/// 当前版本模板的反序列化定义，字段与 DeepPrintTemplate 一一对应
+#[derive(Deserialize)]
+#[serde(remote = "DeepPrintTemplate", rename_all = "camelCase")]
+struct CurrentTemplate {
+    meta: Meta,
+    #[serde(default, deserialize_with = "crate::data_schema::deserialize")]
+    data_schema: Option<Value>,
+    assets: Option<HashMap<String, String>>,
+    partials: Option<HashMap<String, Partial>>,
+    canvas: Canvas,
+    #[serde(skip)]
+    migration_warnings: Vec<MigrationWarning>,
+}
+
+/// 已迁移到当前版本的模板 JSON 按当前协议解析 (供 serde_path_to_error 追踪出错路径)
+pub(crate) struct Current(pub DeepPrintTemplate);
+
+impl<'de> Deserialize<'de> for Current {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CurrentTemplate::deserialize(deserializer).map(Current)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeepPrintTemplate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut raw = Value::deserialize(deserializer)?;
+        let warnings = migration::migrate(&mut raw);
+        let Current(mut template) = serde_path_to_error::deserialize(raw).map_err(de::Error::custom)?;
+        template.migration_warnings = warnings;
+        Ok(template)
+    }
+}
+
+/// 可复用的元素片段，如统一的公司抬头、税务页脚
+/// 片段内元素的坐标相对于 include 元素的左上角，字符串中的 {{@name}} 替换为 include 传入的参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Partial {
+    /// 参数默认值
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, String>,
+    pub elements: Vec<Element>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meta {
+    /// 固定为 "6.1"
+    pub version: String,
+    /// 模板名称
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Canvas {
+    /// 纸张宽度 (pt)
+    pub width: f64,
+    /// 纸张高度 (pt)。若 orientation=3，此值作为最小高度参考。
+    pub height: f64,
+    /// 1:纵向; 2:横向; 3:高度自适应(长小票)。默认 1。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u8>,
+    /// 全局默认样式
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub styles: Option<GlobalStyles>,
+    /// 页边距 (pt)：数字表示四边相同，或 {top, right, bottom, left}。
+    /// 元素坐标以 (页边距 + 内边距) 内的内容区左上角为原点
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin: Option<Insets>,
+    /// 内边距 (pt)：在页边距之内进一步缩小内容区，格式同 margin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<Insets>,
+    /// 出血尺寸 (pt)。元素可延伸到裁切线外该距离内，用于模切等商业印刷
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bleed: Option<f64>,
+    /// 是否在出血区外绘制角线 (裁切标记) (Default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop_marks: Option<bool>,
+    /// 是否在四边中点绘制套准标记 (Default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_marks: Option<bool>,
+    /// 小票/标签打印机的切纸设置，打印请求中的选项优先
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cut: Option<CutSettings>,
+    /// 版面重复份数 (Default: 1)：整个版面向下重复盖印，画布高度随之增加
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copies: Option<u32>,
+    /// 相邻两份版面之间的垂直间距 (pt，Default: 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_gap: Option<f64>,
+    /// 打印项列表。渲染顺序遵循数组顺序。
+    /// 定义了 pages 时为各页共享的元素 (如底纹、页眉)，绘制在每页的页面元素之前
+    pub elements: Vec<Element>,
+    /// 多页模板 (可选)：如卡片的正反面、固定的两页文档。
+    /// 各页共享画布的尺寸、样式与边距，按顺序输出为多页
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<Vec<CanvasPage>>,
+}
+
+/// 多页模板中的一页
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasPage {
+    /// 页面名称，如 "front"、"back"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 本页的元素，linkedTo 可以引用共享元素
+    pub elements: Vec<Element>,
+}
+
+impl Canvas {
+    /// 内容区到纸张四边的距离 (margin + padding)
+    pub fn content_insets(&self) -> InsetSides {
+        let sides = |insets: &Option<Insets>| insets.map(|i| i.sides()).unwrap_or_default();
+        let (margin, padding) = (sides(&self.margin), sides(&self.padding));
+        InsetSides {
+            top: margin.top + padding.top,
+            right: margin.right + padding.right,
+            bottom: margin.bottom + padding.bottom,
+            left: margin.left + padding.left,
+        }
+    }
+
+    /// 内容区宽度 (pt)
+    pub fn content_width(&self) -> f64 {
+        let insets = self.content_insets();
+        (self.width - insets.left - insets.right).max(0.0)
+    }
+
+    /// 内容区高度 (pt)，高度自适应画布为最小高度对应的内容区
+    pub fn content_height(&self) -> f64 {
+        let insets = self.content_insets();
+        (self.height - insets.top - insets.bottom).max(0.0)
+    }
+}
+
+/// 四边距的多态类型 (四边相同的数值，或分别指定各边)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Insets {
+    Uniform(f64),
+    Sides(InsetSides),
+}
+
+impl Insets {
+    pub fn sides(&self) -> InsetSides {
+        match *self {
+            Insets::Uniform(v) => InsetSides {
+                top: v,
+                right: v,
+                bottom: v,
+                left: v,
+            },
+            Insets::Sides(sides) => sides,
+        }
+    }
+}
+
+/// 分别指定的四边距 (pt)，未设置的边为 0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InsetSides {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+/// 切纸方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CutMode {
+    /// 不切纸
+    #[default]
+    None,
+    /// 全切
+    Full,
+    /// 半切 (留一点连接，防止小票掉落)
+    Partial,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CutSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<CutMode>,
+    /// 每份之后都切纸 (Default: false，仅在最后一份之后切纸)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_copy: Option<bool>,
+    /// 切纸前走纸行数，使末行越过切刀
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_lines: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalStyles {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    /// 颜色值：支持 "#RRGGBB" 与 "cmyk(c,m,y,k)" (分量 0-100)，其他颜色字段同理
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_color: Option<String>,
+}
+
+/// 基础元素包装器
+/// 包含所有元素共有的属性，并扁平化具体类型的特有属性
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Element {
+    /// 元素唯一标识符
+    pub id: String,
+    /// 左上角 X 坐标 (pt)
+    pub x: f64,
+    /// 左上角 Y 坐标 (pt)
+    pub y: f64,
+    /// 宽度 (pt)
+    pub w: f64,
+    /// 高度 (pt)
+    pub h: f64,
+    /// 锚点目标元素ID，用于垂直方向相对定位
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linked_to: Option<String>,
+    /// 重复份数 (Default: 1)：元素向下重复盖印，如一张小票上的两联优惠券
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copies: Option<u32>,
+    /// 相邻两份之间的垂直间距 (pt，Default: 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_gap: Option<f64>,
+    /// 最小高度 (pt)：高度自适应的元素 (文本、表格、条件分支) 内容较少时仍占用该高度，
+    /// 其后 linkedTo 的元素相应下移
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<f64>,
+    /// 最大高度 (pt)：超出部分被裁掉，其后 linkedTo 的元素按该高度定位
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<f64>,
+    /// 外边距 (pt)，格式同画布 margin：top 加在 linkedTo 目标底部与本元素之间，
+    /// bottom 加在本元素与其后 linkedTo 的元素之间；水平方向由 x 决定，left/right 不使用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin: Option<Insets>,
+    /// 内边距 (pt)，格式同画布 padding：内容在元素框 (x, y, w, h) 内缩进绘制，
+    /// 高度自适应的元素占用的高度包含上下内边距；线、矩形、椭圆没有内容，按元素框绘制，不受影响
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<Insets>,
+
+    /// 具体元素的特有属性 (根据 type 字段区分)
+    #[serde(flatten)]
+    pub data: ElementData,
+}
+
+impl Element {
+    /// 外边距，未设置时为 0
+    pub fn margin_sides(&self) -> InsetSides {
+        self.margin.map(|m| m.sides()).unwrap_or_default()
+    }
+
+    /// 内边距，未设置时为 0
+    pub fn padding_sides(&self) -> InsetSides {
+        self.padding.map(|p| p.sides()).unwrap_or_default()
+    }
+}
+
+/// 元素类型枚举
+/// 使用 `tag = "type"` 自动处理 JSON 中的 type 字段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ElementData {
+    Text(TextProps),
+    Table(TableProps),
+    Image(ImageProps),
+    Barcode(BarcodeProps),
+    Qrcode(QrcodeProps),
+    Line(LineProps),
+    Rect(RectProps),
+    Ellipse(EllipseProps),
+    /// 复选框 (检验单、装箱清单)
+    Checkbox(CheckboxProps),
+    /// 手写签名 (签收单)
+    Signature(SignatureProps),
+    /// 简单图表 (柱状图、折线图、饼图)
+    Chart(ChartProps),
+    /// 空白占位：不绘制内容，只在 linkedTo 链中占用 h 的高度 (如切纸线前的留白)
+    Spacer(SpacerProps),
+    /// 引用可复用片段，渲染前展开为片段中的元素
+    Include(IncludeProps),
+    /// 条件分支：按数据只渲染其中一组元素
+    Switch(SwitchProps),
+}
+
+// -----------------------------------------------------------------------------
+// 具体元素属性定义
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextProps {
+    /// 字符串内容。支持 {{var}} 插值。
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    /// 支持 "bold", "normal" 或数字 700, 400
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_weight: Option<FontWeight>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_color: Option<String>,
+    /// 行高倍率 (Default: 1.2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_height: Option<f64>,
+    /// "left", "center", "right", "decimal" (多行数字按小数点对齐，整体靠右)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_align: Option<String>,
+    /// "top", "middle", "bottom"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vertical_align: Option<String>,
+    /// "underline", "none"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_decoration: Option<String>,
+    /// 1: 溢出时自动缩小字号；0: 不缩小
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_shrink: Option<u8>,
+    /// 1: 自动换行；0: 禁止换行
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_break: Option<u8>,
+    /// 最多显示的行数，超出时在最后一行末尾显示省略号 (Default: 不限制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<usize>,
+    /// 超出 maxLines 时使用的省略号 (Default: "…")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ellipsis: Option<String>,
+    /// 制表位：内容中的第 n 个制表符 (\t) 之后的文字对齐到第 n 个制表位，
+    /// 如 "小计\t¥128.00" (Default: 元素右边缘处的一个右对齐制表位)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab_stops: Option<Vec<TabStop>>,
+    /// 是否根据内容自动计算高度 (Default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_height: Option<bool>,
+}
+
+/// 制表位
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabStop {
+    /// 到元素左边缘的距离 (pt)
+    pub position: f64,
+    /// "left": 文字从制表位开始；"right": 文字在制表位处结束 (Default: "left")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<String>,
+    /// 前导符：重复填充制表符前后文字之间的空白，如 "." 得到 "小计......¥128.00"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leader: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableProps {
+    /// 数据源变量名，如 "{{items}}"
+    pub data: String,
+    pub columns: Vec<TableColumn>,
+    /// 渲染时按数据生成的列 (如随国家变化的税率列)，插入到 columns 中
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns_from: Option<ColumnsFrom>,
+    /// 1: 每页重复表头；0: 仅首页 (Default: 1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_head: Option<u8>,
+    /// 单元格内边距 (Default: 5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_padding: Option<f64>,
+    /// 边框线宽 (Default: 2.83)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_height: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableColumn {
+    pub title: String,
+    /// 对应 rows 数据中的字段键名
+    pub field: String,
+    /// 列宽。支持百分比（"20%"）或固定pt数值（100.0）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<TableColumnWidth>,
+    /// "left", "center", "right", "decimal" (按小数点对齐整列数字)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_align: Option<String>,
+}
+
+/// 动态列：数据数组中的每一项生成一列
+/// 数组项为对象时从 titleField/fieldField 读取表头与字段键名，为字符串时两者都取该字符串
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnsFrom {
+    /// 数据源变量名，如 "{{taxColumns}}"
+    pub data: String,
+    /// 表头所在的字段 (Default: "title")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_field: Option<String>,
+    /// 列对应的行数据字段键名所在的字段 (Default: "field")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field_field: Option<String>,
+    /// 生成的列插入到 columns 中的位置 (Default: 末尾)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_at: Option<usize>,
+    /// 每个生成列的列宽，规则同 TableColumn.width (Default: 平分剩余宽度)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<TableColumnWidth>,
+    /// 每个生成列的对齐方式，取值同 TableColumn.textAlign
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_align: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageProps {
+    /// 图片引用或 URL
+    pub src: String,
+    /// "contain", "cover", "fill"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object_fit: Option<String>,
+    /// 图片无法加载 (如远程图片下载失败) 时的处理：
+    /// "placeholder" (默认，绘制占位框)、"skip" (留空) 或资源池引用 "asset:{name}"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BarcodeProps {
+    pub value: String,
+    /// 如 "CODE128", "EAN13"
+    pub format: String,
+    /// 是否在条码下方显示文字 (1:是; 0:否)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_value: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QrcodeProps {
+    pub value: String,
+    /// "L", "M", "Q", "H"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correction_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineProps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dash_array: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RectProps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_radius: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dash_array: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EllipseProps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dash_array: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckboxProps {
+    /// 是否勾选，支持 {{var}} 插值。空值、"false"、"0"、"no"、"off" 为未勾选，其他值为勾选
+    pub checked: String,
+    /// 方框边长 (Default: min(w, h))，方框位于元素左上角
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    /// 方框线宽 (Default: 1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    /// 勾选标记："check" (对勾，默认) 或 "cross" (叉)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark: Option<String>,
+    /// 勾选标记颜色 (Default: 同 strokeColor)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mark_color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureProps {
+    /// 签名数据的变量名，如 "{{signature}}"。数据为 base64 编码的 PNG (可带 data URI 前缀)，
+    /// 或笔画列表 [[[x, y], [x, y], ...], ...]，按比例缩放到元素框内
+    pub data: String,
+    /// 笔画线宽 (Default: 1.5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartProps {
+    /// "bar" (默认), "line", "pie"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// 数据源变量名，如 "{{sales}}"。每行为对象 (取 labelField/valueField) 或数值
+    pub data: String,
+    /// 标签字段 (Default: "label")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_field: Option<String>,
+    /// 数值字段 (Default: "value")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_field: Option<String>,
+    /// 柱/线/扇区颜色，按顺序循环使用 (Default: 深浅不同的灰色，适合单色打印)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colors: Option<Vec<String>>,
+    /// 是否绘制坐标轴 (柱状图/折线图，Default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_axis: Option<bool>,
+    /// 是否显示标签 (Default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_labels: Option<bool>,
+    /// 是否显示数值 (Default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_values: Option<bool>,
+    /// 纵轴下限 (Default: 0 与数据最小值中的较小者)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// 纵轴上限 (Default: 数据最大值)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// 标签与数值的字号 (Default: 8)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    /// 折线与坐标轴线宽 (Default: 1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+}
+
+/// 空白占位元素没有特有属性
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpacerProps {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncludeProps {
+    /// 片段名：模板 partials 中的键，Agent 中也可以是已注册模板的 ID
+    pub partial: String,
+    /// 片段参数，值中可以包含 {{var}} 数据插值
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, String>,
+}
+
+/// 条件分支，如已付款时盖 "PAID" 章、未付清时显示 "BALANCE DUE" 框
+/// 分支元素的坐标相对于 switch 元素的左上角，linkedTo 只能引用同一分支内的元素；
+/// switch 元素的高度为所选分支内容的高度，其他元素可以 linkedTo 到它之后
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchProps {
+    /// 判断依据，支持 {{var}} 插值，如 "{{paymentStatus}}"
+    pub value: String,
+    /// 按顺序匹配，渲染第一个 when 与 value 插值结果 (去掉首尾空白) 相同的分支
+    pub cases: Vec<SwitchCase>,
+    /// 没有分支匹配时渲染的元素 (可选)
+    #[serde(rename = "default", skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Vec<Element>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchCase {
+    pub when: String,
+    pub elements: Vec<Element>,
+}
+
+// -----------------------------------------------------------------------------
+// 辅助枚举 (Untagged Enums)
+// -----------------------------------------------------------------------------
+
+/// 处理 fontWeight 的多态类型 (String 或 Number)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FontWeight {
+    String(String),
+    Number(u16),
+}
+
+/// 处理表格列宽的多态类型 (百分比String 或 绝对数值Number)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TableColumnWidth {
+    Fixed(f64),
+    Percentage(String),
+}
\ No newline at end of file
@@ -0,0 +1,72 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// 渲染诊断 (调试模式)：各阶段耗时、元素数量、溢出警告与未解析的变量，
+/// 帮助模板作者定位渲染慢或内容缺失的原因
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderDiagnostics {
+    pub timings: PhaseTimings,
+    /// 模板元素数量
+    pub element_count: usize,
+    /// 内容超出元素框或画布的元素
+    pub overflows: Vec<Overflow>,
+    /// 数据中找不到的插值变量 (去重，按出现顺序)
+    pub unresolved_variables: Vec<String>,
+    /// 无法识别、按黑色绘制的颜色 (去重，按出现顺序)
+    pub invalid_colors: Vec<String>,
+}
+
+/// 各阶段耗时 (毫秒)
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTimings {
+    /// 模板解析与编译 (命中编译缓存时接近 0)
+    pub parse_ms: f64,
+    /// 文本排版 (段落布局与测量)
+    pub layout_ms: f64,
+    /// 绘制 (不含排版)
+    pub draw_ms: f64,
+    /// PNG/PDF 编码
+    pub encode_ms: f64,
+}
+
+/// 溢出警告
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Overflow {
+    pub element_id: String,
+    pub message: String,
+}
+
+impl RenderDiagnostics {
+    pub(crate) fn unresolved(&mut self, variable: String) {
+        if !self.unresolved_variables.contains(&variable) {
+            self.unresolved_variables.push(variable);
+        }
+    }
+
+    pub(crate) fn invalid_color(&mut self, color: String) {
+        if !self.invalid_colors.contains(&color) {
+            self.invalid_colors.push(color);
+        }
+    }
+
+    pub(crate) fn overflow(&mut self, element_id: &str, message: String) {
+        self.overflows.push(Overflow {
+            element_id: element_id.to_string(),
+            message,
+        });
+    }
+}
+
+/// 执行 f 并返回其耗时 (毫秒)
+pub(crate) fn timed<T>(f: impl FnOnce() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let value = f();
+    (value, millis(start.elapsed()))
+}
+
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,114 @@
+use thiserror::Error;
+
+/// 模板错误：JSON 无法解析为模板，或模板结构无效
+#[derive(Debug, Clone, Error)]
+pub enum TemplateError {
+    /// 模板 JSON 无法解析，path 为出错字段的 JSON 路径 (如 canvas.elements[2].w)
+    #[error("Invalid template at {path}: {message}")]
+    Parse { path: String, message: String },
+    /// 严格模式下出现未识别的字段
+    #[error("{message} at {path}")]
+    UnknownField { path: String, message: String },
+    /// linkedTo 形成循环依赖，或片段直接/间接引用了自身
+    #[error("Circular dependency at element '{element_id}'")]
+    CircularDependency { element_id: String },
+    /// include 引用的片段不存在
+    #[error("Element '{element_id}': unknown partial '{partial}'")]
+    UnknownPartial { element_id: String, partial: String },
+    /// 图片引用的资源 (asset:name) 不在模板资源池中
+    #[error("Element '{element_id}': unknown asset '{asset}'")]
+    UnknownAsset { element_id: String, asset: String },
+    /// 片段参数既未传入也没有默认值
+    #[error("Element '{element_id}': missing parameter '{param}' for partial")]
+    MissingParameter { element_id: String, param: String },
+}
+
+impl TemplateError {
+    /// 稳定的错误分类码
+    pub fn code(&self) -> &'static str {
+        match self {
+            TemplateError::Parse { .. } => "template_parse",
+            TemplateError::UnknownField { .. } => "unknown_field",
+            TemplateError::CircularDependency { .. } => "circular_dependency",
+            TemplateError::UnknownPartial { .. } => "unknown_partial",
+            TemplateError::UnknownAsset { .. } => "unknown_asset",
+            TemplateError::MissingParameter { .. } => "missing_parameter",
+        }
+    }
+}
+
+/// 渲染与输出错误
+#[derive(Debug, Clone, Error)]
+pub enum RenderError {
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+    /// 某个元素绘制失败 (如内容过长无法生成二维码)
+    #[error("Element '{element_id}': {message}")]
+    Element { element_id: String, message: String },
+    /// 图片无法解码
+    #[error("Unsupported or corrupt image")]
+    InvalidImage,
+    /// 字体数据无法识别
+    #[error("Unsupported font data")]
+    InvalidFont,
+    /// 输出参数无效 (如栅格尺寸、拼版行列)
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// Skia 录制、栅格化或编码失败
+    #[error("{0}")]
+    Backend(String),
+    /// 渲染被取消 (任务取消或客户端断开)
+    #[error("Render cancelled")]
+    Cancelled,
+    /// 超出资源上限 (画布尺寸、像素数、元素数、表格行数)
+    #[error("{what} {actual} exceeds limit {limit}")]
+    LimitExceeded {
+        what: String,
+        actual: String,
+        limit: String,
+    },
+}
+
+impl RenderError {
+    /// 稳定的错误分类码
+    pub fn code(&self) -> &'static str {
+        match self {
+            RenderError::Template(e) => e.code(),
+            RenderError::Element { .. } => "element_render",
+            RenderError::InvalidImage => "invalid_image",
+            RenderError::InvalidFont => "invalid_font",
+            RenderError::InvalidArgument(_) => "invalid_argument",
+            RenderError::Backend(_) => "render_backend",
+            RenderError::Cancelled => "cancelled",
+            RenderError::LimitExceeded { .. } => "limit_exceeded",
+        }
+    }
+
+    /// 出错的元素 ID
+    pub fn element_id(&self) -> Option<&str> {
+        match self {
+            RenderError::Element { element_id, .. }
+            | RenderError::Template(
+                TemplateError::CircularDependency { element_id }
+                | TemplateError::UnknownPartial { element_id, .. }
+                | TemplateError::UnknownAsset { element_id, .. }
+                | TemplateError::MissingParameter { element_id, .. },
+            ) => Some(element_id),
+            _ => None,
+        }
+    }
+
+    /// 出错字段的 JSON 路径 (仅模板解析错误与未识别的字段)
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            RenderError::Template(
+                TemplateError::Parse { path, .. } | TemplateError::UnknownField { path, .. },
+            ) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn backend(message: &str) -> Self {
+        RenderError::Backend(message.to_string())
+    }
+}
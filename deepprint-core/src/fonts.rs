@@ -0,0 +1,103 @@
+use skia_safe::{FontMgr, FontStyle};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 后备字体：(注册的族名, 文件名)，按简体、繁体、日文的顺序查找缺失的字形
+/// 字体文件由 fonts/subset.py 从 Noto Sans SC/TC/JP 裁剪生成 (常用汉字、假名与全角标点)
+const FALLBACK_FILES: &[(&str, &str)] = &[
+    ("DeepPrint Fallback SC", "NotoSansSC-Subset.otf"),
+    ("DeepPrint Fallback TC", "NotoSansTC-Subset.otf"),
+    ("DeepPrint Fallback JP", "NotoSansJP-Subset.otf"),
+];
+
+/// 启用 cjk-fonts feature 时编译进二进制的后备字体 (与 FALLBACK_FILES 顺序一致)
+#[cfg(feature = "cjk-fonts")]
+const EMBEDDED: &[(&str, &[u8])] = &[
+    (
+        "DeepPrint Fallback SC",
+        include_bytes!("../fonts/NotoSansSC-Subset.otf"),
+    ),
+    (
+        "DeepPrint Fallback TC",
+        include_bytes!("../fonts/NotoSansTC-Subset.otf"),
+    ),
+    (
+        "DeepPrint Fallback JP",
+        include_bytes!("../fonts/NotoSansJP-Subset.otf"),
+    ),
+];
+
+/// skparagraph 未指定字体族时使用的族名
+const DEFAULT_FAMILY: &str = "sans-serif";
+
+/// 运行时从目录加载的后备字体
+static LOADED: OnceLock<Vec<(&'static str, Vec<u8>)>> = OnceLock::new();
+
+/// 后备字体是否已编译进二进制 (cjk-fonts feature)，此时无需 [`load_fallback`]
+pub const fn embedded() -> bool {
+    cfg!(feature = "cjk-fonts")
+}
+
+/// 从目录加载后备字体，需在创建渲染器之前调用 (只生效一次)；
+/// 系统已有 CJK 字体或字体已编译进二进制时不加载。
+/// 返回缺失或无法读取的文件说明，由调用方记录日志
+pub fn load_fallback(dir: &Path) -> Vec<String> {
+    if embedded() || !fallback_needed() {
+        return Vec::new();
+    }
+    let mut errors = Vec::new();
+    let fonts = FALLBACK_FILES
+        .iter()
+        .filter_map(|(family, file)| {
+            let path = dir.join(file);
+            match std::fs::read(&path) {
+                Ok(data) => Some((*family, data)),
+                Err(e) => {
+                    errors.push(format!("{}: {}", path.display(), e));
+                    None
+                }
+            }
+        })
+        .collect();
+    if LOADED.set(fonts).is_err() {
+        errors.push("Fallback fonts are already loaded".to_string());
+    }
+    errors
+}
+
+/// 需要注册的后备字体：优先使用编译进二进制的字体，其次是运行时加载的字体；
+/// 系统已有 CJK 字体 (或两者都没有) 时为空
+pub(crate) fn bundled() -> Vec<(&'static str, &'static [u8])> {
+    #[cfg(feature = "cjk-fonts")]
+    if fallback_needed() {
+        return EMBEDDED.to_vec();
+    }
+    LOADED
+        .get()
+        .map(|fonts| {
+            fonts
+                .iter()
+                .map(|(family, data)| (*family, data.as_slice()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 文本的字体族列表：指定的字体 (或默认字体) 之后追加后备字体，
+/// 系统字体回退找不到字形时仍能显示中日文
+pub(crate) fn families(family: Option<&str>) -> Vec<&str> {
+    let mut families = vec![family.unwrap_or(DEFAULT_FAMILY)];
+    families.extend(bundled().into_iter().map(|(name, _)| name));
+    families
+}
+
+/// 系统字体中找不到常用汉字时才需要后备字体 (如精简版 Windows 镜像、无字体的容器与浏览器预览)，
+/// 已有系统 CJK 字体时保持原有的字体选择与 PDF 体积
+pub fn fallback_needed() -> bool {
+    static NEEDED: OnceLock<bool> = OnceLock::new();
+    *NEEDED.get_or_init(|| {
+        FontMgr::default()
+            .match_family_style_character("", FontStyle::normal(), &["zh-Hans"], '中' as i32)
+            .is_none()
+    })
+}
@@ -0,0 +1,113 @@
+//! DeepPrint 渲染核心：模板协议、Skia 渲染器与 PDF/PNG/单色位图输出。
+//!
+//! 不依赖 Tauri/Axum，服务端可以直接用它无界面地渲染模板，
+//! 输出与打印 Agent 逐像素一致。
+//!
+//! ```no_run
+//! use deepprint_core::{parse_template, render_pdf};
+//! use serde_json::json;
+//!
+//! let template = parse_template(&std::fs::read_to_string("receipt.json")?)?;
+//! let pdf = render_pdf(&template, &json!({ "orderNo": "A001" }))?;
+//! std::fs::write("receipt.pdf", pdf)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! 需要自定义字体、灰度输出或拼版时，直接使用 [`renderer::DeepPrintRenderer`]
+//! 与 [`output`] 中的函数。
+//!
+//! 默认启用的 `parallel` feature 提供基于 rayon 的批量并行渲染；
+//! 编译到 WebAssembly (浏览器预览，见 deepprint-wasm) 时需关闭。
+//! 系统缺少 CJK 字体时使用 Noto Sans SC/TC/JP 子集作为最后的后备字体：
+//! `cjk-fonts` feature 将字体编译进二进制 (约 6 MB)，未启用时可用 [`fonts::load_fallback`] 在运行时加载。
+
+/// 模板资源池 (assets) 中图片的解析
+pub mod assets;
+/// 颜色字符串解析：十六进制、rgb()/rgba() 与 CSS 颜色名
+pub mod colors;
+/// 模板预编译与按内容哈希的编译缓存
+pub mod compiled;
+/// 元素与版面的重复盖印 (copies) 展开
+pub mod copies;
+/// dataSchema (JSON Schema) 的数据校验与示例数据生成
+pub mod data_schema;
+/// DeepPrint 模板协议 (JSON 结构)
+pub mod deep_print_schema;
+/// 调试模式的渲染诊断：阶段耗时、溢出警告、未解析的变量
+pub mod diagnostics;
+/// 模板与渲染错误类型
+pub mod error;
+/// 运行时加载的 CJK 后备字体
+pub mod fonts;
+/// 渲染资源上限：画布尺寸、像素数、元素数、表格行数
+pub mod limits;
+/// 旧版本 (v5.x) 模板迁移为当前协议
+pub mod migration;
+/// 页面输出：PDF、PNG、单色位图，拼版与裁切标记
+pub mod output;
+/// 可复用片段 (include) 的展开
+pub mod partials;
+/// 纯文本排版：小票打印机文本模式按字符列输出模板
+pub mod plain_text;
+/// 严格模式：未识别字段的检测与拼写建议
+pub mod strict;
+/// 二维码编码结果的缓存 (按内容、码制与纠错等级)
+pub mod symbols;
+/// 模板渲染器：插值、布局与各类元素的绘制
+pub mod renderer;
+
+pub use compiled::{CompiledTemplate, TemplateCache};
+pub use deep_print_schema::DeepPrintTemplate;
+pub use diagnostics::RenderDiagnostics;
+pub use error::{RenderError, TemplateError};
+pub use limits::RenderLimits;
+pub use migration::MigrationWarning;
+pub use output::{EncodedImage, GrayBitmap, MonoBitmap, PdfColorSpace, PdfOptions, RenderedPage};
+pub use renderer::{CancelToken, DeepPrintRenderer, RenderOptions};
+pub use symbols::SymbolCache;
+
+use deep_print_schema::Current;
+use serde_json::Value;
+
+/// 解析模板 JSON，旧版本模板先迁移为当前版本 (提示见 migration_warnings)，
+/// 失败时返回出错字段的 JSON 路径与原因
+pub fn parse_template(json: &str) -> Result<DeepPrintTemplate, TemplateError> {
+    let raw = serde_json::from_str(json).map_err(|e| TemplateError::Parse {
+        path: String::new(),
+        message: e.to_string(),
+    })?;
+    parse_template_value(raw)
+}
+
+/// 同 [`parse_template`]，输入为已解析的 JSON
+pub fn parse_template_value(mut raw: Value) -> Result<DeepPrintTemplate, TemplateError> {
+    let warnings = migration::migrate(&mut raw);
+    let Current(mut template) = serde_path_to_error::deserialize(raw).map_err(|e| TemplateError::Parse {
+        path: e.path().to_string(),
+        message: e.inner().to_string(),
+    })?;
+    template.migration_warnings = warnings;
+    Ok(template)
+}
+
+/// 使用系统字体和默认选项将模板渲染为单页 PDF
+pub fn render_pdf(template: &DeepPrintTemplate, data: &Value) -> Result<Vec<u8>, RenderError> {
+    let renderer = DeepPrintRenderer::new();
+    output::render_pdf(
+        &renderer,
+        template,
+        data,
+        &RenderOptions::default(),
+        &PdfOptions::default(),
+    )
+}
+
+/// 使用系统字体和默认选项将模板渲染为 PNG；scale 为 1 时 1pt = 1px (72 dpi)
+pub fn render_png(
+    template: &DeepPrintTemplate,
+    data: &Value,
+    scale: f32,
+) -> Result<EncodedImage, RenderError> {
+    let renderer = DeepPrintRenderer::new();
+    output::render_png(&renderer, template, data, &RenderOptions::default(), scale)
+}
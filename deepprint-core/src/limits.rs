@@ -0,0 +1,93 @@
+use crate::error::RenderError;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// 全局生效的渲染上限
+static LIMITS: RwLock<RenderLimits> = RwLock::new(RenderLimits::DEFAULT);
+
+/// 渲染资源上限：异常模板 (如千万 pt 高的画布、数十万行的表格) 直接报错，
+/// 而不是耗尽内存或长时间占用渲染线程
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RenderLimits {
+    /// 画布宽、高上限 (pt)，高度自适应画布的内容高度同样受此限制 (Default: 100000)
+    pub max_canvas_pt: f64,
+    /// 按目标分辨率栅格化后的像素数上限 (Default: 50,000,000)
+    pub max_pixels: u64,
+    /// 模板元素数上限 (Default: 5000)
+    pub max_elements: usize,
+    /// 单个表格的数据行数上限 (Default: 10000)
+    pub max_table_rows: usize,
+}
+
+impl RenderLimits {
+    const DEFAULT: Self = Self {
+        max_canvas_pt: 100_000.0,
+        max_pixels: 50_000_000,
+        max_elements: 5000,
+        max_table_rows: 10_000,
+    };
+
+    /// 当前生效的上限
+    pub fn global() -> Self {
+        *LIMITS.read().unwrap()
+    }
+
+    /// 修改全局上限，之后的渲染立即生效
+    pub fn set_global(limits: Self) {
+        *LIMITS.write().unwrap() = limits;
+    }
+
+    pub(crate) fn check_canvas(&self, width: f64, height: f64) -> Result<(), RenderError> {
+        for (name, value) in [("Canvas width", width), ("Canvas height", height)] {
+            if value.is_nan() || value > self.max_canvas_pt {
+                return Err(exceeded(name, format!("{}pt", value), format!("{}pt", self.max_canvas_pt)));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_pixels(&self, width: i64, height: i64) -> Result<(), RenderError> {
+        let pixels = width.max(0) as u64 * height.max(0) as u64;
+        if pixels > self.max_pixels {
+            return Err(exceeded(
+                "Raster size",
+                format!("{}x{} ({} pixels)", width, height, pixels),
+                format!("{} pixels", self.max_pixels),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_elements(&self, count: usize) -> Result<(), RenderError> {
+        if count > self.max_elements {
+            return Err(exceeded("Element count", count.to_string(), self.max_elements.to_string()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_table_rows(&self, element_id: &str, rows: usize) -> Result<(), RenderError> {
+        if rows > self.max_table_rows {
+            return Err(exceeded(
+                &format!("Table '{}' row count", element_id),
+                rows.to_string(),
+                self.max_table_rows.to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for RenderLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+fn exceeded(what: &str, actual: String, limit: String) -> RenderError {
+    RenderError::LimitExceeded {
+        what: what.to_string(),
+        actual,
+        limit,
+    }
+}
@@ -0,0 +1,287 @@
+//! 旧版本模板迁移：反序列化前检测 meta.version，将 v5.x 模板的字段名与语义升级为当前协议 (v6.1)。
+//!
+//! v5.x 与 v6.1 的差异：
+//! - 画布可以用 mm / px (96 dpi) 作为单位 (canvas.unit)，v6.1 统一为 pt；
+//! - 高度自适应由 canvas.autoHeight 表示 (v6.1 为 orientation = 3)，全局样式名为 defaultStyle；
+//! - 元素坐标为 left/top/width/height，相对定位字段为 anchor；
+//! - 各类元素的属性名不同 (text → content、align → textAlign、lineWidth → strokeWidth 等)，
+//!   开关类属性为布尔值 (v6.1 为 0/1)，表格数据源不带 {{ }}。
+//!
+//! 每处改动都会产生一条 [`MigrationWarning`]，调用方可以提示用户更新模板库。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 当前协议版本
+pub const CURRENT_VERSION: &str = "6.1";
+
+/// 1 mm 对应的 pt
+const PT_PER_MM: f64 = 72.0 / 25.4;
+/// 1 px (96 dpi) 对应的 pt
+const PT_PER_PX: f64 = 0.75;
+
+/// 迁移提示
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationWarning {
+    /// 旧模板中的 JSON 路径，如 "canvas.elements[2].align"
+    pub path: String,
+    pub message: String,
+}
+
+/// 所有元素共有的字段改名
+const COMMON_RENAMES: &[(&str, &str)] = &[
+    ("left", "x"),
+    ("top", "y"),
+    ("width", "w"),
+    ("height", "h"),
+    ("anchor", "linkedTo"),
+];
+
+/// 元素类型改名
+const TYPE_RENAMES: &[(&str, &str)] = &[
+    ("qrCode", "qrcode"),
+    ("QRCode", "qrcode"),
+    ("barCode", "barcode"),
+    ("circle", "ellipse"),
+];
+
+/// 各类元素的属性改名
+fn type_renames(element_type: &str) -> &'static [(&'static str, &'static str)] {
+    match element_type {
+        "text" => &[
+            ("text", "content"),
+            ("color", "fontColor"),
+            ("align", "textAlign"),
+            ("valign", "verticalAlign"),
+            ("wrap", "lineBreak"),
+            ("shrinkToFit", "autoShrink"),
+        ],
+        "table" => &[("dataSource", "data"), ("headerRepeat", "showHead")],
+        "image" => &[("url", "src"), ("fit", "objectFit")],
+        "barcode" => &[("barcodeType", "format"), ("showText", "displayValue")],
+        "qrcode" => &[("level", "correctionLevel")],
+        "line" | "rect" | "ellipse" => &[
+            ("lineWidth", "strokeWidth"),
+            ("lineColor", "strokeColor"),
+            ("borderColor", "strokeColor"),
+            ("fill", "fillColor"),
+            ("background", "fillColor"),
+            ("radius", "borderRadius"),
+            ("dash", "dashArray"),
+        ],
+        _ => &[],
+    }
+}
+
+/// 表格列的属性改名
+const COLUMN_RENAMES: &[(&str, &str)] = &[("key", "field"), ("label", "title"), ("align", "textAlign")];
+
+/// v6.1 中以 0/1 表示的开关
+const FLAG_FIELDS: &[&str] = &["lineBreak", "autoShrink", "showHead", "displayValue"];
+
+/// 元素上以长度为单位的数值字段 (单位换算时处理)
+const LENGTH_FIELDS: &[&str] = &[
+    "x",
+    "y",
+    "w",
+    "h",
+    "strokeWidth",
+    "borderRadius",
+    "cellPadding",
+    "borderWidth",
+    "size",
+];
+
+/// 模板是否为需要迁移的旧版本 (主版本号低于 6)
+pub fn is_legacy(raw: &Value) -> bool {
+    version(raw).is_some_and(|v| major(&v) < 6)
+}
+
+fn version(raw: &Value) -> Option<String> {
+    let version = raw
+        .pointer("/meta/version")
+        .or_else(|| raw.get("version"))?;
+    match version {
+        Value::String(v) => Some(v.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn major(version: &str) -> u32 {
+    version
+        .split('.')
+        .next()
+        .and_then(|m| m.trim().trim_start_matches(['v', 'V']).parse().ok())
+        .unwrap_or(0)
+}
+
+/// 将旧版本模板原地升级为当前版本，返回迁移提示；当前版本的模板不做改动
+pub fn migrate(raw: &mut Value) -> Vec<MigrationWarning> {
+    let mut warnings = Vec::new();
+    if !is_legacy(raw) {
+        return warnings;
+    }
+    let from = version(raw).unwrap_or_default();
+    let Value::Object(root) = raw else {
+        return warnings;
+    };
+    let mut m = Migrator {
+        warnings: &mut warnings,
+    };
+
+    // meta：v5 的 version/name 可能位于顶层
+    let mut meta = match root.remove("meta") {
+        Some(Value::Object(meta)) => meta,
+        _ => Map::new(),
+    };
+    for key in ["version", "name"] {
+        if let Some(value) = root.remove(key) {
+            meta.entry(key).or_insert(value);
+        }
+    }
+    meta.entry("name").or_insert_with(|| Value::String(String::new()));
+    meta.insert("version".to_string(), Value::String(CURRENT_VERSION.to_string()));
+    root.insert("meta".to_string(), Value::Object(meta));
+    m.warn("meta.version", format!("Template version {} migrated to {}", from, CURRENT_VERSION));
+
+    if let Some(Value::Object(canvas)) = root.get_mut("canvas") {
+        m.canvas(canvas);
+    }
+    warnings
+}
+
+struct Migrator<'a> {
+    warnings: &'a mut Vec<MigrationWarning>,
+}
+
+impl Migrator<'_> {
+    fn warn(&mut self, path: &str, message: String) {
+        self.warnings.push(MigrationWarning {
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    /// 字段改名；新字段已存在时丢弃旧字段
+    fn rename(&mut self, map: &mut Map<String, Value>, path: &str, from: &str, to: &str) {
+        let Some(value) = map.remove(from) else {
+            return;
+        };
+        let field = format!("{}.{}", path, from);
+        if map.contains_key(to) {
+            self.warn(&field, format!("'{}' ignored, '{}' is already set", from, to));
+        } else {
+            map.insert(to.to_string(), value);
+            self.warn(&field, format!("'{}' renamed to '{}'", from, to));
+        }
+    }
+
+    fn canvas(&mut self, canvas: &mut Map<String, Value>) {
+        self.rename(canvas, "canvas", "defaultStyle", "styles");
+
+        if let Some(auto) = canvas.remove("autoHeight") {
+            if auto.as_bool() == Some(true) {
+                canvas.insert("orientation".to_string(), Value::from(3));
+            }
+            self.warn("canvas.autoHeight", "'autoHeight' replaced by orientation 3".to_string());
+        }
+
+        let factor = match canvas.remove("unit").as_ref().and_then(Value::as_str) {
+            Some("mm") => Some(PT_PER_MM),
+            Some("px") => Some(PT_PER_PX),
+            _ => None,
+        };
+        if let Some(factor) = factor {
+            for key in ["width", "height", "bleed"] {
+                scale(canvas.get_mut(key), factor);
+            }
+            self.warn("canvas.unit", "Lengths converted to pt".to_string());
+        }
+
+        if let Some(Value::Array(elements)) = canvas.get_mut("elements") {
+            for (i, element) in elements.iter_mut().enumerate() {
+                if let Value::Object(element) = element {
+                    self.element(element, &format!("canvas.elements[{}]", i), factor);
+                }
+            }
+        }
+    }
+
+    fn element(&mut self, element: &mut Map<String, Value>, path: &str, factor: Option<f64>) {
+        if let Some(Value::String(element_type)) = element.get_mut("type") {
+            if let Some((from, to)) = TYPE_RENAMES.iter().find(|(from, _)| from == element_type) {
+                *element_type = to.to_string();
+                self.warn(&format!("{}.type", path), format!("Type '{}' renamed to '{}'", from, to));
+            }
+        }
+        let element_type = element
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        for (from, to) in COMMON_RENAMES.iter().chain(type_renames(&element_type)) {
+            self.rename(element, path, from, to);
+        }
+        if element_type == "text" && element.get("bold").is_some() {
+            if element.remove("bold").and_then(|b| b.as_bool()) == Some(true) {
+                element
+                    .entry("fontWeight")
+                    .or_insert_with(|| Value::String("bold".to_string()));
+            }
+            self.warn(&format!("{}.bold", path), "'bold' replaced by fontWeight".to_string());
+        }
+        for flag in FLAG_FIELDS {
+            if let Some(value) = element.get_mut(*flag) {
+                if let Some(on) = value.as_bool() {
+                    *value = Value::from(on as u8);
+                }
+            }
+        }
+
+        if element_type == "table" {
+            // v5 的数据源是字段名，v6.1 为 {{path}} 形式
+            if let Some(Value::String(data)) = element.get_mut("data") {
+                if !data.contains("{{") {
+                    *data = format!("{{{{{}}}}}", data);
+                }
+            }
+            if let Some(Value::Array(columns)) = element.get_mut("columns") {
+                for (i, column) in columns.iter_mut().enumerate() {
+                    let Value::Object(column) = column else {
+                        continue;
+                    };
+                    let column_path = format!("{}.columns[{}]", path, i);
+                    for (from, to) in COLUMN_RENAMES {
+                        self.rename(column, &column_path, from, to);
+                    }
+                    if let Some(factor) = factor {
+                        scale(column.get_mut("width"), factor);
+                    }
+                }
+            }
+        }
+
+        if let Some(factor) = factor {
+            for key in LENGTH_FIELDS {
+                scale(element.get_mut(*key), factor);
+            }
+            if let Some(Value::Array(dash)) = element.get_mut("dashArray") {
+                for value in dash {
+                    scale(Some(value), factor);
+                }
+            }
+        }
+    }
+}
+
+/// 按单位换算系数缩放数值字段 (百分比等字符串保持不变)
+fn scale(value: Option<&mut Value>, factor: f64) {
+    if let Some(value) = value {
+        if let Some(n) = value.as_f64() {
+            *value = Value::from(n * factor);
+        }
+    }
+}
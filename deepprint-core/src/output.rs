@@ -0,0 +1,930 @@
+use crate::compiled::{CompiledTemplate, TemplateCache};
+use crate::deep_print_schema::{Canvas, DeepPrintTemplate};
+use crate::diagnostics::{timed, RenderDiagnostics};
+use crate::error::RenderError;
+use crate::limits::RenderLimits;
+use crate::renderer::{DeepPrintRenderer, RenderOptions};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use skia_safe::{
+    pdf, surfaces, AlphaType, Color, ColorType, Data, EncodedImageFormat, Image, ImageInfo,
+    Paint, PaintStyle, Picture, PictureRecorder, Point, Rect,
+};
+
+/// 裁切标记线长 (pt)
+const MARK_LENGTH: f32 = 18.0;
+/// 标记与出血边之间的间隙 (pt)
+const MARK_GAP: f32 = 6.0;
+/// 标记线宽 (pt)
+const MARK_STROKE: f32 = 0.25;
+/// 编排份数上限 (Composition.copies)
+pub const MAX_COPIES: u32 = 999;
+
+/// 单页渲染结果：录制好的绘图指令 + 页面尺寸 (pt)
+/// 先录制为 Picture 再回放到具体后端，便于在确定最终页高后输出
+#[derive(Clone)]
+pub struct RenderedPage {
+    pub picture: Picture,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// PDF 输出选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfOptions {
+    /// 强制嵌入系统字体。
+    /// 注册的自定义字体总是以子集形式嵌入；开启后以 PDF/A 模式输出，
+    /// 要求所有用到的字体 (含系统字体) 都嵌入文件，避免在缺字体的机器上被替换
+    #[serde(default)]
+    pub embed_system_fonts: bool,
+    /// 输出色彩空间 (Default: rgb)
+    #[serde(default)]
+    pub color_space: PdfColorSpace,
+}
+
+/// PDF 色彩空间
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PdfColorSpace {
+    /// DeviceRGB，cmyk() 颜色在绘制前转换为 RGB
+    #[default]
+    Rgb,
+    /// DeviceCMYK：Skia 的 PDF 后端无法输出，请求时返回错误，不会静默生成 RGB 文档
+    Cmyk,
+}
+
+impl PdfOptions {
+    /// 检查选项能否满足
+    pub fn check(&self) -> Result<(), RenderError> {
+        if self.color_space == PdfColorSpace::Cmyk {
+            return Err(RenderError::InvalidArgument(
+                "CMYK PDF output is not supported: the PDF backend only writes DeviceRGB, \
+                 cmyk() colors are converted to RGB"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 拼版参数：在一张物理纸上按网格排列多个标签 (如 3×8 地址标签纸)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Imposition {
+    /// 物理纸张宽度 (pt)
+    pub sheet_width: f32,
+    /// 物理纸张高度 (pt)
+    pub sheet_height: f32,
+    /// 行数
+    pub rows: u32,
+    /// 列数
+    pub cols: u32,
+    /// 水平间距 (pt)
+    #[serde(default)]
+    pub gap_x: f32,
+    /// 垂直间距 (pt)
+    #[serde(default)]
+    pub gap_y: f32,
+    /// 网格左边距 (pt)
+    #[serde(default)]
+    pub margin_left: f32,
+    /// 网格上边距 (pt)
+    #[serde(default)]
+    pub margin_top: f32,
+}
+
+/// 页面变换：适配具体打印设备 (尺寸单位 pt)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PageTransform {
+    /// 顺时针旋转角度 (0/90/180/270)
+    pub rotation: u16,
+    /// 纸宽：内容宽于纸宽时等比缩小，窄于纸宽时水平居中
+    pub paper_width: Option<f32>,
+    /// 内容水平偏移，正值向右
+    pub offset_x: f32,
+    /// 内容垂直偏移，正值向下
+    pub offset_y: f32,
+    /// 横向内容超出纸宽时自动再旋转 90° (如横版标签打在窄幅热敏纸上)
+    pub auto_rotate: bool,
+}
+
+/// 输出编排：拼版 + 设备变换 + 份数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Composition {
+    /// 拼版参数，未设置时每个标签独占一页
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imposition: Option<Imposition>,
+    /// 设备变换 (来自打印机档案)，在拼版之后应用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<PageTransform>,
+    /// 份数：整份文档按顺序重复输出 (逐份打印)
+    #[serde(default = "default_copies")]
+    pub copies: u32,
+}
+
+fn default_copies() -> u32 {
+    1
+}
+
+impl Default for Composition {
+    fn default() -> Self {
+        Self {
+            imposition: None,
+            transform: None,
+            copies: 1,
+        }
+    }
+}
+
+/// 图片适配纸张的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FitMode {
+    /// 等比缩放，完整显示 (可能留白)
+    #[default]
+    Contain,
+    /// 等比缩放，铺满可打印区域 (超出部分裁掉)
+    Cover,
+    /// 拉伸铺满，不保持比例
+    Stretch,
+}
+
+/// 图片打印版式 (尺寸单位 pt)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageLayout {
+    pub page_width: f32,
+    pub page_height: f32,
+    #[serde(default)]
+    pub fit: FitMode,
+    /// 四周边距
+    #[serde(default)]
+    pub margin: f32,
+    /// 图片与纸张方向不一致时自动旋转 90°
+    #[serde(default = "default_auto_rotate")]
+    pub auto_rotate: bool,
+}
+
+fn default_auto_rotate() -> bool {
+    true
+}
+
+/// 将模板渲染为单页 Picture (多页模板只录制第一页)
+pub fn record_page(
+    renderer: &DeepPrintRenderer,
+    template: &DeepPrintTemplate,
+    data: &Value,
+    options: &RenderOptions,
+) -> Result<RenderedPage, RenderError> {
+    let compiled = TemplateCache::global().get_or_compile(template)?;
+    record_compiled_page(renderer, &compiled, data, options)
+}
+
+/// 将已编译的模板渲染为单页 Picture (多页模板只录制第一页)
+pub fn record_compiled_page(
+    renderer: &DeepPrintRenderer,
+    compiled: &CompiledTemplate,
+    data: &Value,
+    options: &RenderOptions,
+) -> Result<RenderedPage, RenderError> {
+    record_page_with(renderer, &compiled.pages()[0], data, options, None)
+}
+
+/// 将已编译的模板逐页渲染 (单页模板为一页)，各页已加上出血与裁切标记
+pub fn record_compiled_pages(
+    renderer: &DeepPrintRenderer,
+    compiled: &CompiledTemplate,
+    data: &Value,
+    options: &RenderOptions,
+) -> Result<Vec<RenderedPage>, RenderError> {
+    record_pages_with(renderer, compiled, data, options, None)
+}
+
+fn record_pages_with(
+    renderer: &DeepPrintRenderer,
+    compiled: &CompiledTemplate,
+    data: &Value,
+    options: &RenderOptions,
+    mut diagnostics: Option<&mut RenderDiagnostics>,
+) -> Result<Vec<RenderedPage>, RenderError> {
+    compiled
+        .pages()
+        .iter()
+        .map(|page| {
+            let recorded = record_page_with(renderer, page, data, options, diagnostics.as_deref_mut())?;
+            apply_print_marks(recorded, &page.template.canvas)
+        })
+        .collect()
+}
+
+/// 多页自上而下拼接为一页 (PNG 预览一张图片显示所有页面)
+pub fn stack_pages(mut pages: Vec<RenderedPage>) -> Result<RenderedPage, RenderError> {
+    if pages.len() == 1 {
+        return Ok(pages.remove(0));
+    }
+    let width = pages.iter().map(|p| p.width).fold(0.0, f32::max);
+    let height: f32 = pages.iter().map(|p| p.height).sum();
+
+    let mut recorder = PictureRecorder::new();
+    let canvas = recorder.begin_recording(Rect::from_wh(width, height), None);
+    let mut top = 0.0;
+    for page in &pages {
+        canvas.save();
+        canvas.translate((0.0, top));
+        canvas.draw_picture(&page.picture, None, None);
+        canvas.restore();
+        top += page.height;
+    }
+    let picture = recorder
+        .finish_recording_as_picture(None)
+        .ok_or_else(|| RenderError::backend("Failed to record pages"))?;
+    Ok(RenderedPage {
+        picture,
+        width,
+        height,
+    })
+}
+
+fn record_page_with(
+    renderer: &DeepPrintRenderer,
+    compiled: &CompiledTemplate,
+    data: &Value,
+    options: &RenderOptions,
+    diagnostics: Option<&mut RenderDiagnostics>,
+) -> Result<RenderedPage, RenderError> {
+    let template = &compiled.template;
+    let limits = RenderLimits::global();
+    limits.check_canvas(template.canvas.width, template.canvas.height)?;
+    limits.check_elements(template.canvas.elements.len())?;
+
+    let width = template.canvas.width as f32;
+    let auto_height = template.canvas.orientation == Some(3);
+    // 高度自适应画布按上限录制，内容超出上限时报错而不是截断
+    let max_height = limits.max_canvas_pt as f32;
+    let bounds_height = if auto_height {
+        max_height
+    } else {
+        template.canvas.height as f32
+    };
+
+    let mut recorder = PictureRecorder::new();
+    let canvas = recorder.begin_recording(Rect::from_wh(width, bounds_height), None);
+    let content_height = match diagnostics {
+        Some(diagnostics) => {
+            renderer.render_compiled_traced(canvas, compiled, data, options, diagnostics)?
+        }
+        None => renderer.render_compiled(canvas, compiled, data, options)?,
+    };
+    let picture = recorder
+        .finish_recording_as_picture(None)
+        .ok_or_else(|| RenderError::backend("Failed to record page"))?;
+
+    // 高度自适应时以内容高度为准，canvas.height 作为最小高度
+    let height = if auto_height {
+        limits.check_canvas(template.canvas.width, content_height)?;
+        (content_height as f32)
+            .max(template.canvas.height as f32)
+            .min(max_height)
+    } else {
+        template.canvas.height as f32
+    };
+
+    Ok(RenderedPage {
+        picture,
+        width,
+        height,
+    })
+}
+
+/// 为页面添加出血与裁切/套准标记
+/// 页面在四周扩展出 (出血 + 间隙 + 标记线长) 的边距，原内容位于中央，
+/// 超出裁切线但位于出血范围内的内容会被保留
+pub fn apply_print_marks(page: RenderedPage, canvas_def: &Canvas) -> Result<RenderedPage, RenderError> {
+    let bleed = canvas_def.bleed.unwrap_or(0.0).max(0.0) as f32;
+    let crop_marks = canvas_def.crop_marks.unwrap_or(false);
+    let registration_marks = canvas_def.registration_marks.unwrap_or(false);
+    if bleed == 0.0 && !crop_marks && !registration_marks {
+        return Ok(page);
+    }
+
+    let slug = if crop_marks || registration_marks {
+        MARK_GAP + MARK_LENGTH
+    } else {
+        0.0
+    };
+    let offset = bleed + slug;
+    let width = page.width + offset * 2.0;
+    let height = page.height + offset * 2.0;
+    let trim = Rect::from_xywh(offset, offset, page.width, page.height);
+
+    let mut recorder = PictureRecorder::new();
+    let canvas = recorder.begin_recording(Rect::from_wh(width, height), None);
+
+    // 内容：裁剪到出血框
+    canvas.save();
+    canvas.clip_rect(trim.with_outset((bleed, bleed)), None, None);
+    canvas.translate((offset, offset));
+    canvas.draw_picture(&page.picture, None, None);
+    canvas.restore();
+
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_style(PaintStyle::Stroke);
+    paint.set_stroke_width(MARK_STROKE);
+    paint.set_color(Color::BLACK);
+
+    // 标记起点：位于出血框外侧一个间隙处
+    let near = bleed + MARK_GAP;
+    let far = near + MARK_LENGTH;
+
+    if crop_marks {
+        for &(x, dx) in &[(trim.left, -1.0), (trim.right, 1.0)] {
+            for &(y, dy) in &[(trim.top, -1.0), (trim.bottom, 1.0)] {
+                // 水平角线 (延长裁切线的上/下边)
+                canvas.draw_line(
+                    Point::new(x + dx * near, y),
+                    Point::new(x + dx * far, y),
+                    &paint,
+                );
+                // 垂直角线 (延长裁切线的左/右边)
+                canvas.draw_line(
+                    Point::new(x, y + dy * near),
+                    Point::new(x, y + dy * far),
+                    &paint,
+                );
+            }
+        }
+    }
+
+    if registration_marks {
+        let radius = MARK_LENGTH / 4.0;
+        let mid = near + MARK_LENGTH / 2.0;
+        let centers = [
+            Point::new(trim.center_x(), trim.top - mid),
+            Point::new(trim.center_x(), trim.bottom + mid),
+            Point::new(trim.left - mid, trim.center_y()),
+            Point::new(trim.right + mid, trim.center_y()),
+        ];
+        for c in centers {
+            canvas.draw_circle(c, radius, &paint);
+            canvas.draw_line(
+                Point::new(c.x - radius * 1.5, c.y),
+                Point::new(c.x + radius * 1.5, c.y),
+                &paint,
+            );
+            canvas.draw_line(
+                Point::new(c.x, c.y - radius * 1.5),
+                Point::new(c.x, c.y + radius * 1.5),
+                &paint,
+            );
+        }
+    }
+
+    let picture = recorder
+        .finish_recording_as_picture(None)
+        .ok_or_else(|| RenderError::backend("Failed to record print marks"))?;
+    Ok(RenderedPage {
+        picture,
+        width,
+        height,
+    })
+}
+
+/// 按编排参数组合页面：先拼版，再做设备变换，最后按份数复制
+pub fn compose(pages: Vec<RenderedPage>, composition: &Composition) -> Result<Vec<RenderedPage>, RenderError> {
+    let sheets = match &composition.imposition {
+        Some(imposition) => impose(&pages, imposition)?,
+        None => pages,
+    };
+    let sheets = match &composition.transform {
+        Some(transform) => sheets
+            .into_iter()
+            .map(|page| transform_page(page, transform))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => sheets,
+    };
+
+    let copies = composition.copies.max(1);
+    if copies > MAX_COPIES {
+        return Err(RenderError::InvalidArgument(format!(
+            "Copies must be at most {}, got {}",
+            MAX_COPIES, copies
+        )));
+    }
+    let copies = copies as usize;
+    let mut result = Vec::with_capacity(sheets.len() * copies);
+    for _ in 0..copies {
+        result.extend(sheets.iter().cloned());
+    }
+    Ok(result)
+}
+
+/// 拼版：按行优先顺序把标签排入网格，每填满一张纸生成一个新页面；
+/// 网格 (含边距与间距) 超出纸张时返回 InvalidArgument
+fn impose(labels: &[RenderedPage], imposition: &Imposition) -> Result<Vec<RenderedPage>, RenderError> {
+    let invalid = |message: String| Err(RenderError::InvalidArgument(message));
+    if imposition.rows == 0 || imposition.cols == 0 {
+        return invalid("Imposition rows and cols must be greater than 0".to_string());
+    }
+    let Some(per_sheet) = imposition.rows.checked_mul(imposition.cols) else {
+        return invalid(format!(
+            "Imposition grid {}x{} is too large",
+            imposition.cols, imposition.rows
+        ));
+    };
+    RenderLimits::global().check_canvas(
+        f64::from(imposition.sheet_width),
+        f64::from(imposition.sheet_height),
+    )?;
+
+    // 网格单元尺寸取所有标签的最大尺寸，保证每张纸的网格一致
+    let cell_width = labels.iter().map(|p| p.width).fold(0.0, f32::max);
+    let cell_height = labels.iter().map(|p| p.height).fold(0.0, f32::max);
+    let span = |count: u32, cell: f32, gap: f32, margin: f32| {
+        margin + count as f32 * cell + count.saturating_sub(1) as f32 * gap
+    };
+    let grid_width = span(imposition.cols, cell_width, imposition.gap_x, imposition.margin_left);
+    let grid_height = span(imposition.rows, cell_height, imposition.gap_y, imposition.margin_top);
+    // 允许 0.5pt 的舍入误差 (mm 换算为 pt)
+    if grid_width > imposition.sheet_width + 0.5 || grid_height > imposition.sheet_height + 0.5 {
+        return invalid(format!(
+            "Imposition grid {}x{} of {}x{}pt labels needs {}x{}pt but the sheet is {}x{}pt",
+            imposition.cols,
+            imposition.rows,
+            cell_width,
+            cell_height,
+            grid_width,
+            grid_height,
+            imposition.sheet_width,
+            imposition.sheet_height
+        ));
+    }
+    let per_sheet = per_sheet as usize;
+
+    let mut sheets = Vec::new();
+    for chunk in labels.chunks(per_sheet) {
+        let mut recorder = PictureRecorder::new();
+        let canvas = recorder.begin_recording(
+            Rect::from_wh(imposition.sheet_width, imposition.sheet_height),
+            None,
+        );
+
+        for (i, label) in chunk.iter().enumerate() {
+            let row = (i as u32 / imposition.cols) as f32;
+            let col = (i as u32 % imposition.cols) as f32;
+            let x = imposition.margin_left + col * (cell_width + imposition.gap_x);
+            let y = imposition.margin_top + row * (cell_height + imposition.gap_y);
+
+            canvas.save();
+            canvas.translate((x, y));
+            // 裁剪到标签区域，防止内容溢出到相邻标签
+            canvas.clip_rect(Rect::from_wh(label.width, label.height), None, None);
+            canvas.draw_picture(&label.picture, None, None);
+            canvas.restore();
+        }
+
+        let picture = recorder
+            .finish_recording_as_picture(None)
+            .ok_or_else(|| RenderError::backend("Failed to record imposed sheet"))?;
+        sheets.push(RenderedPage {
+            picture,
+            width: imposition.sheet_width,
+            height: imposition.sheet_height,
+        });
+    }
+    Ok(sheets)
+}
+
+/// 设备变换：旋转 → 适配纸宽 → 偏移
+fn transform_page(page: RenderedPage, transform: &PageTransform) -> Result<RenderedPage, RenderError> {
+    let mut rotation = transform.rotation % 360;
+    let oriented = |rotation: u16| match rotation {
+        90 | 270 => (page.height, page.width),
+        _ => (page.width, page.height),
+    };
+    let (w, h) = oriented(rotation);
+    if transform.auto_rotate && w > h && transform.paper_width.is_some_and(|paper| w > paper) {
+        rotation = (rotation + 90) % 360;
+    }
+    let (rotated_w, rotated_h) = oriented(rotation);
+    let (width, scale) = match transform.paper_width {
+        Some(paper) if paper > 0.0 => (paper, (paper / rotated_w).min(1.0)),
+        _ => (rotated_w, 1.0),
+    };
+    let height = rotated_h * scale;
+
+    let mut recorder = PictureRecorder::new();
+    let canvas = recorder.begin_recording(Rect::from_wh(width, height), None);
+    canvas.translate((
+        (width - rotated_w * scale) / 2.0 + transform.offset_x,
+        transform.offset_y,
+    ));
+    canvas.scale((scale, scale));
+    // 旋转后平移回第一象限
+    match rotation {
+        90 => {
+            canvas.translate((rotated_w, 0.0));
+            canvas.rotate(90.0, None);
+        }
+        180 => {
+            canvas.translate((rotated_w, rotated_h));
+            canvas.rotate(180.0, None);
+        }
+        270 => {
+            canvas.translate((0.0, rotated_h));
+            canvas.rotate(270.0, None);
+        }
+        _ => {}
+    }
+    canvas.draw_picture(&page.picture, None, None);
+
+    let picture = recorder
+        .finish_recording_as_picture(None)
+        .ok_or_else(|| RenderError::backend("Failed to record transformed page"))?;
+    Ok(RenderedPage {
+        picture,
+        width,
+        height,
+    })
+}
+
+/// 将页面顺时针旋转 90°
+pub fn rotate_page(page: RenderedPage) -> Result<RenderedPage, RenderError> {
+    let transform = PageTransform {
+        rotation: 90,
+        ..PageTransform::default()
+    };
+    transform_page(page, &transform)
+}
+
+/// 将页面按指定比例缩放后居中放到 width × height 的纸张上 (超出部分裁掉)
+pub fn place_page(
+    page: &RenderedPage,
+    width: f32,
+    height: f32,
+    scale: f32,
+) -> Result<RenderedPage, RenderError> {
+    let mut recorder = PictureRecorder::new();
+    let canvas = recorder.begin_recording(Rect::from_wh(width, height), None);
+    canvas.clip_rect(Rect::from_wh(width, height), None, None);
+    canvas.translate((
+        (width - page.width * scale) / 2.0,
+        (height - page.height * scale) / 2.0,
+    ));
+    canvas.scale((scale, scale));
+    canvas.draw_picture(&page.picture, None, None);
+
+    let picture = recorder
+        .finish_recording_as_picture(None)
+        .ok_or_else(|| RenderError::backend("Failed to record page"))?;
+    Ok(RenderedPage {
+        picture,
+        width,
+        height,
+    })
+}
+
+/// 位图编码结果
+pub struct EncodedImage {
+    pub bytes: Vec<u8>,
+    /// 像素宽度
+    pub width: i32,
+    /// 像素高度
+    pub height: i32,
+}
+
+/// 将页面栅格化为 PNG
+/// scale: 每 pt 对应的像素数 (dpi / 72)
+pub fn encode_png(page: &RenderedPage, scale: f32) -> Result<EncodedImage, RenderError> {
+    let width = (page.width * scale).ceil() as i32;
+    let height = (page.height * scale).ceil() as i32;
+    if width <= 0 || height <= 0 {
+        let message = format!("Invalid raster size {}x{}", width, height);
+        return Err(RenderError::InvalidArgument(message));
+    }
+    RenderLimits::global().check_pixels(width as i64, height as i64)?;
+
+    let mut surface = surfaces::raster_n32_premul((width, height))
+        .ok_or_else(|| RenderError::backend("Failed to create raster surface"))?;
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+    canvas.scale((scale, scale));
+    canvas.draw_picture(&page.picture, None, None);
+
+    let image = surface.image_snapshot();
+    let data = image
+        .encode(None, EncodedImageFormat::PNG, 100)
+        .ok_or_else(|| RenderError::backend("PNG encoding failed"))?;
+
+    Ok(EncodedImage {
+        bytes: data.as_bytes().to_vec(),
+        width,
+        height,
+    })
+}
+
+/// 1 位黑白位图：每行按字节对齐，高位对应左侧像素，1 为黑点
+pub struct MonoBitmap {
+    /// 像素宽度
+    pub width: u32,
+    /// 像素高度
+    pub height: u32,
+    pub bytes_per_row: usize,
+    pub data: Vec<u8>,
+}
+
+/// 8 位灰度位图：每像素一字节，0 为黑、255 为白，行间无填充
+pub struct GrayBitmap {
+    /// 像素宽度
+    pub width: u32,
+    /// 像素高度
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// 黑白阈值：灰度低于该值的像素打印为黑点
+const MONO_THRESHOLD: u8 = 128;
+
+/// 将页面栅格化为 8 位灰度位图，供只接受光栅格式的 IPP 打印机 (PWG Raster) 使用
+/// dpi: 输出分辨率
+pub fn rasterize_gray(page: &RenderedPage, dpi: f32) -> Result<GrayBitmap, RenderError> {
+    let scale = dpi / 72.0;
+    let width = (page.width * scale).ceil() as i32;
+    let height = (page.height * scale).ceil() as i32;
+    if width <= 0 || height <= 0 {
+        let message = format!("Invalid raster size {}x{}", width, height);
+        return Err(RenderError::InvalidArgument(message));
+    }
+    RenderLimits::global().check_pixels(width as i64, height as i64)?;
+
+    let mut surface = surfaces::raster_n32_premul((width, height))
+        .ok_or_else(|| RenderError::backend("Failed to create raster surface"))?;
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+    canvas.scale((scale, scale));
+    canvas.draw_picture(&page.picture, None, None);
+
+    let gray_info = ImageInfo::new((width, height), ColorType::Gray8, AlphaType::Opaque, None);
+    let mut data = vec![0u8; (width * height) as usize];
+    if !surface.read_pixels(&gray_info, &mut data, width as usize, (0, 0)) {
+        return Err(RenderError::backend("Failed to read raster pixels"));
+    }
+
+    Ok(GrayBitmap {
+        width: width as u32,
+        height: height as u32,
+        data,
+    })
+}
+
+/// 将页面栅格化为黑白位图，供热敏/标签打印机使用
+/// dpi: 打印头分辨率
+pub fn rasterize_mono(page: &RenderedPage, dpi: f32) -> Result<MonoBitmap, RenderError> {
+    let gray = rasterize_gray(page, dpi)?;
+    let width = gray.width as usize;
+    let bytes_per_row = width.div_ceil(8);
+    let mut data = vec![0u8; bytes_per_row * gray.height as usize];
+    for (y, row) in gray.data.chunks_exact(width).enumerate() {
+        for (x, &luma) in row.iter().enumerate() {
+            if luma < MONO_THRESHOLD {
+                data[y * bytes_per_row + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    Ok(MonoBitmap {
+        width: gray.width,
+        height: gray.height,
+        bytes_per_row,
+        data,
+    })
+}
+
+/// 渲染模板并输出 PNG (含出血与裁切标记，与 PDF 输出一致)
+/// 多页模板的各页自上而下拼接为一张图片
+pub fn render_png(
+    renderer: &DeepPrintRenderer,
+    template: &DeepPrintTemplate,
+    data: &Value,
+    render_options: &RenderOptions,
+    scale: f32,
+) -> Result<EncodedImage, RenderError> {
+    let compiled = TemplateCache::global().get_or_compile(template)?;
+    let pages = record_compiled_pages(renderer, &compiled, data, render_options)?;
+    encode_png(&stack_pages(pages)?, scale)
+}
+
+/// 渲染 PNG 并收集诊断信息 (调试模式)
+pub fn render_png_traced(
+    renderer: &DeepPrintRenderer,
+    template: &DeepPrintTemplate,
+    data: &Value,
+    render_options: &RenderOptions,
+    scale: f32,
+) -> Result<(EncodedImage, RenderDiagnostics), RenderError> {
+    let mut diagnostics = RenderDiagnostics::default();
+    let (compiled, parse_ms) = timed(|| TemplateCache::global().get_or_compile(template));
+    diagnostics.timings.parse_ms = parse_ms;
+    let pages = record_pages_with(renderer, &compiled?, data, render_options, Some(&mut diagnostics))?;
+    let (image, encode_ms) = timed(|| stack_pages(pages).and_then(|page| encode_png(&page, scale)));
+    diagnostics.timings.encode_ms = encode_ms;
+    Ok((image?, diagnostics))
+}
+
+/// 将 PNG/JPEG 图片按版式排入一页
+pub fn layout_image(bytes: &[u8], layout: &ImageLayout) -> Result<RenderedPage, RenderError> {
+    let image = Image::from_encoded(Data::new_copy(bytes))
+        .ok_or(RenderError::InvalidImage)?;
+    let (image_w, image_h) = (image.width() as f32, image.height() as f32);
+
+    let area = Rect::from_xywh(
+        layout.margin,
+        layout.margin,
+        (layout.page_width - 2.0 * layout.margin).max(1.0),
+        (layout.page_height - 2.0 * layout.margin).max(1.0),
+    );
+    // 横图配竖纸 (或反之) 时旋转，使图片长边与纸张长边一致
+    let rotate = layout.auto_rotate
+        && image_w != image_h
+        && area.width() != area.height()
+        && (image_w > image_h) != (area.width() > area.height());
+    let (box_w, box_h) = if rotate {
+        (image_h, image_w)
+    } else {
+        (image_w, image_h)
+    };
+
+    let (draw_w, draw_h) = match layout.fit {
+        FitMode::Contain => {
+            let s = (area.width() / box_w).min(area.height() / box_h);
+            (box_w * s, box_h * s)
+        }
+        FitMode::Cover => {
+            let s = (area.width() / box_w).max(area.height() / box_h);
+            (box_w * s, box_h * s)
+        }
+        FitMode::Stretch => (area.width(), area.height()),
+    };
+
+    let mut recorder = PictureRecorder::new();
+    let canvas = recorder.begin_recording(
+        Rect::from_wh(layout.page_width, layout.page_height),
+        None,
+    );
+    canvas.clip_rect(area, None, None);
+    canvas.translate((area.center_x(), area.center_y()));
+    // 以中心为原点绘制；旋转后图片的宽对应纸面上的高
+    let (dest_w, dest_h) = if rotate {
+        canvas.rotate(90.0, None);
+        (draw_h, draw_w)
+    } else {
+        (draw_w, draw_h)
+    };
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    canvas.draw_image_rect(
+        &image,
+        None,
+        Rect::from_xywh(-dest_w / 2.0, -dest_h / 2.0, dest_w, dest_h),
+        &paint,
+    );
+
+    let picture = recorder
+        .finish_recording_as_picture(None)
+        .ok_or_else(|| RenderError::backend("Failed to record page"))?;
+    Ok(RenderedPage {
+        picture,
+        width: layout.page_width,
+        height: layout.page_height,
+    })
+}
+
+/// 图片排版后按编排参数输出 PDF
+pub fn render_image_pdf(
+    bytes: &[u8],
+    layout: &ImageLayout,
+    composition: &Composition,
+) -> Result<Vec<u8>, RenderError> {
+    let pages = compose(vec![layout_image(bytes, layout)?], composition)?;
+    Ok(write_pdf(&pages, "Image", &PdfOptions::default()))
+}
+
+/// 将录制好的页面写为 PDF 文档
+pub fn write_pdf(pages: &[RenderedPage], title: &str, options: &PdfOptions) -> Vec<u8> {
+    let metadata = pdf::Metadata {
+        title: title.to_string(),
+        creator: "DeepPrint Agent".to_string(),
+        pdfa: options.embed_system_fonts,
+        ..Default::default()
+    };
+
+    let mut document_buffer = Vec::new();
+    {
+        let mut document = pdf::new_document(&mut document_buffer, Some(&metadata));
+        for page in pages {
+            let mut on_page_doc = document.begin_page((page.width, page.height), None);
+            on_page_doc
+                .canvas()
+                .draw_picture(&page.picture, None, None);
+            document = on_page_doc.end_page();
+        }
+        document.close();
+    }
+    document_buffer
+}
+
+/// 渲染模板并输出 PDF (多页模板每页一页)
+pub fn render_pdf(
+    renderer: &DeepPrintRenderer,
+    template: &DeepPrintTemplate,
+    data: &Value,
+    render_options: &RenderOptions,
+    pdf_options: &PdfOptions,
+) -> Result<Vec<u8>, RenderError> {
+    pdf_options.check()?;
+    let compiled = TemplateCache::global().get_or_compile(template)?;
+    let pages = record_compiled_pages(renderer, &compiled, data, render_options)?;
+    Ok(write_pdf(&pages, &template.meta.name, pdf_options))
+}
+
+/// 渲染 PDF 并收集诊断信息 (调试模式)
+pub fn render_pdf_traced(
+    renderer: &DeepPrintRenderer,
+    template: &DeepPrintTemplate,
+    data: &Value,
+    render_options: &RenderOptions,
+    pdf_options: &PdfOptions,
+) -> Result<(Vec<u8>, RenderDiagnostics), RenderError> {
+    pdf_options.check()?;
+    let mut diagnostics = RenderDiagnostics::default();
+    let (compiled, parse_ms) = timed(|| TemplateCache::global().get_or_compile(template));
+    diagnostics.timings.parse_ms = parse_ms;
+    let pages = record_pages_with(renderer, &compiled?, data, render_options, Some(&mut diagnostics))?;
+    let (pdf, encode_ms) = timed(|| write_pdf(&pages, &template.meta.name, pdf_options));
+    diagnostics.timings.encode_ms = encode_ms;
+    Ok((pdf, diagnostics))
+}
+
+/// 渲染模板的多条数据记录，按编排参数拼版/复制后输出为页面列表
+/// 多页模板的每条记录依次输出各页
+pub fn render_pages(
+    renderer: &DeepPrintRenderer,
+    template: &DeepPrintTemplate,
+    records: &[Value],
+    render_options: &RenderOptions,
+    composition: &Composition,
+) -> Result<Vec<RenderedPage>, RenderError> {
+    let compiled = TemplateCache::global().get_or_compile(template)?;
+    let pages = records
+        .iter()
+        .map(|data| record_compiled_pages(renderer, &compiled, data, render_options))
+        .collect::<Result<Vec<_>, _>>()?;
+    compose(pages.into_iter().flatten().collect(), composition)
+}
+
+/// 并行渲染多条数据记录：每条记录在各自的画布上录制 (每个工作线程使用 new_renderer 创建的渲染器)，
+/// 按原顺序合并后再拼版/复制，结果与 render_pages 相同。
+/// 在 rayon 线程池中执行；需要限制并发时由调用方在自己的线程池中调用 (ThreadPool::install)
+#[cfg(feature = "parallel")]
+pub fn render_pages_parallel<F>(
+    new_renderer: F,
+    template: &DeepPrintTemplate,
+    records: &[Value],
+    render_options: &RenderOptions,
+    composition: &Composition,
+) -> Result<Vec<RenderedPage>, RenderError>
+where
+    F: Fn() -> DeepPrintRenderer + Sync + Send,
+{
+    let compiled = TemplateCache::global().get_or_compile(template)?;
+    let pages = records
+        .par_iter()
+        .map_init(&new_renderer, |renderer, data| {
+            record_compiled_pages(renderer, &compiled, data, render_options)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    compose(pages.into_iter().flatten().collect(), composition)
+}
+
+/// 并行将页面栅格化为单色位图，顺序与输入一致
+#[cfg(feature = "parallel")]
+pub fn rasterize_mono_pages(pages: &[RenderedPage], dpi: f32) -> Result<Vec<MonoBitmap>, RenderError> {
+    pages
+        .par_iter()
+        .map(|page| rasterize_mono(page, dpi))
+        .collect()
+}
+
+/// 并行将页面栅格化为灰度位图，顺序与输入一致
+#[cfg(feature = "parallel")]
+pub fn rasterize_gray_pages(pages: &[RenderedPage], dpi: f32) -> Result<Vec<GrayBitmap>, RenderError> {
+    pages
+        .par_iter()
+        .map(|page| rasterize_gray(page, dpi))
+        .collect()
+}
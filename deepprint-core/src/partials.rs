@@ -0,0 +1,211 @@
+use crate::copies;
+use crate::deep_print_schema::*;
+use crate::error::{RenderError, TemplateError};
+use crate::limits::RenderLimits;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// 展开模板中的 include 元素
+///
+/// 片段先在模板自身的 partials 中查找，找不到时交给 resolve (如 Agent 的模板注册表)。
+/// 展开规则：
+/// - 片段元素的 ID 加上 include 元素 ID 作为前缀 ("footer.tax")，片段内部的 linkedTo 同步改写；
+/// - 片段元素的坐标相对于 include 元素：x 总是加上 include 的 x，没有 linkedTo 的元素 y 加上 include 的 y
+///   并继承 include 的 linkedTo；
+/// - 其他元素 linkedTo 指向 include 元素时，改为指向片段的最后一个元素；
+/// - 字符串中的 {{@name}} 替换为 include 传入的参数，未传入时使用片段的默认值。
+///
+/// 元素与画布的 copies 同时展开 (见 [`crate::copies`])，include 元素的每一份分别展开为片段；
+/// 多页模板的画布 copies 在编译各页时展开。
+///
+/// 模板中 (含多页模板的各页) 没有 include 元素与 copies 时返回 None；
+/// 片段与 copies 展开后的元素数 (或画布 copies 展开后的高度) 超过渲染上限时
+/// 返回 [`RenderError::LimitExceeded`]
+pub fn expand(
+    template: &DeepPrintTemplate,
+    resolve: &dyn Fn(&str) -> Option<Partial>,
+) -> Result<Option<DeepPrintTemplate>, RenderError> {
+    let canvas = &template.canvas;
+    let pages = canvas.pages.iter().flatten();
+    let canvas_copies = canvas.pages.is_none() && (canvas.copies.is_some() || canvas.copy_gap.is_some());
+    if !needs_expand(&canvas.elements) && !pages.clone().any(|p| needs_expand(&p.elements)) && !canvas_copies {
+        return Ok(None);
+    }
+    let inline = template.partials.as_ref();
+    let lookup = |name: &str| inline.and_then(|p| p.get(name).cloned()).or_else(|| resolve(name));
+
+    let mut expanded = template.clone();
+    expanded.canvas.elements = expand_elements(&template.canvas.elements, &lookup, &mut Vec::new())?;
+    for page in expanded.canvas.pages.iter_mut().flatten() {
+        page.elements = expand_elements(&page.elements, &lookup, &mut Vec::new())?;
+    }
+    if canvas_copies {
+        copies::expand_canvas(&mut expanded.canvas)?;
+    }
+    Ok(Some(expanded))
+}
+
+fn needs_expand(elements: &[Element]) -> bool {
+    elements.iter().any(|e| {
+        e.copies.is_some()
+            || e.copy_gap.is_some()
+            || match &e.data {
+                ElementData::Include(_) => true,
+                ElementData::Switch(props) => switch_branches(props).any(needs_expand),
+                _ => false,
+            }
+    })
+}
+
+/// switch 元素各分支 (含 default) 的元素
+fn switch_branches(props: &SwitchProps) -> impl Iterator<Item = &[Element]> {
+    props
+        .cases
+        .iter()
+        .map(|case| case.elements.as_slice())
+        .chain(props.fallback.as_deref())
+}
+
+/// 展开一组元素；stack 为正在展开的片段名，用于发现循环引用
+fn expand_elements(
+    elements: &[Element],
+    lookup: &dyn Fn(&str) -> Option<Partial>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<Element>, RenderError> {
+    let repeated = copies::expand_elements(elements)?;
+    let elements = repeated.as_deref().unwrap_or(elements);
+    let mut result = Vec::with_capacity(elements.len());
+    // include 元素 ID → 片段展开后用于锚定的元素 ID
+    let mut anchors: HashMap<String, Option<String>> = HashMap::new();
+
+    for element in elements {
+        let props = match &element.data {
+            ElementData::Include(props) => props,
+            // 分支内的元素同样展开
+            ElementData::Switch(props) => {
+                let mut props = props.clone();
+                for case in &mut props.cases {
+                    case.elements = expand_elements(&case.elements, lookup, stack)?;
+                }
+                if let Some(fallback) = &mut props.fallback {
+                    *fallback = expand_elements(fallback, lookup, stack)?;
+                }
+                let mut element = element.clone();
+                element.data = ElementData::Switch(props);
+                result.push(element);
+                continue;
+            }
+            _ => {
+                result.push(element.clone());
+                continue;
+            }
+        };
+        if stack.contains(&props.partial) {
+            return Err(TemplateError::CircularDependency {
+                element_id: element.id.clone(),
+            }
+            .into());
+        }
+        let partial = lookup(&props.partial).ok_or_else(|| TemplateError::UnknownPartial {
+            element_id: element.id.clone(),
+            partial: props.partial.clone(),
+        })?;
+
+        let children = substitute(&partial, props, &element.id)?;
+        stack.push(props.partial.clone());
+        let children = expand_elements(&children, lookup, stack)?;
+        stack.pop();
+
+        let anchor = match children.last() {
+            Some(last) => Some(format!("{}.{}", element.id, last.id)),
+            None => element.linked_to.clone(),
+        };
+        anchors.insert(element.id.clone(), anchor);
+        // 片段逐个展开时累计检查元素数，嵌套引用不会先展开出海量元素
+        RenderLimits::global().check_elements(result.len() + children.len())?;
+        result.extend(children.into_iter().map(|child| place(child, element)));
+    }
+
+    for element in &mut result {
+        if let Some(anchor) = element.linked_to.as_ref().and_then(|t| anchors.get(t)) {
+            element.linked_to = anchor.clone();
+        }
+    }
+    Ok(result)
+}
+
+/// 将片段元素放到 include 元素的位置上
+fn place(mut child: Element, include: &Element) -> Element {
+    child.x += include.x;
+    match &child.linked_to {
+        Some(target) => child.linked_to = Some(format!("{}.{}", include.id, target)),
+        None => {
+            child.y += include.y;
+            child.linked_to = include.linked_to.clone();
+        }
+    }
+    child.id = format!("{}.{}", include.id, child.id);
+    child
+}
+
+/// 替换片段元素中的 {{@name}} 参数
+fn substitute(
+    partial: &Partial,
+    props: &IncludeProps,
+    element_id: &str,
+) -> Result<Vec<Element>, TemplateError> {
+    let invalid = |message: String| TemplateError::Parse {
+        path: format!("partials.{}", props.partial),
+        message,
+    };
+    let mut value = serde_json::to_value(&partial.elements).map_err(|e| invalid(e.to_string()))?;
+    substitute_value(&mut value, &|name| {
+        props
+            .params
+            .get(name)
+            .or_else(|| partial.params.get(name))
+            .cloned()
+            .ok_or_else(|| TemplateError::MissingParameter {
+                element_id: element_id.to_string(),
+                param: name.to_string(),
+            })
+    })?;
+    serde_json::from_value(value).map_err(|e| invalid(e.to_string()))
+}
+
+fn substitute_value(
+    value: &mut Value,
+    param: &dyn Fn(&str) -> Result<String, TemplateError>,
+) -> Result<(), TemplateError> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"\{\{\s*@([a-zA-Z0-9_]+)\s*\}\}").unwrap());
+
+    match value {
+        Value::String(s) if s.contains("{{") => {
+            let mut out = String::with_capacity(s.len());
+            let mut last = 0;
+            for caps in re.captures_iter(s) {
+                let whole = caps.get(0).unwrap();
+                out.push_str(&s[last..whole.start()]);
+                out.push_str(&param(&caps[1])?);
+                last = whole.end();
+            }
+            out.push_str(&s[last..]);
+            *s = out;
+        }
+        Value::Array(items) => {
+            for item in items {
+                substitute_value(item, param)?;
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                substitute_value(item, param)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
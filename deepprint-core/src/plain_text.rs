@@ -0,0 +1,279 @@
+use crate::compiled::TemplateCache;
+use crate::deep_print_schema::*;
+use crate::limits::RenderLimits;
+use crate::renderer::Interpolator;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 留白元素按 1/6 英寸 (ESC/POS 默认行距) 换算为空行
+const LINE_HEIGHT_PT: f64 = 12.0;
+
+/// 纯文本输出中的一行，已按字符列排好 (不含换行符)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    pub bold: bool,
+}
+
+/// 按字符列排版模板，供小票打印机的文本模式使用 (打印机内置字库，比位图快且省流量)。
+/// 元素的水平位置按比例换算为列，同一行上的元素 (y 相同或链接到同一目标) 合并输出；
+/// 中日韩等全角字符占两列。模板含多页、重复盖印，或文本、表格、分隔线与留白以外的元素，
+/// 或超出渲染上限时返回 None，调用方应改用栅格输出 (由栅格渲染报告超限错误)
+pub fn layout(template: &DeepPrintTemplate, data: &Value, columns: usize) -> Option<Vec<TextLine>> {
+    if columns == 0 {
+        return None;
+    }
+    let compiled = TemplateCache::global().get_or_compile(template).ok()?;
+    if !compiled.pages.is_empty() {
+        return None;
+    }
+    let canvas = &compiled.template.canvas;
+    let elements = &canvas.elements;
+    let limits = RenderLimits::global();
+    limits.check_canvas(canvas.width, canvas.height).ok()?;
+    limits.check_elements(elements.len()).ok()?;
+    let supported = elements.iter().all(|e| {
+        e.copies.unwrap_or(1) <= 1
+            && match &e.data {
+                ElementData::Table(props) => props.columns_from.is_none(),
+                ElementData::Text(_) | ElementData::Line(_) | ElementData::Spacer(_) => true,
+                _ => false,
+            }
+    });
+    if !supported {
+        return None;
+    }
+
+    // 行序：独立定位的元素按 y，linkedTo 的元素排在目标之后
+    let index: HashMap<&str, usize> = elements
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.id.as_str(), i))
+        .collect();
+    let mut keys = vec![(0.0, 0); elements.len()];
+    for &i in &compiled.order {
+        keys[i] = match elements[i].linked_to.as_deref().and_then(|t| index.get(t)) {
+            Some(&target) => (keys[target].0, keys[target].1 + 1),
+            None => (elements[i].y, 0),
+        };
+    }
+    let mut sorted: Vec<usize> = (0..elements.len()).collect();
+    sorted.sort_by(|&a, &b| {
+        keys[a]
+            .0
+            .total_cmp(&keys[b].0)
+            .then(keys[a].1.cmp(&keys[b].1))
+            .then(elements[a].x.total_cmp(&elements[b].x))
+    });
+
+    let width = canvas.content_width().max(1.0);
+    let to_column = |x: f64| ((x / width * columns as f64).round().max(0.0) as usize).min(columns);
+    let mut out = Vec::new();
+    for row in sorted.chunk_by(|&a, &b| keys[a] == keys[b]) {
+        // 每个元素：起始列与各行内容 (已按元素宽度对齐)
+        let blocks: Vec<(usize, Vec<TextLine>)> = row
+            .iter()
+            .map(|&i| {
+                let element = &elements[i];
+                let start = to_column(element.x).min(columns - 1);
+                let span = to_column(element.x + element.w).max(start + 1) - start;
+                let content = compiled.elements[i].content.as_ref().map(|c| c.render(data));
+                (start, element_lines(element, content, &compiled.elements[i].column_widths, data, span))
+            })
+            .collect();
+        let height = blocks.iter().map(|(_, lines)| lines.len()).max().unwrap_or(0);
+        for n in 0..height {
+            let mut text = String::new();
+            let mut used = 0;
+            let mut bold = true;
+            for (start, lines) in &blocks {
+                let Some(line) = lines.get(n) else {
+                    continue;
+                };
+                if *start > used {
+                    text.push_str(&" ".repeat(start - used));
+                    used = *start;
+                }
+                text.push_str(&line.text);
+                used += display_width(&line.text);
+                bold &= line.bold;
+            }
+            out.push(TextLine {
+                text: text.trim_end().to_string(),
+                bold,
+            });
+        }
+    }
+    Some(out)
+}
+
+/// 单个元素的各行，每行恰好占 span 列
+fn element_lines(
+    element: &Element,
+    content: Option<String>,
+    column_widths: &[f64],
+    data: &Value,
+    span: usize,
+) -> Vec<TextLine> {
+    match &element.data {
+        ElementData::Text(props) => {
+            let content = content.unwrap_or_default();
+            if content.is_empty() {
+                return Vec::new();
+            }
+            let bold = match &props.font_weight {
+                Some(FontWeight::String(s)) => s.eq_ignore_ascii_case("bold"),
+                Some(FontWeight::Number(n)) => *n >= 600,
+                None => false,
+            };
+            let text_align = props.text_align.as_deref().unwrap_or("left");
+            let mut lines: Vec<String> = if props.line_break == Some(0) {
+                content.lines().map(|line| truncate(line, span)).collect()
+            } else {
+                content.lines().flat_map(|line| wrap(line, span)).collect()
+            };
+            if let Some(max) = props.max_lines {
+                lines.truncate(max);
+            }
+            lines
+                .iter()
+                .map(|line| TextLine {
+                    text: tabbed(line, span, text_align),
+                    bold,
+                })
+                .collect()
+        }
+        ElementData::Table(props) => {
+            let total: f64 = column_widths.iter().sum::<f64>().max(1.0);
+            let mut widths: Vec<usize> = column_widths
+                .iter()
+                .map(|w| (w / total * span as f64).floor() as usize)
+                .collect();
+            if let Some(last) = widths.last_mut() {
+                *last += span - widths.iter().sum::<usize>().min(span);
+            }
+            let row_line = |cells: Vec<String>, bold: bool| {
+                let text = cells
+                    .iter()
+                    .zip(&props.columns)
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(i, ((cell, column), &width))| {
+                        // 相邻列之间留一个空格
+                        let width = if i + 1 < widths.len() { width.saturating_sub(1) } else { width };
+                        let cell = align(&truncate(cell, width), width, column.text_align.as_deref().unwrap_or("left"));
+                        if i + 1 < widths.len() { format!("{} ", cell) } else { cell }
+                    })
+                    .collect();
+                TextLine { text, bold }
+            };
+            let mut lines = Vec::new();
+            if props.show_head != Some(0) {
+                lines.push(row_line(props.columns.iter().map(|c| c.title.clone()).collect(), true));
+            }
+            for row in Interpolator::get_array_by_path(data, &props.data).into_iter().flatten() {
+                let cells = props
+                    .columns
+                    .iter()
+                    .map(|c| Interpolator::get_value_from_obj(row, &c.field))
+                    .collect();
+                lines.push(row_line(cells, false));
+            }
+            lines
+        }
+        ElementData::Line(_) => vec![TextLine {
+            text: "-".repeat(span),
+            bold: false,
+        }],
+        ElementData::Spacer(_) => {
+            // 留白高度不超过画布高度上限，异常的高度不会生成海量空行
+            let height = element.h.min(RenderLimits::global().max_canvas_pt);
+            let count = (height / LINE_HEIGHT_PT).round() as usize;
+            vec![
+                TextLine {
+                    text: " ".repeat(span),
+                    bold: false,
+                };
+                count
+            ]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// 字符占用的列数：打印机字库中全角字符 (中日韩文字、全角符号) 占两列
+pub fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// 文本占用的列数
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// 截断到 width 列以内
+fn truncate(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|&c| {
+            used += char_width(c);
+            used <= width
+        })
+        .collect()
+}
+
+/// 按列宽折行，优先在空格处断开
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut rest = text;
+    while display_width(rest) > width {
+        let head = truncate(rest, width.max(1));
+        let cut = match head.rfind(' ') {
+            Some(space) if space > 0 && head.len() < rest.len() => space,
+            // 列宽不足一个全角字符时至少输出一个字符
+            _ if head.is_empty() => rest.chars().next().map_or(rest.len(), char::len_utf8),
+            _ => head.len(),
+        };
+        lines.push(rest[..cut].trim_end().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    lines.push(rest.to_string());
+    lines
+}
+
+/// 对齐到 width 列；含制表符时制表符之前靠左、之后靠右 (同渲染器的默认制表位)
+fn tabbed(line: &str, width: usize, text_align: &str) -> String {
+    match line.split_once('\t') {
+        Some((left, right)) => {
+            let right = truncate(&right.replace('\t', " "), width);
+            let left = truncate(left, width - display_width(&right));
+            let gap = width - display_width(&left) - display_width(&right);
+            format!("{}{}{}", left, " ".repeat(gap), right)
+        }
+        None => align(line, width, text_align),
+    }
+}
+
+fn align(text: &str, width: usize, text_align: &str) -> String {
+    let gap = width.saturating_sub(display_width(text));
+    let left = match text_align {
+        "center" => gap / 2,
+        "right" | "decimal" => gap,
+        _ => 0,
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(gap - left))
+}
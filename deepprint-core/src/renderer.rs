@@ -0,0 +1,1664 @@
+use crate::assets::{self, ImageSource};
+use crate::colors;
+use crate::compiled::{column_widths, CompiledElement, CompiledGroup, CompiledTemplate, Interpolation, TemplateCache};
+use crate::diagnostics::{timed, RenderDiagnostics};
+use crate::error::RenderError;
+use crate::fonts;
+use crate::limits::RenderLimits;
+use crate::symbols::SymbolCache;
+use crate::deep_print_schema::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use skia_safe::{
+    textlayout::{
+        FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, TextAlign, TextStyle,
+        TypefaceFontProvider,
+    },
+    canvas::PointMode, color_filters, Canvas, Color, Color4f, ColorFilter, Data, FontMgr, Image,
+    Paint, PaintCap, PaintJoin, PaintStyle, PathEffect, Point, Rect,
+};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 渲染上下文，存储渲染过程中的中间状态
+struct RenderContext<'a> {
+    /// 原始数据
+    data: &'a Value,
+    /// 字体集合 (用于 textlayout)
+    font_collection: FontCollection,
+    /// 字体管理器 (用于查找系统字体)
+    font_mgr: FontMgr,
+    /// 已计算的元素布局 {id: (y, height)}，height 含 margin.bottom
+    layout_cache: HashMap<String, (f64, f64)>,
+    /// 渲染选项
+    options: &'a RenderOptions,
+    /// 调试模式下收集的诊断信息
+    diagnostics: Option<&'a RefCell<RenderDiagnostics>>,
+}
+
+impl RenderContext<'_> {
+    /// 设置字体族 (family 为空时使用默认字体)，并追加内置后备字体
+    fn set_font_families(&self, style: &mut TextStyle, family: Option<&str>) {
+        style.set_font_families(&fonts::families(family));
+    }
+
+    /// 解析颜色字符串，并按渲染选项做输出前的颜色变换；
+    /// 无法识别的颜色按黑色绘制，调试模式下记录到诊断信息
+    fn color(&self, value: &str) -> Color {
+        let color = try_parse_color(value).unwrap_or_else(|| {
+            if let Some(diagnostics) = self.diagnostics {
+                diagnostics.borrow_mut().invalid_color(value.to_string());
+            }
+            Color::BLACK
+        });
+        self.map_color(color)
+    }
+
+    /// 对已有颜色做输出前的颜色变换 (如灰度化)
+    fn map_color(&self, color: Color) -> Color {
+        match &self.options.grayscale {
+            Some(weights) => weights.apply(color),
+            None => color,
+        }
+    }
+
+    /// 插值渲染，数据中的控制字符已去除；调试模式下记录数据中缺失的变量
+    fn interpolate(&self, content: Option<&Interpolation>) -> String {
+        let Some(content) = content else {
+            return String::new();
+        };
+        self.record_missing(content);
+        content.render(self.data)
+    }
+
+    /// 同 interpolate，但保留数据中的控制字符：二维码只以模块图形输出，
+    /// GS1 等格式需要 GS (0x1D) 作为字段分隔符
+    fn interpolate_symbol(&self, content: Option<&Interpolation>) -> String {
+        let Some(content) = content else {
+            return String::new();
+        };
+        self.record_missing(content);
+        content.render_raw(self.data)
+    }
+
+    fn record_missing(&self, content: &Interpolation) {
+        if let Some(diagnostics) = self.diagnostics {
+            let mut diagnostics = diagnostics.borrow_mut();
+            for field in content.missing_fields(self.data) {
+                diagnostics.unresolved(field.to_string());
+            }
+        }
+    }
+
+    /// 段落排版；调试模式下累计排版耗时
+    fn layout(&self, paragraph: &mut Paragraph, width: f32) {
+        match self.diagnostics {
+            Some(diagnostics) => {
+                let (_, ms) = timed(|| paragraph.layout(width));
+                diagnostics.borrow_mut().timings.layout_ms += ms;
+            }
+            None => paragraph.layout(width),
+        }
+    }
+
+    fn overflow(&self, element_id: &str, message: String) {
+        if let Some(diagnostics) = self.diagnostics {
+            diagnostics.borrow_mut().overflow(element_id, message);
+        }
+    }
+}
+
+/// 渲染选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RenderOptions {
+    /// 灰度模式：所有颜色在输出前按亮度权重转换为灰度，
+    /// 避免彩色模板在黑白激光打印机上产生意外的半色调网点
+    pub grayscale: Option<LumaWeights>,
+    /// 取消令牌 (不序列化)
+    #[serde(skip)]
+    pub cancel: Option<CancelToken>,
+}
+
+impl RenderOptions {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+}
+
+/// 渲染取消令牌：任务被取消或客户端断开时置位，渲染在元素之间和表格行之间检查，
+/// 超大表格、慢速图片等不会长时间占住渲染线程
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// 返回一个守卫，守卫被丢弃时取消 (如 HTTP 客户端断开、请求 future 被丢弃)
+    pub fn drop_guard(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+/// 丢弃时取消关联的令牌
+pub struct CancelOnDrop(CancelToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// 灰度转换的亮度权重 (默认 ITU-R BT.601: 0.299, 0.587, 0.114)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LumaWeights {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Default for LumaWeights {
+    fn default() -> Self {
+        Self { r: 0.299, g: 0.587, b: 0.114 }
+    }
+}
+
+impl LumaWeights {
+    /// 将颜色转换为灰度，保留 alpha 通道
+    pub fn apply(&self, color: Color) -> Color {
+        let (r, g, b) = self.normalized();
+        let luma = color.r() as f32 * r + color.g() as f32 * g + color.b() as f32 * b;
+        let l = luma.round().clamp(0.0, 255.0) as u8;
+        Color::from_argb(color.a(), l, l, l)
+    }
+
+    /// 图片使用的灰度颜色滤镜
+    fn color_filter(&self) -> ColorFilter {
+        let (r, g, b) = self.normalized();
+        color_filters::matrix_row_major(
+            &[
+                r, g, b, 0.0, 0.0, //
+                r, g, b, 0.0, 0.0, //
+                r, g, b, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            None,
+        )
+    }
+
+    /// 权重归一化，防止配置的权重之和不为 1 时整体偏亮/偏暗
+    fn normalized(&self) -> (f32, f32, f32) {
+        let sum = self.r + self.g + self.b;
+        if sum > 0.0 {
+            (self.r / sum, self.g / sum, self.b / sum)
+        } else {
+            (1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0)
+        }
+    }
+}
+
+pub struct DeepPrintRenderer {
+    /// 自定义注册字体 (优先于系统字体查找，输出 PDF 时以子集形式嵌入)
+    fonts: TypefaceFontProvider,
+    /// 宿主注册的图片资源，键为 SHA-256 (小写十六进制)，模板资源池以 "sha256:..." 引用
+    assets: HashMap<String, Arc<Vec<u8>>>,
+}
+
+impl Default for DeepPrintRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeepPrintRenderer {
+    pub fn new() -> Self {
+        let mut fonts = TypefaceFontProvider::new();
+        for (family, data) in fonts::bundled() {
+            if let Some(typeface) = FontMgr::default().new_from_data(data, None) {
+                fonts.register_typeface(typeface, Some(family));
+            }
+        }
+        Self {
+            fonts,
+            assets: HashMap::new(),
+        }
+    }
+
+    /// 注册图片资源 (png/jpeg 等编码数据)，模板资源池中的 "sha256:{hash}" 引用到这里查找
+    pub fn register_asset(&mut self, hash: &str, data: Vec<u8>) {
+        self.assets.insert(hash.to_ascii_lowercase(), Arc::new(data));
+    }
+
+    /// 注册自定义字体文件数据 (ttf/otf)
+    /// alias: 可选的族名别名，模板中的 fontFamily 可直接引用该别名
+    pub fn register_font(&mut self, data: &[u8], alias: Option<&str>) -> Result<(), RenderError> {
+        let typeface = FontMgr::default()
+            .new_from_data(data, None)
+            .ok_or(RenderError::InvalidFont)?;
+        self.fonts.register_typeface(typeface, alias);
+        Ok(())
+    }
+
+    /// 注册目录下的所有字体文件，返回成功注册的数量
+    pub fn register_font_dir(&mut self, dir: &Path) -> usize {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => return 0,
+        };
+
+        let mut count = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_font = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| matches!(e.to_ascii_lowercase().as_str(), "ttf" | "otf" | "ttc"))
+                .unwrap_or(false);
+            if !is_font {
+                continue;
+            }
+            if let Ok(data) = fs::read(&path) {
+                if self.register_font(&data, None).is_ok() {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// 核心渲染入口
+    pub fn render(
+        &self,
+        canvas: &Canvas,
+        template: &DeepPrintTemplate,
+        data: &Value,
+    ) -> Result<(), RenderError> {
+        self.render_with_options(canvas, template, data, &RenderOptions::default())
+            .map(|_| ())
+    }
+
+    /// 带渲染选项的渲染入口
+    /// 返回内容实际占用的高度 (所有元素底边的最大值加上下边距)，用于高度自适应画布
+    pub fn render_with_options(
+        &self,
+        canvas: &Canvas,
+        template: &DeepPrintTemplate,
+        data: &Value,
+        options: &RenderOptions,
+    ) -> Result<f64, RenderError> {
+        let compiled = TemplateCache::global().get_or_compile(template)?;
+        self.render_compiled(canvas, &compiled, data, options)
+    }
+
+    /// 渲染已编译的模板，批量打印时每条数据复用同一份编译结果
+    pub fn render_compiled(
+        &self,
+        canvas: &Canvas,
+        compiled: &CompiledTemplate,
+        data: &Value,
+        options: &RenderOptions,
+    ) -> Result<f64, RenderError> {
+        self.render_inner(canvas, compiled, data, options, None)
+    }
+
+    /// 渲染并收集诊断信息 (排版/绘制耗时、溢出、未解析的变量)，用于调试模板
+    pub fn render_compiled_traced(
+        &self,
+        canvas: &Canvas,
+        compiled: &CompiledTemplate,
+        data: &Value,
+        options: &RenderOptions,
+        diagnostics: &mut RenderDiagnostics,
+    ) -> Result<f64, RenderError> {
+        let cell = RefCell::new(std::mem::take(diagnostics));
+        let layout_before = cell.borrow().timings.layout_ms;
+        let (result, total_ms) =
+            timed(|| self.render_inner(canvas, compiled, data, options, Some(&cell)));
+        *diagnostics = cell.into_inner();
+        diagnostics.element_count += compiled.pages()[0].template.canvas.elements.len();
+        let layout_ms = diagnostics.timings.layout_ms - layout_before;
+        diagnostics.timings.draw_ms += (total_ms - layout_ms).max(0.0);
+        result
+    }
+
+    fn render_inner(
+        &self,
+        canvas: &Canvas,
+        compiled: &CompiledTemplate,
+        data: &Value,
+        options: &RenderOptions,
+        diagnostics: Option<&RefCell<RenderDiagnostics>>,
+    ) -> Result<f64, RenderError> {
+        // 直接绘制到单个画布时，多页模板只渲染第一页 (逐页输出见 output::record_compiled_pages)
+        let compiled = &compiled.pages()[0];
+
+        // 初始化字体管理器和集合
+        let font_mgr = FontMgr::default();
+        let mut font_collection = FontCollection::new();
+        font_collection.set_asset_font_manager(Some(FontMgr::from(self.fonts.clone())));
+        font_collection.set_default_font_manager(font_mgr.clone(), None);
+
+        let mut ctx = RenderContext {
+            data,
+            font_collection,
+            font_mgr,
+            layout_cache: HashMap::new(),
+            options,
+            diagnostics,
+        };
+
+        // 元素坐标以内容区 (页边距 + 内边距) 左上角为原点
+        let insets = compiled.template.canvas.content_insets();
+        canvas.save();
+        canvas.translate((insets.left as f32, insets.top as f32));
+
+        let elements = &compiled.template.canvas.elements;
+        self.render_group(canvas, elements, &compiled.order, &compiled.elements, &mut ctx)?;
+        canvas.restore();
+        // 最后一个元素 (如超大表格) 渲染中途被取消
+        if options.is_cancelled() {
+            return Err(RenderError::Cancelled);
+        }
+        if diagnostics.is_some() {
+            check_canvas_bounds(compiled, &ctx);
+        }
+
+        let content_height = ctx
+            .layout_cache
+            .values()
+            .map(|(y, h)| y + h)
+            .fold(0.0, f64::max)
+            + insets.top
+            + insets.bottom;
+
+        Ok(content_height)
+    }
+
+    /// 按编译时的拓扑顺序 (处理 linkedTo 依赖) 逐个渲染元素
+    fn render_group(
+        &self,
+        canvas: &Canvas,
+        elements: &[Element],
+        order: &[usize],
+        compiled: &[CompiledElement],
+        ctx: &mut RenderContext,
+    ) -> Result<(), RenderError> {
+        for &i in order {
+            if ctx.options.is_cancelled() {
+                return Err(RenderError::Cancelled);
+            }
+            self.render_element(canvas, &elements[i], &compiled[i], ctx)?;
+        }
+        Ok(())
+    }
+
+    /// 渲染单个元素 (分发器)
+    fn render_element(
+        &self,
+        canvas: &Canvas,
+        element: &Element,
+        compiled: &CompiledElement,
+        ctx: &mut RenderContext,
+    ) -> Result<(), RenderError> {
+        // 计算 Y 坐标
+        let (actual_y, _) = self.calculate_y(element, ctx);
+
+        // maxHeight：超出部分裁掉
+        if let Some(max_height) = element.max_height {
+            canvas.save();
+            canvas.clip_rect(
+                Rect::new(f32::MIN / 2.0, actual_y as f32, f32::MAX / 2.0, (actual_y + max_height) as f32),
+                None,
+                None,
+            );
+        }
+        // 图形元素 (线、矩形、椭圆) 没有内容，边框与填充始终按元素框绘制，不受 padding 影响
+        let is_shape = matches!(
+            element.data,
+            ElementData::Line(_) | ElementData::Rect(_) | ElementData::Ellipse(_)
+        );
+        let drawn = match element.padding.filter(|_| !is_shape) {
+            // padding：内容在缩进后的元素框中绘制，占用高度加上上下内边距
+            Some(padding) => {
+                let padding = padding.sides();
+                let mut inner = element.clone();
+                inner.x += padding.left;
+                inner.w = (inner.w - padding.left - padding.right).max(0.0);
+                inner.h = (inner.h - padding.top - padding.bottom).max(0.0);
+                self.draw_element(canvas, &inner, compiled, actual_y + padding.top, ctx)
+                    .map(|h| h + padding.top + padding.bottom)
+            }
+            None => self.draw_element(canvas, element, compiled, actual_y, ctx),
+        };
+        if element.max_height.is_some() {
+            canvas.restore();
+        }
+        // margin.bottom：其后 linkedTo 的元素与本元素之间的距离
+        let actual_height = self.constrain_height(element, drawn?, ctx) + element.margin_sides().bottom;
+
+        // 更新布局缓存
+        ctx.layout_cache
+            .insert(element.id.clone(), (actual_y, actual_height));
+
+        Ok(())
+    }
+
+    /// 绘制元素，返回其实际高度，错误附带元素 ID
+    fn draw_element(
+        &self,
+        canvas: &Canvas,
+        element: &Element,
+        compiled: &CompiledElement,
+        y: f64,
+        ctx: &mut RenderContext,
+    ) -> Result<f64, RenderError> {
+        let height = match &element.data {
+            ElementData::Text(props) => {
+                self.draw_text(canvas, element, props, compiled, y, ctx)
+            }
+            ElementData::Table(props) => {
+                if let Some(rows) = Interpolator::get_array_by_path(ctx.data, &props.data) {
+                    RenderLimits::global().check_table_rows(&element.id, rows.len())?;
+                }
+                self.draw_table(canvas, element, props, compiled, y, ctx)
+            }
+            ElementData::Line(props) => self.draw_line(canvas, element, props, y, ctx),
+            ElementData::Rect(props) => self.draw_rect(canvas, element, props, y, ctx),
+            ElementData::Ellipse(props) => self.draw_ellipse(canvas, element, props, y, ctx),
+            ElementData::Signature(props) => self.draw_signature(canvas, element, props, y, ctx),
+            ElementData::Chart(props) => self.draw_chart(canvas, element, props, y, ctx),
+            ElementData::Checkbox(props) => {
+                self.draw_checkbox(canvas, element, props, compiled, y, ctx)
+            }
+            ElementData::Image(props) => {
+                self.draw_image(canvas, element, props, compiled, y, ctx)
+            }
+            ElementData::Barcode(_) => self.draw_barcode(canvas, element, compiled, y, ctx),
+            ElementData::Qrcode(props) => {
+                self.draw_qrcode(canvas, element, props, compiled, y, ctx)
+            }
+            ElementData::Spacer(_) => Ok(element.h),
+            // 编译时已展开为片段中的元素
+            ElementData::Include(_) => Ok(0.0),
+            ElementData::Switch(_) => return self.render_switch(canvas, element, compiled, y, ctx),
+        };
+        height.map_err(|message| RenderError::Element {
+            element_id: element.id.clone(),
+            message,
+        })
+    }
+
+    /// 按 minHeight/maxHeight 约束元素占用的高度，内容超出 maxHeight 时记录溢出
+    fn constrain_height(&self, element: &Element, height: f64, ctx: &RenderContext) -> f64 {
+        let mut height = height.max(element.min_height.unwrap_or(0.0));
+        if let Some(max_height) = element.max_height {
+            if height > max_height {
+                ctx.overflow(
+                    &element.id,
+                    format!("Content height {:.1}pt exceeds maxHeight {:.1}pt", height, max_height),
+                );
+                height = max_height;
+            }
+        }
+        height
+    }
+
+    /// 渲染 switch 元素中匹配的分支，返回分支内容的高度 (没有匹配的分支时为 0)
+    fn render_switch(
+        &self,
+        canvas: &Canvas,
+        base: &Element,
+        compiled: &CompiledElement,
+        y: f64,
+        ctx: &mut RenderContext,
+    ) -> Result<f64, RenderError> {
+        let Some(switch) = &compiled.switch else {
+            return Ok(0.0);
+        };
+        let value = ctx.interpolate(Some(&switch.value));
+        let Some(CompiledGroup { elements, order, compiled }) = switch.branch(value.trim()) else {
+            return Ok(0.0);
+        };
+
+        // 分支内的坐标相对于 switch 元素，linkedTo 只在分支内解析
+        let outer = std::mem::take(&mut ctx.layout_cache);
+        canvas.save();
+        canvas.translate((base.x as f32, y as f32));
+        let result = self.render_group(canvas, elements, order, compiled, ctx);
+        canvas.restore();
+        let branch = std::mem::replace(&mut ctx.layout_cache, outer);
+        result?;
+        Ok(branch.values().map(|(y, h)| y + h).fold(0.0, f64::max))
+    }
+
+    // -------------------------------------------------------------------------
+    // 组件绘制逻辑
+    // -------------------------------------------------------------------------
+
+    fn draw_text(
+        &self,
+        canvas: &Canvas,
+        base: &Element,
+        props: &TextProps,
+        compiled: &CompiledElement,
+        y: f64,
+        ctx: &RenderContext,
+    ) -> Result<f64, String> {
+        let content = ctx.interpolate(compiled.content.as_ref());
+        if content.is_empty() && props.auto_height.unwrap_or(true) {
+            return Ok(0.0);
+        }
+
+        // 样式配置 (编译时已合并全局样式)
+        let style = compiled
+            .text_style
+            .as_ref()
+            .ok_or_else(|| "Text element is not compiled".to_string())?;
+        let font_size = style.font_size;
+        let color = ctx.map_color(style.color);
+        let font_family = style.font_family.as_deref();
+
+        // 构建文本样式
+        let mut text_style = TextStyle::new();
+        text_style.set_font_size(font_size as f32);
+        // FIXED: 使用 set_foreground_paint 替代 set_foreground_color，并将 Color 转换为 Color4f
+        text_style.set_foreground_paint(&Paint::new(Color4f::from(color), None));
+        
+        ctx.set_font_families(&mut text_style, font_family);
+
+        // 处理 Font Weight (简单映射)
+        // 注意: skia-safe 的 api 可能会变动，这里做最基础的处理
+        if let Some(weight) = &props.font_weight {
+             match weight {
+                 FontWeight::String(s) if s.eq_ignore_ascii_case("bold") => {
+                     // text_style.set_font_style(...) // 实际设置需配合 FontMgr
+                 },
+                 _ => {}
+             }
+        }
+
+        // 构建段落样式
+        let mut para_style = ParagraphStyle::new();
+        if let Some(align) = &props.text_align {
+            para_style.set_text_align(match align.as_str() {
+                "center" => TextAlign::Center,
+                "right" => TextAlign::Right,
+                _ => TextAlign::Left,
+            });
+        }
+        if let Some(max_lines) = props.max_lines.filter(|&n| n > 0) {
+            para_style.set_max_lines(max_lines);
+            para_style.set_ellipsis(props.ellipsis.as_deref().unwrap_or("…"));
+        }
+
+        // 生成段落并布局；含制表符的文本逐行按制表位排版
+        let layout = if content.contains('\t') {
+            TextLayout::Tabbed(self.layout_tabbed(&content, base, props, &text_style, ctx))
+        } else if props.text_align.as_deref() == Some("decimal") {
+            TextLayout::Tabbed(self.layout_decimal(&content, base, props, &text_style, ctx))
+        } else {
+            let mut builder = ParagraphBuilder::new(&para_style, &ctx.font_collection);
+            builder.push_style(&text_style);
+            builder.add_text(&content);
+            let mut paragraph = builder.build();
+            ctx.layout(&mut paragraph, base.w as f32);
+            TextLayout::Paragraph(paragraph)
+        };
+        let text_height = layout.height() as f64;
+        if matches!(&layout, TextLayout::Paragraph(p) if p.did_exceed_max_lines()) {
+            ctx.overflow(
+                &base.id,
+                format!("Text truncated to {} lines", props.max_lines.unwrap_or_default()),
+            );
+        }
+        if !props.auto_height.unwrap_or(true) && text_height > base.h {
+            ctx.overflow(
+                &base.id,
+                format!("Text height {:.1}pt exceeds box height {:.1}pt", text_height, base.h),
+            );
+        }
+
+        // 计算绘制位置 (垂直对齐)
+        let draw_y = if !props.auto_height.unwrap_or(true) && base.h > text_height {
+            match props.vertical_align.as_deref() {
+                Some("middle") => y + (base.h - text_height) / 2.0,
+                Some("bottom") => y + (base.h - text_height),
+                _ => y,
+            }
+        } else {
+            y
+        };
+
+        layout.paint(canvas, Point::new(base.x as f32, draw_y as f32));
+
+        if props.auto_height.unwrap_or(true) {
+            Ok(text_height)
+        } else {
+            Ok(base.h)
+        }
+    }
+
+    /// 含制表符的文本：按换行符分行 (不自动换行)，每行按制表符分段，
+    /// 第 n 段对齐到第 n 个制表位，前导符填充段间空白；超过 maxLines 的行不显示
+    fn layout_tabbed(
+        &self,
+        content: &str,
+        base: &Element,
+        props: &TextProps,
+        text_style: &TextStyle,
+        ctx: &RenderContext,
+    ) -> TabbedText {
+        let default_stops = [TabStop {
+            position: base.w,
+            align: Some("right".to_string()),
+            leader: None,
+        }];
+        let stops = props
+            .tab_stops
+            .as_deref()
+            .filter(|stops| !stops.is_empty())
+            .unwrap_or(&default_stops);
+        let segment = |text: &str| self.layout_line(text, text_style, ctx);
+
+        let max_lines = props.max_lines.filter(|&n| n > 0).unwrap_or(usize::MAX);
+        let mut lines = Vec::new();
+        for line in content.split('\n').take(max_lines) {
+            let mut segments = Vec::new();
+            let mut height = 0.0_f32;
+            let mut cursor = 0.0_f32;
+            for (i, text) in line.split('\t').enumerate() {
+                let (paragraph, width) = segment(text);
+                // 第一段从左边缘开始，多于制表位的段紧接上一段
+                let x = match i.checked_sub(1).and_then(|n| stops.get(n)) {
+                    None if i == 0 => 0.0,
+                    None => cursor,
+                    Some(stop) => {
+                        let position = stop.position as f32;
+                        let x = match stop.align.as_deref() {
+                            Some("right") => position - width,
+                            _ => position,
+                        }
+                        .max(cursor);
+                        if let Some(leader) = stop.leader.as_deref().filter(|l| !l.is_empty()) {
+                            let (_, leader_width) = segment(leader);
+                            let count = if leader_width > 0.0 { ((x - cursor) / leader_width) as usize } else { 0 };
+                            if count > 0 {
+                                let (fill, fill_width) = segment(&leader.repeat(count));
+                                segments.push((x - fill_width, fill));
+                            }
+                        }
+                        x
+                    }
+                };
+                height = height.max(paragraph.height());
+                segments.push((x, paragraph));
+                cursor = x + width;
+            }
+            lines.push(TabLine { height, segments });
+        }
+        TabbedText { lines }
+    }
+
+    /// textAlign 为 "decimal" 的文本：按换行符分行，各行的小数点对齐，
+    /// 小数部分最宽的一行靠右边缘；没有小数点的行以末尾对齐小数点位置
+    fn layout_decimal(
+        &self,
+        content: &str,
+        base: &Element,
+        props: &TextProps,
+        text_style: &TextStyle,
+        ctx: &RenderContext,
+    ) -> TabbedText {
+        let max_lines = props.max_lines.filter(|&n| n > 0).unwrap_or(usize::MAX);
+        let measured: Vec<_> = content
+            .split('\n')
+            .take(max_lines)
+            .map(|line| {
+                let (paragraph, width) = self.layout_line(line, text_style, ctx);
+                let (_, fraction) = self.layout_line(decimal_fraction(line), text_style, ctx);
+                (paragraph, width, fraction)
+            })
+            .collect();
+        let max_fraction = measured.iter().map(|(_, _, fraction)| *fraction).fold(0.0, f32::max);
+        let lines = measured
+            .into_iter()
+            .map(|(paragraph, width, fraction)| TabLine {
+                height: paragraph.height(),
+                segments: vec![(base.w as f32 - max_fraction + fraction - width, paragraph)],
+            })
+            .collect();
+        TabbedText { lines }
+    }
+
+    /// 不换行地排版单行文字，返回段落与其宽度
+    fn layout_line(&self, text: &str, text_style: &TextStyle, ctx: &RenderContext) -> (Paragraph, f32) {
+        let mut builder = ParagraphBuilder::new(&ParagraphStyle::new(), &ctx.font_collection);
+        builder.push_style(text_style);
+        builder.add_text(text);
+        let mut paragraph = builder.build();
+        ctx.layout(&mut paragraph, f32::MAX);
+        let width = paragraph.max_intrinsic_width();
+        (paragraph, width)
+    }
+
+    fn draw_table(
+        &self,
+        canvas: &Canvas,
+        base: &Element,
+        props: &TableProps,
+        compiled: &CompiledElement,
+        start_y: f64,
+        ctx: &RenderContext,
+    ) -> Result<f64, String> {
+        let mut current_y = start_y;
+        
+        // 边框画笔
+        let mut border_paint = Paint::default();
+        border_paint.set_style(PaintStyle::Stroke);
+        border_paint.set_stroke_width(props.border_width.unwrap_or(2.83) as f32);
+        border_paint.set_color(ctx.color(props.border_color.as_deref().unwrap_or("#000000")));
+
+        let rows = Interpolator::get_array_by_path(ctx.data, &props.data);
+        if rows.is_none() {
+            if let Some(diagnostics) = ctx.diagnostics {
+                diagnostics.borrow_mut().unresolved(Interpolator::trim_path(&props.data).to_string());
+            }
+        }
+        let rows_data = rows.map(|v| v.as_slice()).unwrap_or(&[]);
+        let cell_padding = props.cell_padding.unwrap_or(5.0);
+
+        // 列宽在编译时按元素宽度计算；动态列在渲染时生成，有 padding 时内容宽度变窄，均需重新计算列宽
+        let columns = self.table_columns(props, ctx);
+        let dynamic_widths;
+        let col_widths = if props.columns_from.is_some() || base.padding.is_some() {
+            dynamic_widths = column_widths(base.w, &columns);
+            &dynamic_widths
+        } else {
+            &compiled.column_widths
+        };
+        // textAlign 为 "decimal" 的列：记录该列最宽的小数部分，各单元格的小数点对齐到同一位置
+        let decimal_widths: Vec<Option<f32>> = columns
+            .iter()
+            .map(|col| {
+                (col.text_align.as_deref() == Some("decimal")).then(|| {
+                    rows_data
+                        .iter()
+                        .map(|row| {
+                            let text = Interpolator::get_value_from_obj(row, &col.field);
+                            self.measure_cell_width(decimal_fraction(&text), ctx)
+                        })
+                        .fold(0.0, f32::max)
+                })
+            })
+            .collect();
+
+        // 绘制表头
+        if props.show_head.unwrap_or(1) == 1 {
+            let mut x_cursor = base.x;
+            let mut max_h = 0.0;
+
+            // 预计算高度
+            for (i, col) in columns.iter().enumerate() {
+                let h = self.measure_simple_text(&col.title, col_widths[i], ctx, true);
+                if h > max_h { max_h = h; }
+            }
+            max_h += cell_padding * 2.0;
+
+            // 绘制
+            for (i, col) in columns.iter().enumerate() {
+                let w = col_widths[i];
+                let rect = Rect::from_xywh(x_cursor as f32, current_y as f32, w as f32, max_h as f32);
+                
+                // 只有当线宽大于0时才绘制边框
+                if border_paint.stroke_width() > 0.0 {
+                    canvas.draw_rect(rect, &border_paint);
+                }
+                
+                self.draw_cell_text(canvas, &col.title, rect, cell_padding, ctx, true, col.text_align.as_deref());
+                x_cursor += w;
+            }
+            current_y += max_h;
+        }
+
+        // 绘制数据行；取消时停止，由调用方在元素之间返回取消错误
+        for row in rows_data {
+            if ctx.options.is_cancelled() {
+                break;
+            }
+            let mut x_cursor = base.x;
+            let mut row_height = 0.0;
+            let mut cell_texts = Vec::new();
+
+            // 预计算行高
+            for (i, col) in columns.iter().enumerate() {
+                let text = Interpolator::get_value_from_obj(row, &col.field);
+                let h = self.measure_simple_text(&text, col_widths[i], ctx, false);
+                if h > row_height { row_height = h; }
+                cell_texts.push(text);
+            }
+            row_height += cell_padding * 2.0;
+
+            // 绘制
+            for (i, text) in cell_texts.iter().enumerate() {
+                let w = col_widths[i];
+                let rect = Rect::from_xywh(x_cursor as f32, current_y as f32, w as f32, row_height as f32);
+                
+                if border_paint.stroke_width() > 0.0 {
+                    canvas.draw_rect(rect, &border_paint);
+                }
+
+                match decimal_widths[i] {
+                    // 右对齐，并按小数部分的宽度差向左收缩
+                    Some(max_fraction) => {
+                        let fraction = self.measure_cell_width(decimal_fraction(text), ctx);
+                        let rect = Rect::new(rect.left(), rect.top(), rect.right() - (max_fraction - fraction), rect.bottom());
+                        self.draw_cell_text(canvas, text, rect, cell_padding, ctx, false, Some("right"));
+                    }
+                    None => self.draw_cell_text(canvas, text, rect, cell_padding, ctx, false, columns[i].text_align.as_deref()),
+                }
+                x_cursor += w;
+            }
+            current_y += row_height;
+        }
+
+        let table_height = current_y - start_y;
+        if props.auto_height == Some(false) && table_height > base.h {
+            ctx.overflow(
+                &base.id,
+                format!("Table height {:.1}pt exceeds box height {:.1}pt", table_height, base.h),
+            );
+        }
+        Ok(table_height)
+    }
+
+    /// 表格的列：columnsFrom 的数据数组每项生成一列，插入到静态列中
+    fn table_columns<'a>(&self, props: &'a TableProps, ctx: &RenderContext) -> Cow<'a, [TableColumn]> {
+        let Some(from) = &props.columns_from else {
+            return Cow::Borrowed(&props.columns);
+        };
+        let Some(entries) = Interpolator::get_array_by_path(ctx.data, &from.data) else {
+            if let Some(diagnostics) = ctx.diagnostics {
+                diagnostics.borrow_mut().unresolved(Interpolator::trim_path(&from.data).to_string());
+            }
+            return Cow::Borrowed(&props.columns);
+        };
+        let title_field = from.title_field.as_deref().unwrap_or("title");
+        let field_field = from.field_field.as_deref().unwrap_or("field");
+        let generated = entries.iter().filter_map(|entry| {
+            let (title, field) = match entry {
+                Value::Object(_) => (
+                    Interpolator::get_value_from_obj(entry, title_field),
+                    Interpolator::get_value_from_obj(entry, field_field),
+                ),
+                Value::String(key) => (key.clone(), key.clone()),
+                Value::Number(key) => (key.to_string(), key.to_string()),
+                _ => return None,
+            };
+            Some(TableColumn {
+                title,
+                field,
+                width: from.width.clone(),
+                text_align: from.text_align.clone(),
+            })
+        });
+        let mut columns = props.columns.clone();
+        let at = from.insert_at.unwrap_or(columns.len()).min(columns.len());
+        columns.splice(at..at, generated);
+        Cow::Owned(columns)
+    }
+
+    // 辅助: 简单文本测量 (用于表格)
+    fn measure_simple_text(&self, text: &str, width: f64, ctx: &RenderContext, _bold: bool) -> f64 {
+        let mut ts = TextStyle::new();
+        ts.set_font_size(10.0);
+        ctx.set_font_families(&mut ts, None);
+        let mut builder = ParagraphBuilder::new(&ParagraphStyle::new(), &ctx.font_collection);
+        builder.push_style(&ts);
+        builder.add_text(text);
+        let mut p = builder.build();
+        ctx.layout(&mut p, width as f32);
+        p.height() as f64
+    }
+
+    // 辅助: 单元格文字的单行宽度 (用于小数点对齐)
+    fn measure_cell_width(&self, text: &str, ctx: &RenderContext) -> f32 {
+        let mut ts = TextStyle::new();
+        ts.set_font_size(10.0);
+        ctx.set_font_families(&mut ts, None);
+        let (_, width) = self.layout_line(text, &ts, ctx);
+        width
+    }
+
+    // 辅助: 绘制单元格文字
+    fn draw_cell_text(&self, canvas: &Canvas, text: &str, rect: Rect, padding: f64, ctx: &RenderContext, _bold: bool, align: Option<&str>) {
+        let mut ts = TextStyle::new();
+        ts.set_font_size(10.0);
+        ctx.set_font_families(&mut ts, None);
+        // FIXED: 使用 set_foreground_paint 替代 set_foreground_color，并将 Color 转换为 Color4f
+        ts.set_foreground_paint(&Paint::new(Color4f::from(ctx.map_color(Color::BLACK)), None));
+
+        let mut ps = ParagraphStyle::new();
+        if let Some(a) = align {
+            ps.set_text_align(match a {
+                "center" => TextAlign::Center,
+                "right" | "decimal" => TextAlign::Right,
+                _ => TextAlign::Left,
+            });
+        }
+
+        let mut builder = ParagraphBuilder::new(&ps, &ctx.font_collection);
+        builder.push_style(&ts);
+        builder.add_text(text);
+        let mut p = builder.build();
+        
+        // 考虑 padding 后的可用宽度
+        let avail_w = (rect.width() - (padding * 2.0) as f32).max(0.0);
+        ctx.layout(&mut p, avail_w);
+
+        // 垂直居中
+        let text_h = p.height();
+        let y = rect.top() + (rect.height() - text_h) / 2.0;
+        
+        p.paint(canvas, Point::new(rect.left() + padding as f32, y));
+    }
+
+    fn draw_line(&self, canvas: &Canvas, base: &Element, props: &LineProps, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let mut p = Paint::default();
+        p.set_style(PaintStyle::Stroke);
+        p.set_stroke_width(props.stroke_width.unwrap_or(2.83) as f32);
+        p.set_color(ctx.color(props.stroke_color.as_deref().unwrap_or("#000000")));
+        
+        // 处理虚线
+        if let Some(dash) = &props.dash_array {
+            let intervals: Vec<f32> = dash.iter().map(|&x| x as f32).collect();
+            // FIXED: Use PathEffect::dash instead of skia_safe::path_effect::dash
+            p.set_path_effect(PathEffect::dash(&intervals, 0.0));
+        }
+
+        canvas.draw_line(
+            Point::new(base.x as f32, y as f32),
+            Point::new((base.x + base.w) as f32, (y + base.h) as f32),
+            &p
+        );
+        Ok(base.h)
+    }
+
+    fn draw_rect(&self, canvas: &Canvas, base: &Element, props: &RectProps, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+        
+        if let Some(fill) = &props.fill_color {
+            if !fill.is_empty() {
+                let mut p = Paint::default();
+                p.set_style(PaintStyle::Fill);
+                p.set_color(ctx.color(fill));
+                canvas.draw_rect(rect, &p);
+            }
+        }
+
+        let stroke_w = props.stroke_width.unwrap_or(2.83);
+        if stroke_w > 0.0 {
+            let mut p = Paint::default();
+            p.set_style(PaintStyle::Stroke);
+            p.set_stroke_width(stroke_w as f32);
+            p.set_color(ctx.color(props.stroke_color.as_deref().unwrap_or("#000000")));
+            
+            if let Some(dash) = &props.dash_array {
+                let intervals: Vec<f32> = dash.iter().map(|&x| x as f32).collect();
+                // FIXED: Use PathEffect::dash instead of skia_safe::path_effect::dash
+                p.set_path_effect(PathEffect::dash(&intervals, 0.0));
+            }
+            
+            canvas.draw_rect(rect, &p);
+        }
+        Ok(base.h)
+    }
+
+    fn draw_ellipse(&self, canvas: &Canvas, base: &Element, props: &EllipseProps, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+        let mut p = Paint::default();
+        p.set_style(PaintStyle::Stroke);
+        p.set_stroke_width(props.stroke_width.unwrap_or(2.83) as f32);
+        p.set_color(ctx.color(props.stroke_color.as_deref().unwrap_or("#000000")));
+        
+        if let Some(dash) = &props.dash_array {
+            let intervals: Vec<f32> = dash.iter().map(|&x| x as f32).collect();
+            // FIXED: Use PathEffect::dash instead of skia_safe::path_effect::dash
+            p.set_path_effect(PathEffect::dash(&intervals, 0.0));
+        }
+
+        canvas.draw_oval(rect, &p);
+        Ok(base.h)
+    }
+
+    fn draw_checkbox(&self, canvas: &Canvas, base: &Element, props: &CheckboxProps, compiled: &CompiledElement, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let size = props.size.unwrap_or_else(|| base.w.min(base.h)) as f32;
+        let stroke_color = props.stroke_color.as_deref().unwrap_or("#000000");
+        let rect = Rect::from_xywh(base.x as f32, y as f32, size, size);
+
+        let mut p = Paint::default();
+        p.set_anti_alias(true);
+        p.set_style(PaintStyle::Stroke);
+        p.set_stroke_width(props.stroke_width.unwrap_or(1.0) as f32);
+        p.set_color(ctx.color(stroke_color));
+        canvas.draw_rect(rect, &p);
+
+        if !is_checked(&ctx.interpolate(compiled.content.as_ref())) {
+            return Ok(base.h);
+        }
+        // 勾选标记按方框边长缩放，线宽约为边长的 1/8
+        p.set_stroke_width((size / 8.0).max(0.5));
+        p.set_stroke_cap(PaintCap::Round);
+        p.set_color(ctx.color(props.mark_color.as_deref().unwrap_or(stroke_color)));
+        let at = |fx: f32, fy: f32| Point::new(rect.left() + size * fx, rect.top() + size * fy);
+        match props.mark.as_deref() {
+            Some("cross") => {
+                canvas.draw_line(at(0.22, 0.22), at(0.78, 0.78), &p);
+                canvas.draw_line(at(0.78, 0.22), at(0.22, 0.78), &p);
+            }
+            _ => {
+                canvas.draw_line(at(0.2, 0.52), at(0.42, 0.74), &p);
+                canvas.draw_line(at(0.42, 0.74), at(0.8, 0.26), &p);
+            }
+        }
+        Ok(base.h)
+    }
+
+    /// 签名：base64 编码的图片按比例缩放到框内；笔画列表按所有点的外接矩形等比缩放并居中
+    fn draw_signature(&self, canvas: &Canvas, base: &Element, props: &SignatureProps, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+        let value = match Interpolator::get_by_path(ctx.data, &props.data) {
+            Some(value) if !value.is_null() => value,
+            _ => {
+                if let Some(diagnostics) = ctx.diagnostics {
+                    diagnostics.borrow_mut().unresolved(Interpolator::trim_path(&props.data).to_string());
+                }
+                return Ok(base.h);
+            }
+        };
+
+        if let Value::String(encoded) = value {
+            if encoded.trim().is_empty() {
+                return Ok(base.h);
+            }
+            let bytes = assets::decode(encoded)?;
+            let image = Image::from_encoded(Data::new_copy(&bytes))
+                .ok_or_else(|| "Unsupported or corrupt signature image".to_string())?;
+            let (iw, ih) = (image.width() as f32, image.height() as f32);
+            let scale = (rect.width() / iw).min(rect.height() / ih);
+            let dst = Rect::from_xywh(0.0, 0.0, iw * scale, ih * scale)
+                .with_offset(centered(rect, iw * scale, ih * scale));
+            let mut paint = Paint::default();
+            if let Some(weights) = &ctx.options.grayscale {
+                paint.set_color_filter(weights.color_filter());
+            }
+            canvas.draw_image_rect(&image, None, dst, &paint);
+            return Ok(base.h);
+        }
+
+        let strokes: Vec<Vec<Point>> = value
+            .as_array()
+            .ok_or_else(|| "Signature must be a base64 image or a list of strokes".to_string())?
+            .iter()
+            .map(|stroke| stroke.as_array().map(|points| points.iter().filter_map(signature_point).collect()))
+            .collect::<Option<_>>()
+            .ok_or_else(|| "Each signature stroke must be an array of points".to_string())?;
+        let mut points = strokes.iter().flatten();
+        let Some(first) = points.next() else {
+            return Ok(base.h);
+        };
+        let bounds = points.fold(Rect::new(first.x, first.y, first.x, first.y), |b, p| {
+            Rect::new(b.left.min(p.x), b.top.min(p.y), b.right.max(p.x), b.bottom.max(p.y))
+        });
+
+        // 留出半个线宽，避免笔画贴边被裁掉；单点或直线时按非零的边计算比例
+        let stroke_width = props.stroke_width.unwrap_or(1.5) as f32;
+        let area = rect.with_inset((stroke_width / 2.0, stroke_width / 2.0));
+        let scale = match (bounds.width() > 0.0, bounds.height() > 0.0) {
+            (true, true) => (area.width() / bounds.width()).min(area.height() / bounds.height()),
+            (true, false) => area.width() / bounds.width(),
+            (false, true) => area.height() / bounds.height(),
+            (false, false) => 1.0,
+        };
+        let origin = centered(area, bounds.width() * scale, bounds.height() * scale);
+        let map = |p: &Point| Point::new(origin.x + (p.x - bounds.left) * scale, origin.y + (p.y - bounds.top) * scale);
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(stroke_width);
+        paint.set_stroke_cap(PaintCap::Round);
+        paint.set_stroke_join(PaintJoin::Round);
+        paint.set_color(ctx.color(props.stroke_color.as_deref().unwrap_or("#000000")));
+        for stroke in &strokes {
+            let points: Vec<Point> = stroke.iter().map(map).collect();
+            match points.as_slice() {
+                [] => {}
+                [dot] => {
+                    canvas.draw_point(*dot, &paint);
+                }
+                _ => {
+                    canvas.draw_points(PointMode::Polygon, &points, &paint);
+                }
+            }
+        }
+        Ok(base.h)
+    }
+
+    /// 图表：柱状图/折线图的纵轴从 min 到 max，标签位于底部；饼图按数值占比分扇区
+    fn draw_chart(&self, canvas: &Canvas, base: &Element, props: &ChartProps, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let rows = Interpolator::get_array_by_path(ctx.data, &props.data);
+        if rows.is_none() {
+            if let Some(diagnostics) = ctx.diagnostics {
+                diagnostics.borrow_mut().unresolved(Interpolator::trim_path(&props.data).to_string());
+            }
+        }
+        let label_field = props.label_field.as_deref().unwrap_or("label");
+        let value_field = props.value_field.as_deref().unwrap_or("value");
+        let points: Vec<(String, f64)> = rows
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .map(|row| match row {
+                Value::Object(_) => (
+                    Interpolator::get_value_from_obj(row, label_field),
+                    chart_value(row.get(value_field)),
+                ),
+                _ => (String::new(), chart_value(Some(row))),
+            })
+            .collect();
+        if points.is_empty() {
+            return Ok(base.h);
+        }
+
+        let colors: Vec<Color> = match &props.colors {
+            Some(colors) if !colors.is_empty() => colors.iter().map(|c| ctx.color(c)).collect(),
+            _ => CHART_PALETTE.iter().map(|c| ctx.color(c)).collect(),
+        };
+        let color = |i: usize| colors[i % colors.len()];
+        let font_size = props.font_size.unwrap_or(8.0);
+        let show_labels = props.show_labels.unwrap_or(true);
+        let show_values = props.show_values.unwrap_or(false);
+        let stroke_width = props.stroke_width.unwrap_or(1.0) as f32;
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+
+        let mut fill = Paint::default();
+        fill.set_anti_alias(true);
+        fill.set_style(PaintStyle::Fill);
+        let mut stroke = Paint::default();
+        stroke.set_anti_alias(true);
+        stroke.set_style(PaintStyle::Stroke);
+        stroke.set_stroke_width(stroke_width);
+        stroke.set_color(ctx.map_color(Color::BLACK));
+
+        if props.kind.as_deref() == Some("pie") {
+            let total: f64 = points.iter().map(|(_, v)| v.max(0.0)).sum();
+            if total <= 0.0 {
+                return Ok(base.h);
+            }
+            let diameter = rect.width().min(rect.height());
+            let oval = Rect::from_xywh(0.0, 0.0, diameter, diameter).with_offset(centered(rect, diameter, diameter));
+            let radius = diameter / 2.0;
+            let mut start = -90.0_f32;
+            for (i, (label, value)) in points.iter().enumerate() {
+                if *value <= 0.0 {
+                    continue;
+                }
+                let sweep = (value / total * 360.0) as f32;
+                fill.set_color(color(i));
+                canvas.draw_arc(oval, start, sweep, true, &fill);
+                // 标签位于扇区中线上 0.65 倍半径处
+                let text = chart_text(label, *value, show_labels, show_values);
+                if !text.is_empty() {
+                    let mid = (start + sweep / 2.0).to_radians();
+                    let at = Point::new(
+                        oval.center_x() + radius * 0.65 * mid.cos(),
+                        oval.center_y() + radius * 0.65 * mid.sin(),
+                    );
+                    self.draw_chart_text(canvas, &text, at, font_size, ctx);
+                }
+                start += sweep;
+            }
+            return Ok(base.h);
+        }
+
+        // 柱状图/折线图：底部留出标签行
+        let label_h = if show_labels { (font_size * 1.5) as f32 } else { 0.0 };
+        let values_h = if show_values { (font_size * 1.5) as f32 } else { 0.0 };
+        let plot = Rect::new(rect.left(), rect.top() + values_h, rect.right(), rect.bottom() - label_h);
+        let data_min = points.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+        let data_max = points.iter().map(|(_, v)| *v).fold(f64::NEG_INFINITY, f64::max);
+        let min = props.min.unwrap_or(data_min.min(0.0));
+        let mut max = props.max.unwrap_or(data_max);
+        if max <= min {
+            max = min + 1.0;
+        }
+        let to_y = |v: f64| plot.bottom() - (((v.clamp(min, max) - min) / (max - min)) as f32) * plot.height();
+        let slot = plot.width() / points.len() as f32;
+        let center_x = |i: usize| plot.left() + slot * (i as f32 + 0.5);
+
+        if props.kind.as_deref() == Some("line") {
+            let line: Vec<Point> = points.iter().enumerate().map(|(i, (_, v))| Point::new(center_x(i), to_y(*v))).collect();
+            let mut line_paint = stroke.clone();
+            line_paint.set_color(color(0));
+            line_paint.set_stroke_join(PaintJoin::Round);
+            canvas.draw_points(PointMode::Polygon, &line, &line_paint);
+            fill.set_color(color(0));
+            for p in &line {
+                canvas.draw_circle(*p, stroke_width * 1.5, &fill);
+            }
+        } else {
+            let bar_w = slot * 0.7;
+            let zero = to_y(0.0_f64.clamp(min, max));
+            for (i, (_, v)) in points.iter().enumerate() {
+                let top = to_y(*v);
+                fill.set_color(color(i));
+                canvas.draw_rect(
+                    Rect::new(center_x(i) - bar_w / 2.0, top.min(zero), center_x(i) + bar_w / 2.0, top.max(zero)),
+                    &fill,
+                );
+            }
+        }
+
+        if props.show_axis.unwrap_or(true) {
+            canvas.draw_line(Point::new(plot.left(), plot.top()), Point::new(plot.left(), plot.bottom()), &stroke);
+            canvas.draw_line(Point::new(plot.left(), plot.bottom()), Point::new(plot.right(), plot.bottom()), &stroke);
+        }
+        for (i, (label, v)) in points.iter().enumerate() {
+            if show_values {
+                let at = Point::new(center_x(i), to_y(*v) - (font_size * 0.75) as f32);
+                self.draw_chart_text(canvas, &format_chart_value(*v), at, font_size, ctx);
+            }
+            if show_labels && !label.is_empty() {
+                let at = Point::new(center_x(i), plot.bottom() + label_h / 2.0);
+                self.draw_chart_text(canvas, label, at, font_size, ctx);
+            }
+        }
+        Ok(base.h)
+    }
+
+    /// 以 center 为中心绘制单行的图表文字
+    fn draw_chart_text(&self, canvas: &Canvas, text: &str, center: Point, font_size: f64, ctx: &RenderContext) {
+        let mut ts = TextStyle::new();
+        ts.set_font_size(font_size as f32);
+        ctx.set_font_families(&mut ts, None);
+        ts.set_foreground_paint(&Paint::new(Color4f::from(ctx.map_color(Color::BLACK)), None));
+        let mut builder = ParagraphBuilder::new(&ParagraphStyle::new(), &ctx.font_collection);
+        builder.push_style(&ts);
+        builder.add_text(text);
+        let mut p = builder.build();
+        ctx.layout(&mut p, f32::MAX);
+        let w = p.max_intrinsic_width();
+        p.paint(canvas, Point::new(center.x - w / 2.0, center.y - p.height() / 2.0));
+    }
+
+    fn draw_qrcode(&self, canvas: &Canvas, base: &Element, props: &QrcodeProps, compiled: &CompiledElement, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let content = ctx.interpolate_symbol(compiled.content.as_ref());
+        if content.is_empty() { return Ok(base.h); }
+
+        // 批量打印时同一内容反复出现，编码结果按内容缓存
+        let code = SymbolCache::global().qr(&content, props.correction_level.as_deref().unwrap_or("M"))?;
+
+        let modules_count = code.width;
+        if modules_count == 0 { return Ok(base.h); }
+
+        let render_size = props.size.unwrap_or_else(|| base.w.min(base.h));
+        let module_size = render_size / modules_count as f64;
+
+        let mut p = Paint::default();
+        p.set_color(ctx.map_color(Color::BLACK));
+        p.set_style(PaintStyle::Fill);
+        p.set_anti_alias(false);
+
+        for (i, &dark) in code.dark.iter().enumerate() {
+            if dark {
+                let row = i / modules_count;
+                let col = i % modules_count;
+                let rect = Rect::from_xywh(
+                    (base.x + col as f64 * module_size) as f32,
+                    (y + row as f64 * module_size) as f32,
+                    module_size as f32,
+                    module_size as f32
+                );
+                canvas.draw_rect(rect, &p);
+            }
+        }
+        Ok(base.h)
+    }
+
+    fn draw_barcode(&self, canvas: &Canvas, base: &Element, compiled: &CompiledElement, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let content = ctx.interpolate(compiled.content.as_ref());
+        // 占位符绘制
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+        let mut p = Paint::default();
+        p.set_style(PaintStyle::Stroke);
+        p.set_color(ctx.map_color(Color::BLACK));
+        canvas.draw_rect(rect, &p);
+
+        // 绘制文字标识
+        let text = format!("[Barcode: {}]", content);
+        let mut ts = TextStyle::new();
+        ts.set_font_size(10.0);
+        ctx.set_font_families(&mut ts, None);
+        // FIXED: 使用 set_foreground_paint 替代 set_foreground_color，并将 Color 转换为 Color4f
+        ts.set_foreground_paint(&Paint::new(Color4f::from(ctx.map_color(Color::BLACK)), None));
+        let mut builder = ParagraphBuilder::new(&ParagraphStyle::new(), &ctx.font_collection);
+        builder.push_style(&ts);
+        builder.add_text(&text);
+        let mut para = builder.build();
+        ctx.layout(&mut para, base.w as f32);
+        para.paint(canvas, Point::new(base.x as f32, y as f32 + (base.h as f32 - para.height())/2.0));
+
+        Ok(base.h)
+    }
+
+    /// 绘制资源池中的图片；无法加载的图片 (未下载的 URL、文件路径) 按 fallback 绘制占位框或留空
+    fn draw_image(&self, canvas: &Canvas, base: &Element, props: &ImageProps, compiled: &CompiledElement, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let bytes = match &compiled.image {
+            Some(ImageSource::Embedded(bytes)) => bytes.clone(),
+            Some(ImageSource::Registered(hash)) => self
+                .assets
+                .get(hash)
+                .cloned()
+                .ok_or_else(|| format!("Asset sha256:{} is not registered", hash))?,
+            None if props.fallback.as_deref() == Some("skip") => return Ok(base.h),
+            None => return self.draw_image_placeholder(canvas, base, y, ctx),
+        };
+        let image = Image::from_encoded(Data::new_copy(&bytes))
+            .ok_or_else(|| format!("Unsupported or corrupt image asset '{}'", props.src))?;
+
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+        let (iw, ih) = (image.width() as f32, image.height() as f32);
+        let dst = match props.object_fit.as_deref() {
+            Some("fill") => rect,
+            Some("cover") => {
+                let scale = (rect.width() / iw).max(rect.height() / ih);
+                Rect::from_xywh(0.0, 0.0, iw * scale, ih * scale).with_offset(centered(rect, iw * scale, ih * scale))
+            }
+            // 默认 contain：完整显示并居中
+            _ => {
+                let scale = (rect.width() / iw).min(rect.height() / ih);
+                Rect::from_xywh(0.0, 0.0, iw * scale, ih * scale).with_offset(centered(rect, iw * scale, ih * scale))
+            }
+        };
+
+        let mut paint = Paint::default();
+        if let Some(weights) = &ctx.options.grayscale {
+            paint.set_color_filter(weights.color_filter());
+        }
+        canvas.save();
+        canvas.clip_rect(rect, None, None);
+        canvas.draw_image_rect(&image, None, dst, &paint);
+        canvas.restore();
+        Ok(base.h)
+    }
+
+    fn draw_image_placeholder(&self, canvas: &Canvas, base: &Element, y: f64, ctx: &RenderContext) -> Result<f64, String> {
+        let rect = Rect::from_xywh(base.x as f32, y as f32, base.w as f32, base.h as f32);
+        let mut p = Paint::default();
+        p.set_color(ctx.map_color(Color::LIGHT_GRAY));
+        p.set_style(PaintStyle::Fill);
+        canvas.draw_rect(rect, &p);
+        
+        p.set_color(ctx.map_color(Color::RED));
+        p.set_style(PaintStyle::Stroke);
+        p.set_stroke_width(1.0);
+        canvas.draw_line(Point::new(rect.left(), rect.top()), Point::new(rect.right(), rect.bottom()), &p);
+        canvas.draw_line(Point::new(rect.right(), rect.top()), Point::new(rect.left(), rect.bottom()), &p);
+        Ok(base.h)
+    }
+
+    // -------------------------------------------------------------------------
+    // 逻辑计算
+    // -------------------------------------------------------------------------
+
+    fn calculate_y(&self, element: &Element, ctx: &RenderContext) -> (f64, f64) {
+        if let Some(target_id) = &element.linked_to {
+            if let Some((target_y, target_h)) = ctx.layout_cache.get(target_id) {
+                let prev_bottom = target_y + target_h;
+                return (prev_bottom + element.margin_sides().top + element.y, prev_bottom);
+            }
+        }
+        (element.y, 0.0)
+    }
+}
+
+/// 数字的小数部分 (含小数点)，用于小数点对齐；没有小数点时为空
+fn decimal_fraction(text: &str) -> &str {
+    text.rfind('.').map_or("", |i| &text[i..])
+}
+
+/// 已排版的文本
+enum TextLayout {
+    Paragraph(Paragraph),
+    Tabbed(TabbedText),
+}
+
+/// 按制表位排版的文本行
+struct TabbedText {
+    lines: Vec<TabLine>,
+}
+
+struct TabLine {
+    height: f32,
+    /// (相对元素左边缘的 x, 段落)
+    segments: Vec<(f32, Paragraph)>,
+}
+
+impl TextLayout {
+    fn height(&self) -> f32 {
+        match self {
+            TextLayout::Paragraph(paragraph) => paragraph.height(),
+            TextLayout::Tabbed(text) => text.lines.iter().map(|line| line.height).sum(),
+        }
+    }
+
+    fn paint(&self, canvas: &Canvas, origin: Point) {
+        match self {
+            TextLayout::Paragraph(paragraph) => paragraph.paint(canvas, origin),
+            TextLayout::Tabbed(text) => {
+                let mut y = origin.y;
+                for line in &text.lines {
+                    for (x, paragraph) in &line.segments {
+                        paragraph.paint(canvas, Point::new(origin.x + x, y));
+                    }
+                    y += line.height;
+                }
+            }
+        }
+    }
+}
+
+/// 图表默认配色：深浅不同的灰色，单色打印时仍可区分
+const CHART_PALETTE: [&str; 5] = ["#333333", "#888888", "#BBBBBB", "#555555", "#DDDDDD"];
+
+/// 图表数值：数字或可解析为数字的字符串，其他值按 0 处理
+fn chart_value(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(0.0),
+        Some(Value::String(s)) => s.trim().parse().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+fn format_chart_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn chart_text(label: &str, value: f64, show_labels: bool, show_values: bool) -> String {
+    match (show_labels && !label.is_empty(), show_values) {
+        (true, true) => format!("{} {}", label, format_chart_value(value)),
+        (true, false) => label.to_string(),
+        (false, true) => format_chart_value(value),
+        (false, false) => String::new(),
+    }
+}
+
+/// 签名笔画中的点：[x, y] 或 {"x": .., "y": ..}
+fn signature_point(value: &Value) -> Option<Point> {
+    let (x, y) = match value {
+        Value::Array(xy) => (xy.first()?, xy.get(1)?),
+        Value::Object(p) => (p.get("x")?, p.get("y")?),
+        _ => return None,
+    };
+    Some(Point::new(x.as_f64()? as f32, y.as_f64()? as f32))
+}
+
+/// 复选框的勾选状态：空值与常见的否定值为未勾选
+fn is_checked(value: &str) -> bool {
+    let value = value.trim();
+    !(value.is_empty()
+        || ["false", "0", "no", "off", "null"]
+            .iter()
+            .any(|no| value.eq_ignore_ascii_case(no)))
+}
+
+/// 尺寸为 w×h 的内容在 rect 中居中时的左上角
+fn centered(rect: Rect, w: f32, h: f32) -> Point {
+    Point::new(rect.left() + (rect.width() - w) / 2.0, rect.top() + (rect.height() - h) / 2.0)
+}
+
+/// 调试模式：检查元素是否超出画布的内容区 (扣除页边距与内边距，高度自适应画布只检查宽度)
+fn check_canvas_bounds(compiled: &CompiledTemplate, ctx: &RenderContext) {
+    let canvas = &compiled.template.canvas;
+    let auto_height = canvas.orientation == Some(3);
+    let (width, height) = (canvas.content_width(), canvas.content_height());
+    for element in &canvas.elements {
+        let Some(&(y, h)) = ctx.layout_cache.get(&element.id) else {
+            continue;
+        };
+        let h = h - element.margin_sides().bottom;
+        if element.x < 0.0 || y < 0.0 {
+            ctx.overflow(
+                &element.id,
+                format!("Top-left corner ({:.1}, {:.1})pt is outside the content area", element.x, y),
+            );
+        }
+        if element.x + element.w > width {
+            ctx.overflow(
+                &element.id,
+                format!("Right edge {:.1}pt exceeds content width {:.1}pt", element.x + element.w, width),
+            );
+        }
+        if !auto_height && y + h > height {
+            ctx.overflow(
+                &element.id,
+                format!("Bottom edge {:.1}pt exceeds content height {:.1}pt", y + h, height),
+            );
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 工具类
+// -----------------------------------------------------------------------------
+
+pub(crate) struct Interpolator;
+
+impl Interpolator {
+    /// 去掉路径两侧的 {{ }} 与空格
+    fn trim_path(raw_path: &str) -> &str {
+        raw_path.trim_matches(|c| c == '{' || c == '}' || c == ' ')
+    }
+
+    pub(crate) fn get_array_by_path<'a>(data: &'a Value, raw_path: &str) -> Option<&'a Vec<Value>> {
+        Self::get_by_path(data, raw_path)?.as_array()
+    }
+
+    fn get_by_path<'a>(data: &'a Value, raw_path: &str) -> Option<&'a Value> {
+        let path = Self::trim_path(raw_path);
+        let parts: Vec<&str> = path.split('.').collect();
+        let mut current = data;
+        for part in parts {
+            if let Some(v) = current.get(part) { current = v; } else { return None; }
+        }
+        Some(current)
+    }
+
+    /// 取字段的文本值，去除控制字符 (见 sanitize_text)
+    pub(crate) fn get_value_from_obj(data: &Value, key: &str) -> String {
+        let value = Self::get_raw_value_from_obj(data, key);
+        if let Cow::Owned(clean) = sanitize_text(&value) {
+            return clean;
+        }
+        value
+    }
+
+    /// 取字段的原始文本值 (保留控制字符)
+    pub(crate) fn get_raw_value_from_obj(data: &Value, key: &str) -> String {
+        data.get(key)
+            .and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                Value::Bool(b) => Some(b.to_string()),
+                _ => None
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// 去除数据中的控制字符 (保留换行与制表符)：ESC、GS、NUL 等进入文本排版会显示为方框，
+/// 经 ESC/POS 等原始指令后端时还可能被打印机当作指令执行 (如弹钱箱)
+pub fn sanitize_text(text: &str) -> Cow<'_, str> {
+    let is_unsafe = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if text.contains(is_unsafe) {
+        Cow::Owned(text.chars().filter(|&c| !is_unsafe(c)).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+pub(crate) fn parse_color(hex: &str) -> Color {
+    try_parse_color(hex).unwrap_or(Color::BLACK)
+}
+
+/// 解析颜色字符串，无法识别时返回 None (供模板校验使用)
+/// 支持 #RGB、#RGBA、#RRGGBB、#RRGGBBAA、rgb()/rgba()、cmyk() 与 CSS 颜色名
+pub fn try_parse_color(value: &str) -> Option<Color> {
+    match parse_cmyk(value) {
+        Some(cmyk) => Some(cmyk_to_rgb(cmyk)),
+        None => colors::parse_css_color(value),
+    }
+}
+
+/// 解析 "cmyk(c, m, y, k)" 格式，各分量取值 0-100 (可带 %)
+fn parse_cmyk(value: &str) -> Option<[f32; 4]> {
+    let inner = value
+        .trim()
+        .strip_prefix("cmyk(")?
+        .strip_suffix(')')?;
+    let parts = inner
+        .split(',')
+        .map(|p| p.trim().trim_end_matches('%').parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    match parts.as_slice() {
+        [c, m, y, k] => Some([*c, *m, *y, *k].map(|v| v.clamp(0.0, 100.0) / 100.0)),
+        _ => None,
+    }
+}
+
+/// CMYK 转 RGB
+/// Skia 的 PDF 后端只输出 DeviceRGB，因此 CMYK 颜色在绘制前按朴素公式转换
+fn cmyk_to_rgb([c, m, y, k]: [f32; 4]) -> Color {
+    let channel = |v: f32| (255.0 * (1.0 - v) * (1.0 - k)).round() as u8;
+    Color::from_rgb(channel(c), channel(m), channel(y))
+}
@@ -0,0 +1,312 @@
+//! 严格模式：找出模板 JSON 中未被协议识别的字段 (默认解析会静默忽略它们)，
+//! 如把 fontSize 写成 fontsize，并给出最接近的正确字段名。
+
+use crate::deep_print_schema::*;
+use crate::error::TemplateError;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// 元素的公共字段：Element 扁平化了各类型的属性，serde 不提供其字段列表，
+/// 因此以相同的字段与 serde 属性声明 (不含 data)，字段名由 serde 生成。
+/// From<Element> 中的解构不带 `..`，Element 增删字段时此处编译失败，提醒同步修改
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)] // 只用于读取字段名
+struct ElementFields {
+    id: String,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    linked_to: Option<String>,
+    copies: Option<u32>,
+    copy_gap: Option<f64>,
+    min_height: Option<f64>,
+    max_height: Option<f64>,
+    margin: Option<Insets>,
+    padding: Option<Insets>,
+}
+
+impl From<Element> for ElementFields {
+    fn from(element: Element) -> Self {
+        let Element {
+            id,
+            x,
+            y,
+            w,
+            h,
+            linked_to,
+            copies,
+            copy_gap,
+            min_height,
+            max_height,
+            margin,
+            padding,
+            data: _,
+        } = element;
+        Self {
+            id,
+            x,
+            y,
+            w,
+            h,
+            linked_to,
+            copies,
+            copy_gap,
+            min_height,
+            max_height,
+            margin,
+            padding,
+        }
+    }
+}
+
+/// 未识别的字段
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnknownField {
+    /// 字段的 JSON 路径，如 "canvas.elements[2].fontsize"
+    pub path: String,
+    pub field: String,
+    /// 拼写最接近的合法字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<&'static str>,
+}
+
+impl UnknownField {
+    pub fn message(&self) -> String {
+        match self.suggestion {
+            Some(suggestion) => format!("Unknown field '{}', did you mean '{}'?", self.field, suggestion),
+            None => format!("Unknown field '{}'", self.field),
+        }
+    }
+}
+
+/// 严格解析：存在未识别的字段时返回错误 (deny_unknown_fields 语义)
+pub fn parse_template_strict(json: &str) -> Result<DeepPrintTemplate, TemplateError> {
+    let mut raw: Value = serde_json::from_str(json).map_err(|e| TemplateError::Parse {
+        path: String::new(),
+        message: e.to_string(),
+    })?;
+    let template = crate::parse_template_value(raw.clone())?;
+    // 旧版本模板按迁移后的字段检查
+    crate::migration::migrate(&mut raw);
+    match unknown_fields(&raw).into_iter().next() {
+        Some(unknown) => Err(TemplateError::UnknownField {
+            message: unknown.message(),
+            path: unknown.path,
+        }),
+        None => Ok(template),
+    }
+}
+
+/// 找出模板 JSON (当前协议版本) 中所有未识别的字段
+/// dataSchema、assets、include 参数等自由格式的内容不检查
+pub fn unknown_fields(raw: &Value) -> Vec<UnknownField> {
+    let mut out = Vec::new();
+    let Value::Object(root) = raw else {
+        return out;
+    };
+    check(root, "", fields_of::<Current>(), &mut out);
+
+    if let Some(Value::Object(meta)) = root.get("meta") {
+        check(meta, "meta", fields_of::<Meta>(), &mut out);
+    }
+    if let Some(Value::Object(partials)) = root.get("partials") {
+        for (name, partial) in partials {
+            let Value::Object(partial) = partial else {
+                continue;
+            };
+            let path = format!("partials.{}", name);
+            check(partial, &path, fields_of::<Partial>(), &mut out);
+            check_elements(partial.get("elements"), &format!("{}.elements", path), &mut out);
+        }
+    }
+    if let Some(Value::Object(canvas)) = root.get("canvas") {
+        check(canvas, "canvas", fields_of::<Canvas>(), &mut out);
+        if let Some(Value::Object(styles)) = canvas.get("styles") {
+            check(styles, "canvas.styles", fields_of::<GlobalStyles>(), &mut out);
+        }
+        if let Some(Value::Object(cut)) = canvas.get("cut") {
+            check(cut, "canvas.cut", fields_of::<CutSettings>(), &mut out);
+        }
+        for key in ["margin", "padding"] {
+            if let Some(Value::Object(sides)) = canvas.get(key) {
+                check(sides, &format!("canvas.{}", key), fields_of::<InsetSides>(), &mut out);
+            }
+        }
+        check_elements(canvas.get("elements"), "canvas.elements", &mut out);
+        if let Some(Value::Array(pages)) = canvas.get("pages") {
+            for (i, page) in pages.iter().enumerate() {
+                if let Value::Object(page) = page {
+                    let path = format!("canvas.pages[{}]", i);
+                    check(page, &path, fields_of::<CanvasPage>(), &mut out);
+                    check_elements(page.get("elements"), &format!("{}.elements", path), &mut out);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// elements 为元素数组，array_path 为数组的 JSON 路径
+fn check_elements(elements: Option<&Value>, array_path: &str, out: &mut Vec<UnknownField>) {
+    let Some(Value::Array(elements)) = elements else {
+        return;
+    };
+    for (i, element) in elements.iter().enumerate() {
+        let Value::Object(element) = element else {
+            continue;
+        };
+        let path = format!("{}[{}]", array_path, i);
+        // 类型未知时只检查公共字段会误报，交给解析错误处理
+        let Some(props) = element.get("type").and_then(Value::as_str).and_then(props_fields) else {
+            continue;
+        };
+        // type 为元素类型标签
+        let allowed: Vec<&'static str> = fields_of::<ElementFields>()
+            .iter()
+            .chain(&["type"])
+            .chain(props)
+            .copied()
+            .collect();
+        check(element, &path, &allowed, out);
+
+        for key in ["margin", "padding"] {
+            if let Some(Value::Object(sides)) = element.get(key) {
+                check(sides, &format!("{}.{}", path, key), fields_of::<InsetSides>(), out);
+            }
+        }
+        if let Some(Value::Array(columns)) = element.get("columns") {
+            for (j, column) in columns.iter().enumerate() {
+                if let Value::Object(column) = column {
+                    let column_path = format!("{}.columns[{}]", path, j);
+                    check(column, &column_path, fields_of::<TableColumn>(), out);
+                }
+            }
+        }
+        if let Some(Value::Array(stops)) = element.get("tabStops") {
+            for (j, stop) in stops.iter().enumerate() {
+                if let Value::Object(stop) = stop {
+                    check(stop, &format!("{}.tabStops[{}]", path, j), fields_of::<TabStop>(), out);
+                }
+            }
+        }
+        if let Some(Value::Object(columns_from)) = element.get("columnsFrom") {
+            check(columns_from, &format!("{}.columnsFrom", path), fields_of::<ColumnsFrom>(), out);
+        }
+        if let Some(Value::Array(cases)) = element.get("cases") {
+            for (j, case) in cases.iter().enumerate() {
+                if let Value::Object(case) = case {
+                    let case_path = format!("{}.cases[{}]", path, j);
+                    check(case, &case_path, fields_of::<SwitchCase>(), out);
+                    check_elements(case.get("elements"), &format!("{}.elements", case_path), out);
+                }
+            }
+        }
+        if element.get("type").and_then(Value::as_str) == Some("switch") {
+            check_elements(element.get("default"), &format!("{}.default", path), out);
+        }
+    }
+}
+
+/// 各类元素特有的字段
+fn props_fields(element_type: &str) -> Option<&'static [&'static str]> {
+    Some(match element_type {
+        "text" => fields_of::<TextProps>(),
+        "table" => fields_of::<TableProps>(),
+        "image" => fields_of::<ImageProps>(),
+        "barcode" => fields_of::<BarcodeProps>(),
+        "qrcode" => fields_of::<QrcodeProps>(),
+        "line" => fields_of::<LineProps>(),
+        "rect" => fields_of::<RectProps>(),
+        "ellipse" => fields_of::<EllipseProps>(),
+        "checkbox" => fields_of::<CheckboxProps>(),
+        "signature" => fields_of::<SignatureProps>(),
+        "chart" => fields_of::<ChartProps>(),
+        "spacer" => fields_of::<SpacerProps>(),
+        "include" => fields_of::<IncludeProps>(),
+        "switch" => fields_of::<SwitchProps>(),
+        _ => return None,
+    })
+}
+
+fn check(map: &Map<String, Value>, path: &str, allowed: &[&'static str], out: &mut Vec<UnknownField>) {
+    for key in map.keys() {
+        if allowed.contains(&key.as_str()) {
+            continue;
+        }
+        out.push(UnknownField {
+            path: if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            },
+            field: key.clone(),
+            suggestion: suggest(key, allowed),
+        });
+    }
+}
+
+/// 最接近的合法字段：忽略大小写相同，或编辑距离不超过 2
+fn suggest(field: &str, allowed: &[&'static str]) -> Option<&'static str> {
+    let lower = field.to_ascii_lowercase();
+    allowed
+        .iter()
+        .map(|candidate| {
+            let distance = edit_distance(&lower, &candidate.to_ascii_lowercase());
+            (distance, *candidate)
+        })
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 读取派生 Deserialize 的结构体声明的字段名 (serde 通过 deserialize_struct 传入)，
+/// 字段列表因此始终与协议结构体一致
+fn fields_of<T: for<'de> Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields = None;
+    let _ = T::deserialize(FieldCapture(&mut fields));
+    fields.unwrap_or(&[])
+}
+
+struct FieldCapture<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for FieldCapture<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("fields captured"))
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
@@ -0,0 +1,115 @@
+use qrcode::{EcLevel, QrCode};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// 全局缓存保留的编码结果数
+const CACHE_CAPACITY: usize = 1024;
+/// 超过该长度的内容不缓存 (通常是一次性的长文本，缓存命中率低且占内存)
+const MAX_CACHED_CONTENT: usize = 1024;
+
+/// 二维码的模块矩阵，按行存储
+#[derive(Debug)]
+pub struct QrMatrix {
+    /// 每行/每列的模块数
+    pub width: usize,
+    /// 深色模块，下标为 row * width + col
+    pub dark: Vec<bool>,
+}
+
+/// 缓存键：内容、码制与纠错等级
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SymbolKey {
+    content: String,
+    /// 码制，如 "QR"
+    format: &'static str,
+    /// 纠错等级 "L" / "M" / "Q" / "H"
+    level: char,
+}
+
+/// 按内容缓存的二维码编码结果。批量打印标签时同一内容 (如公司网址) 会编码成千上万次，
+/// 编码比绘制更耗时，缓存后每次渲染只需绘制模块
+pub struct SymbolCache {
+    capacity: usize,
+    inner: Mutex<SymbolInner>,
+}
+
+#[derive(Default)]
+struct SymbolInner {
+    entries: HashMap<SymbolKey, Arc<QrMatrix>>,
+    /// 插入顺序，超出容量时淘汰最早的结果
+    order: VecDeque<SymbolKey>,
+}
+
+impl SymbolCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(SymbolInner::default()),
+        }
+    }
+
+    /// 进程内共享的缓存，渲染器默认使用
+    pub fn global() -> &'static SymbolCache {
+        static CACHE: OnceLock<SymbolCache> = OnceLock::new();
+        CACHE.get_or_init(|| SymbolCache::new(CACHE_CAPACITY))
+    }
+
+    /// 取出二维码矩阵，未命中时编码并缓存；level 为 "L" / "M" / "Q" / "H"，其他值按 "M"
+    pub fn qr(&self, content: &str, level: &str) -> Result<Arc<QrMatrix>, String> {
+        let (level, ec_level) = match level {
+            "L" => ('L', EcLevel::L),
+            "Q" => ('Q', EcLevel::Q),
+            "H" => ('H', EcLevel::H),
+            _ => ('M', EcLevel::M),
+        };
+        let key = SymbolKey {
+            content: content.to_string(),
+            format: "QR",
+            level,
+        };
+        if let Some(matrix) = self.inner.lock().unwrap().entries.get(&key) {
+            return Ok(matrix.clone());
+        }
+
+        let code = QrCode::with_error_correction_level(content.as_bytes(), ec_level)
+            .map_err(|e| format!("QR Error: {}", e))?;
+        let matrix = Arc::new(QrMatrix {
+            width: code.width(),
+            dark: code
+                .to_colors()
+                .into_iter()
+                .map(|color| matches!(color, qrcode::Color::Dark))
+                .collect(),
+        });
+        if content.len() <= MAX_CACHED_CONTENT {
+            self.insert(key, matrix.clone());
+        }
+        Ok(matrix)
+    }
+
+    fn insert(&self, key: SymbolKey, matrix: Arc<QrMatrix>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.insert(key.clone(), matrix).is_none() {
+            inner.order.push_back(key);
+        }
+        while inner.order.len() > self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.order.clear();
+    }
+}
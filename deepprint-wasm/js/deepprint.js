@@ -0,0 +1,86 @@
+// DeepPrint 浏览器端渲染器：封装 deepprint-wasm 导出的 C ABI
+//
+//   import createDeepPrintModule from './deepprint_wasm.js';
+//   import { DeepPrint } from './deepprint.js';
+//
+//   const deepprint = new DeepPrint(await createDeepPrintModule());
+//   deepprint.registerFont(await (await fetch('/fonts/NotoSansSC.otf')).arrayBuffer());
+//   const png = deepprint.renderPng(template, { orderNo: 'A001' }, { scale: 2 });
+//   img.src = URL.createObjectURL(new Blob([png], { type: 'image/png' }));
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+/** 渲染失败，code 与 Agent 返回的错误分类码一致 */
+export class DeepPrintError extends Error {
+  constructor({ code, message, elementId, path }) {
+    super(message);
+    this.name = 'DeepPrintError';
+    this.code = code;
+    this.elementId = elementId ?? null;
+    this.path = path ?? null;
+  }
+}
+
+export class DeepPrint {
+  constructor(module) {
+    this.module = module;
+  }
+
+  /** 注册字体 (ttf/otf)，alias 为模板 fontFamily 可引用的别名 */
+  registerFont(data, alias = '') {
+    this.#call(
+      (font, name) => this.module._deepprint_register_font(font.ptr, font.len, name.ptr, name.len),
+      new Uint8Array(data),
+      encoder.encode(alias),
+    );
+  }
+
+  /** 渲染为 PNG 字节；scale 为每 pt 对应的像素数 (Default: 1)，其余选项同 RenderOptions */
+  renderPng(template, data = {}, { scale = 1, ...options } = {}) {
+    return this.#call(
+      (t, d, o) => this.module._deepprint_render_png(t.ptr, t.len, d.ptr, d.len, o.ptr, o.len, scale),
+      ...this.#inputs(template, data, options),
+    );
+  }
+
+  /** 渲染为 PDF 字节 */
+  renderPdf(template, data = {}, options = {}) {
+    return this.#call(
+      (t, d, o) => this.module._deepprint_render_pdf(t.ptr, t.len, d.ptr, d.len, o.ptr, o.len),
+      ...this.#inputs(template, data, options),
+    );
+  }
+
+  #inputs(template, data, options) {
+    const json = (value) => encoder.encode(typeof value === 'string' ? value : JSON.stringify(value));
+    return [json(template), json(data), json(options)];
+  }
+
+  // 将输入复制到 wasm 内存，调用 f，取回结果并释放所有缓冲区
+  #call(f, ...inputs) {
+    const m = this.module;
+    const buffers = inputs.map((bytes) => {
+      const ptr = bytes.length ? m._deepprint_alloc(bytes.length) : 0;
+      if (ptr) m.HEAPU8.set(bytes, ptr);
+      return { ptr, len: bytes.length };
+    });
+    try {
+      const output = f(...buffers);
+      try {
+        const ptr = m._deepprint_output_ptr(output);
+        const bytes = m.HEAPU8.slice(ptr, ptr + m._deepprint_output_len(output));
+        if (!m._deepprint_output_ok(output)) {
+          throw new DeepPrintError(JSON.parse(decoder.decode(bytes)));
+        }
+        return bytes;
+      } finally {
+        m._deepprint_output_free(output);
+      }
+    } finally {
+      for (const { ptr, len } of buffers) {
+        if (ptr) m._deepprint_dealloc(ptr, len);
+      }
+    }
+  }
+}
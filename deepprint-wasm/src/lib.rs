@@ -0,0 +1,223 @@
+//! DeepPrint 渲染核心的 WebAssembly 构建，网页模板设计器用它在浏览器中预览，
+//! 与 Agent 打印输出使用同一份模板解析、排版与 Skia 绘制代码，结果逐像素一致。
+//!
+//! skia-safe 只支持 `wasm32-unknown-emscripten` 目标，因此这里导出 C ABI 函数，
+//! 由 emscripten 生成的胶水代码 (Module) 调用，JS 封装见 `js/deepprint.js`：
+//!
+//! 1. `deepprint_alloc` 在 wasm 内存中分配缓冲区，写入模板 JSON、数据 JSON 或字体文件；
+//! 2. 调用 `deepprint_render_png` / `deepprint_render_pdf` / `deepprint_register_font`，
+//!    返回 [`Output`] 指针；
+//! 3. `deepprint_output_ok` 为真时 `deepprint_output_ptr/len` 指向 PNG/PDF 字节，
+//!    否则指向错误 JSON `{"code", "message", "elementId", "path"}`，code 与 Agent 的错误分类码一致；
+//! 4. `deepprint_output_free` 释放结果，`deepprint_dealloc` 释放输入缓冲区。
+//!
+//! 浏览器中没有系统字体，预览前需用 `deepprint_register_font` 注册与打印机端相同的字体，
+//! 否则文字会回退到不同字形，与纸面不一致。
+
+use deepprint_core::output::{self, PdfOptions};
+use deepprint_core::{parse_template, DeepPrintRenderer, RenderError, RenderOptions};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+
+thread_local! {
+    /// 浏览器中单线程运行，注册的字体在多次渲染之间保留
+    static RENDERER: RefCell<DeepPrintRenderer> = RefCell::new(DeepPrintRenderer::new());
+}
+
+/// 调用结果：成功时为输出字节，失败时为错误 JSON
+pub struct Output {
+    ok: bool,
+    bytes: Vec<u8>,
+}
+
+impl Output {
+    fn into_raw(result: Result<Vec<u8>, Value>) -> *mut Output {
+        let output = match result {
+            Ok(bytes) => Output { ok: true, bytes },
+            Err(error) => Output {
+                ok: false,
+                bytes: error.to_string().into_bytes(),
+            },
+        };
+        Box::into_raw(Box::new(output))
+    }
+}
+
+fn error(code: &str, message: impl ToString) -> Value {
+    json!({ "code": code, "message": message.to_string() })
+}
+
+fn render_error(e: RenderError) -> Value {
+    json!({
+        "code": e.code(),
+        "message": e.to_string(),
+        "elementId": e.element_id(),
+        "path": e.path(),
+    })
+}
+
+/// 由 JS 传入的 (指针, 长度) 还原切片；长度为 0 时允许空指针
+unsafe fn slice<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// 解析模板、数据与渲染选项；options 为空时使用默认选项
+unsafe fn inputs(
+    template: (*const u8, usize),
+    data: (*const u8, usize),
+    options: (*const u8, usize),
+) -> Result<(deepprint_core::DeepPrintTemplate, Value, RenderOptions), Value> {
+    let template = std::str::from_utf8(slice(template.0, template.1))
+        .map_err(|e| error("template_parse", e))?;
+    let template = parse_template(template).map_err(|e| render_error(e.into()))?;
+
+    let data = slice(data.0, data.1);
+    let data = if data.is_empty() {
+        Value::Object(Default::default())
+    } else {
+        serde_json::from_slice(data).map_err(|e| error("invalid_data", e))?
+    };
+
+    let options = slice(options.0, options.1);
+    let options = if options.is_empty() {
+        RenderOptions::default()
+    } else {
+        serde_json::from_slice(options).map_err(|e| error("invalid_options", e))?
+    };
+    Ok((template, data, options))
+}
+
+/// 在 wasm 内存中分配 len 字节，供 JS 写入输入数据
+#[no_mangle]
+pub extern "C" fn deepprint_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// 释放 deepprint_alloc 分配的缓冲区
+///
+/// # Safety
+/// ptr 与 len 必须来自同一次 deepprint_alloc 调用
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_dealloc(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Vec::from_raw_parts(ptr, 0, len));
+    }
+}
+
+/// 注册字体 (ttf/otf)；alias 可为空，非空时模板中的 fontFamily 可直接引用该别名
+///
+/// # Safety
+/// 指针与长度必须指向有效的 wasm 内存
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_register_font(
+    data: *const u8,
+    data_len: usize,
+    alias: *const u8,
+    alias_len: usize,
+) -> *mut Output {
+    let data = slice(data, data_len);
+    let alias = std::str::from_utf8(slice(alias, alias_len))
+        .ok()
+        .filter(|a| !a.is_empty());
+    let result = RENDERER.with(|renderer| renderer.borrow_mut().register_font(data, alias));
+    Output::into_raw(result.map(|_| Vec::new()).map_err(render_error))
+}
+
+/// 渲染为 PNG；scale 为每 pt 对应的像素数 (dpi / 72)
+///
+/// # Safety
+/// 指针与长度必须指向有效的 wasm 内存
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_render_png(
+    template: *const u8,
+    template_len: usize,
+    data: *const u8,
+    data_len: usize,
+    options: *const u8,
+    options_len: usize,
+    scale: f32,
+) -> *mut Output {
+    let result = inputs((template, template_len), (data, data_len), (options, options_len))
+        .and_then(|(template, data, options)| {
+            RENDERER.with(|renderer| {
+                output::render_png(&renderer.borrow(), &template, &data, &options, scale)
+                    .map(|image| image.bytes)
+                    .map_err(render_error)
+            })
+        });
+    Output::into_raw(result)
+}
+
+/// 渲染为单页 PDF
+///
+/// # Safety
+/// 指针与长度必须指向有效的 wasm 内存
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_render_pdf(
+    template: *const u8,
+    template_len: usize,
+    data: *const u8,
+    data_len: usize,
+    options: *const u8,
+    options_len: usize,
+) -> *mut Output {
+    let result = inputs((template, template_len), (data, data_len), (options, options_len))
+        .and_then(|(template, data, options)| {
+            RENDERER.with(|renderer| {
+                output::render_pdf(
+                    &renderer.borrow(),
+                    &template,
+                    &data,
+                    &options,
+                    &PdfOptions::default(),
+                )
+                .map_err(render_error)
+            })
+        });
+    Output::into_raw(result)
+}
+
+/// 调用是否成功
+///
+/// # Safety
+/// output 必须是尚未释放的 Output 指针
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_output_ok(output: *const Output) -> bool {
+    (*output).ok
+}
+
+/// 输出字节 (或错误 JSON) 的起始地址
+///
+/// # Safety
+/// output 必须是尚未释放的 Output 指针
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_output_ptr(output: *const Output) -> *const u8 {
+    (*output).bytes.as_ptr()
+}
+
+/// 输出字节 (或错误 JSON) 的长度
+///
+/// # Safety
+/// output 必须是尚未释放的 Output 指针
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_output_len(output: *const Output) -> usize {
+    (*output).bytes.len()
+}
+
+/// 释放调用结果
+///
+/// # Safety
+/// output 必须来自本模块的渲染/注册函数，且只能释放一次
+#[no_mangle]
+pub unsafe extern "C" fn deepprint_output_free(output: *mut Output) {
+    if !output.is_null() {
+        drop(Box::from_raw(output));
+    }
+}
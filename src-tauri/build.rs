@@ -1,3 +1,18 @@
+use std::process::Command;
+
 fn main() {
+    // 构建时记录 git commit，供 /version 接口返回 (非 git 环境下为 "unknown")
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=DEEPPRINT_GIT_COMMIT={}", commit);
+
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/deepprint.proto").expect("failed to compile deepprint.proto");
+
     tauri_build::build()
 }
@@ -0,0 +1,102 @@
+use crate::api_error::ApiError;
+use crate::server::AppState;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use tracing::warn;
+
+/// 客户端白名单中的一项：单个 IP 或 CIDR 网段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_eq(u32::from(net) as u128, u32::from(ip) as u128, self.prefix, 32)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_eq(u128::from(net), u128::from(ip), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = String;
+
+    /// "192.168.1.0/24"、"10.0.0.5"、"fd00::/8"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid client address '{}', expected an IP or CIDR", s);
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|&p| p <= max).ok_or_else(invalid)?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+fn prefix_eq(a: u128, b: u128, prefix: u8, bits: u8) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = bits - prefix;
+    (a >> shift) == (b >> shift)
+}
+
+/// 解析白名单，忽略无法识别的条目 (启动时已告警)
+pub fn parse(entries: &[String]) -> Vec<IpNet> {
+    entries.iter().filter_map(|entry| entry.parse().ok()).collect()
+}
+
+/// 启动时检查白名单中无法识别的条目
+pub fn check(entries: &[String]) {
+    for entry in entries {
+        if let Err(e) = entry.parse::<IpNet>() {
+            warn!("客户端白名单条目已忽略: {}", e);
+        }
+    }
+}
+
+/// 客户端白名单中间件：配置了 allowedClients 时只接受本机与白名单内地址的请求，其余返回 403
+pub async fn restrict_clients(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+    if let Some(ip) = peer.filter(|ip| !ip.is_loopback()) {
+        let allowed = {
+            let config = state.config.read().unwrap();
+            let entries = &config.server.allowed_clients;
+            entries.is_empty() || parse(entries).iter().any(|net| net.contains(ip))
+        };
+        if !allowed {
+            warn!("拒绝白名单外的客户端请求: {} {}", ip, req.uri().path());
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "client_forbidden",
+                format!("Client {} is not allowed to access this agent", ip),
+            ));
+        }
+    }
+    Ok(next.run(req).await)
+}
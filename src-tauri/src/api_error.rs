@@ -0,0 +1,130 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// HTTP 接口统一错误
+/// 响应体固定为 { code, message, details }：code 为稳定的机器可读错误码，供客户端分支处理；
+/// message 为可读描述；details 为可选的结构化补充信息 (如模板校验报告、可用打印机列表)
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'a Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// 400：请求参数错误
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    /// 400：模板无法解析、校验未通过或渲染失败
+    pub fn invalid_template(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_template", message)
+    }
+
+    /// 404：资源不存在，code 区分资源类型 (如 "printer_not_found")
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    /// 503：打印队列已满，客户端应稍后重试
+    pub fn queue_full() -> Self {
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "queue_full",
+            "Print queue is full, please retry later",
+        )
+    }
+
+    /// 409：相同 task_id 的任务仍在排队或打印中
+    pub fn task_in_progress(task_id: &str) -> Self {
+        Self::new(
+            StatusCode::CONFLICT,
+            "task_in_progress",
+            format!("Task {} is already queued or printing", task_id),
+        )
+    }
+
+    /// 413：请求体超出大小限制
+    pub fn payload_too_large() -> Self {
+        Self::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            "Request body exceeds the size limit",
+        )
+    }
+
+    /// 500：服务内部错误 (存储、配置写入等)
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    /// 附加结构化详情，序列化失败时忽略
+    pub fn with_details(mut self, details: impl Serialize) -> Self {
+        self.details = serde_json::to_value(details).ok();
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code,
+            message: &self.message,
+            details: self.details.as_ref(),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// 请求体 JSON 无法解析 (语法错误、字段类型不符、缺少 Content-Type) 时同样返回统一错误体，
+/// code 为 "invalid_json"；超出大小限制时为 413
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            return Self::payload_too_large();
+        }
+        Self::new(rejection.status(), "invalid_json", rejection.body_text())
+    }
+}
+
+/// JSON 请求体提取器：同 axum 的 Json，解析失败时返回 ApiError 而不是纯文本，
+/// 所有接收 JSON 请求体的接口都应使用它
+pub struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(Self(value))
+    }
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+/// 审计日志条目：任务进入 spooled / printed / failed 时各追加一条，只增不改
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// 记录时间 (Unix 毫秒)
+    pub timestamp: u64,
+    pub task_id: String,
+    /// 任务类型 (content / template / pdf / image / raw / batch)
+    pub kind: String,
+    /// 提交任务时使用的 API Key 名称
+    pub api_key: Option<String>,
+    pub template_name: Option<String>,
+    pub printer: Option<String>,
+    pub page_count: Option<u32>,
+    /// 结果 (spooled / printed / failed)
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+/// 审计日志查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    /// 记录时间下限 (Unix 毫秒，含)
+    pub from: Option<u64>,
+    /// 记录时间上限 (Unix 毫秒，不含)
+    pub to: Option<u64>,
+    /// 只看某个 API Key 提交的任务
+    pub api_key: Option<String>,
+    /// 输出格式："json" (默认) 或 "csv"
+    pub format: Option<String>,
+}
+
+const CSV_HEADER: &str =
+    "timestamp,taskId,kind,apiKey,templateName,printer,pageCount,outcome,error";
+
+/// 导出为 CSV (RFC 4180，CRLF 换行)
+pub fn to_csv(entries: &[AuditEntry]) -> String {
+    let mut out = String::with_capacity(64 * (entries.len() + 1));
+    out.push_str(CSV_HEADER);
+    out.push_str("\r\n");
+    for entry in entries {
+        let fields = [
+            entry.timestamp.to_string(),
+            entry.task_id.clone(),
+            entry.kind.clone(),
+            entry.api_key.clone().unwrap_or_default(),
+            entry.template_name.clone().unwrap_or_default(),
+            entry.printer.clone().unwrap_or_default(),
+            entry.page_count.map(|n| n.to_string()).unwrap_or_default(),
+            entry.outcome.clone(),
+            entry.error.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// 含逗号、引号或换行的字段加引号，内部引号加倍
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 粗略统计 PDF 页数：计数 "/Type /Page" 对象 (排除 "/Type /Pages")
+/// 仅用于审计记录，无法识别时返回 None
+pub fn pdf_page_count(pdf: &[u8]) -> Option<u32> {
+    let mut count = 0;
+    let mut i = 0;
+    while let Some(pos) = find(&pdf[i..], b"/Type") {
+        let mut j = i + pos + b"/Type".len();
+        while pdf.get(j).is_some_and(|b| b.is_ascii_whitespace()) {
+            j += 1;
+        }
+        if pdf[j..].starts_with(b"/Page") && pdf.get(j + b"/Page".len()) != Some(&b's') {
+            count += 1;
+        }
+        i = j;
+    }
+    (count > 0).then_some(count)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
@@ -0,0 +1,163 @@
+use crate::api_error::ApiError;
+use crate::config::ApiKey;
+use crate::server::AppState;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::net::SocketAddr;
+
+/// 无需 API Key 即可访问的路径 (存活探测)
+const PUBLIC_PATHS: &[&str] = &["/", "/health", "/version"];
+/// 修改配置的接口前缀：其中的写操作 (PUT/POST/DELETE) 仅限 admin Key
+const CONFIG_PATHS: &[&str] = &["/settings", "/aliases", "/direct-printers", "/profiles", "/pools"];
+
+/// 当前请求的调用方权限，由鉴权中间件写入请求扩展
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    /// 调用方使用的 Key 名称，未配置任何 Key 时为空
+    pub key_name: Option<String>,
+    /// 允许使用的打印机/别名，为空表示不限
+    printers: Vec<String>,
+    /// 使用 admin Key 调用 (未配置任何 Key 时为 false)
+    admin: bool,
+}
+
+impl Access {
+    /// 不受限的调用方 (未启用 API Key，或来自 MQTT/云端等内部通道)
+    pub fn unrestricted() -> Self {
+        Self::default()
+    }
+
+    /// 判断是否允许使用某台打印机；names 为同一台打印机的各个名称 (别名、显示名、系统名)
+    pub fn allows_printer(&self, names: &[&str]) -> bool {
+        self.printers.is_empty()
+            || names
+                .iter()
+                .any(|name| self.printers.iter().any(|p| p.eq_ignore_ascii_case(name)))
+    }
+
+    /// 是否使用 admin Key 调用
+    pub fn is_admin(&self) -> bool {
+        self.admin
+    }
+
+    /// 打印机不在授权范围时返回 403
+    pub fn check_printer(&self, names: &[&str]) -> Result<(), ApiError> {
+        if self.allows_printer(names) {
+            return Ok(());
+        }
+        Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "printer_forbidden",
+            format!(
+                "API key '{}' is not allowed to print to '{}'",
+                self.key_name.as_deref().unwrap_or_default(),
+                names.first().copied().unwrap_or_default()
+            ),
+        ))
+    }
+}
+
+/// 鉴权中间件
+/// 未配置任何 API Key 时打印等接口保持开放 (兼容旧部署)，管理接口只接受本机请求
+/// (第一个 Key 只能在设置界面或本机创建)；
+/// 配置后除存活探测外的请求都需携带 "X-API-Key: {key}" 或 "Authorization: Bearer {key}"，
+/// 并受该 Key 的接口与打印机范围限制。/admin 下的接口与修改配置的接口仅限 admin Key
+pub async fn require_api_key(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let path = req.uri().path().to_string();
+    let admin_route = is_admin_route(req.method(), &path);
+    let access = {
+        let config = state.config.read().unwrap();
+        if PUBLIC_PATHS.contains(&path.as_str()) {
+            Access::unrestricted()
+        } else if config.api_keys.is_empty() {
+            if admin_route && !from_loopback(&req) {
+                return Err(ApiError::new(
+                    StatusCode::FORBIDDEN,
+                    "endpoint_forbidden",
+                    format!("{} is only available from this computer until an API key is configured", path),
+                ));
+            }
+            Access::unrestricted()
+        } else {
+            let presented = presented_key(&req).ok_or_else(|| {
+                ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "API key required")
+            })?;
+            let (name, key) = config
+                .api_keys
+                .iter()
+                .find(|(_, key)| constant_time_eq(key.key.as_bytes(), presented.as_bytes()))
+                .ok_or_else(|| {
+                    ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", "Invalid API key")
+                })?;
+            if !endpoint_allowed(key, &path, admin_route) {
+                return Err(ApiError::new(
+                    StatusCode::FORBIDDEN,
+                    "endpoint_forbidden",
+                    format!("API key '{}' is not allowed to access {}", name, path),
+                ));
+            }
+            Access {
+                key_name: Some(name.clone()),
+                printers: if key.admin { Vec::new() } else { key.printers.clone() },
+                admin: key.admin,
+            }
+        }
+    };
+    req.extensions_mut().insert(access);
+    Ok(next.run(req).await)
+}
+
+/// 读取请求携带的 Key
+fn presented_key(req: &Request) -> Option<String> {
+    let headers = req.headers();
+    if let Some(key) = headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|key| key.trim().to_string())
+}
+
+/// 请求是否来自本机
+fn from_loopback(req: &Request) -> bool {
+    req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .is_some_and(|ConnectInfo(addr)| addr.ip().to_canonical().is_loopback())
+}
+
+/// 仅限 admin Key 的接口：/admin 与 /dev 下的全部接口，以及修改配置 (别名、打印机、档案、池、默认打印机) 的写操作
+fn is_admin_route(method: &Method, path: &str) -> bool {
+    let under = |prefix: &str| path == prefix || path.starts_with(&format!("{}/", prefix));
+    under("/admin") || under("/dev") || (!matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) && CONFIG_PATHS.iter().any(|prefix| under(prefix)))
+}
+
+/// 接口范围："/print/template" 精确匹配，"/print/*" 匹配该前缀下的所有接口；
+/// admin Key 不受限，非 admin Key 不能访问管理接口
+fn endpoint_allowed(key: &ApiKey, path: &str, admin_route: bool) -> bool {
+    if key.admin {
+        return true;
+    }
+    if admin_route {
+        return false;
+    }
+    key.endpoints.is_empty()
+        || key.endpoints.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == pattern,
+        })
+}
+
+/// 定长比较，避免通过响应时间逐字节猜测 Key
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,131 @@
+use crate::config::CloudConfig;
+use crate::jobs::JobRecord;
+use crate::remote::{self, RemoteAck, RemoteTasks};
+use crate::server::AppState;
+use futures_util::{Sink, SinkExt, StreamExt};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+/// 重连退避区间
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+/// 心跳间隔，用于及时发现 NAT/代理静默断开的连接
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Agent 发往云端的消息
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum CloudMessage<'a> {
+    /// 连接建立后首先发送，标识 Agent
+    #[serde(rename_all = "camelCase")]
+    Hello {
+        agent_id: &'a str,
+        version: &'static str,
+    },
+    /// 打印指令受理结果
+    Ack(&'a RemoteAck),
+    /// 远程任务的状态变化
+    Status { job: &'a JobRecord },
+}
+
+/// 云端连接模式：Agent 主动建立到云端的 WebSocket 长连接 (带鉴权、自动重连)，
+/// 接收打印指令 (文本帧，与 POST /print/template 请求体相同) 并回传受理结果与任务状态。
+/// 适用于位于 NAT 之后、云端无法访问本地 HTTP 服务的门店
+pub async fn run(state: AppState, config: CloudConfig) {
+    let tracker = RemoteTasks::default();
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        match connect_and_serve(&state, &config, &tracker).await {
+            Ok(()) => {
+                warn!("云端连接已断开");
+                delay = MIN_RECONNECT_DELAY;
+            }
+            Err(e) => warn!("云端连接失败: {}", e),
+        }
+        info!("{} 秒后重连云端", delay.as_secs());
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// 建立连接并处理消息，连接正常关闭时返回 Ok
+async fn connect_and_serve(
+    state: &AppState,
+    config: &CloudConfig,
+    tracker: &RemoteTasks,
+) -> Result<(), String> {
+    let mut request = config
+        .url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| format!("Invalid cloud url: {}", e))?;
+    if let Some(token) = &config.token {
+        let value = HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|e| format!("Invalid token: {}", e))?;
+        request.headers_mut().insert("Authorization", value);
+    }
+
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    info!("已连接云端: {}", config.url);
+    let (mut sink, mut stream) = socket.split();
+
+    let hello = CloudMessage::Hello {
+        agent_id: &config.agent_id,
+        version: env!("CARGO_PKG_VERSION"),
+    };
+    send(&mut sink, &hello).await?;
+
+    let mut events = state.jobs.subscribe();
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let ack = remote::dispatch(state, tracker, text.as_bytes()).await;
+                    info!("云端打印指令: {:?} -> {}", ack.task_id, ack.message);
+                    send(&mut sink, &CloudMessage::Ack(&ack)).await?;
+                }
+                Some(Ok(Message::Binary(data))) => {
+                    let ack = remote::dispatch(state, tracker, &data).await;
+                    send(&mut sink, &CloudMessage::Ack(&ack)).await?;
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                // Ping/Pong 由 tungstenite 自动应答
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.to_string()),
+            },
+            event = events.recv() => match event {
+                Ok(record) => {
+                    if tracker.should_report(&record) {
+                        send(&mut sink, &CloudMessage::Status { job: &record }).await?;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("云端状态回传过慢，丢弃 {} 条事件", skipped);
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            _ = heartbeat.tick() => {
+                sink.send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+}
+
+async fn send<S>(sink: &mut S, message: &CloudMessage<'_>) -> Result<(), String>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    let text = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    sink.send(Message::Text(text)).await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,180 @@
+use crate::api_error::ApiJson;
+use crate::auth::Access;
+use crate::config::LogLevel;
+use crate::diagnostics;
+use crate::hot_reload::WatchRequest;
+use crate::jobs::JobRecord;
+use crate::logging::{self, LogEntry};
+use crate::server::{
+    self, ApiResponse, AppState, PagedPreviewRequest, PreviewRequest, PreviewResponse,
+    PrinterInfo, TemplatePrintRequest,
+};
+use crate::settings::{self, Settings};
+use crate::templates::TemplateSummary;
+use axum::extract::{Extension, Json, Path};
+use tauri::{AppHandle, Manager, Runtime, State, WebviewUrl, WebviewWindowBuilder};
+
+// 前端 (Tauri invoke) 调用的命令，与 HTTP 接口共用同一份状态；
+// 打印相关命令直接调用对应的接口处理函数，桌面端是本机可信调用方，不受 API Key 限制
+
+/// 打印机列表 (同 GET /printers)
+#[tauri::command]
+pub async fn list_printers(state: State<'_, AppState>) -> Result<Vec<PrinterInfo>, String> {
+    let Json(printers) = server::get_printers(axum::extract::State(state.inner().clone())).await;
+    Ok(printers)
+}
+
+/// 模板打印 (同 POST /print/template)
+#[tauri::command]
+pub async fn print_template(
+    state: State<'_, AppState>,
+    request: TemplatePrintRequest,
+) -> Result<ApiResponse, String> {
+    server::handle_print_template(
+        axum::extract::State(state.inner().clone()),
+        Extension(Access::unrestricted()),
+        ApiJson(request),
+    )
+    .await
+    .map(|(_, Json(response))| response)
+    .map_err(|e| e.message)
+}
+
+/// 渲染预览 PNG (同 POST /preview，始终返回 base64)
+#[tauri::command]
+pub async fn preview_template(
+    state: State<'_, AppState>,
+    mut request: PreviewRequest,
+) -> Result<PreviewResponse, String> {
+    server::prepare_preview(state.inner(), &mut request)
+        .await
+        .map_err(|e| e.message)?;
+    tauri::async_runtime::spawn_blocking(move || server::render_preview(&request))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|image| PreviewResponse::png(&image))
+        .map_err(|e| e.message)
+}
+
+/// 预览窗口：逐页渲染模板，每条数据记录一页
+#[tauri::command]
+pub async fn preview_pages(
+    state: State<'_, AppState>,
+    request: PagedPreviewRequest,
+) -> Result<Vec<PreviewResponse>, String> {
+    let pages = server::render_preview_pages(state.inner(), request)
+        .await
+        .map_err(|e| e.message)?;
+    Ok(pages.iter().map(PreviewResponse::png).collect())
+}
+
+/// 模板热重载：监视模板文件或已注册模板，重新渲染结果通过 preview:update 事件推送
+#[tauri::command]
+pub async fn watch_preview(
+    state: State<'_, AppState>,
+    request: WatchRequest,
+) -> Result<(), String> {
+    state.preview.start(state.inner().clone(), request)
+}
+
+/// 停止模板热重载
+#[tauri::command]
+pub fn unwatch_preview(state: State<'_, AppState>) {
+    state.preview.stop();
+}
+
+/// 已注册模板列表 (同 GET /templates)
+#[tauri::command]
+pub async fn list_templates(state: State<'_, AppState>) -> Result<Vec<TemplateSummary>, String> {
+    let Json(templates) = server::list_templates(axum::extract::State(state.inner().clone())).await;
+    Ok(templates)
+}
+
+/// 任务状态 (同 GET /jobs/{taskId})
+#[tauri::command]
+pub async fn get_job_status(
+    state: State<'_, AppState>,
+    task_id: String,
+) -> Result<JobRecord, String> {
+    server::get_job(axum::extract::State(state.inner().clone()), Path(task_id))
+        .await
+        .map(|Json(record)| record)
+        .map_err(|e| e.message)
+}
+
+/// HTTP 服务当前的访问地址 (未在监听时为 None)，之后的变化通过 server:started 事件推送
+#[tauri::command]
+pub fn get_server_address(state: State<'_, AppState>) -> Option<String> {
+    state.bound.borrow().map(|addr| format!("http://{}", addr))
+}
+
+/// 读取设置
+#[tauri::command]
+pub fn get_settings(state: State<'_, AppState>) -> Settings {
+    settings::get(&state)
+}
+
+/// 保存设置并立即生效，返回保存后的设置
+#[tauri::command]
+pub async fn update_settings(
+    state: State<'_, AppState>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    settings::update(&state, settings)
+}
+
+/// 打开预览窗口 (已打开时聚焦)
+#[tauri::command]
+pub async fn open_preview<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    open_window(&app, "preview", "DeepPrint Agent 打印预览", (960.0, 720.0), true)
+}
+
+/// 最近的日志 (默认 500 条)，level 指定时只返回该级别及更严重的日志
+#[tauri::command]
+pub fn get_logs(limit: Option<usize>, level: Option<LogLevel>) -> Vec<LogEntry> {
+    logging::recent(limit.unwrap_or(500), level)
+}
+
+/// 导出诊断包，返回 zip 文件路径
+#[tauri::command]
+pub async fn export_diagnostics(state: State<'_, AppState>) -> Result<String, String> {
+    diagnostics::export(&state)
+        .await
+        .map(|path| path.display().to_string())
+}
+
+/// 打开日志窗口 (已打开时聚焦)
+#[tauri::command]
+pub async fn open_logs<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    open_window(&app, "logs", "DeepPrint Agent 日志", (900.0, 600.0), true)
+}
+
+/// 打开设置窗口 (已打开时聚焦)
+#[tauri::command]
+pub async fn open_settings<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    open_window(&app, "settings", "DeepPrint Agent 设置", (480.0, 560.0), false)
+}
+
+/// 打开 (或聚焦) 辅助窗口，前端按 ?view={label} 渲染对应页面
+/// 移动端只有单个窗口，标题与尺寸不适用
+fn open_window<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+    title: &str,
+    size: (f64, f64),
+    resizable: bool,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(label) {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    let url = WebviewUrl::App(format!("index.html?view={}", label).into());
+    let builder = WebviewWindowBuilder::new(app, label, url);
+    #[cfg(desktop)]
+    let builder = builder
+        .title(title)
+        .inner_size(size.0, size.1)
+        .resizable(resizable);
+    #[cfg(mobile)]
+    let _ = (title, size, resizable);
+    builder.build().map(|_| ()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,684 @@
+use crate::deep_print_schema::CutMode;
+use crate::output::PageTransform;
+use crate::printing::direct::DirectTarget;
+use crate::printing::escpos_text::CodePage;
+use crate::printing::PrintOptions;
+use deepprint_core::RenderLimits;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// 应用标识，与 tauri.conf.json 的 identifier 保持一致，
+/// 使配置/数据目录与 Tauri 的 app_config_dir / app_data_dir 相同
+pub const APP_IDENTIFIER: &str = "com.deepprint.agent";
+
+/// 由 Tauri 提供的应用目录 (移动端没有通用的用户目录，需在启动时设置)
+static APP_DIRS: OnceLock<(PathBuf, PathBuf)> = OnceLock::new();
+
+/// Agent 配置，持久化为 {config_dir}/com.deepprint.agent/config.json
+/// 缺失的字段使用默认值，因此旧版本的配置文件可以直接读取
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AgentConfig {
+    /// HTTP 服务监听地址
+    pub server: ServerConfig,
+    /// 日志级别 (Default: info)
+    pub log_level: LogLevel,
+    /// 调试 PDF 等输出文件
+    pub output: OutputConfig,
+    /// 桌面界面
+    pub ui: UiConfig,
+    /// 打印队列
+    pub queue: QueueConfig,
+    /// HTTP 压缩与请求体大小限制
+    pub http: HttpConfig,
+    /// 渲染资源上限 (画布尺寸、像素数、元素数、表格行数)，超出时拒绝渲染
+    pub limits: RenderLimits,
+    /// HTTPS 服务
+    pub tls: TlsConfig,
+    /// 跨域白名单
+    pub cors: CorsConfig,
+    /// Agent 级默认打印机 (独立于系统默认打印机)
+    pub printers: PrinterDefaults,
+    /// 逻辑打印机别名，如 {"kitchen": {...}, "labels": {...}}
+    pub aliases: HashMap<String, PrinterAlias>,
+    /// 具名直连打印机 (网络 9100 端口、串口、IPP)，如 {"zebra-1": {"type": "socket", ...}}
+    pub direct_printers: HashMap<String, DirectTarget>,
+    /// 打印机池：多台等价打印机轮流出单，离线时自动切换，如 {"packing": {...}}
+    pub pools: HashMap<String, PrinterPool>,
+    /// 打印机档案，键为别名、直连打印机名或物理打印机名
+    pub profiles: HashMap<String, PrinterProfile>,
+    /// 具名 API Key，如 {"kiosk": {...}}；为空时不启用鉴权
+    pub api_keys: HashMap<String, ApiKey>,
+    /// 局域网服务发现
+    pub discovery: DiscoveryConfig,
+    /// gRPC 服务 (需以 grpc feature 编译)
+    pub grpc: GrpcConfig,
+    /// MQTT 云端派单
+    pub mqtt: MqttConfig,
+    /// 云端 WebSocket 长连接
+    pub cloud: CloudConfig,
+    /// 自动更新
+    pub updater: UpdaterConfig,
+    /// 崩溃报告
+    pub crash_reports: CrashReportConfig,
+    /// 远程图片下载 (代理)
+    pub remote_assets: RemoteAssetConfig,
+    /// 模板热重载的 HTTP 接口 (开发模式)
+    pub dev_preview: DevPreviewConfig,
+}
+
+/// 桌面界面配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UiConfig {
+    /// 启动时不显示主窗口，只显示托盘图标 (Default: false)；也可用 --tray 参数启动
+    pub start_in_tray: bool,
+}
+
+/// 模板热重载的 HTTP 接口配置 (开发模式)：/dev/preview 与 /dev/preview/watch 默认关闭，
+/// 开启后仍只允许 admin Key 调用，桌面端的热重载不受影响
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DevPreviewConfig {
+    /// 是否开放接口 (Default: false)
+    pub enabled: bool,
+    /// 允许监视的模板文件目录，path 必须位于该目录内；为空时只能监视已注册模板
+    pub templates_dir: Option<PathBuf>,
+}
+
+/// 自动更新配置 (桌面端)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UpdaterConfig {
+    /// 是否定期检查更新 (Default: false)；需先在 tauri.conf.json 中配置更新签名公钥 (pubkey)
+    pub enabled: bool,
+    /// 更新清单地址，为空时使用 tauri.conf.json 中的发布渠道
+    pub endpoints: Vec<String>,
+    /// 检查间隔 (小时) (Default: 6)
+    pub check_interval_hours: u64,
+    /// 发现新版本后自动下载安装并重启 (Default: false，需在托盘菜单中确认安装)
+    pub auto_install: bool,
+}
+
+impl Default for UpdaterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoints: Vec::new(),
+            check_interval_hours: 6,
+            auto_install: false,
+        }
+    }
+}
+
+/// 崩溃报告配置：进程 panic 时在 {data_dir}/crashes 写入报告 (调用栈、当前任务、配置摘要)，
+/// 配置了 endpoint 时在下次启动后上传
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CrashReportConfig {
+    /// 上传地址 (以 JSON POST)，为空时只保存在本地
+    pub endpoint: Option<String>,
+    /// 上传时携带的 Bearer 令牌
+    pub token: Option<String>,
+    /// 本地保留的报告数，0 表示不限制 (Default: 20)
+    pub max_reports: usize,
+}
+
+impl Default for CrashReportConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            token: None,
+            max_reports: 20,
+        }
+    }
+}
+
+/// 远程图片下载配置：门店网络通常要求所有出站流量经过代理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoteAssetConfig {
+    /// 代理方式 (Default: system)
+    pub proxy_mode: ProxyMode,
+    /// 代理地址 (proxyMode 为 manual 时)，如 "http://10.0.0.1:3128"
+    pub proxy: Option<String>,
+    /// 代理的 Basic 认证
+    pub proxy_username: Option<String>,
+    pub proxy_password: Option<String>,
+    /// 不经过代理的主机，逗号分隔 (格式同 NO_PROXY 环境变量)，如 "localhost,.internal,10.0.0.0/8"
+    pub no_proxy: Option<String>,
+    /// 单个图片的下载超时 (毫秒) (Default: 1000)；超时的图片按元素的 fallback 绘制占位框或留空，
+    /// 所有图片并发下载，因此一个不可达的 CDN 最多使小票延迟这么久
+    pub timeout_ms: u64,
+    /// 允许下载的主机，支持 "*.example.com" 通配；为空时不限主机
+    pub allowed_hosts: Vec<String>,
+    /// 允许访问内网、回环与链路本地地址 (Default: false)；模板来自网页，默认禁止以免被用来探测内网
+    pub allow_private: bool,
+    /// 单个图片的大小上限 (字节) (Default: 5MB)
+    pub max_bytes: u64,
+}
+
+impl Default for RemoteAssetConfig {
+    fn default() -> Self {
+        Self {
+            proxy_mode: ProxyMode::System,
+            proxy: None,
+            proxy_username: None,
+            proxy_password: None,
+            no_proxy: None,
+            timeout_ms: 1000,
+            allowed_hosts: Vec::new(),
+            allow_private: false,
+            max_bytes: 5 * 1024 * 1024,
+        }
+    }
+}
+
+/// 代理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyMode {
+    /// 系统代理设置与 HTTP_PROXY / HTTPS_PROXY / NO_PROXY 环境变量
+    #[default]
+    System,
+    /// 直连，不使用代理
+    None,
+    /// 使用 proxy 中配置的代理
+    Manual,
+}
+
+/// 云端连接配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CloudConfig {
+    /// 是否启用 (Default: false)
+    pub enabled: bool,
+    /// 云端 WebSocket 地址，如 "wss://cloud.example.com/agents/connect"
+    pub url: String,
+    /// 鉴权令牌，以 "Authorization: Bearer {token}" 发送
+    pub token: Option<String>,
+    /// Agent 标识，连接后随 hello 消息发送
+    pub agent_id: String,
+}
+
+/// MQTT 云端派单配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MqttConfig {
+    /// 是否启用 (Default: false)
+    pub enabled: bool,
+    /// Broker 地址
+    pub host: String,
+    /// Broker 端口 (Default: 1883)
+    pub port: u16,
+    /// 使用 TLS 连接
+    pub tls: bool,
+    /// 客户端 ID，为空时使用 "deepprint-{storeId}"
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 门店 ID，决定订阅的主题
+    pub store_id: String,
+    /// 主题前缀 (Default: "deepprint")
+    pub topic_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            tls: false,
+            client_id: None,
+            username: None,
+            password: None,
+            store_id: "default".to_string(),
+            topic_prefix: "deepprint".to_string(),
+        }
+    }
+}
+
+/// gRPC 服务配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GrpcConfig {
+    /// 是否启用 (Default: false)
+    pub enabled: bool,
+    /// 监听端口 (Default: 18089)
+    pub port: u16,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 18089,
+        }
+    }
+}
+
+/// mDNS/Bonjour 服务发现配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiscoveryConfig {
+    /// 是否广播 _deepprint._tcp 服务 (Default: false)
+    pub enabled: bool,
+    /// 服务实例名，为空时使用 "DeepPrint Agent (主机名)"
+    pub instance_name: Option<String>,
+}
+
+/// 逻辑打印机别名：业务系统按角色 (厨房、标签、发票) 指定打印机，
+/// 而不必关心各门店机器上的驱动名称
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrinterAlias {
+    /// 物理打印机 (显示名或系统名)
+    pub printer: String,
+    /// 该别名的默认作业选项，请求中显式设置的选项优先
+    #[serde(default)]
+    pub options: PrintOptions,
+}
+
+/// 打印机池：任务按轮询分配到池中的打印机，跳过离线的成员
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrinterPool {
+    /// 成员打印机 (系统打印机名、直连打印机名或 IPP 地址)
+    pub printers: Vec<String>,
+    /// 池的默认作业选项，请求与别名中的选项优先
+    #[serde(default)]
+    pub options: PrintOptions,
+}
+
+/// 打印机档案：按设备保存纸宽、分辨率、安装方向等参数，
+/// 任务路由到该打印机时自动应用，模板本身无需关心具体设备
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrinterProfile {
+    /// 默认作业选项 (纸张、纸盒、切纸等)，优先级低于请求与别名中的选项
+    pub options: PrintOptions,
+    /// 纸宽 (mm)：内容宽于纸宽时等比缩小，窄于纸宽时水平居中
+    pub paper_width_mm: Option<f32>,
+    /// 打印头分辨率，覆盖直连打印机配置的 dpi
+    pub dpi: Option<u32>,
+    /// 顺时针旋转角度 (0/90/180/270)
+    pub rotation: u16,
+    /// 水平偏移 (mm)，补偿打印机的机械偏差，正值向右
+    pub offset_x_mm: f32,
+    /// 垂直偏移 (mm)，正值向下
+    pub offset_y_mm: f32,
+    /// 默认打印浓度 0-30 (ZPL；TSPL 按比例换算)
+    pub darkness: Option<u8>,
+    /// 不可打印边距 (mm)：纸张四周打印头无法触及的区域，模板元素落入其中时给出警告
+    pub unprintable_margin_mm: f32,
+    /// ESC/POS 直连打印机的文本模式代码页：设置后只含文本、表格、分隔线与留白的模板
+    /// 以打印机内置字库输出文字，其他模板或含代码页无法表示的字符时仍按位图输出 (Default: 不使用文本模式)
+    pub code_page: Option<CodePage>,
+    /// 文本模式每行的字符数 (Default: 按模板内容区宽度与 dpi 换算，如 80mm 纸 48 列)
+    pub text_columns: Option<usize>,
+    /// 旧版本的走纸行数 (现为 options.feedLines)，读取后由 migrate_legacy 并入 options，不再写出
+    #[serde(rename = "feedLines", skip_serializing)]
+    pub legacy_feed_lines: Option<u8>,
+    /// 旧版本的切纸开关 (现为 options.cut)，true 按全切处理
+    #[serde(rename = "cut", skip_serializing)]
+    pub legacy_cut: Option<bool>,
+}
+
+impl PrinterProfile {
+    /// 旧版本档案的 feedLines / cut 并入 options (options 中已设置的优先)
+    pub fn migrate_legacy(&mut self) {
+        if let Some(feed_lines) = self.legacy_feed_lines.take() {
+            self.options.feed_lines.get_or_insert(feed_lines);
+        }
+        if let Some(cut) = self.legacy_cut.take() {
+            self.options
+                .cut
+                .get_or_insert(if cut { CutMode::Full } else { CutMode::None });
+        }
+    }
+
+    /// 渲染页面的设备变换 (mm -> pt)
+    pub fn page_transform(&self, auto_rotate: bool) -> PageTransform {
+        const MM_TO_PT: f32 = 72.0 / 25.4;
+        PageTransform {
+            rotation: self.rotation,
+            paper_width: self.paper_width_mm.map(|w| w * MM_TO_PT),
+            offset_x: self.offset_x_mm * MM_TO_PT,
+            offset_y: self.offset_y_mm * MM_TO_PT,
+            auto_rotate,
+        }
+    }
+
+    /// 不可打印边距 (pt)
+    pub fn unprintable_margin(&self) -> f64 {
+        const MM_TO_PT: f64 = 72.0 / 25.4;
+        f64::from(self.unprintable_margin_mm.max(0.0)) * MM_TO_PT
+    }
+}
+
+/// API Key 及其授权范围
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub key: String,
+    /// 允许使用的打印机 (别名、显示名或系统名)，为空表示不限
+    #[serde(default)]
+    pub printers: Vec<String>,
+    /// 允许访问的接口，如 "/print/template"、"/jobs/*"，为空表示不限 (/admin 与修改配置的接口除外)
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// 管理员 Key：不受上述限制，可访问 /admin 接口
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// 默认打印机配置
+/// 请求未指定打印机时依次使用：文档类型默认 → Agent 默认 → 系统默认
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrinterDefaults {
+    /// Agent 默认打印机
+    pub default_printer: Option<String>,
+    /// 按文档类型指定的默认打印机，如 {"receipt": "POS-80", "label": "ZDesigner"}
+    pub document_types: HashMap<String, String>,
+}
+
+/// HTTP 服务配置，修改后服务在新地址重新监听
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerConfig {
+    /// HTTP 端口 (Default: 18088)
+    pub port: u16,
+    /// 监听范围 (Default: localhost)
+    pub bind: BindMode,
+    /// 允许访问的客户端 (IP 或 CIDR 网段，如 "192.168.1.0/24"、"fd00::/8")，为空表示不限制；
+    /// 本机请求始终允许
+    pub allowed_clients: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: 18088,
+            bind: BindMode::Localhost,
+            allowed_clients: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 监听的 IP (HTTP 与 HTTPS 共用)
+    pub fn ip(&self) -> IpAddr {
+        match self.bind {
+            BindMode::Localhost => Ipv4Addr::LOCALHOST.into(),
+            BindMode::Lan => Ipv4Addr::UNSPECIFIED.into(),
+            BindMode::LanIpv6 => Ipv6Addr::UNSPECIFIED.into(),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip(), self.port)
+    }
+}
+
+/// 监听范围；局域网监听需先配置 API Key 或启用 HTTPS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BindMode {
+    /// 仅本机 (127.0.0.1)
+    #[default]
+    Localhost,
+    /// 局域网 IPv4 (0.0.0.0)，平板、其他收银机等设备可共用本机的 Agent
+    Lan,
+    /// 局域网 IPv6 (::)，多数系统同时接受 IPv4 连接 (Windows 默认只接受 IPv6)
+    LanIpv6,
+}
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+/// 输出文件配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutputConfig {
+    /// 打印内容副本 (PDF，栅格化任务为 PNG) 的保存目录，可按任务 ID 下载；
+    /// 未设置时保存到桌面 (没有桌面目录时，如移动端，保存到数据目录的 output 下)
+    pub dir: Option<PathBuf>,
+    /// 保存目录中最多保留的副本数量，超出时删除最旧的，0 表示不限制 (Default: 500)
+    pub max_files: usize,
+    /// 副本的最长保留天数，0 表示不限制 (Default: 7)
+    pub max_age_days: u64,
+    /// 副本的总大小上限 (MiB)，超出时删除最旧的，0 表示不限制 (Default: 200)
+    pub max_total_mb: u64,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_files: 500,
+            max_age_days: 7,
+            max_total_mb: 200,
+        }
+    }
+}
+
+impl OutputConfig {
+    /// 实际使用的保存目录
+    pub fn dir(&self) -> PathBuf {
+        self.dir
+            .clone()
+            .or_else(dirs::desktop_dir)
+            .unwrap_or_else(|| AgentConfig::data_dir().join("output"))
+    }
+}
+
+/// 打印队列配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QueueConfig {
+    /// 同时执行的任务数上限 (Default: 4)；不同打印机并行，同一打印机始终按入队顺序串行
+    pub workers: usize,
+    /// 队列容量，超出时返回 HTTP 503 (Default: 100)
+    pub capacity: usize,
+    /// 系统打印队列作业状态的查询间隔 (秒)，0 表示不跟踪 (Default: 2)
+    pub spooler_poll_secs: u64,
+    /// 多条记录 (合并批量打印) 并行渲染的线程数，0 表示按 CPU 核数 (Default: 0)，重启后生效
+    pub render_threads: usize,
+    /// 重复内容检测 (防止重复点击打印按钮重复出单)
+    pub duplicates: DuplicateConfig,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            capacity: 100,
+            spooler_poll_secs: 2,
+            render_threads: 0,
+            duplicates: DuplicateConfig::default(),
+        }
+    }
+}
+
+/// 重复内容检测配置：同一打印机在时间窗口内收到渲染结果完全相同的文档时告警或拒绝，
+/// 请求 options.force 为 true 时跳过检测
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DuplicateConfig {
+    /// 时间窗口 (秒)，0 表示不检测 (Default: 0)
+    pub window_secs: u64,
+    /// 检测到重复时的处理方式 (Default: warn)
+    pub action: DuplicateAction,
+}
+
+/// 重复内容的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateAction {
+    /// 记录警告日志，照常打印
+    #[default]
+    Warn,
+    /// 任务失败 (错误码 duplicate_document)
+    Block,
+}
+
+/// HTTP 服务配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HttpConfig {
+    /// 对响应启用 gzip/deflate 压缩 (按客户端 Accept-Encoding 协商) (Default: true)
+    pub compression: bool,
+    /// 一般 JSON 请求体上限 (字节)，超出时返回 413 (Default: 10 MiB)
+    pub max_body_bytes: usize,
+    /// 文件上传接口 (/print/pdf、/print/image、/print/raw) 的请求体上限 (Default: 50 MiB)
+    pub max_upload_bytes: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            compression: true,
+            max_body_bytes: 10 * 1024 * 1024,
+            max_upload_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
+
+/// HTTPS 配置
+/// 启用后在 HTTP 端口之外额外监听 HTTPS 端口，供 HTTPS 页面调用 (避免混合内容拦截)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TlsConfig {
+    /// 是否启用 HTTPS (Default: false)
+    pub enabled: bool,
+    /// HTTPS 端口 (Default: 18443)
+    pub port: u16,
+    /// 用户提供的证书 (PEM)，与 keyPath 同时设置时生效，否则使用自签名证书
+    pub cert_path: Option<PathBuf>,
+    /// 用户提供的私钥 (PEM)
+    pub key_path: Option<PathBuf>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 18443,
+            cert_path: None,
+            key_path: None,
+        }
+    }
+}
+
+/// 跨域配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CorsConfig {
+    /// 允许调用 Agent 的网页来源，支持 "https://*.example.com" 子域名通配
+    /// 与 "http://localhost:*" 任意端口 (Default: 仅本机与 Tauri 窗口)
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![
+                "http://localhost:*".to_string(),
+                "https://localhost:*".to_string(),
+                "http://127.0.0.1:*".to_string(),
+                "https://127.0.0.1:*".to_string(),
+                "tauri://localhost".to_string(),
+                "http://tauri.localhost".to_string(),
+            ],
+        }
+    }
+}
+
+impl AgentConfig {
+    /// 使用指定的数据目录与配置目录 (移动端使用 Tauri 的 app_data_dir / app_config_dir)，
+    /// 需在读取配置之前调用
+    pub fn use_app_dirs(data_dir: PathBuf, config_dir: PathBuf) {
+        let _ = APP_DIRS.set((data_dir, config_dir));
+    }
+
+    /// 配置目录
+    pub fn config_dir() -> PathBuf {
+        if let Some((_, config_dir)) = APP_DIRS.get() {
+            return config_dir.clone();
+        }
+        dirs::config_dir()
+            .unwrap_or(PathBuf::from("."))
+            .join(APP_IDENTIFIER)
+    }
+
+    /// 检查监听范围是否安全：非本机监听时必须配置 API Key 或启用 HTTPS
+    pub fn check_bind(&self) -> Result<(), String> {
+        if self.server.bind != BindMode::Localhost && self.api_keys.is_empty() && !self.tls.enabled {
+            return Err(
+                "Listening on the local network requires an API key or HTTPS to be configured"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// 数据目录 (任务数据库、模板等)
+    pub fn data_dir() -> PathBuf {
+        if let Some((data_dir, _)) = APP_DIRS.get() {
+            return data_dir.clone();
+        }
+        dirs::data_dir()
+            .unwrap_or(PathBuf::from("."))
+            .join(APP_IDENTIFIER)
+    }
+
+    /// 内嵌数据库路径 (任务历史、模板仓库)
+    pub fn database_path() -> PathBuf {
+        Self::data_dir().join("deepprint.db")
+    }
+
+    /// 配置文件路径
+    pub fn path() -> PathBuf {
+        Self::config_dir().join("config.json")
+    }
+
+    /// 读取配置，文件不存在或解析失败时使用默认配置
+    pub fn load() -> Self {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                let mut config: Self = serde_json::from_str(&text).unwrap_or_else(|e| {
+                    warn!("配置文件解析失败 ({}): {}，使用默认配置", path.display(), e);
+                    Self::default()
+                });
+                config.profiles.values_mut().for_each(PrinterProfile::migrate_legacy);
+                config
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 写入配置文件
+    pub fn save(&self) -> Result<(), String> {
+        let dir = Self::config_dir();
+        fs::create_dir_all(&dir).map_err(|e| format!("Create config dir error: {}", e))?;
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(Self::path(), text).map_err(|e| format!("Write config error: {}", e))
+    }
+}
@@ -0,0 +1,107 @@
+use crate::api_error::ApiError;
+use crate::config::CorsConfig;
+use crate::server::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::warn;
+
+/// 根据白名单构建 CORS 层
+/// 只有白名单内的网页可以跨域调用 Agent，防止任意网站静默提交打印任务
+pub fn layer(config: &CorsConfig) -> CorsLayer {
+    let patterns = config.allowed_origins.clone();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(
+            move |origin: &HeaderValue, _: &Parts| {
+                origin
+                    .to_str()
+                    .map(|origin| patterns.iter().any(|p| origin_matches(p, origin)))
+                    .unwrap_or(false)
+            },
+        ))
+        .allow_methods(Any)
+        .allow_headers(Any)
+}
+
+/// 服务端拒绝白名单外网页发起的请求
+/// CORS 层只控制响应头：multipart 表单、WebSocket 握手等"简单请求"没有预检，
+/// 浏览器仍会把请求发到 Agent，因此带 Origin 且不在白名单内的请求在这里直接拒绝
+/// (不带 Origin 的请求来自 SDK、命令行等非浏览器客户端，不受影响)
+pub async fn reject_foreign_origins(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(origin) = req.headers().get(header::ORIGIN) {
+        let allowed = origin
+            .to_str()
+            .is_ok_and(|origin| origin_allowed(&state.config.read().unwrap().cors, origin));
+        if !allowed {
+            warn!("拒绝白名单外网页的请求: {:?} {}", origin, req.uri().path());
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "origin_forbidden",
+                "Origin is not allowed",
+            ));
+        }
+    }
+    Ok(next.run(req).await)
+}
+
+/// Origin 是否在白名单内
+pub fn origin_allowed(config: &CorsConfig, origin: &str) -> bool {
+    config.allowed_origins.iter().any(|p| origin_matches(p, origin))
+}
+
+/// 判断 Origin 是否匹配白名单规则
+/// 支持的写法：
+/// - 精确匹配: "https://shop.example.com"
+/// - 子域名通配: "https://*.example.com" (匹配 a.example.com、a.b.example.com，不含 example.com 本身)
+/// - 任意端口: "http://localhost:*" (同时匹配不带端口的 http://localhost)
+/// - 全部放行: "*"
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    let (Some((p_scheme, p_host)), Some((o_scheme, o_host))) =
+        (pattern.split_once("://"), origin.split_once("://"))
+    else {
+        return false;
+    };
+    if !p_scheme.eq_ignore_ascii_case(o_scheme) {
+        return false;
+    }
+
+    let (p_name, p_port) = split_port(p_host);
+    let (o_name, o_port) = split_port(o_host);
+    let port_ok = match p_port {
+        Some("*") => true,
+        _ => p_port == o_port,
+    };
+    if !port_ok {
+        return false;
+    }
+
+    let o_name = o_name.to_ascii_lowercase();
+    match p_name.strip_prefix("*.") {
+        Some(domain) => o_name.ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+        None => o_name == p_name.to_ascii_lowercase(),
+    }
+}
+
+/// 拆分 host 与端口 ("localhost:5173" -> ("localhost", Some("5173")))
+fn split_port(host: &str) -> (&str, Option<&str>) {
+    // IPv6 字面量形如 [::1]:8080
+    if let Some(end) = host.find(']') {
+        let (name, rest) = host.split_at(end + 1);
+        return (name, rest.strip_prefix(':'));
+    }
+    match host.rsplit_once(':') {
+        Some((name, port)) => (name, Some(port)),
+        None => (host, None),
+    }
+}
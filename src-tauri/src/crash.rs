@@ -0,0 +1,210 @@
+use crate::config::{AgentConfig, CrashReportConfig};
+use crate::jobs::now_millis;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 最近开始执行的打印任务，写入崩溃报告便于复现
+static CURRENT_JOB: Mutex<Option<String>> = Mutex::new(None);
+/// 安装钩子时的配置摘要
+static CONFIG_SUMMARY: Mutex<Option<ConfigSummary>> = Mutex::new(None);
+
+/// 已上传报告的文件名后缀 (crash-{ts}.json → crash-{ts}.sent.json)
+const SENT_SUFFIX: &str = ".sent.json";
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 崩溃报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// Unix 毫秒
+    pub timestamp: u64,
+    pub version: String,
+    pub commit: String,
+    pub os: String,
+    pub arch: String,
+    /// 发生 panic 的线程
+    pub thread: Option<String>,
+    pub message: String,
+    /// 源码位置 (file:line:column)
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// 最近开始执行的打印任务
+    pub last_job_id: Option<String>,
+    pub config: Option<ConfigSummary>,
+}
+
+/// 配置摘要 (不含密钥与打印机细节)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSummary {
+    pub port: u16,
+    pub tls: bool,
+    pub grpc: bool,
+    pub mqtt: bool,
+    pub cloud: bool,
+    pub queue_workers: usize,
+    pub api_keys: usize,
+    pub aliases: usize,
+    pub direct_printers: usize,
+    pub pools: usize,
+}
+
+impl ConfigSummary {
+    fn from_config(config: &AgentConfig) -> Self {
+        Self {
+            port: config.server.port,
+            tls: config.tls.enabled,
+            grpc: config.grpc.enabled,
+            mqtt: config.mqtt.enabled,
+            cloud: config.cloud.enabled,
+            queue_workers: config.queue.workers,
+            api_keys: config.api_keys.len(),
+            aliases: config.aliases.len(),
+            direct_printers: config.direct_printers.len(),
+            pools: config.pools.len(),
+        }
+    }
+}
+
+/// 崩溃报告目录
+pub fn crash_dir() -> PathBuf {
+    AgentConfig::data_dir().join("crashes")
+}
+
+/// 安装 panic 钩子：保留默认输出，并把崩溃报告写入 {data_dir}/crashes；
+/// 同时提示上次运行留下的崩溃报告
+pub fn install(config: &AgentConfig) {
+    *CONFIG_SUMMARY.lock().unwrap() = Some(ConfigSummary::from_config(config));
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Some(path) => eprintln!("DeepPrint Agent crash report: {}", path.display()),
+            None => eprintln!("DeepPrint Agent crash report could not be written"),
+        }
+        previous(info);
+    }));
+
+    let pending = pending_reports().len();
+    if pending > 0 {
+        warn!("发现 {} 份未上传的崩溃报告 ({})", pending, crash_dir().display());
+    }
+}
+
+/// 记录开始执行的任务
+pub fn set_current_job(task_id: &str) {
+    if let Ok(mut current) = CURRENT_JOB.lock() {
+        *current = Some(task_id.to_string());
+    }
+}
+
+/// 所有崩溃报告 (含已上传的)，按时间顺序
+pub fn reports() -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(crash_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| is_report(path))
+                .collect()
+        })
+        .unwrap_or_default();
+    reports.sort();
+    reports
+}
+
+/// 配置了上传地址时逐份上传未上传的报告，成功后标记为已上传；之后按 maxReports 删除旧报告
+pub async fn upload_pending(config: CrashReportConfig) {
+    if let Some(endpoint) = config.endpoint.as_deref().filter(|e| !e.is_empty()) {
+        let client = match reqwest::Client::builder().timeout(UPLOAD_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("崩溃报告上传失败: {}", e);
+                return;
+            }
+        };
+        for path in pending_reports() {
+            let Ok(body) = fs::read(&path) else {
+                continue;
+            };
+            let mut request = client
+                .post(endpoint)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+            if let Some(token) = &config.token {
+                request = request.bearer_auth(token);
+            }
+            match request.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => {
+                    info!("崩溃报告已上传: {}", path.display());
+                    let _ = fs::rename(&path, sent_path(&path));
+                }
+                Err(e) => {
+                    warn!("崩溃报告上传失败 ({}): {}", path.display(), e);
+                    break;
+                }
+            }
+        }
+    }
+    if config.max_reports > 0 {
+        let reports = reports();
+        for path in reports.iter().take(reports.len().saturating_sub(config.max_reports)) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn write_report(info: &PanicHookInfo) -> Option<PathBuf> {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    // panic 可能发生在持有锁时，这里不等待锁
+    let last_job_id = CURRENT_JOB.try_lock().ok().and_then(|job| job.clone());
+    let config = CONFIG_SUMMARY.try_lock().ok().and_then(|summary| summary.clone());
+    let report = CrashReport {
+        timestamp: now_millis(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: env!("DEEPPRINT_GIT_COMMIT").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        thread: std::thread::current().name().map(str::to_string),
+        message,
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: Backtrace::force_capture().to_string(),
+        last_job_id,
+        config,
+    };
+
+    let dir = crash_dir();
+    let path = dir.join(format!("crash-{}.json", report.timestamp));
+    let json = serde_json::to_vec_pretty(&report).ok()?;
+    fs::create_dir_all(&dir).and_then(|_| fs::write(&path, json)).ok()?;
+    Some(path)
+}
+
+fn is_report(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".json"))
+}
+
+fn pending_reports() -> Vec<PathBuf> {
+    reports()
+        .into_iter()
+        .filter(|path| !path.to_string_lossy().ends_with(SENT_SUFFIX))
+        .collect()
+}
+
+fn sent_path(path: &Path) -> PathBuf {
+    path.with_extension("").with_extension(&SENT_SUFFIX[1..])
+}
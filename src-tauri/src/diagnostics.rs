@@ -0,0 +1,95 @@
+use crate::config::AgentConfig;
+use crate::crash;
+use crate::jobs::{now_millis, JobQuery};
+use crate::logging;
+use crate::printing;
+use crate::server::{self, AppState};
+use axum::extract::State;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// 诊断包中包含的最近任务数
+const RECENT_JOBS: usize = 200;
+/// 替换敏感字段的占位符
+const REDACTED: &str = "***";
+
+/// 导出诊断包 (zip)：日志、崩溃报告、配置 (密钥已脱敏)、打印机列表与状态、最近任务、运行状态，
+/// 保存到输出目录，返回文件路径，便于附在工单中
+pub async fn export(state: &AppState) -> Result<PathBuf, String> {
+    let axum::Json(health) = server::health(State(state.clone())).await;
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || write_bundle(&state, &health))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn write_bundle<T: Serialize>(state: &AppState, health: &T) -> Result<PathBuf, String> {
+    let config = state.config.read().unwrap().clone();
+    let dir = config.output.dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("deepprint-diagnostics-{}.zip", now_millis()));
+    let file = File::create(&path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+
+    let printers: Vec<_> = printing::system_printers().iter().map(printing::status).collect();
+    let jobs = state.jobs.list(&JobQuery {
+        limit: Some(RECENT_JOBS),
+        ..Default::default()
+    });
+
+    let mut zip = ZipWriter::new(file);
+    let mut add = |name: &str, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(name, SimpleFileOptions::default())
+            .and_then(|_| zip.write_all(bytes).map_err(Into::into))
+            .map_err(|e| format!("Cannot write {}: {}", name, e))
+    };
+    add("health.json", &to_json(health)?)?;
+    add("config.json", &to_json(&redact(config))?)?;
+    add("printers.json", &to_json(&printers)?)?;
+    add("jobs.json", &to_json(&jobs)?)?;
+    add("logs/recent.json", &to_json(&logging::recent(usize::MAX, None))?)?;
+    for log in logging::log_files() {
+        let name = log.file_name().unwrap_or_default().to_string_lossy();
+        if let Ok(bytes) = std::fs::read(&log) {
+            add(&format!("logs/{}", name), &bytes)?;
+        }
+    }
+    for report in crash::reports() {
+        let name = report.file_name().unwrap_or_default().to_string_lossy();
+        if let Ok(bytes) = std::fs::read(&report) {
+            add(&format!("crashes/{}", name), &bytes)?;
+        }
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+
+    info!("诊断包已导出: {}", path.display());
+    Ok(path)
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| e.to_string())
+}
+
+/// 去除配置中的密钥：API Key、MQTT 密码、云端令牌、崩溃报告上传令牌、代理密码
+fn redact(mut config: AgentConfig) -> AgentConfig {
+    for key in config.api_keys.values_mut() {
+        key.key = REDACTED.to_string();
+    }
+    if config.mqtt.password.is_some() {
+        config.mqtt.password = Some(REDACTED.to_string());
+    }
+    if config.cloud.token.is_some() {
+        config.cloud.token = Some(REDACTED.to_string());
+    }
+    if config.crash_reports.token.is_some() {
+        config.crash_reports.token = Some(REDACTED.to_string());
+    }
+    if config.remote_assets.proxy_password.is_some() {
+        config.remote_assets.proxy_password = Some(REDACTED.to_string());
+    }
+    config
+}
@@ -0,0 +1,46 @@
+use crate::config::AgentConfig;
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use tracing::info;
+
+/// mDNS 服务类型
+pub const SERVICE_TYPE: &str = "_deepprint._tcp.local.";
+
+/// 通过 mDNS/Bonjour 广播 Agent (服务类型 _deepprint._tcp)，
+/// TXT 记录包含版本号与 HTTPS 端口，供局域网内的客户端自动发现
+/// 返回的 daemon 需保持存活，drop 后不再响应查询
+pub fn advertise(config: &AgentConfig, port: u16) -> Result<ServiceDaemon, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS daemon error: {}", e))?;
+
+    let host = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "deepprint-agent".to_string());
+    let instance = config
+        .discovery
+        .instance_name
+        .clone()
+        .unwrap_or_else(|| format!("DeepPrint Agent ({})", host));
+
+    let version = env!("CARGO_PKG_VERSION");
+    let tls_port = config.tls.port.to_string();
+    let mut properties = vec![("version", version)];
+    if config.tls.enabled {
+        properties.push(("httpsPort", tls_port.as_str()));
+    }
+
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &instance,
+        &format!("{}.local.", host),
+        "",
+        port,
+        &properties[..],
+    )
+    .map_err(|e| format!("mDNS service info error: {}", e))?
+    .enable_addr_auto();
+
+    daemon
+        .register(info)
+        .map_err(|e| format!("mDNS register error: {}", e))?;
+    info!("mDNS 广播: {} ({})", instance, SERVICE_TYPE);
+    Ok(daemon)
+}
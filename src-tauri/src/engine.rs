@@ -5,12 +5,80 @@ use skia_safe::{
 };
 // 引入二维码库
 use qrcode::QrCode;
-
-pub struct Engine;
+use serde_json::Value;
+use std::sync::OnceLock;
+use tracing::warn;
+use crate::deep_print_schema::DeepPrintTemplate;
+use crate::output::{self, Composition, GrayBitmap, MonoBitmap, RenderedPage};
+use crate::renderer::{DeepPrintRenderer, RenderOptions};
+use deepprint_core::RenderError;
+
+/// 多条记录并行渲染的线程池，限制批量渲染占用的 CPU
+static RENDER_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// 按配置创建渲染线程池 (threads 为 0 时按 CPU 核数)，只在首次调用时生效
+pub fn init_render_pool(threads: usize) {
+    RENDER_POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("render-{}", i))
+            .build()
+            .unwrap_or_else(|e| {
+                warn!("渲染线程池创建失败: {}，使用单线程", e);
+                rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap()
+            })
+    });
+}
+
+pub struct Engine {
+    renderer: DeepPrintRenderer,
+}
 
 impl Engine {
     pub fn new() -> Self {
-        Engine
+        Engine {
+            renderer: DeepPrintRenderer::new(),
+        }
+    }
+
+    /// 使用 DeepPrint 模板渲染多条数据记录为页面列表
+    /// 支持 N-up 拼版 (一张纸排多个标签)、设备变换与多份复制；
+    /// 多条记录在渲染线程池中并行渲染，按原顺序合并
+    pub fn generate_template_pages(
+        &self,
+        template: &DeepPrintTemplate,
+        records: &[Value],
+        render_options: &RenderOptions,
+        composition: &Composition,
+    ) -> Result<Vec<RenderedPage>, RenderError> {
+        match RENDER_POOL.get() {
+            Some(pool) if records.len() > 1 => pool.install(|| {
+                output::render_pages_parallel(
+                    DeepPrintRenderer::new,
+                    template,
+                    records,
+                    render_options,
+                    composition,
+                )
+            }),
+            _ => output::render_pages(&self.renderer, template, records, render_options, composition),
+        }
+    }
+
+    /// 将页面栅格化为单色位图 (多页时并行)
+    pub fn rasterize_pages(&self, pages: &[RenderedPage], dpi: f32) -> Result<Vec<MonoBitmap>, RenderError> {
+        match RENDER_POOL.get() {
+            Some(pool) if pages.len() > 1 => pool.install(|| output::rasterize_mono_pages(pages, dpi)),
+            _ => pages.iter().map(|page| output::rasterize_mono(page, dpi)).collect(),
+        }
+    }
+
+    /// 将页面栅格化为灰度位图 (多页时并行)
+    pub fn rasterize_gray_pages(&self, pages: &[RenderedPage], dpi: f32) -> Result<Vec<GrayBitmap>, RenderError> {
+        match RENDER_POOL.get() {
+            Some(pool) if pages.len() > 1 => pool.install(|| output::rasterize_gray_pages(pages, dpi)),
+            _ => pages.iter().map(|page| output::rasterize_gray(page, dpi)).collect(),
+        }
     }
 
     fn mm_to_pt(mm: f32) -> f32 {
@@ -0,0 +1,85 @@
+use crate::api_error::ApiError;
+use axum::http::StatusCode;
+use deepprint_core::{RenderError, TemplateError};
+use thiserror::Error;
+
+/// 打印任务执行错误 (渲染 → 栅格化 → 提交)，code 作为稳定的分类码写入任务记录
+#[derive(Debug, Error)]
+pub enum PrintError {
+    #[error("Render error: {0}")]
+    Render(RenderError),
+    /// 任务被取消 (排队中或渲染中)
+    #[error("Job was cancelled")]
+    Cancelled,
+    /// 任务类型与输出方式不兼容 (如直传 PDF 发往热敏打印机)
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("Printer '{0}' not found")]
+    PrinterNotFound(String),
+    /// 系统打印队列或直连设备拒绝/写入失败
+    #[error("{0}")]
+    Submit(String),
+    #[error("Worker panicked: {0}")]
+    Panicked(String),
+    /// 时间窗口内同一打印机已打印过相同内容 (重复内容检测为 block 时)
+    #[error("Identical document was sent to this printer {0}s ago; set options.force to print it again")]
+    Duplicate(u64),
+}
+
+impl PrintError {
+    /// 稳定的错误分类码
+    pub fn code(&self) -> &'static str {
+        match self {
+            PrintError::Render(e) => e.code(),
+            PrintError::Cancelled => "cancelled",
+            PrintError::Unsupported(_) => "unsupported_job",
+            PrintError::PrinterNotFound(_) => "printer_not_found",
+            PrintError::Submit(_) => "submit_failed",
+            PrintError::Panicked(_) => "internal_error",
+            PrintError::Duplicate(_) => "duplicate_document",
+        }
+    }
+}
+
+impl From<RenderError> for PrintError {
+    fn from(e: RenderError) -> Self {
+        match e {
+            RenderError::Cancelled => PrintError::Cancelled,
+            e => PrintError::Render(e),
+        }
+    }
+}
+
+impl From<TemplateError> for PrintError {
+    fn from(e: TemplateError) -> Self {
+        PrintError::Render(e.into())
+    }
+}
+
+/// 渲染错误：模板与元素问题为 400 invalid_template (details 中带分类码、元素 ID 或 JSON 路径)，
+/// 超出资源上限为 400 limit_exceeded，Skia 后端失败为 500
+impl From<RenderError> for ApiError {
+    fn from(e: RenderError) -> Self {
+        let details = serde_json::json!({
+            "kind": e.code(),
+            "elementId": e.element_id(),
+            "path": e.path(),
+        });
+        let message = format!("Render error: {}", e);
+        match e {
+            RenderError::Backend(_) => ApiError::internal(message),
+            RenderError::Cancelled => ApiError::new(StatusCode::CONFLICT, "cancelled", message),
+            RenderError::LimitExceeded { .. } => {
+                ApiError::new(StatusCode::BAD_REQUEST, "limit_exceeded", message)
+            }
+            _ => ApiError::invalid_template(message),
+        }
+        .with_details(details)
+    }
+}
+
+impl From<TemplateError> for ApiError {
+    fn from(e: TemplateError) -> Self {
+        RenderError::from(e).into()
+    }
+}
@@ -0,0 +1,126 @@
+use crate::printing::{self, PrinterStatus};
+use crate::server::AppState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+/// 任务状态变化，载荷为 JobRecord
+pub const JOB_UPDATE: &str = "job:update";
+/// 系统打印机状态变化 (只推送有变化的打印机)，载荷为 PrinterStatus
+pub const PRINTER_STATUS: &str = "printer:status";
+/// HTTP 服务 (重新) 开始监听，载荷为 ServerStarted
+pub const SERVER_STARTED: &str = "server:started";
+/// 模板热重载后的预览结果，载荷为 PreviewUpdate
+pub const PREVIEW_UPDATE: &str = "preview:update";
+/// HTTP 服务异常退出 (稍后自动重启)，载荷为 ServerFailure
+pub const SERVER_FAILED: &str = "server:failed";
+
+/// 打印机状态轮询间隔
+const PRINTER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerStarted {
+    /// 实际监听地址，如 127.0.0.1:18088
+    addr: String,
+    port: u16,
+    url: String,
+}
+
+/// 将任务、打印机、服务状态以 Tauri 事件推送给前端，界面无需轮询自身的 HTTP 接口
+pub fn forward<R: Runtime>(app: &AppHandle<R>, state: &AppState) {
+    tauri::async_runtime::spawn(job_updates(app.clone(), state.clone()));
+    tauri::async_runtime::spawn(printer_status(app.clone()));
+    tauri::async_runtime::spawn(server_started(app.clone(), state.clone()));
+    tauri::async_runtime::spawn(preview_updates(app.clone(), state.clone()));
+    tauri::async_runtime::spawn(server_failures(app.clone(), state.clone()));
+}
+
+async fn job_updates<R: Runtime>(app: AppHandle<R>, state: AppState) {
+    let mut events = state.jobs.subscribe();
+    loop {
+        match events.recv().await {
+            Ok(record) => {
+                let _ = app.emit(JOB_UPDATE, &record);
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("界面事件推送过慢，丢弃 {} 条任务事件", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn printer_status<R: Runtime>(app: AppHandle<R>) {
+    let mut last: HashMap<String, PrinterStatus> = HashMap::new();
+    loop {
+        let statuses = tauri::async_runtime::spawn_blocking(|| {
+            printing::system_printers()
+                .iter()
+                .map(printing::status)
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+        for status in statuses {
+            if last.get(&status.name) != Some(&status) {
+                let _ = app.emit(PRINTER_STATUS, &status);
+                last.insert(status.name.clone(), status);
+            }
+        }
+        tokio::time::sleep(PRINTER_POLL_INTERVAL).await;
+    }
+}
+
+async fn server_started<R: Runtime>(app: AppHandle<R>, state: AppState) {
+    let mut bound = state.bound.subscribe();
+    loop {
+        let addr = *bound.borrow_and_update();
+        if let Some(addr) = addr {
+            let _ = app.emit(
+                SERVER_STARTED,
+                ServerStarted {
+                    addr: addr.to_string(),
+                    port: addr.port(),
+                    url: format!("http://{}", addr),
+                },
+            );
+        }
+        if bound.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// HTTP 服务异常退出时推送事件并在托盘提示中显示，恢复监听后清除托盘提示
+async fn server_failures<R: Runtime>(app: AppHandle<R>, state: AppState) {
+    let mut failures = state.server_failure.subscribe();
+    loop {
+        let failure = failures.borrow_and_update().clone();
+        if let Some(failure) = &failure {
+            let _ = app.emit(SERVER_FAILED, failure);
+        }
+        #[cfg(desktop)]
+        crate::tray::show_server_failure(&app, failure.as_ref());
+        if failures.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn preview_updates<R: Runtime>(app: AppHandle<R>, state: AppState) {
+    let mut updates = state.preview.subscribe();
+    loop {
+        match updates.recv().await {
+            Ok(update) => {
+                let _ = app.emit(PREVIEW_UPDATE, &update);
+            }
+            // 只关心最新的预览，跳过积压的旧结果
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use crate::deep_print_schema::{CutMode, DeepPrintTemplate};
+use crate::jobs::JobRecord;
+use crate::output;
+use crate::printing::media::MediaScaling;
+use crate::printing::{self, ColorMode, DuplexMode, PrintOptions};
+use crate::queue::{JobPayload, PrintJob};
+use crate::renderer::{CancelToken, DeepPrintRenderer, LumaWeights, RenderOptions};
+use crate::api_error::ApiError;
+use crate::auth::Access;
+use crate::server::{self, AppState};
+use axum::http::StatusCode;
+use deepprint_core::MigrationWarning;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{info, warn};
+
+pub mod proto {
+    tonic::include_proto!("deepprint.v1");
+}
+
+use proto::deep_print_server::{DeepPrint, DeepPrintServer};
+
+/// gRPC 服务，与 REST 接口共享同一份 AppState (任务存储、队列、模板仓库、配置)
+pub struct GrpcService {
+    state: AppState,
+}
+
+/// 启动 gRPC 服务
+pub async fn serve(state: AppState, addr: SocketAddr) -> Result<(), String> {
+    info!("DeepPrint Agent gRPC listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(DeepPrintServer::new(GrpcService { state }))
+        .serve(addr)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// REST 错误 → gRPC 状态码
+fn to_status(err: ApiError) -> Status {
+    let message = err.message;
+    match err.status {
+        StatusCode::BAD_REQUEST => Status::invalid_argument(message),
+        StatusCode::NOT_FOUND => Status::not_found(message),
+        StatusCode::CONFLICT if err.code == "task_in_progress" => Status::already_exists(message),
+        StatusCode::SERVICE_UNAVAILABLE => Status::resource_exhausted(message),
+        _ => Status::internal(message),
+    }
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(text: &str, field: &str) -> Result<T, Status> {
+    serde_json::from_str(text).map_err(|e| Status::invalid_argument(format!("{}: {}", field, e)))
+}
+
+/// 空字符串视为无数据
+fn parse_data(text: &str) -> Result<Value, Status> {
+    if text.trim().is_empty() {
+        Ok(Value::Null)
+    } else {
+        parse_json(text, "data_json")
+    }
+}
+
+fn convert_options(options: Option<proto::PrintOptions>) -> PrintOptions {
+    let Some(options) = options else {
+        return PrintOptions::default();
+    };
+    PrintOptions {
+        copies: options.copies,
+        duplex: match options.duplex() {
+            proto::Duplex::Unspecified => None,
+            proto::Duplex::Simplex => Some(DuplexMode::Simplex),
+            proto::Duplex::LongEdge => Some(DuplexMode::LongEdge),
+            proto::Duplex::ShortEdge => Some(DuplexMode::ShortEdge),
+        },
+        color_mode: match options.color_mode() {
+            proto::ColorMode::Unspecified => None,
+            proto::ColorMode::Color => Some(ColorMode::Color),
+            proto::ColorMode::Monochrome => Some(ColorMode::Monochrome),
+        },
+        media: options.media,
+        tray: options.tray,
+        fit_to_page: options.fit_to_page,
+        media_scaling: match options.media_scaling() {
+            proto::MediaScaling::Unspecified => None,
+            proto::MediaScaling::Auto => Some(MediaScaling::Auto),
+            proto::MediaScaling::Fit => Some(MediaScaling::Fit),
+            proto::MediaScaling::Center => Some(MediaScaling::Center),
+            proto::MediaScaling::Off => Some(MediaScaling::Off),
+        },
+        auto_rotate: options.auto_rotate,
+        cut: match options.cut() {
+            proto::CutMode::Unspecified => None,
+            proto::CutMode::None => Some(CutMode::None),
+            proto::CutMode::Full => Some(CutMode::Full),
+            proto::CutMode::Partial => Some(CutMode::Partial),
+        },
+        cut_per_copy: options.cut_per_copy,
+        feed_lines: options.feed_lines.map(|lines| lines.min(u8::MAX as u32) as u8),
+        force: options.force,
+    }
+}
+
+fn to_proto_warnings(warnings: Vec<MigrationWarning>) -> Vec<proto::Warning> {
+    warnings
+        .into_iter()
+        .map(|w| proto::Warning {
+            path: w.path,
+            message: w.message,
+        })
+        .collect()
+}
+
+fn to_proto_job(record: JobRecord) -> proto::Job {
+    proto::Job {
+        task_id: record.task_id,
+        kind: record.kind,
+        printer: record.printer,
+        status: record.status.as_str().to_string(),
+        error: record.error,
+        spooler_job_id: record.spooler_job_id,
+        output_path: record.output_path,
+        batch_id: record.batch_id,
+        created_at: record.created_at,
+        updated_at: record.updated_at,
+    }
+}
+
+impl GrpcService {
+    async fn build_job(&self, req: proto::PrintRequest) -> Result<PrintJob, Status> {
+        let (inline, template_id) = match req.template {
+            Some(proto::print_request::Template::TemplateJson(json)) => {
+                (Some(parse_json::<DeepPrintTemplate>(&json, "template_json")?), None)
+            }
+            Some(proto::print_request::Template::TemplateId(id)) => (None, Some(id)),
+            None => (None, None),
+        };
+        let template = server::resolve_template(
+            &self.state.templates,
+            inline,
+            template_id.as_deref(),
+            req.template_version,
+        )
+        .map_err(to_status)?;
+        let data = parse_data(&req.data_json)?;
+        server::check_data(&template, &data).map_err(to_status)?;
+
+        let server::Route {
+            printer,
+            options,
+            profile,
+            pool,
+        } = server::route_printer(
+            &self.state,
+            &Access::unrestricted(),
+            req.printer.as_deref(),
+            req.document_type.as_deref(),
+            convert_options(req.options),
+        )
+        .await
+        .map_err(to_status)?;
+        let render_options = RenderOptions {
+            grayscale: (options.color_mode == Some(ColorMode::Monochrome))
+                .then(LumaWeights::default),
+            ..Default::default()
+        };
+
+        Ok(PrintJob {
+            task_id: req.task_id,
+            printer,
+            payload: JobPayload::Template {
+                template: Box::new(template),
+                data,
+                render_options,
+            },
+            options,
+            profile,
+            pool,
+        })
+    }
+}
+
+type JobStream = Pin<Box<dyn Stream<Item = Result<proto::Job, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl DeepPrint for GrpcService {
+    async fn list_printers(
+        &self,
+        _request: Request<proto::ListPrintersRequest>,
+    ) -> Result<Response<proto::ListPrintersResponse>, Status> {
+        let printers = printing::system_printers()
+            .into_iter()
+            .map(|p| proto::Printer {
+                name: p.name,
+                system_name: p.system_name,
+                is_default: p.is_default,
+            })
+            .collect();
+        Ok(Response::new(proto::ListPrintersResponse { printers }))
+    }
+
+    async fn print(
+        &self,
+        request: Request<proto::PrintRequest>,
+    ) -> Result<Response<proto::PrintResponse>, Status> {
+        let req = request.into_inner();
+        let task_id = req.task_id.clone();
+        info!("接收到 gRPC 打印任务: {}", task_id);
+        self.state
+            .jobs
+            .create(&task_id, "template", req.printer.clone(), None)
+            .map_err(|_| to_status(ApiError::task_in_progress(&task_id)))?;
+
+        let job = self.build_job(req).await.map_err(|status| {
+            self.state
+                .jobs
+                .mark_failed(&task_id, status.message().to_string());
+            status
+        })?;
+        let printer = job.printer.name();
+        let warnings = match &job.payload {
+            JobPayload::Template { template, .. } => {
+                to_proto_warnings(server::template_warnings(template, job.profile.as_ref()))
+            }
+            _ => Vec::new(),
+        };
+        if self.state.queue.enqueue(job).is_err() {
+            let message = "Print queue is full, please retry later".to_string();
+            self.state.jobs.mark_failed(&task_id, message.clone());
+            return Err(Status::resource_exhausted(message));
+        }
+
+        Ok(Response::new(proto::PrintResponse {
+            task_id,
+            printer,
+            warnings,
+        }))
+    }
+
+    async fn preview(
+        &self,
+        request: Request<proto::PreviewRequest>,
+    ) -> Result<Response<proto::PreviewResponse>, Status> {
+        let req = request.into_inner();
+        let template: DeepPrintTemplate = parse_json(&req.template_json, "template_json")?;
+        let warnings = to_proto_warnings(template.migration_warnings.clone());
+        let data = parse_data(&req.data_json)?;
+        let data = server::preview_data(&template, &data)
+            .map_err(to_status)?
+            .into_owned();
+        let scale = req.dpi.unwrap_or(72.0) / 72.0;
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(Status::invalid_argument("dpi must be positive"));
+        }
+
+        // 客户端断开时取消渲染
+        let cancel = CancelToken::new();
+        let _guard = cancel.drop_guard();
+        let render_options = RenderOptions {
+            grayscale: req.grayscale.then(LumaWeights::default),
+            cancel: Some(cancel),
+        };
+        // 渲染是 CPU 密集操作，不在异步运行时中执行
+        let image = tokio::task::spawn_blocking(move || {
+            let renderer = DeepPrintRenderer::new();
+            output::render_png(&renderer, &template, &data, &render_options, scale)
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::invalid_argument(format!("Render error: {}", e)))?;
+
+        Ok(Response::new(proto::PreviewResponse {
+            png: image.bytes,
+            width: image.width,
+            height: image.height,
+            warnings,
+        }))
+    }
+
+    async fn get_job(
+        &self,
+        request: Request<proto::GetJobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let task_id = request.into_inner().task_id;
+        self.state
+            .jobs
+            .get(&task_id)
+            .map(|record| Response::new(to_proto_job(record)))
+            .ok_or_else(|| Status::not_found(format!("Job '{}' not found", task_id)))
+    }
+
+    type WatchJobsStream = JobStream;
+
+    async fn watch_jobs(
+        &self,
+        request: Request<Streaming<proto::WatchRequest>>,
+    ) -> Result<Response<Self::WatchJobsStream>, Status> {
+        let mut incoming = request.into_inner();
+        let mut events = self.state.jobs.subscribe();
+        let (tx, rx) = mpsc::channel(64);
+
+        tokio::spawn(async move {
+            let mut watched: HashSet<String> = HashSet::new();
+            // 客户端关闭发送端后仍继续推送，直到客户端断开
+            let mut incoming_open = true;
+            loop {
+                tokio::select! {
+                    message = incoming.next(), if incoming_open => match message {
+                        Some(Ok(req)) => watched.extend(req.task_ids),
+                        Some(Err(_)) => break,
+                        None => incoming_open = false,
+                    },
+                    event = events.recv() => match event {
+                        Ok(record) => {
+                            if !watched.is_empty() && !watched.contains(&record.task_id) {
+                                continue;
+                            }
+                            if tx.send(Ok(to_proto_job(record))).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("gRPC 事件流处理过慢，丢弃 {} 条事件", skipped);
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
@@ -0,0 +1,185 @@
+use crate::deep_print_schema::DeepPrintTemplate;
+use crate::jobs::now_millis;
+use crate::server::{self, AppState, PagedPreviewRequest, PreviewResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+/// 检查模板变化的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 开发模式的监视目标：本地模板 JSON 文件或已注册模板
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchRequest {
+    /// 模板 JSON 文件路径，优先于 templateId
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// 已注册模板的 ID，模板更新 (PUT /templates/{id}) 后重新渲染
+    #[serde(default)]
+    pub template_id: Option<String>,
+    /// 预览数据，为数组时每条记录一页
+    #[serde(default)]
+    pub data: Value,
+    pub dpi: Option<f32>,
+}
+
+/// 一次重新渲染的结果
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewUpdate {
+    /// 监视的文件路径或模板 ID
+    pub source: String,
+    pub success: bool,
+    /// 渲染结果，每条数据记录一页
+    pub pages: Vec<PreviewResponse>,
+    /// 解析或渲染失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 渲染时间 (Unix 毫秒)
+    pub rendered_at: u64,
+}
+
+/// 模板热重载 (开发模式)：监视模板文件或已注册模板，每次保存后自动重新渲染，
+/// 预览结果推送给桌面端 (preview:update 事件) 和 WebSocket 订阅者 (GET /dev/preview)
+#[derive(Clone)]
+pub struct PreviewWatcher {
+    updates: broadcast::Sender<PreviewUpdate>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl Default for PreviewWatcher {
+    fn default() -> Self {
+        let (updates, _) = broadcast::channel(16);
+        Self {
+            updates,
+            task: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl PreviewWatcher {
+    /// 订阅预览更新
+    pub fn subscribe(&self) -> broadcast::Receiver<PreviewUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// 开始监视 (替换之前的监视目标)，立即渲染一次
+    pub fn start(&self, state: AppState, request: WatchRequest) -> Result<(), String> {
+        let source = match (&request.path, &request.template_id) {
+            (Some(path), _) => path.display().to_string(),
+            (None, Some(id)) => id.clone(),
+            (None, None) => return Err("path or templateId is required".to_string()),
+        };
+        info!("模板热重载：开始监视 {}", source);
+        let updates = self.updates.clone();
+        let task = tokio::spawn(async move {
+            let mut last = None;
+            loop {
+                let fingerprint = fingerprint(&state, &request);
+                if fingerprint != last {
+                    last = fingerprint;
+                    let update = render(&state, &request, &source).await;
+                    let _ = updates.send(update);
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        });
+        if let Some(previous) = self.task.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// 停止监视
+    pub fn stop(&self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+            info!("模板热重载：已停止监视");
+        }
+    }
+}
+
+/// 模板是否变化的判断依据：文件按修改时间与大小，已注册模板按版本号
+#[derive(PartialEq)]
+enum Fingerprint {
+    File(SystemTime, u64),
+    Registered(u32),
+}
+
+fn fingerprint(state: &AppState, request: &WatchRequest) -> Option<Fingerprint> {
+    match (&request.path, &request.template_id) {
+        (Some(path), _) => {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some(Fingerprint::File(metadata.modified().ok()?, metadata.len()))
+        }
+        (None, Some(id)) => state
+            .templates
+            .get(id)
+            .map(|record| Fingerprint::Registered(record.version)),
+        (None, None) => None,
+    }
+}
+
+async fn render(state: &AppState, request: &WatchRequest, source: &str) -> PreviewUpdate {
+    let template = match &request.path {
+        Some(path) => load_template(path).map(Some),
+        None => Ok(None),
+    };
+    let result = match template {
+        Ok(template) => server::render_preview_pages(
+            state,
+            PagedPreviewRequest {
+                template,
+                template_id: request.template_id.clone(),
+                template_version: None,
+                data: request.data.clone(),
+                dpi: request.dpi,
+                grayscale: false,
+            },
+        )
+        .await
+        .map_err(|e| e.message),
+        Err(e) => Err(e),
+    };
+
+    let (pages, error) = match result {
+        Ok(pages) => (pages.iter().map(PreviewResponse::png).collect(), None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    PreviewUpdate {
+        source: source.to_string(),
+        success: error.is_none(),
+        pages,
+        error,
+        rendered_at: now_millis(),
+    }
+}
+
+/// 限制 HTTP 接口可监视的文件：规范化后 (解析 .. 与符号链接) 必须位于 templates_dir 内
+pub fn confine_path(templates_dir: Option<&Path>, path: &Path) -> Result<PathBuf, String> {
+    let dir = templates_dir
+        .ok_or_else(|| "devPreview.templatesDir is not configured".to_string())?
+        .canonicalize()
+        .map_err(|e| format!("Cannot open templates directory: {}", e))?;
+    let path = dir
+        .join(path)
+        .canonicalize()
+        .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    if !path.starts_with(&dir) {
+        return Err(format!("{} is outside the templates directory", path.display()));
+    }
+    Ok(path)
+}
+
+/// 读取模板文件，解析失败时返回出错的 JSON 路径
+fn load_template(path: &Path) -> Result<DeepPrintTemplate, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    deepprint_core::parse_template(&text).map_err(|e| e.to_string())
+}
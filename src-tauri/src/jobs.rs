@@ -0,0 +1,742 @@
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::error::PrintError;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::error;
+
+/// 打印任务生命周期
+/// queued → rendering → submitting → spooled → printing → printed / failed
+/// 提交到系统打印队列后持续跟踪系统作业状态，printed 表示纸张已实际打出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    /// 已接收，等待处理
+    Queued,
+    /// 正在渲染
+    Rendering,
+    /// 正在提交到打印机，此后无法取消
+    Submitting,
+    /// 已提交到系统打印队列
+    Spooled,
+    /// 系统打印队列报告正在打印
+    Printing,
+    /// 打印完成
+    Printed,
+    /// 失败 (见 error 字段)
+    Failed,
+}
+
+impl JobStatus {
+    /// 任务已结束 (打印完成或失败)
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Printed | JobStatus::Failed)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Rendering => "rendering",
+            JobStatus::Submitting => "submitting",
+            JobStatus::Spooled => "spooled",
+            JobStatus::Printing => "printing",
+            JobStatus::Printed => "printed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "queued" => JobStatus::Queued,
+            "rendering" => JobStatus::Rendering,
+            "submitting" => JobStatus::Submitting,
+            "spooled" => JobStatus::Spooled,
+            "printing" => JobStatus::Printing,
+            "printed" => JobStatus::Printed,
+            _ => JobStatus::Failed,
+        }
+    }
+}
+
+/// 登记任务失败：相同 task_id 的任务尚未结束
+#[derive(Debug)]
+pub struct TaskInProgress;
+
+/// 任务记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRecord {
+    pub task_id: String,
+    /// 任务来源，如 "content" / "template"
+    pub kind: String,
+    /// 目标打印机名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub printer: Option<String>,
+    pub status: JobStatus,
+    /// 失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 失败分类码 (如 "element_render"、"printer_not_found")，供客户端分支处理
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// 系统打印队列中的作业 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spooler_job_id: Option<u64>,
+    /// 打印内容副本 (PDF/PNG) 的路径，可通过 GET /jobs/{id}/artifact 下载
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    /// 所属批次 (批量打印)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// 提交任务时使用的 API Key 名称 (未启用鉴权或内部通道为空)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// 模板名称 (模板打印)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_name: Option<String>,
+    /// 渲染后的页数 (原始指令任务为空)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    /// 未导致任务失败的问题 (如远程图片下载超时后使用了占位图)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 创建时间 (Unix 毫秒)
+    pub created_at: u64,
+    /// 最后更新时间 (Unix 毫秒)
+    pub updated_at: u64,
+}
+
+/// 任务列表查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobQuery {
+    pub status: Option<JobStatus>,
+    pub printer: Option<String>,
+    pub batch_id: Option<String>,
+    /// 创建时间下限 (Unix 毫秒，含)
+    pub from: Option<u64>,
+    /// 创建时间上限 (Unix 毫秒，不含)
+    pub to: Option<u64>,
+    /// 每页条数 (Default: 50，最大 500)
+    pub limit: Option<usize>,
+    /// 跳过条数 (Default: 0)
+    pub offset: Option<usize>,
+}
+
+/// 分页结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobPage {
+    /// 满足条件的总数
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<JobRecord>,
+}
+
+/// 批次汇总
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    pub batch_id: String,
+    pub total: usize,
+    /// 各状态的任务数
+    pub counts: HashMap<JobStatus, usize>,
+    /// 全部任务均已结束 (已提交到系统队列、打印完成或失败)
+    pub finished: bool,
+    /// 批次内的任务，按提交顺序
+    pub items: Vec<JobRecord>,
+}
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    task_id        TEXT PRIMARY KEY,
+    kind           TEXT NOT NULL,
+    printer        TEXT,
+    status         TEXT NOT NULL,
+    error          TEXT,
+    spooler_job_id INTEGER,
+    output_path    TEXT,
+    payload        TEXT,
+    batch_id       TEXT,
+    error_code     TEXT,
+    api_key        TEXT,
+    template_name  TEXT,
+    page_count     INTEGER,
+    warnings       TEXT,
+    created_at     INTEGER NOT NULL,
+    updated_at     INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_jobs_created_at ON jobs (created_at);
+CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs (status);
+
+-- 审计日志：只允许追加，触发器拒绝修改与删除
+CREATE TABLE IF NOT EXISTS audit_log (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp     INTEGER NOT NULL,
+    task_id       TEXT NOT NULL,
+    kind          TEXT NOT NULL,
+    api_key       TEXT,
+    template_name TEXT,
+    printer       TEXT,
+    page_count    INTEGER,
+    outcome       TEXT NOT NULL,
+    error         TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log (timestamp);
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+";
+
+const COLUMNS: &str = "task_id, kind, printer, status, error, spooler_job_id, output_path, \
+     created_at, updated_at, batch_id, error_code, api_key, template_name, page_count, warnings";
+
+/// COLUMNS 之后附加查询的 payload 列的位置
+const PAYLOAD_COLUMN: usize = 15;
+
+impl JobQuery {
+    /// 生成 WHERE 子句及其参数
+    fn where_clause(&self) -> (String, Vec<SqlValue>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        if let Some(status) = self.status {
+            conditions.push("status = ?");
+            values.push(SqlValue::Text(status.as_str().to_string()));
+        }
+        if let Some(printer) = &self.printer {
+            conditions.push("printer = ?");
+            values.push(SqlValue::Text(printer.clone()));
+        }
+        if let Some(batch_id) = &self.batch_id {
+            conditions.push("batch_id = ?");
+            values.push(SqlValue::Text(batch_id.clone()));
+        }
+        if let Some(from) = self.from {
+            conditions.push("created_at >= ?");
+            values.push(SqlValue::Integer(from as i64));
+        }
+        if let Some(to) = self.to {
+            conditions.push("created_at < ?");
+            values.push(SqlValue::Integer(to as i64));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+}
+
+/// 当前 Unix 时间戳 (毫秒)
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn record_from_row(row: &Row) -> rusqlite::Result<JobRecord> {
+    Ok(JobRecord {
+        task_id: row.get(0)?,
+        kind: row.get(1)?,
+        printer: row.get(2)?,
+        status: JobStatus::parse(&row.get::<_, String>(3)?),
+        error: row.get(4)?,
+        spooler_job_id: row.get::<_, Option<i64>>(5)?.map(|v| v as u64),
+        output_path: row.get(6)?,
+        created_at: row.get::<_, i64>(7)? as u64,
+        updated_at: row.get::<_, i64>(8)? as u64,
+        batch_id: row.get(9)?,
+        error_code: row.get(10)?,
+        api_key: row.get(11)?,
+        template_name: row.get(12)?,
+        page_count: row.get::<_, Option<i64>>(13)?.map(|v| v as u32),
+        warnings: row
+            .get::<_, Option<String>>(14)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+    })
+}
+
+/// 状态事件缓冲区大小，订阅方处理过慢时丢弃最旧的事件
+const EVENT_CAPACITY: usize = 256;
+
+/// 任务存储，持久化在内嵌 SQLite 数据库中，Agent 重启后历史记录仍然可查
+/// 每次任务记录变化都会广播给订阅方 (gRPC 流、桌面端事件等)
+#[derive(Clone)]
+pub struct JobStore {
+    conn: Arc<Mutex<Connection>>,
+    events: broadcast::Sender<JobRecord>,
+}
+
+impl JobStore {
+    /// 打开 (或创建) 任务数据库
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Create data dir error: {}", e))?;
+        }
+        let conn = Connection::open(path).map_err(|e| format!("Open job database error: {}", e))?;
+        Self::init(conn)
+    }
+
+    /// 内存数据库 (数据库文件无法打开时的兜底)
+    pub fn in_memory() -> Self {
+        let conn = Connection::open_in_memory().expect("Failed to open in-memory database");
+        Self::init(conn).expect("Failed to initialize in-memory database")
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("Initialize job database error: {}", e))?;
+        Self::migrate(&conn).map_err(|e| format!("Migrate job database error: {}", e))?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        })
+    }
+
+    /// 订阅任务状态变化
+    pub fn subscribe(&self) -> broadcast::Receiver<JobRecord> {
+        self.events.subscribe()
+    }
+
+    /// 旧版数据库缺少 batch_id、error_code、api_key、template_name、page_count、warnings 列时补上
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        for (column, sql_type) in [
+            ("batch_id", "TEXT"),
+            ("error_code", "TEXT"),
+            ("api_key", "TEXT"),
+            ("template_name", "TEXT"),
+            ("page_count", "INTEGER"),
+            ("warnings", "TEXT"),
+        ] {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info('jobs') WHERE name = ?1")?
+                .exists([column])?;
+            if !exists {
+                conn.execute_batch(&format!("ALTER TABLE jobs ADD COLUMN {} {};", column, sql_type))?;
+            }
+        }
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_jobs_batch_id ON jobs (batch_id);")
+    }
+
+    /// 登记新任务 (状态为 queued)。相同 task_id 的旧记录已结束 (打印完成或失败) 时覆盖 (客户端重试)，
+    /// 尚未结束时返回 TaskInProgress，不影响正在执行的任务
+    /// api_key 为调用方使用的 Key 名称，记入审计日志
+    pub fn create(
+        &self,
+        task_id: &str,
+        kind: &str,
+        printer: Option<String>,
+        api_key: Option<&str>,
+    ) -> Result<JobRecord, TaskInProgress> {
+        self.create_in_batch(task_id, kind, printer, None, api_key)
+    }
+
+    /// 登记属于某个批次的新任务
+    pub fn create_in_batch(
+        &self,
+        task_id: &str,
+        kind: &str,
+        printer: Option<String>,
+        batch_id: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Result<JobRecord, TaskInProgress> {
+        let now = now_millis();
+        let record = JobRecord {
+            task_id: task_id.to_string(),
+            kind: kind.to_string(),
+            printer,
+            status: JobStatus::Queued,
+            error: None,
+            error_code: None,
+            spooler_job_id: None,
+            output_path: None,
+            batch_id: batch_id.map(str::to_string),
+            api_key: api_key.map(str::to_string),
+            template_name: None,
+            page_count: None,
+            warnings: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        let conn = self.conn.lock().unwrap();
+        // 查询与写入在同一把锁内完成，并发提交相同 task_id 时只有一个成功
+        if Self::get_locked(&conn, task_id).is_some_and(|existing| !existing.status.is_finished()) {
+            return Err(TaskInProgress);
+        }
+        let result = conn.execute(
+            "INSERT OR REPLACE INTO jobs
+                (task_id, kind, printer, status, error, spooler_job_id, output_path, payload,
+                 batch_id, api_key, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, NULL, NULL, NULL, NULL, ?5, ?6, ?7, ?7)",
+            params![
+                record.task_id,
+                record.kind,
+                record.printer,
+                record.status.as_str(),
+                record.batch_id,
+                record.api_key,
+                now as i64
+            ],
+        );
+        drop(conn);
+        if let Err(e) = result {
+            error!("任务记录写入失败 ({}): {}", task_id, e);
+        }
+        // 没有订阅方时 send 返回错误，忽略即可
+        let _ = self.events.send(record.clone());
+        Ok(record)
+    }
+
+    pub fn get(&self, task_id: &str) -> Option<JobRecord> {
+        let conn = self.conn.lock().unwrap();
+        Self::get_locked(&conn, task_id)
+    }
+
+    fn get_locked(conn: &Connection, task_id: &str) -> Option<JobRecord> {
+        conn.query_row(
+            &format!("SELECT {} FROM jobs WHERE task_id = ?1", COLUMNS),
+            params![task_id],
+            record_from_row,
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            error!("任务记录读取失败 ({}): {}", task_id, e);
+            None
+        })
+    }
+
+    /// 按条件查询任务，按创建时间倒序分页返回
+    pub fn list(&self, query: &JobQuery) -> JobPage {
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let (where_clause, values) = query.where_clause();
+
+        let conn = self.conn.lock().unwrap();
+        let total = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM jobs {}", where_clause),
+                params_from_iter(values.iter()),
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap_or(0) as usize;
+
+        let sql = format!(
+            "SELECT {} FROM jobs {} ORDER BY created_at DESC LIMIT {} OFFSET {}",
+            COLUMNS, where_clause, limit, offset
+        );
+        let items = conn
+            .prepare(&sql)
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map(params_from_iter(values.iter()), record_from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("任务列表查询失败: {}", e);
+                Vec::new()
+            });
+
+        JobPage {
+            total,
+            offset,
+            limit,
+            items,
+        }
+    }
+
+    /// 批次汇总，批次不存在时返回 None
+    pub fn batch(&self, batch_id: &str) -> Option<BatchSummary> {
+        let conn = self.conn.lock().unwrap();
+        let items = conn
+            .prepare(&format!(
+                "SELECT {} FROM jobs WHERE batch_id = ?1 ORDER BY created_at ASC, rowid ASC",
+                COLUMNS
+            ))
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map(params![batch_id], record_from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("批次查询失败 ({}): {}", batch_id, e);
+                Vec::new()
+            });
+        if items.is_empty() {
+            return None;
+        }
+
+        let mut counts = HashMap::new();
+        for item in &items {
+            *counts.entry(item.status).or_insert(0) += 1;
+        }
+        let finished = items
+            .iter()
+            .all(|i| !matches!(i.status, JobStatus::Queued | JobStatus::Rendering | JobStatus::Submitting));
+
+        Some(BatchSummary {
+            batch_id: batch_id.to_string(),
+            total: items.len(),
+            counts,
+            finished,
+            items,
+        })
+    }
+
+    /// 修改任务记录并刷新更新时间
+    /// 状态变为 spooled / printed / failed 时追加一条审计日志
+    pub fn update<F: FnOnce(&mut JobRecord)>(&self, task_id: &str, f: F) {
+        let conn = self.conn.lock().unwrap();
+        let Some(mut record) = Self::get_locked(&conn, task_id) else {
+            return;
+        };
+        let previous = record.status;
+        f(&mut record);
+        record.updated_at = now_millis();
+
+        let result = conn.execute(
+            "UPDATE jobs SET printer = ?2, status = ?3, error = ?4, spooler_job_id = ?5,
+                output_path = ?6, updated_at = ?7, error_code = ?8, template_name = ?9, page_count = ?10,
+                warnings = ?11
+             WHERE task_id = ?1",
+            params![
+                record.task_id,
+                record.printer,
+                record.status.as_str(),
+                record.error,
+                record.spooler_job_id.map(|v| v as i64),
+                record.output_path,
+                record.updated_at as i64,
+                record.error_code,
+                record.template_name,
+                record.page_count.map(i64::from),
+                (!record.warnings.is_empty())
+                    .then(|| serde_json::to_string(&record.warnings).unwrap_or_default())
+            ],
+        );
+        match result {
+            Ok(_) => {
+                let outcome = matches!(record.status, JobStatus::Spooled | JobStatus::Printed | JobStatus::Failed);
+                if outcome && record.status != previous {
+                    Self::append_audit(&conn, &record);
+                }
+                let _ = self.events.send(record);
+            }
+            Err(e) => error!("任务记录更新失败 ({}): {}", task_id, e),
+        }
+    }
+
+    fn append_audit(conn: &Connection, record: &JobRecord) {
+        let result = conn.execute(
+            "INSERT INTO audit_log
+                (timestamp, task_id, kind, api_key, template_name, printer, page_count, outcome, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.updated_at as i64,
+                record.task_id,
+                record.kind,
+                record.api_key,
+                record.template_name,
+                record.printer,
+                record.page_count.map(i64::from),
+                record.status.as_str(),
+                record.error
+            ],
+        );
+        if let Err(e) = result {
+            error!("审计日志写入失败 ({}): {}", record.task_id, e);
+        }
+    }
+
+    /// 按时间范围查询审计日志，按时间顺序返回
+    pub fn audit_log(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(from) = query.from {
+            conditions.push("timestamp >= ?");
+            values.push(SqlValue::Integer(from as i64));
+        }
+        if let Some(to) = query.to {
+            conditions.push("timestamp < ?");
+            values.push(SqlValue::Integer(to as i64));
+        }
+        if let Some(api_key) = &query.api_key {
+            conditions.push("api_key = ?");
+            values.push(SqlValue::Text(api_key.clone()));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT timestamp, task_id, kind, api_key, template_name, printer, page_count, outcome, error
+             FROM audit_log {} ORDER BY id ASC",
+            where_clause
+        );
+        let conn = self.conn.lock().unwrap();
+        conn.prepare(&sql)
+            .and_then(|mut stmt| {
+                stmt.query_map(params_from_iter(values.iter()), |row| {
+                    Ok(AuditEntry {
+                        timestamp: row.get::<_, i64>(0)? as u64,
+                        task_id: row.get(1)?,
+                        kind: row.get(2)?,
+                        api_key: row.get(3)?,
+                        template_name: row.get(4)?,
+                        printer: row.get(5)?,
+                        page_count: row.get::<_, Option<i64>>(6)?.map(|v| v as u32),
+                        outcome: row.get(7)?,
+                        error: row.get(8)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .unwrap_or_else(|e| {
+                error!("审计日志查询失败: {}", e);
+                Vec::new()
+            })
+    }
+
+    /// 保存任务的原始请求内容，用于崩溃/重启后恢复未完成的任务
+    pub fn set_payload(&self, task_id: &str, payload: &str) {
+        let result = self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET payload = ?2 WHERE task_id = ?1",
+            params![task_id, payload],
+        );
+        if let Err(e) = result {
+            error!("任务内容写入失败 ({}): {}", task_id, e);
+        }
+    }
+
+    /// 未完成 (queued / rendering / submitting) 且保存了请求内容的任务，按创建顺序返回
+    pub fn unfinished(&self) -> Vec<(JobRecord, String)> {
+        let conn = self.conn.lock().unwrap();
+        let sql = format!(
+            "SELECT {}, payload FROM jobs
+             WHERE status IN ('queued', 'rendering', 'submitting') AND payload IS NOT NULL
+             ORDER BY created_at ASC, rowid ASC",
+            COLUMNS
+        );
+        conn.prepare(&sql)
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map([], |row| Ok((record_from_row(row)?, row.get::<_, String>(PAYLOAD_COLUMN)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("未完成任务查询失败: {}", e);
+                Vec::new()
+            })
+    }
+
+    /// 最近一个保存了任务内容的任务 (用于重打上一单)
+    pub fn last_with_payload(&self) -> Option<(JobRecord, String)> {
+        let conn = self.conn.lock().unwrap();
+        let sql = format!(
+            "SELECT {}, payload FROM jobs WHERE payload IS NOT NULL
+             ORDER BY created_at DESC, rowid DESC LIMIT 1",
+            COLUMNS
+        );
+        conn.query_row(&sql, [], |row| {
+            Ok((record_from_row(row)?, row.get::<_, String>(PAYLOAD_COLUMN)?))
+        })
+        .optional()
+        .unwrap_or_else(|e| {
+            error!("最近任务查询失败: {}", e);
+            None
+        })
+    }
+
+    /// 已提交到系统打印队列、尚未确认打印结果的任务 (spooled / printing)
+    pub fn in_spooler(&self) -> Vec<(JobRecord, String)> {
+        let conn = self.conn.lock().unwrap();
+        let sql = format!(
+            "SELECT {}, payload FROM jobs
+             WHERE status IN ('spooled', 'printing')
+               AND spooler_job_id IS NOT NULL AND payload IS NOT NULL
+             ORDER BY created_at ASC",
+            COLUMNS
+        );
+        conn.prepare(&sql)
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map([], |row| Ok((record_from_row(row)?, row.get::<_, String>(PAYLOAD_COLUMN)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("打印中任务查询失败: {}", e);
+                Vec::new()
+            })
+    }
+
+    pub fn set_status(&self, task_id: &str, status: JobStatus) {
+        self.update(task_id, |r| r.status = status);
+    }
+
+    pub fn set_output(&self, task_id: &str, path: String) {
+        self.update(task_id, |r| r.output_path = Some(path));
+    }
+
+    /// 记录渲染结果：模板名称与页数 (写入审计日志)
+    pub fn set_rendered(&self, task_id: &str, template_name: Option<String>, page_count: Option<u32>) {
+        self.update(task_id, |r| {
+            r.template_name = template_name;
+            r.page_count = page_count;
+        });
+    }
+
+    /// 追加任务告警 (不影响任务状态)
+    pub fn add_warnings(&self, task_id: &str, warnings: Vec<String>) {
+        if warnings.is_empty() {
+            return;
+        }
+        self.update(task_id, |r| r.warnings.extend(warnings));
+    }
+
+    pub fn mark_spooled(&self, task_id: &str, spooler_job_id: u64) {
+        self.update(task_id, |r| {
+            r.status = JobStatus::Spooled;
+            r.spooler_job_id = Some(spooler_job_id);
+        });
+    }
+
+    pub fn mark_failed(&self, task_id: &str, error: String) {
+        self.update(task_id, |r| {
+            r.status = JobStatus::Failed;
+            r.error = Some(error);
+            r.error_code = None;
+        });
+    }
+
+    /// 标记失败并记录错误分类码
+    pub fn mark_failed_with_code(&self, task_id: &str, code: &str, error: String) {
+        self.update(task_id, |r| {
+            r.status = JobStatus::Failed;
+            r.error = Some(error);
+            r.error_code = Some(code.to_string());
+        });
+    }
+
+    /// 任务执行失败：记录分类码与错误信息
+    pub fn mark_error(&self, task_id: &str, error: &PrintError) {
+        self.mark_failed_with_code(task_id, error.code(), error.to_string());
+    }
+}
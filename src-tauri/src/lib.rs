@@ -1,28 +1,205 @@
 // 引入模块
+mod allowlist;
+mod api_error;
+mod audit;
+mod auth;
+mod cloud;
+mod commands;
+mod config;
+mod cors;
+mod crash;
+mod diagnostics;
+mod discovery;
 mod engine;
+mod error;
+mod events;
+mod hot_reload;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod jobs;
+mod logging;
+mod mqtt;
+mod pools;
+mod printing;
+mod queue;
+mod remote;
+mod remote_assets;
+mod retention;
 mod server;
-use tauri::Manager;
+mod settings;
+mod supervisor;
+mod templates;
+mod tls;
+mod tracker;
+#[cfg(desktop)]
+mod tray;
+#[cfg(desktop)]
+mod updater;
+mod validator;
+// 模板协议、渲染器与输出后端 (deepprint-core)
+use deepprint_core::{deep_print_schema, output, renderer};
+use std::path::PathBuf;
+use tauri::{Manager, WindowEvent};
+use tracing::{error, info};
+
+/// 无界面模式 (--headless)：只启动 HTTP 服务、打印队列与打印子系统，不创建窗口和托盘，
+/// 用于 Windows Server 后台、Linux 自助终端等不需要 (或无法) 显示界面的环境
+pub fn run_headless() {
+    let config = config::AgentConfig::load();
+    logging::init(config.log_level);
+    crash::install(&config);
+    load_fallback_fonts();
+
+    let runtime = tokio::runtime::Runtime::new().expect("error while starting tokio runtime");
+    runtime.block_on(async {
+        let state = server::init_state();
+        info!("DeepPrint Agent 以无界面模式运行");
+        tokio::select! {
+            _ = server::start_server(state) => {}
+            _ = shutdown_signal() => info!("收到退出信号，DeepPrint Agent 停止"),
+        }
+    });
+}
+
+/// 系统缺少中日文字体时加载后备字体 (deepprint-core/fonts/subset.py 生成)：
+/// 以 cjk-fonts feature 编译时字体已内置，否则依次查找 DEEPPRINT_FONTS_DIR、
+/// 程序目录 (安装包资源目录) 与数据目录下的 fonts 目录
+fn load_fallback_fonts() {
+    if !deepprint_core::fonts::fallback_needed() {
+        return;
+    }
+    if deepprint_core::fonts::embedded() {
+        info!("系统缺少中日文字体，使用内置后备字体");
+        return;
+    }
+    let dirs: Vec<PathBuf> = std::env::var_os("DEEPPRINT_FONTS_DIR")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(std::env::current_exe().ok().and_then(|exe| Some(exe.parent()?.join("fonts"))))
+        .chain([config::AgentConfig::data_dir().join("fonts")])
+        .collect();
+    let Some(dir) = dirs.iter().find(|dir| dir.is_dir()) else {
+        let searched: Vec<String> = dirs.iter().map(|dir| dir.display().to_string()).collect();
+        error!("系统缺少中日文字体，且未找到后备字体目录 ({})，中日文将无法显示", searched.join(", "));
+        return;
+    };
+    let errors = deepprint_core::fonts::load_fallback(dir);
+    for e in &errors {
+        error!("后备字体加载失败: {}", e);
+    }
+    if errors.is_empty() {
+        info!("已加载后备字体: {}", dir.display());
+    }
+}
+
+/// 等待 Ctrl+C (以及 Unix 下服务管理器发送的 SIGTERM)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    #[cfg(desktop)]
+    {
+        let config = config::AgentConfig::load();
+        logging::init(config.log_level);
+        crash::install(&config);
+        load_fallback_fonts();
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .invoke_handler(tauri::generate_handler![
+            commands::list_printers,
+            commands::print_template,
+            commands::preview_template,
+            commands::preview_pages,
+            commands::list_templates,
+            commands::get_job_status,
+            commands::watch_preview,
+            commands::unwatch_preview,
+            commands::get_server_address,
+            commands::get_settings,
+            commands::update_settings,
+            commands::open_settings,
+            commands::open_preview,
+            commands::get_logs,
+            commands::export_diagnostics,
+            commands::open_logs,
+        ])
         .setup(|app| {
+            // 移动端没有通用的用户目录，配置、数据库与日志使用 Tauri 提供的应用目录
+            #[cfg(mobile)]
+            {
+                config::AgentConfig::use_app_dirs(
+                    app.path().app_data_dir()?,
+                    app.path().app_config_dir()?,
+                );
+                let config = config::AgentConfig::load();
+                logging::init(config.log_level);
+                crash::install(&config);
+                load_fallback_fonts();
+            }
+
+            // 共享状态需在异步运行时中创建 (打印队列会启动后台任务)
+            let state = tauri::async_runtime::block_on(async { server::init_state() });
+            app.manage(state.clone());
+            #[cfg(desktop)]
+            {
+                tray::create(app.handle(), state.clone())?;
+                app.handle().plugin(tauri_plugin_updater::Builder::new().build())?;
+                updater::start(app.handle(), state.config.read().unwrap().updater.clone());
+            }
+            events::forward(app.handle(), &state);
+            // 移动端没有托盘，始终显示窗口
+            let start_in_tray = cfg!(desktop)
+                && (std::env::args().skip(1).any(|arg| arg == "--tray")
+                    || state.config.read().unwrap().ui.start_in_tray);
+
             // --- 核心修改：启动 Axum 后台服务 ---
             // 使用 Tauri 的异步运行时生成一个独立任务
             // 这样 HTTP 服务不会阻塞 GUI 界面
-            tauri::async_runtime::spawn(async {
-                server::start_server().await;
-            });
+            tauri::async_runtime::spawn(server::start_server(state));
             // ----------------------------------
 
-            // 仅做演示：启动时打开前端窗口
-            let main_window = app.get_webview_window("main").unwrap();
-            #[cfg(debug_assertions)] // 仅在开发模式打开控制台
-            main_window.open_devtools();
+            // 主窗口默认隐藏创建 (tauri.conf.json)，收银台等场景只显示托盘图标
+            if !start_in_tray {
+                let main_window = app.get_webview_window("main").unwrap();
+                main_window.show()?;
+                #[cfg(debug_assertions)] // 仅在开发模式打开控制台
+                main_window.open_devtools();
+            }
 
             Ok(())
         })
+        // 关闭主窗口时隐藏到托盘，打印服务继续运行 (从托盘菜单退出)
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() != "main" {
+                    return;
+                }
+                api.prevent_close();
+                let _ = window.hide();
+            }
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
-}
\ No newline at end of file
+}
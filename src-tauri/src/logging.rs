@@ -0,0 +1,169 @@
+use crate::config::{AgentConfig, LogLevel};
+use crate::jobs::now_millis;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Layer, Registry};
+
+/// 运行时调整日志级别的句柄
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+/// 最近的日志 (日志窗口与诊断包使用)
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// 内存中保留的日志条数
+const RECENT_CAPACITY: usize = 2000;
+/// 日志文件超过该大小时在启动时轮转为 agent.log.1
+const MAX_LOG_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// 一条结构化日志
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// Unix 毫秒
+    pub timestamp: u64,
+    pub level: String,
+    /// 产生日志的模块
+    pub target: String,
+    pub message: String,
+    /// 其余结构化字段
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// 初始化日志输出 (控制台、日志文件、内存中的最近日志)，重复调用时忽略
+pub fn init(level: LogLevel) {
+    let (filter, handle) = reload::Layer::new(level_filter(level));
+    let file = open_log_file().map(|file| {
+        fmt::layer()
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+    });
+    if tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file)
+        .with(RecentLayer)
+        .try_init()
+        .is_ok()
+    {
+        let _ = LEVEL.set(handle);
+    }
+}
+
+/// 修改日志级别，立即生效
+pub fn set_level(level: LogLevel) {
+    if let Some(handle) = LEVEL.get() {
+        let _ = handle.modify(|filter| *filter = level_filter(level));
+    }
+}
+
+/// 日志文件目录
+pub fn log_dir() -> PathBuf {
+    AgentConfig::data_dir().join("logs")
+}
+
+/// 当前日志文件及轮转后的旧文件
+pub fn log_files() -> Vec<PathBuf> {
+    let dir = log_dir();
+    [dir.join("agent.log.1"), dir.join("agent.log")]
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// 最近的日志，按时间顺序；level 指定时只返回该级别及更严重的日志
+pub fn recent(limit: usize, level: Option<LogLevel>) -> Vec<LogEntry> {
+    let min = level.map(level_filter).unwrap_or(LevelFilter::TRACE);
+    let recent = RECENT.lock().unwrap();
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|entry| {
+            entry
+                .level
+                .parse::<tracing::Level>()
+                .map(|level| level <= min)
+                .unwrap_or(true)
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+    entries.reverse();
+    entries
+}
+
+fn open_log_file() -> Option<File> {
+    let dir = log_dir();
+    fs::create_dir_all(&dir).ok()?;
+    let path = dir.join("agent.log");
+    if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_FILE_BYTES) {
+        let _ = fs::rename(&path, dir.join("agent.log.1"));
+    }
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::Error => LevelFilter::ERROR,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Trace => LevelFilter::TRACE,
+    }
+}
+
+/// 将日志事件保存到内存环形缓冲区
+struct RecentLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let entry = LogEntry {
+            timestamp: now_millis(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        };
+        let mut recent = RECENT.lock().unwrap();
+        if recent.len() >= RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+}
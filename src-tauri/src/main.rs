@@ -2,5 +2,24 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    deepprint_agent_lib::run()
+    // --headless: 只运行打印服务，不启动界面
+    if std::env::args().skip(1).any(|arg| arg == "--headless") {
+        attach_parent_console();
+        deepprint_agent_lib::run_headless()
+    } else {
+        deepprint_agent_lib::run()
+    }
 }
+
+/// 发布版是 GUI 子系统程序，启动时没有控制台：从终端以 --headless 运行时
+/// 附加到父进程的控制台，日志与错误信息才能输出到终端 (无父控制台时调用失败，忽略)
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
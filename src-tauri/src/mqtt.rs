@@ -0,0 +1,98 @@
+use crate::config::MqttConfig;
+use crate::remote::{self, RemoteTasks};
+use crate::server::AppState;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS, Transport};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+/// 连接断开后的重试间隔
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// MQTT 云端派单模式
+/// 订阅 {topicPrefix}/{storeId}/jobs 接收打印指令 (与 POST /print/template 请求体相同)，
+/// 受理结果发布到 {topicPrefix}/{storeId}/acks，任务状态变化发布到 {topicPrefix}/{storeId}/status。
+/// Agent 只需出站连接，门店无需开放任何入站端口
+pub async fn run(state: AppState, config: MqttConfig) {
+    let base = format!("{}/{}", config.topic_prefix, config.store_id);
+    let jobs_topic = format!("{}/jobs", base);
+    let acks_topic = format!("{}/acks", base);
+    let status_topic = format!("{}/status", base);
+
+    let client_id = config
+        .client_id
+        .clone()
+        .unwrap_or_else(|| format!("deepprint-{}", config.store_id));
+    let mut options = MqttOptions::new(client_id, config.host.clone(), config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(username) = &config.username {
+        options.set_credentials(username.clone(), config.password.clone().unwrap_or_default());
+    }
+    if config.tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 32);
+    let tracker = RemoteTasks::default();
+
+    // 回传远程任务的状态变化
+    {
+        let client = client.clone();
+        let tracker = tracker.clone();
+        let mut events = state.jobs.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(record) => {
+                        if !tracker.should_report(&record) {
+                            continue;
+                        }
+                        let Ok(payload) = serde_json::to_vec(&record) else {
+                            continue;
+                        };
+                        if let Err(e) = client
+                            .publish(&status_topic, QoS::AtLeastOnce, false, payload)
+                            .await
+                        {
+                            warn!("MQTT 状态发布失败: {}", e);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("MQTT 状态回传过慢，丢弃 {} 条事件", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    info!("MQTT 连接 {}:{} (topic: {})", config.host, config.port, jobs_topic);
+    loop {
+        match eventloop.poll().await {
+            // 每次 (重新) 连接成功后订阅，避免 clean session 下丢失订阅
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("MQTT 已连接");
+                if let Err(e) = client.subscribe(&jobs_topic, QoS::AtLeastOnce).await {
+                    warn!("MQTT 订阅失败: {}", e);
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let ack = remote::dispatch(&state, &tracker, &publish.payload).await;
+                info!("MQTT 打印指令: {:?} -> {}", ack.task_id, ack.message);
+                if let Ok(payload) = serde_json::to_vec(&ack) {
+                    if let Err(e) = client
+                        .publish(&acks_topic, QoS::AtLeastOnce, false, payload)
+                        .await
+                    {
+                        warn!("MQTT 受理结果发布失败: {}", e);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("MQTT 连接异常: {}，{} 秒后重连", e, RECONNECT_DELAY.as_secs());
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
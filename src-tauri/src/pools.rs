@@ -0,0 +1,112 @@
+use crate::config::PrinterPool;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// 池成员状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolMemberStatus {
+    pub printer: String,
+    pub online: bool,
+    /// Agent 启动以来分配到该成员的任务数
+    pub dispatched: u64,
+}
+
+/// 打印机池状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatus {
+    pub name: String,
+    pub printers: Vec<PoolMemberStatus>,
+    /// 在线成员数
+    pub online: usize,
+}
+
+/// 单个池的调度状态
+#[derive(Default)]
+struct Rotation {
+    /// 下一次轮询的起始成员
+    next: usize,
+    dispatched: HashMap<String, u64>,
+}
+
+/// 打印机池调度：按轮询顺序分配任务，跳过离线的成员 (内存状态，重启后从头开始)
+#[derive(Clone, Default)]
+pub struct PoolBalancer {
+    rotations: Arc<Mutex<HashMap<String, Rotation>>>,
+}
+
+impl PoolBalancer {
+    /// 为任务选择池中的打印机：从轮询位置开始依次探测，选择第一个在线的成员；
+    /// 全部离线时仍按轮询顺序返回，由打印机排队或在执行时报错
+    /// online 为阻塞探测，不在锁内执行
+    pub fn select(
+        &self,
+        name: &str,
+        pool: &PrinterPool,
+        online: impl Fn(&str) -> bool,
+    ) -> Option<String> {
+        let count = pool.printers.len();
+        if count == 0 {
+            return None;
+        }
+        // 先推进游标，并发请求从不同成员开始
+        let start = {
+            let mut rotations = self.rotations.lock().unwrap();
+            let rotation = rotations.entry(name.to_string()).or_default();
+            let start = rotation.next % count;
+            rotation.next = start + 1;
+            start
+        };
+        let selected = match (0..count)
+            .map(|i| &pool.printers[(start + i) % count])
+            .find(|printer| online(printer))
+        {
+            Some(printer) => printer.clone(),
+            None => {
+                warn!("打印机池 {} 中没有在线的打印机，仍分配到 {}", name, pool.printers[start]);
+                pool.printers[start].clone()
+            }
+        };
+        if selected != pool.printers[start] {
+            info!("打印机池 {}: {} 离线，切换到 {}", name, pool.printers[start], selected);
+        }
+
+        let mut rotations = self.rotations.lock().unwrap();
+        let rotation = rotations.entry(name.to_string()).or_default();
+        *rotation.dispatched.entry(selected.clone()).or_default() += 1;
+        Some(selected)
+    }
+
+    /// 池状态 (实时探测各成员是否在线)
+    pub fn status(
+        &self,
+        name: &str,
+        pool: &PrinterPool,
+        online: impl Fn(&str) -> bool,
+    ) -> PoolStatus {
+        let dispatched = self
+            .rotations
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|rotation| rotation.dispatched.clone())
+            .unwrap_or_default();
+        let printers: Vec<PoolMemberStatus> = pool
+            .printers
+            .iter()
+            .map(|printer| PoolMemberStatus {
+                printer: printer.clone(),
+                online: online(printer),
+                dispatched: dispatched.get(printer).copied().unwrap_or(0),
+            })
+            .collect();
+        PoolStatus {
+            name: name.to_string(),
+            online: printers.iter().filter(|p| p.online).count(),
+            printers,
+        }
+    }
+}
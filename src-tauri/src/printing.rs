@@ -0,0 +1,420 @@
+#[cfg(all(unix, desktop))]
+mod cups;
+pub mod direct;
+pub mod escpos_text;
+mod ipp;
+pub mod media;
+#[cfg(mobile)]
+mod mobile;
+pub mod raster;
+pub mod usb;
+#[cfg(windows)]
+mod winspool;
+
+use crate::deep_print_schema::{CutMode, CutSettings};
+use direct::DirectTarget;
+use media::MediaScaling;
+#[cfg(mobile)]
+pub use mobile::{Printer, PrinterState};
+#[cfg(all(not(unix), desktop))]
+use printers::common::base::job::PrinterJobOptions;
+#[cfg(desktop)]
+pub use printers::common::base::printer::{Printer, PrinterState};
+use serde::{Deserialize, Serialize};
+
+/// 双面打印模式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplexMode {
+    /// 单面
+    Simplex,
+    /// 双面，长边翻转
+    LongEdge,
+    /// 双面，短边翻转
+    ShortEdge,
+}
+
+/// 色彩模式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorMode {
+    Color,
+    Monochrome,
+}
+
+/// 单个作业的份数上限 (同渲染编排的份数上限)：直连栅格与原始数据的份数由 Agent 重复发送，
+/// 过大的份数直接拒绝
+pub use crate::output::MAX_COPIES;
+
+/// 标准打印作业选项
+/// 未设置的项沿用打印机驱动的默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintOptions {
+    /// 份数 (Default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copies: Option<u32>,
+    /// 双面模式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplex: Option<DuplexMode>,
+    /// 彩色/黑白
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_mode: Option<ColorMode>,
+    /// 纸张尺寸名称，如 "A4", "Letter", "Custom.100x60mm"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    /// 进纸盒，如 "Tray1", "Manual"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tray: Option<String>,
+    /// 缩放以适应纸张 (文档尺寸与纸张不一致时)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit_to_page: Option<bool>,
+    /// 模板尺寸与打印机纸张不一致时的处理方式 (Default: auto)，
+    /// 未指定 media 时自动选择打印机支持的最接近纸张
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_scaling: Option<MediaScaling>,
+    /// 页面方向与纸张方向不一致时自动旋转 90° (Default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_rotate: Option<bool>,
+    /// 切纸方式 (直连小票/标签打印机栅格输出时生效)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cut: Option<CutMode>,
+    /// 每份之后都切纸 (Default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cut_per_copy: Option<bool>,
+    /// 切纸前 (或打印结束后) 走纸行数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_lines: Option<u8>,
+    /// 跳过重复内容检测，确需重打相同内容时使用 (Default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+}
+
+impl PrintOptions {
+    /// 检查选项取值，份数须在 1..=MAX_COPIES 之间
+    pub fn validate(&self) -> Result<(), String> {
+        match self.copies {
+            Some(copies) if !(1..=MAX_COPIES).contains(&copies) => Err(format!(
+                "copies must be between 1 and {}, got {}",
+                MAX_COPIES, copies
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// 用 fallback 补全未设置的选项
+    pub fn with_defaults(self, fallback: &PrintOptions) -> PrintOptions {
+        PrintOptions {
+            copies: self.copies.or(fallback.copies),
+            duplex: self.duplex.or(fallback.duplex),
+            color_mode: self.color_mode.or(fallback.color_mode),
+            media: self.media.or_else(|| fallback.media.clone()),
+            tray: self.tray.or_else(|| fallback.tray.clone()),
+            fit_to_page: self.fit_to_page.or(fallback.fit_to_page),
+            media_scaling: self.media_scaling.or(fallback.media_scaling),
+            auto_rotate: self.auto_rotate.or(fallback.auto_rotate),
+            cut: self.cut.or(fallback.cut),
+            cut_per_copy: self.cut_per_copy.or(fallback.cut_per_copy),
+            feed_lines: self.feed_lines.or(fallback.feed_lines),
+            force: self.force.or(fallback.force),
+        }
+    }
+
+    /// 以模板中的切纸设置补全未设置的切纸选项
+    pub fn with_cut_defaults(self, cut: &CutSettings) -> PrintOptions {
+        PrintOptions {
+            cut: self.cut.or(cut.mode),
+            cut_per_copy: self.cut_per_copy.or(cut.per_copy),
+            feed_lines: self.feed_lines.or(cut.feed_lines),
+            ..self
+        }
+    }
+
+    /// 转换为 CUPS/IPP 风格的作业属性 (Unix 上作为 lp 的 -o 选项，其他平台由 printers crate 透传)
+    pub fn to_job_properties(&self) -> Vec<(String, String)> {
+        let mut props = Vec::new();
+
+        if let Some(copies) = self.copies {
+            props.push(("copies".to_string(), copies.max(1).to_string()));
+        }
+        if let Some(duplex) = self.duplex {
+            let sides = match duplex {
+                DuplexMode::Simplex => "one-sided",
+                DuplexMode::LongEdge => "two-sided-long-edge",
+                DuplexMode::ShortEdge => "two-sided-short-edge",
+            };
+            props.push(("sides".to_string(), sides.to_string()));
+        }
+        if let Some(color_mode) = self.color_mode {
+            let mode = match color_mode {
+                ColorMode::Color => "color",
+                ColorMode::Monochrome => "monochrome",
+            };
+            props.push(("print-color-mode".to_string(), mode.to_string()));
+        }
+        if let Some(media) = &self.media {
+            props.push(("media".to_string(), media.clone()));
+        }
+        if let Some(tray) = &self.tray {
+            props.push(("media-source".to_string(), tray.clone()));
+        }
+        if self.fit_to_page == Some(true) {
+            props.push(("fit-to-page".to_string(), "true".to_string()));
+        }
+
+        props
+    }
+}
+
+/// 打印机实时状态
+/// 数据来自系统打印队列 (Windows 后台处理程序状态 / CUPS printer-state-reasons)，
+/// 各项标志只在驱动上报时才可靠，无法确定时为 false
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrinterStatus {
+    pub name: String,
+    pub system_name: String,
+    /// idle / printing / paused / unknown
+    pub state: &'static str,
+    /// 设备在线且未暂停
+    pub online: bool,
+    /// 缺纸
+    pub paper_out: bool,
+    /// 上盖/舱门打开
+    pub cover_open: bool,
+    /// 卡纸
+    pub paper_jam: bool,
+    /// 碳粉/墨水不足
+    pub toner_low: bool,
+    /// 原始状态原因 (IPP printer-state-reasons 风格，如 "media-empty-error")
+    pub reasons: Vec<String>,
+}
+
+/// 读取打印机状态
+pub fn status(printer: &Printer) -> PrinterStatus {
+    let reasons: Vec<String> = printer
+        .state_reasons
+        .iter()
+        .map(|r| r.trim().to_ascii_lowercase())
+        .filter(|r| !r.is_empty() && r != "none")
+        .collect();
+    let has = |keys: &[&str]| reasons.iter().any(|r| keys.iter().any(|k| r.contains(k)));
+
+    let state = match printer.state {
+        PrinterState::READY => "idle",
+        PrinterState::PRINTING => "printing",
+        PrinterState::PAUSED => "paused",
+        _ => "unknown",
+    };
+    let offline = has(&["offline", "shutdown", "connecting-to-device"]);
+
+    PrinterStatus {
+        name: printer.name.clone(),
+        system_name: printer.system_name.clone(),
+        state,
+        online: !offline && state != "paused",
+        paper_out: has(&["media-empty", "media-needed", "paper-out"]),
+        cover_open: has(&["cover-open", "door-open", "interlock-open"]),
+        paper_jam: has(&["media-jam", "paper-jam"]),
+        toner_low: has(&["toner-low", "toner-empty", "marker-supply-low", "marker-supply-empty"]),
+        reasons,
+    }
+}
+
+/// 打印机是否在线：系统打印机按队列上报的状态，直连设备做连接探测
+pub fn online(destination: &Destination) -> bool {
+    match destination {
+        Destination::Spooler(printer) => status(printer).online,
+        Destination::Direct(target) => target.reachable(),
+    }
+}
+
+/// 已提交作业在系统打印队列 (或 IPP 打印机) 中的状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpoolerJobState {
+    /// 排队中或被挂起
+    Pending,
+    /// 正在打印 (含打印机暂停、缺纸等待处理)
+    Printing,
+    /// 打印完成
+    Completed,
+    /// 在系统队列中被取消/删除
+    Cancelled,
+    /// 出错中止 (附带原因)
+    Aborted(String),
+    /// 队列中已查不到该作业 (多数系统在打印完成后即移除作业)
+    Gone,
+}
+
+/// 任务的输出目标：系统打印队列中的打印机，或直连设备
+#[derive(Debug, Clone)]
+pub enum Destination {
+    Spooler(Printer),
+    Direct(DirectTarget),
+}
+
+impl Destination {
+    /// 显示名称
+    pub fn name(&self) -> String {
+        match self {
+            Destination::Spooler(printer) => printer.name.clone(),
+            Destination::Direct(target) => target.name(),
+        }
+    }
+
+    /// 设备标识，同一设备的任务在同一子队列中按顺序执行
+    pub fn key(&self) -> String {
+        match self {
+            Destination::Spooler(printer) => printer.system_name.clone(),
+            Destination::Direct(target) => target.name(),
+        }
+    }
+}
+
+impl From<Printer> for Destination {
+    fn from(printer: Printer) -> Self {
+        Destination::Spooler(printer)
+    }
+}
+
+/// 系统打印队列中的打印机 (移动端没有系统打印队列，始终为空)
+pub fn system_printers() -> Vec<Printer> {
+    #[cfg(desktop)]
+    {
+        printers::get_printers()
+    }
+    #[cfg(mobile)]
+    {
+        Vec::new()
+    }
+}
+
+/// 按名称查找打印机 (先精确匹配显示名/系统名，再忽略大小写匹配)
+/// 未找到时返回当前可用的打印机名称列表
+pub fn find_printer(name: &str) -> Result<Printer, Vec<String>> {
+    let mut printers = system_printers();
+    let position = printers
+        .iter()
+        .position(|p| p.name == name || p.system_name == name)
+        .or_else(|| {
+            printers.iter().position(|p| {
+                p.name.eq_ignore_ascii_case(name) || p.system_name.eq_ignore_ascii_case(name)
+            })
+        });
+
+    match position {
+        Some(i) => Ok(printers.swap_remove(i)),
+        None => Err(printers.iter().map(|p| p.name.clone()).collect()),
+    }
+}
+
+/// 获取系统默认打印机
+pub fn default_printer() -> Option<Printer> {
+    #[cfg(desktop)]
+    {
+        printers::get_default_printer()
+    }
+    #[cfg(mobile)]
+    {
+        None
+    }
+}
+
+/// 提交文档到系统打印队列，返回系统作业 ID
+pub fn submit(
+    printer: &Printer,
+    job_name: &str,
+    data: &[u8],
+    options: &PrintOptions,
+) -> Result<u64, String> {
+    submit_with_properties(printer, job_name, data, options.to_job_properties())
+}
+
+/// 查询已提交作业的状态；端口/串口/USB 直连设备没有作业队列，返回 Gone
+pub fn job_state(destination: &Destination, job_id: u64) -> Result<SpoolerJobState, String> {
+    match destination {
+        Destination::Spooler(printer) => spooler_job_state(printer, job_id),
+        Destination::Direct(DirectTarget::Ipp {
+            uri,
+            allow_self_signed,
+        }) => ipp::job_state(uri, *allow_self_signed, job_id),
+        Destination::Direct(_) => Ok(SpoolerJobState::Gone),
+    }
+}
+
+#[cfg(all(unix, desktop))]
+fn spooler_job_state(printer: &Printer, job_id: u64) -> Result<SpoolerJobState, String> {
+    cups::job_state(&printer.system_name, job_id)
+}
+
+#[cfg(windows)]
+fn spooler_job_state(printer: &Printer, job_id: u64) -> Result<SpoolerJobState, String> {
+    winspool::job_state(&printer.system_name, job_id)
+}
+
+#[cfg(not(any(all(unix, desktop), windows)))]
+fn spooler_job_state(_printer: &Printer, _job_id: u64) -> Result<SpoolerJobState, String> {
+    Ok(SpoolerJobState::Gone)
+}
+
+/// 以 RAW 方式提交 (ESC/POS、ZPL 等打印机指令)，跳过驱动的格式转换
+/// Windows 直接调用 winspool 写入 RAW 文档；CUPS 通过 raw 选项透传
+pub fn submit_raw(
+    printer: &Printer,
+    job_name: &str,
+    data: &[u8],
+    options: &PrintOptions,
+) -> Result<u64, String> {
+    #[cfg(windows)]
+    {
+        winspool::submit_raw(&printer.system_name, job_name, data, options.copies.unwrap_or(1))
+    }
+    #[cfg(not(windows))]
+    {
+        let mut props = options.to_job_properties();
+        props.push(("raw".to_string(), "true".to_string()));
+        submit_with_properties(printer, job_name, data, props)
+    }
+}
+
+/// Unix 上经 CUPS 提交并返回 CUPS 作业 ID
+#[cfg(all(unix, desktop))]
+fn submit_with_properties(
+    printer: &Printer,
+    job_name: &str,
+    data: &[u8],
+    props: Vec<(String, String)>,
+) -> Result<u64, String> {
+    cups::submit(&printer.system_name, job_name, data, &props)
+}
+
+#[cfg(all(not(unix), desktop))]
+fn submit_with_properties(
+    printer: &Printer,
+    job_name: &str,
+    data: &[u8],
+    props: Vec<(String, String)>,
+) -> Result<u64, String> {
+    let raw_properties: Vec<(&str, &str)> = props
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+
+    let mut job_options = PrinterJobOptions::none();
+    job_options.name = Some(job_name);
+    job_options.raw_properties = &raw_properties;
+
+    printer
+        .print(data, job_options)
+        .map_err(|e| format!("Print error: {:?}", e))
+}
+
+#[cfg(mobile)]
+fn submit_with_properties(
+    _printer: &Printer,
+    _job_name: &str,
+    _data: &[u8],
+    _props: Vec<(String, String)>,
+) -> Result<u64, String> {
+    Err("System print spooler is not available on mobile".to_string())
+}
@@ -0,0 +1,59 @@
+use super::{ipp, SpoolerJobState};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// 通过 CUPS 命令行 (lp) 提交文档，作业选项以 "-o name=value" 传递，返回 CUPS 作业 ID
+/// 文档经 stdin 传入，不落地临时文件；由 CUPS 过滤器负责 PDF → 打印机语言的转换
+pub fn submit(
+    printer: &str,
+    job_name: &str,
+    data: &[u8],
+    props: &[(String, String)],
+) -> Result<u64, String> {
+    let mut command = Command::new("lp");
+    command.arg("-d").arg(printer).arg("-t").arg(job_name);
+    for (name, value) in props {
+        // 布尔选项 (raw、fit-to-page) 以 "-o name" 形式传递
+        if value == "true" {
+            command.arg("-o").arg(name);
+        } else {
+            command.arg("-o").arg(format!("{}={}", name, value));
+        }
+    }
+
+    let mut child = command
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Run lp error: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(data)
+            .map_err(|e| format!("Write to lp error: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Run lp error: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "CUPS error: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_job_id(&stdout).ok_or_else(|| format!("Unexpected lp output: {}", stdout.trim()))
+}
+
+/// 解析 lp 输出 "request id is Printer-123 (1 file(s))" 中的作业 ID
+fn parse_job_id(output: &str) -> Option<u64> {
+    let request = output.split("request id is ").nth(1)?.split_whitespace().next()?;
+    request.rsplit_once('-')?.1.parse().ok()
+}
+
+/// 通过本机 CUPS 的 IPP 接口查询作业状态
+pub fn job_state(printer: &str, job_id: u64) -> Result<SpoolerJobState, String> {
+    ipp::job_state(&format!("ipp://localhost:631/printers/{}", printer), false, job_id)
+}
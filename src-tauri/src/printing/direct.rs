@@ -0,0 +1,495 @@
+use super::{ipp, usb, PrintOptions, PrinterStatus};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+/// 串口写入超时
+#[cfg(desktop)]
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// 网络打印机连接失败后的重试间隔
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// 在线探测的连接超时
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+/// 实时状态应答的读取超时
+const STATUS_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// IPP 文档格式
+pub const PDF_FORMAT: &str = "application/pdf";
+pub const PWG_FORMAT: &str = "image/pwg-raster";
+
+/// ESC/POS 实时状态查询 DLE EOT n：n=2 脱机原因，n=4 纸卷传感器
+const DLE_EOT_OFFLINE: [u8; 3] = [0x10, 0x04, 0x02];
+const DLE_EOT_PAPER: [u8; 3] = [0x10, 0x04, 0x04];
+
+/// 直连打印机使用的打印语言，决定渲染结果以何种格式发送
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PrinterLanguage {
+    /// 发送渲染出的 PDF (支持 PDF 直接打印的激光打印机)
+    #[default]
+    Pdf,
+    /// ESC/POS 光栅位图 (小票打印机)
+    EscPos,
+    /// ZPL 图形 (Zebra 等标签打印机)
+    Zpl,
+    /// TSPL 位图 (TSC、佳博等标签打印机)
+    Tspl,
+}
+
+/// 直连设备 (不经过系统打印队列)
+/// 移动端只支持 Socket 与 Ipp；没有蓝牙类型，蓝牙打印机需经网络打印服务器等方式接入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DirectTarget {
+    /// 网络打印机 RAW 端口 (JetDirect / AppSocket)
+    Socket {
+        host: String,
+        #[serde(default = "default_socket_port")]
+        port: u16,
+        /// 模板/图片任务的输出格式 (原始指令任务不受影响)
+        #[serde(default)]
+        language: PrinterLanguage,
+        /// 打印头分辨率，栅格化时使用 (Default: 203)
+        #[serde(default = "default_dpi")]
+        dpi: u32,
+        /// 连接超时 (Default: 5000ms)
+        #[serde(default = "default_connect_timeout_ms")]
+        connect_timeout_ms: u64,
+        /// 写入超时 (Default: 30000ms)
+        #[serde(default = "default_write_timeout_ms")]
+        write_timeout_ms: u64,
+        /// 连接失败时的重试次数 (Default: 2)
+        #[serde(default = "default_retries")]
+        retries: u32,
+    },
+    /// 串口打印机，如 "COM3" 或 "/dev/ttyUSB0"
+    Serial {
+        path: String,
+        #[serde(default = "default_baud_rate")]
+        baud_rate: u32,
+    },
+    /// 免驱 IPP 打印机 (AirPrint / IPP Everywhere)，如 "ipp://192.168.1.50/ipp/print"；
+    /// ipps:// 经 TLS 连接
+    Ipp {
+        uri: String,
+        /// 接受打印机的自签名证书 (仅 ipps://)
+        #[serde(default)]
+        allow_self_signed: bool,
+    },
+    /// USB 打印机，直接写入批量端点 (绕过系统驱动)；均未指定时自动选择
+    /// Windows 上需为设备安装 WinUSB 驱动 (如使用 Zadig)
+    Usb {
+        #[serde(default)]
+        vendor_id: Option<u16>,
+        #[serde(default)]
+        product_id: Option<u16>,
+        /// 设备序列号，同型号多台时用于区分
+        #[serde(default)]
+        serial: Option<String>,
+        /// 模板/图片任务的输出格式 (Default: escPos)
+        #[serde(default = "default_usb_language")]
+        language: PrinterLanguage,
+        #[serde(default = "default_dpi")]
+        dpi: u32,
+    },
+}
+
+fn default_socket_port() -> u16 {
+    9100
+}
+
+fn default_usb_language() -> PrinterLanguage {
+    PrinterLanguage::EscPos
+}
+
+fn default_dpi() -> u32 {
+    203
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_write_timeout_ms() -> u64 {
+    30000
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+fn default_baud_rate() -> u32 {
+    9600
+}
+
+impl DirectTarget {
+    /// 显示名称，同时作为打印机子队列的键
+    pub fn name(&self) -> String {
+        match self {
+            DirectTarget::Socket { host, port, .. } => format!("socket://{}:{}", host, port),
+            DirectTarget::Serial { path, .. } => format!("serial://{}", path),
+            DirectTarget::Ipp { uri, .. } => uri.clone(),
+            DirectTarget::Usb {
+                vendor_id,
+                product_id,
+                serial,
+                ..
+            } => {
+                let id = |v: &Option<u16>| v.map_or("*".to_string(), |v| format!("{:04x}", v));
+                let mut name = format!("usb://{}:{}", id(vendor_id), id(product_id));
+                if let Some(serial) = serial {
+                    name.push('/');
+                    name.push_str(serial);
+                }
+                name
+            }
+        }
+    }
+
+    /// 从打印机名称解析直连设备：
+    /// ipp:// 或 http:// 开头的名称视为 IPP 打印机地址，
+    /// socket://host[:port] 视为网络打印机 RAW 端口 (端口缺省 9100，其余参数取默认值)
+    pub fn from_name(name: &str) -> Option<DirectTarget> {
+        let lower = name.to_ascii_lowercase();
+        if ["ipp://", "ipps://", "http://", "https://"]
+            .iter()
+            .any(|scheme| lower.starts_with(scheme))
+        {
+            return Some(DirectTarget::Ipp {
+                uri: name.to_string(),
+                allow_self_signed: false,
+            });
+        }
+        if !lower.starts_with("socket://") {
+            return None;
+        }
+        let authority = name["socket://".len()..].trim_end_matches('/');
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => (host, port.parse().ok()?),
+            _ => (authority, default_socket_port()),
+        };
+        (!host.is_empty()).then(|| DirectTarget::Socket {
+            host: host.to_string(),
+            port,
+            language: PrinterLanguage::default(),
+            dpi: default_dpi(),
+            connect_timeout_ms: default_connect_timeout_ms(),
+            write_timeout_ms: default_write_timeout_ms(),
+            retries: default_retries(),
+        })
+    }
+
+    /// 设备当前是否可用：网络打印机能建立连接，串口存在，USB 设备已连接
+    pub fn reachable(&self) -> bool {
+        match self {
+            DirectTarget::Socket { host, port, .. } => (host.as_str(), *port)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .is_some_and(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok()),
+            DirectTarget::Serial { path, .. } => serial_available(path),
+            DirectTarget::Ipp { uri, .. } => ipp::reachable(uri, PROBE_TIMEOUT),
+            DirectTarget::Usb {
+                vendor_id,
+                product_id,
+                serial,
+                ..
+            } => usb::connected(*vendor_id, *product_id, serial.as_deref()),
+        }
+    }
+
+    /// IPP 打印机不支持 PDF、但支持 PWG 光栅 (IPP Everywhere 必备格式) 时返回光栅分辨率；
+    /// 其余设备、支持 PDF 的打印机及查询失败时返回 None (按 PDF 提交)
+    pub fn pwg_raster_dpi(&self) -> Option<u32> {
+        let DirectTarget::Ipp {
+            uri,
+            allow_self_signed,
+        } = self
+        else {
+            return None;
+        };
+        match ipp::printer_formats(uri, *allow_self_signed) {
+            Ok(formats) if !formats.supports(PDF_FORMAT) && formats.supports(PWG_FORMAT) => {
+                Some(formats.pwg_dpi())
+            }
+            Ok(_) => None,
+            Err(e) => {
+                warn!("查询 IPP 打印机 {} 支持的格式失败: {}", uri, e);
+                None
+            }
+        }
+    }
+
+    /// 需要栅格化输出时返回 (打印语言, dpi)
+    pub fn raster_language(&self) -> Option<(PrinterLanguage, u32)> {
+        match self {
+            DirectTarget::Socket { language, dpi, .. } | DirectTarget::Usb { language, dpi, .. }
+                if *language != PrinterLanguage::Pdf =>
+            {
+                Some((*language, *dpi))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 读取直连打印机状态
+/// ESC/POS 打印机 (网络端口/串口/USB) 以 DLE EOT 查询上盖与纸卷状态，
+/// 其余设备只能做连接探测，缺纸等标志为 false
+pub fn status(name: &str, target: &DirectTarget) -> PrinterStatus {
+    let escpos = match target {
+        DirectTarget::Socket { language, .. } | DirectTarget::Usb { language, .. } => {
+            *language == PrinterLanguage::EscPos
+        }
+        DirectTarget::Serial { .. } => true,
+        DirectTarget::Ipp { .. } => false,
+    };
+    let mut status = PrinterStatus {
+        name: name.to_string(),
+        system_name: target.name(),
+        state: "unknown",
+        online: false,
+        paper_out: false,
+        cover_open: false,
+        paper_jam: false,
+        toner_low: false,
+        reasons: Vec::new(),
+    };
+
+    if !escpos {
+        status.online = target.reachable();
+        if status.online {
+            status.state = "idle";
+        } else {
+            status.reasons.push("offline".to_string());
+        }
+        return status;
+    }
+
+    let offline = match query_status(target, &DLE_EOT_OFFLINE) {
+        Ok(byte) => byte,
+        Err(e) => {
+            warn!("查询打印机 {} 状态失败: {}", name, e);
+            status.reasons.push("offline".to_string());
+            return status;
+        }
+    };
+    // 纸卷传感器查询失败时只缺少该项信息，不影响在线判断
+    let paper = query_status(target, &DLE_EOT_PAPER).ok();
+
+    status.state = "idle";
+    // 脱机原因：bit 2 上盖打开，bit 5 缺纸停止打印，bit 6 发生错误
+    status.cover_open = offline & 0x04 != 0;
+    // 纸卷传感器：bit 5/6 纸尽，bit 2/3 纸将尽
+    status.paper_out = offline & 0x20 != 0 || paper.is_some_and(|b| b & 0x60 != 0);
+    let error = offline & 0x40 != 0;
+    if status.cover_open {
+        status.reasons.push("cover-open".to_string());
+    }
+    if status.paper_out {
+        status.reasons.push("media-empty-error".to_string());
+    } else if paper.is_some_and(|b| b & 0x0C != 0) {
+        status.reasons.push("media-low-report".to_string());
+    }
+    if error {
+        status.reasons.push("other-error".to_string());
+    }
+    status.online = !(status.cover_open || status.paper_out || error);
+    status
+}
+
+/// 发送一条 DLE EOT 查询并读取 1 字节应答
+/// 应答格式固定为 0xx1xx10 (bit 0/7 为 0，bit 1/4 为 1)，不符合时视为设备不支持实时状态
+fn query_status(target: &DirectTarget, request: &[u8]) -> Result<u8, String> {
+    let response = match target {
+        DirectTarget::Socket {
+            host,
+            port,
+            connect_timeout_ms,
+            ..
+        } => {
+            let connect_timeout = Duration::from_millis(*connect_timeout_ms);
+            query_socket(host, *port, connect_timeout, request)?
+        }
+        DirectTarget::Serial { path, baud_rate } => query_serial(path, *baud_rate, request)?,
+        DirectTarget::Usb {
+            vendor_id,
+            product_id,
+            serial,
+            ..
+        } => usb::query(*vendor_id, *product_id, serial.as_deref(), request)?,
+        DirectTarget::Ipp { .. } => return Err("IPP printers do not support DLE EOT".to_string()),
+    };
+    match response.last() {
+        Some(&byte) if byte & 0x93 == 0x12 => Ok(byte),
+        Some(byte) => Err(format!("Unexpected status response 0x{:02x}", byte)),
+        None => Err("Printer returned no status".to_string()),
+    }
+}
+
+fn query_socket(
+    host: &str,
+    port: u16,
+    connect_timeout: Duration,
+    request: &[u8],
+) -> Result<Vec<u8>, String> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Resolve {}:{} error: {}", host, port, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}:{}", host, port))?;
+    let mut stream = TcpStream::connect_timeout(&addr, connect_timeout)
+        .map_err(|e| format!("Connect {} error: {}", addr, e))?;
+    stream
+        .set_read_timeout(Some(STATUS_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(STATUS_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+    stream
+        .write_all(request)
+        .map_err(|e| format!("Write {} error: {}", addr, e))?;
+    let mut byte = [0u8; 1];
+    stream
+        .read_exact(&mut byte)
+        .map_err(|e| format!("Read {} error: {}", addr, e))?;
+    Ok(byte.to_vec())
+}
+
+/// 将数据写入直连设备
+/// 网络端口/串口/USB 原样写入，写完即视为已打印，返回 None；
+/// IPP 打印机以 document_format 提交并返回打印机上的作业 ID
+pub fn send(
+    target: &DirectTarget,
+    job_name: &str,
+    document_format: &str,
+    data: &[u8],
+    options: &PrintOptions,
+) -> Result<Option<u64>, String> {
+    match target {
+        DirectTarget::Socket {
+            host,
+            port,
+            connect_timeout_ms,
+            write_timeout_ms,
+            retries,
+            ..
+        } => send_socket(
+            host,
+            *port,
+            Duration::from_millis(*connect_timeout_ms),
+            Duration::from_millis(*write_timeout_ms),
+            *retries,
+            data,
+        )
+        .map(|_| None),
+        DirectTarget::Serial { path, baud_rate } => {
+            send_serial(path, *baud_rate, data).map(|_| None)
+        }
+        DirectTarget::Ipp {
+            uri,
+            allow_self_signed,
+        } => ipp::print_job(
+            uri,
+            *allow_self_signed,
+            job_name,
+            document_format,
+            data,
+            options,
+        )
+        .map(Some),
+        DirectTarget::Usb {
+            vendor_id,
+            product_id,
+            serial,
+            ..
+        } => usb::send(*vendor_id, *product_id, serial.as_deref(), data).map(|_| None),
+    }
+}
+
+/// 连接失败 (打印机忙于上一个连接、短暂离线) 时按 retries 重试；
+/// 写入开始后不再重试，避免打印机收到部分数据后重复出纸
+fn send_socket(
+    host: &str,
+    port: u16,
+    connect_timeout: Duration,
+    write_timeout: Duration,
+    retries: u32,
+    data: &[u8],
+) -> Result<(), String> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Resolve {}:{} error: {}", host, port, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}:{}", host, port))?;
+
+    let mut attempt = 0;
+    let mut stream = loop {
+        match TcpStream::connect_timeout(&addr, connect_timeout) {
+            Ok(stream) => break stream,
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                warn!("连接 {} 失败 ({})，第 {} 次重试", addr, e, attempt);
+                thread::sleep(RETRY_DELAY);
+            }
+            Err(e) => return Err(format!("Connect {} error: {}", addr, e)),
+        }
+    };
+    stream
+        .set_write_timeout(Some(write_timeout))
+        .map_err(|e| e.to_string())?;
+    stream
+        .write_all(data)
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("Write {} error: {}", addr, e))
+}
+
+#[cfg(desktop)]
+fn serial_available(path: &str) -> bool {
+    serialport::available_ports()
+        .is_ok_and(|ports| ports.iter().any(|p| p.port_name.eq_ignore_ascii_case(path)))
+}
+
+#[cfg(mobile)]
+fn serial_available(_path: &str) -> bool {
+    false
+}
+
+#[cfg(desktop)]
+fn send_serial(path: &str, baud_rate: u32, data: &[u8]) -> Result<(), String> {
+    let mut port = serialport::new(path, baud_rate)
+        .timeout(IO_TIMEOUT)
+        .open()
+        .map_err(|e| format!("Open serial port {} error: {}", path, e))?;
+    port.write_all(data)
+        .and_then(|_| port.flush())
+        .map_err(|e| format!("Write serial port {} error: {}", path, e))
+}
+
+#[cfg(desktop)]
+fn query_serial(path: &str, baud_rate: u32, request: &[u8]) -> Result<Vec<u8>, String> {
+    let mut port = serialport::new(path, baud_rate)
+        .timeout(STATUS_TIMEOUT)
+        .open()
+        .map_err(|e| format!("Open serial port {} error: {}", path, e))?;
+    port.write_all(request)
+        .and_then(|_| port.flush())
+        .map_err(|e| format!("Write serial port {} error: {}", path, e))?;
+    let mut byte = [0u8; 1];
+    port.read_exact(&mut byte)
+        .map_err(|e| format!("Read serial port {} error: {}", path, e))?;
+    Ok(byte.to_vec())
+}
+
+/// 移动端没有串口
+#[cfg(mobile)]
+fn query_serial(_path: &str, _baud_rate: u32, _request: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Serial printers are not supported on mobile".to_string())
+}
+
+/// 移动端没有串口
+#[cfg(mobile)]
+fn send_serial(_path: &str, _baud_rate: u32, _data: &[u8]) -> Result<(), String> {
+    Err("Serial printers are not supported on mobile".to_string())
+}
@@ -0,0 +1,90 @@
+use super::raster::{escpos_finish, RasterSettings};
+use deepprint_core::plain_text::TextLine;
+use serde::{Deserialize, Serialize};
+
+/// ESC/POS 字体 A 的字符宽度 (点)
+const FONT_A_WIDTH_DOTS: f32 = 12.0;
+
+/// CP437 的 0x80-0xFF
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// 小票打印机文本模式使用的代码页
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodePage {
+    /// 美国 (PC437)
+    Cp437,
+    /// 西里尔文 (PC866)
+    Cp866,
+    /// 西欧 (WPC1252)
+    Cp1252,
+    /// 简体中文 (汉字模式)
+    Gb18030,
+}
+
+impl CodePage {
+    /// 选择代码页的指令 (ESC t 按 Epson 的编号)；国产机型默认处于汉字模式，
+    /// 单字节代码页需先以 FS . 退出
+    fn select(self) -> &'static [u8] {
+        match self {
+            CodePage::Cp437 => &[0x1C, 0x2E, 0x1B, 0x74, 0],
+            CodePage::Cp866 => &[0x1C, 0x2E, 0x1B, 0x74, 17],
+            CodePage::Cp1252 => &[0x1C, 0x2E, 0x1B, 0x74, 16],
+            CodePage::Gb18030 => &[0x1C, 0x26], // FS & 进入汉字模式
+        }
+    }
+
+    /// 转码；含代码页无法表示的字符时返回 None
+    fn encode(self, text: &str) -> Option<Vec<u8>> {
+        let encoding = match self {
+            CodePage::Cp437 => {
+                return text
+                    .chars()
+                    .map(|c| match c {
+                        c if c.is_ascii() => Some(c as u8),
+                        c => CP437_HIGH.chars().position(|h| h == c).map(|i| 0x80 + i as u8),
+                    })
+                    .collect()
+            }
+            CodePage::Cp866 => encoding_rs::IBM866,
+            CodePage::Cp1252 => encoding_rs::WINDOWS_1252,
+            // 打印机字库只覆盖双字节部分 (GBK)，GB18030 的四字节字符按无法表示处理
+            CodePage::Gb18030 => encoding_rs::GBK,
+        };
+        let (bytes, _, unmappable) = encoding.encode(text);
+        (!unmappable).then(|| bytes.into_owned())
+    }
+}
+
+/// 纸宽 (pt) 对应的字体 A 字符列数
+pub fn columns(width_pt: f64, dpi: u32) -> usize {
+    (width_pt as f32 / 72.0 * dpi as f32 / FONT_A_WIDTH_DOTS).round() as usize
+}
+
+/// 编码为 ESC/POS 文本指令：初始化、选择代码页后逐行输出 (粗体以 ESC E 切换)，
+/// 份数与切纸同光栅输出；任一行含代码页无法表示的字符时返回 None，调用方应改用栅格输出
+pub fn to_escpos_text(documents: &[Vec<TextLine>], code_page: CodePage, settings: &RasterSettings) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    for line in documents.iter().flatten() {
+        if line.bold {
+            body.extend_from_slice(&[0x1B, 0x45, 1]); // ESC E 1
+        }
+        body.extend(code_page.encode(&line.text)?);
+        body.push(b'\n');
+        if line.bold {
+            body.extend_from_slice(&[0x1B, 0x45, 0]);
+        }
+    }
+
+    let mut out = vec![0x1B, 0x40]; // ESC @ 初始化
+    out.extend_from_slice(code_page.select());
+    for copy in 0..settings.copies.max(1) {
+        out.extend_from_slice(&body);
+        if settings.finish_after(copy) {
+            escpos_finish(&mut out, settings);
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,395 @@
+use super::{ColorMode, DuplexMode, PrintOptions, SpoolerJobState};
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// 连接/读写超时 (大文档上传与打印机处理需要更长时间)
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 操作码与状态码 (RFC 8011)
+const OP_PRINT_JOB: u16 = 0x0002;
+const OP_GET_JOB_ATTRIBUTES: u16 = 0x0009;
+const OP_GET_PRINTER_ATTRIBUTES: u16 = 0x000B;
+const STATUS_SUCCESS_MAX: u16 = 0x00FF;
+const STATUS_NOT_FOUND: u16 = 0x0406;
+
+// 分组分隔符
+const TAG_OPERATION: u8 = 0x01;
+const TAG_JOB: u8 = 0x02;
+const TAG_END: u8 = 0x03;
+
+// 值类型
+const TAG_INTEGER: u8 = 0x21;
+const TAG_ENUM: u8 = 0x23;
+const TAG_RESOLUTION: u8 = 0x32;
+const TAG_TEXT: u8 = 0x41;
+const TAG_NAME: u8 = 0x42;
+const TAG_KEYWORD: u8 = 0x44;
+const TAG_URI: u8 = 0x45;
+const TAG_CHARSET: u8 = 0x47;
+const TAG_LANGUAGE: u8 = 0x48;
+const TAG_MIME_TYPE: u8 = 0x49;
+
+/// PWG 光栅的首选分辨率 (打印机支持时)
+const PWG_PREFERRED_DPI: u32 = 300;
+
+/// 打印机接受的文档格式 (Get-Printer-Attributes)
+pub struct PrinterFormats {
+    /// document-format-supported，如 "application/pdf"、"image/pwg-raster"
+    pub document_formats: Vec<String>,
+    /// pwg-raster-document-resolution-supported 中的分辨率 (dpi)
+    pub pwg_resolutions: Vec<u32>,
+}
+
+impl PrinterFormats {
+    pub fn supports(&self, format: &str) -> bool {
+        self.document_formats
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(format))
+    }
+
+    /// PWG 光栅分辨率：优先 300dpi，其次不低于 300 的最低分辨率，否则取最高分辨率
+    pub fn pwg_dpi(&self) -> u32 {
+        let resolutions = &self.pwg_resolutions;
+        if resolutions.contains(&PWG_PREFERRED_DPI) {
+            return PWG_PREFERRED_DPI;
+        }
+        resolutions
+            .iter()
+            .copied()
+            .filter(|dpi| *dpi >= PWG_PREFERRED_DPI)
+            .min()
+            .or_else(|| resolutions.iter().copied().max())
+            .unwrap_or(PWG_PREFERRED_DPI)
+    }
+}
+
+/// 以 IPP Print-Job 提交文档到免驱网络打印机 (AirPrint / IPP Everywhere)，返回打印机上的作业 ID
+/// uri 形如 "ipp://192.168.1.50/ipp/print"，端口缺省为 631；ipps:// 经 TLS 连接，
+/// allow_self_signed 时接受打印机的自签名证书
+pub fn print_job(
+    uri: &str,
+    allow_self_signed: bool,
+    job_name: &str,
+    document_format: &str,
+    data: &[u8],
+    options: &PrintOptions,
+) -> Result<u64, String> {
+    let endpoint = Endpoint::parse(uri, allow_self_signed)?;
+
+    let mut body = request_header(OP_PRINT_JOB, &endpoint, data.len() + 512);
+    attribute(&mut body, TAG_NAME, "job-name", job_name.as_bytes());
+    attribute(&mut body, TAG_MIME_TYPE, "document-format", document_format.as_bytes());
+
+    body.push(TAG_JOB);
+    if let Some(copies) = options.copies {
+        let copies = i32::try_from(copies.max(1)).unwrap_or(i32::MAX);
+        attribute(&mut body, TAG_INTEGER, "copies", &copies.to_be_bytes());
+    }
+    if let Some(duplex) = options.duplex {
+        let sides = match duplex {
+            DuplexMode::Simplex => "one-sided",
+            DuplexMode::LongEdge => "two-sided-long-edge",
+            DuplexMode::ShortEdge => "two-sided-short-edge",
+        };
+        attribute(&mut body, TAG_KEYWORD, "sides", sides.as_bytes());
+    }
+    if let Some(color_mode) = options.color_mode {
+        let mode = match color_mode {
+            ColorMode::Color => "color",
+            ColorMode::Monochrome => "monochrome",
+        };
+        attribute(&mut body, TAG_KEYWORD, "print-color-mode", mode.as_bytes());
+    }
+    if let Some(media) = &options.media {
+        attribute(&mut body, TAG_KEYWORD, "media", media.as_bytes());
+    }
+    if options.fit_to_page == Some(true) {
+        attribute(&mut body, TAG_KEYWORD, "print-scaling", b"fit");
+    }
+    body.push(TAG_END);
+    body.extend_from_slice(data);
+
+    let response = endpoint.post(body)?;
+    let (status, attributes) = parse_response(&response)?;
+    check_status(status, &attributes)?;
+    attributes
+        .iter()
+        .find(|(tag, name, value)| *tag == TAG_INTEGER && *name == b"job-id" && value.len() == 4)
+        .map(|(_, _, value)| i32::from_be_bytes([value[0], value[1], value[2], value[3]]) as u64)
+        .ok_or_else(|| "IPP response has no job-id".to_string())
+}
+
+/// 以 Get-Job-Attributes 查询作业状态 (job-state)
+/// 打印机 (或 CUPS) 已清除该作业的记录时返回 Gone
+pub fn job_state(
+    uri: &str,
+    allow_self_signed: bool,
+    job_id: u64,
+) -> Result<SpoolerJobState, String> {
+    let endpoint = Endpoint::parse(uri, allow_self_signed)?;
+
+    let mut body = request_header(OP_GET_JOB_ATTRIBUTES, &endpoint, 256);
+    attribute(&mut body, TAG_INTEGER, "job-id", &(job_id as i32).to_be_bytes());
+    attribute(&mut body, TAG_KEYWORD, "requested-attributes", b"job-state");
+    attribute(&mut body, TAG_KEYWORD, "", b"job-state-message"); // 多值属性的附加值
+    body.push(TAG_END);
+
+    let response = endpoint.post(body)?;
+    let (status, attributes) = parse_response(&response)?;
+    if status == STATUS_NOT_FOUND {
+        return Ok(SpoolerJobState::Gone);
+    }
+    check_status(status, &attributes)?;
+
+    let state = attributes
+        .iter()
+        .find(|(tag, name, value)| *tag == TAG_ENUM && *name == b"job-state" && value.len() == 4)
+        .map(|(_, _, value)| i32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+        .ok_or_else(|| "IPP response has no job-state".to_string())?;
+    // RFC 8011 §5.3.7：3 pending, 4 pending-held, 5 processing, 6 processing-stopped,
+    // 7 canceled, 8 aborted, 9 completed
+    Ok(match state {
+        5 | 6 => SpoolerJobState::Printing,
+        7 => SpoolerJobState::Cancelled,
+        8 => SpoolerJobState::Aborted(
+            text_value(&attributes, b"job-state-message").unwrap_or_default(),
+        ),
+        9 => SpoolerJobState::Completed,
+        _ => SpoolerJobState::Pending,
+    })
+}
+
+/// 以 Get-Printer-Attributes 查询打印机接受的文档格式与 PWG 光栅分辨率
+pub fn printer_formats(uri: &str, allow_self_signed: bool) -> Result<PrinterFormats, String> {
+    let endpoint = Endpoint::parse(uri, allow_self_signed)?;
+
+    let mut body = request_header(OP_GET_PRINTER_ATTRIBUTES, &endpoint, 256);
+    attribute(&mut body, TAG_KEYWORD, "requested-attributes", b"document-format-supported");
+    attribute(&mut body, TAG_KEYWORD, "", b"pwg-raster-document-resolution-supported");
+    body.push(TAG_END);
+
+    let response = endpoint.post(body)?;
+    let (status, attributes) = parse_response(&response)?;
+    check_status(status, &attributes)?;
+
+    let document_formats = values(&attributes, b"document-format-supported")
+        .filter(|(tag, _)| *tag == TAG_MIME_TYPE)
+        .map(|(_, value)| String::from_utf8_lossy(value).to_string())
+        .collect();
+    // resolution 值：横向 (4 字节)、纵向 (4 字节)、单位 (3 = 每英寸点数)
+    let pwg_resolutions = values(&attributes, b"pwg-raster-document-resolution-supported")
+        .filter(|(tag, value)| *tag == TAG_RESOLUTION && value.len() == 9 && value[8] == 3)
+        .map(|(_, value)| u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+        .collect();
+    Ok(PrinterFormats {
+        document_formats,
+        pwg_resolutions,
+    })
+}
+
+/// 请求头与公共操作属性 (请求体未写入结束标记)
+fn request_header(operation: u16, endpoint: &Endpoint, capacity: usize) -> Vec<u8> {
+    let mut body = Vec::with_capacity(capacity);
+    body.extend_from_slice(&[1, 1]); // IPP/1.1
+    body.extend_from_slice(&operation.to_be_bytes());
+    body.extend_from_slice(&1u32.to_be_bytes()); // request-id
+
+    body.push(TAG_OPERATION);
+    attribute(&mut body, TAG_CHARSET, "attributes-charset", b"utf-8");
+    attribute(&mut body, TAG_LANGUAGE, "attributes-natural-language", b"en");
+    attribute(&mut body, TAG_URI, "printer-uri", endpoint.printer_uri().as_bytes());
+    attribute(&mut body, TAG_NAME, "requesting-user-name", b"deepprint");
+    body
+}
+
+/// 写入单值属性：值类型 + 名称长度 + 名称 + 值长度 + 值
+fn attribute(buf: &mut Vec<u8>, tag: u8, name: &str, value: &[u8]) {
+    buf.push(tag);
+    buf.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    buf.extend_from_slice(name.as_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value);
+}
+
+/// 响应中的属性：(值类型, 名称, 值)，多值属性的附加值名称为空
+type Attributes<'a> = Vec<(u8, &'a [u8], &'a [u8])>;
+
+/// 解析响应，返回状态码与全部属性
+fn parse_response(response: &[u8]) -> Result<(u16, Attributes<'_>), String> {
+    if response.len() < 8 {
+        return Err("Invalid IPP response".to_string());
+    }
+    let status = u16::from_be_bytes([response[2], response[3]]);
+
+    let mut attributes = Vec::new();
+    let mut pos = 8;
+    while pos < response.len() {
+        let tag = response[pos];
+        pos += 1;
+        if tag == TAG_END {
+            break;
+        }
+        if tag < 0x10 {
+            continue; // 分组分隔符
+        }
+        let Some((name, value, next)) = read_attribute(response, pos) else {
+            break;
+        };
+        pos = next;
+        attributes.push((tag, name, value));
+    }
+    Ok((status, attributes))
+}
+
+/// 状态码非成功时返回错误 (附带 status-message)
+fn check_status(status: u16, attributes: &Attributes) -> Result<(), String> {
+    if status > STATUS_SUCCESS_MAX {
+        return Err(format!(
+            "IPP error 0x{:04x}: {}",
+            status,
+            text_value(attributes, b"status-message").unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// 属性的全部值 (值类型, 值)，包括其后名称为空的附加值
+fn values<'a>(
+    attributes: &'a Attributes<'a>,
+    name: &'a [u8],
+) -> impl Iterator<Item = (u8, &'a [u8])> + 'a {
+    let mut current: &[u8] = &[];
+    attributes.iter().filter_map(move |(tag, n, value)| {
+        if !n.is_empty() {
+            current = *n;
+        }
+        (current == name).then_some((*tag, *value))
+    })
+}
+
+fn text_value(attributes: &Attributes, name: &[u8]) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(tag, n, _)| *tag == TAG_TEXT && *n == name)
+        .map(|(_, _, value)| String::from_utf8_lossy(value).to_string())
+}
+
+/// 读取属性的名称与值，返回 (名称, 值, 下一个位置)
+fn read_attribute(buf: &[u8], pos: usize) -> Option<(&[u8], &[u8], usize)> {
+    let read_u16 = |at: usize| -> Option<usize> {
+        Some(u16::from_be_bytes([*buf.get(at)?, *buf.get(at + 1)?]) as usize)
+    };
+    let name_len = read_u16(pos)?;
+    let name = buf.get(pos + 2..pos + 2 + name_len)?;
+    let value_pos = pos + 2 + name_len;
+    let value_len = read_u16(value_pos)?;
+    let value = buf.get(value_pos + 2..value_pos + 2 + value_len)?;
+    Some((name, value, value_pos + 2 + value_len))
+}
+
+/// 打印机端口能否在 timeout 内建立连接 (用于判断打印机是否在线)
+pub fn reachable(uri: &str, timeout: Duration) -> bool {
+    Endpoint::parse(uri, false).is_ok_and(|endpoint| {
+        (endpoint.host.as_str(), endpoint.port)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .is_some_and(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok())
+    })
+}
+
+/// IPP 端点 (ipp:// 即 HTTP 上的 IPP，ipps:// 即 HTTPS 上的 IPP)
+struct Endpoint {
+    host: String,
+    port: u16,
+    path: String,
+    tls: bool,
+    allow_self_signed: bool,
+}
+
+impl Endpoint {
+    fn parse(uri: &str, allow_self_signed: bool) -> Result<Self, String> {
+        let (scheme, rest) = uri
+            .split_once("://")
+            .ok_or_else(|| format!("Invalid IPP printer URI: {}", uri))?;
+        let tls = match scheme.to_ascii_lowercase().as_str() {
+            "ipp" | "http" => false,
+            "ipps" | "https" => true,
+            _ => return Err(format!("Invalid IPP printer URI: {}", uri)),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/ipp/print"),
+        };
+        // IPv6 字面量形如 [fe80::1]:631
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(v6) => match v6.split_once(']') {
+                Some((host, port)) => (host, port.strip_prefix(':')),
+                None => return Err(format!("Invalid IPP printer URI: {}", uri)),
+            },
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| format!("Invalid port in IPP printer URI: {}", uri))?,
+            None => 631,
+        };
+        if host.is_empty() {
+            return Err(format!("Invalid IPP printer URI: {}", uri));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            tls,
+            allow_self_signed,
+        })
+    }
+
+    /// host:port，IPv6 地址加方括号
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    fn printer_uri(&self) -> String {
+        let scheme = if self.tls { "ipps" } else { "ipp" };
+        format!("{}://{}{}", scheme, self.authority(), self.path)
+    }
+
+    /// 发送 HTTP(S) POST (application/ipp) 并返回响应体
+    /// 打印机普遍使用自签名证书，仅在 allow_self_signed 时跳过证书校验
+    fn post(&self, body: Vec<u8>) -> Result<Vec<u8>, String> {
+        let scheme = if self.tls { "https" } else { "http" };
+        let url = format!("{}://{}{}", scheme, self.authority(), self.path);
+        let client = Client::builder()
+            .connect_timeout(IO_TIMEOUT)
+            .timeout(IO_TIMEOUT)
+            .danger_accept_invalid_certs(self.tls && self.allow_self_signed)
+            .build()
+            .map_err(|e| format!("Create HTTP client error: {}", e))?;
+        let response = client
+            .post(&url)
+            .header(CONTENT_TYPE, "application/ipp")
+            .body(body)
+            .send()
+            .map_err(|e| format!("Request {} error: {}", self.authority(), e))?;
+        if !response.status().is_success() {
+            return Err(format!("Printer responded with HTTP {}", response.status()));
+        }
+        response
+            .bytes()
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| format!("Read {} error: {}", self.authority(), e))
+    }
+}
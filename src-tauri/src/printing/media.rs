@@ -0,0 +1,274 @@
+use super::PrintOptions;
+use crate::output::{self, RenderedPage};
+use deepprint_core::RenderError;
+use super::Printer;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tracing::info;
+
+const MM_TO_PT: f32 = 72.0 / 25.4;
+/// 尺寸比较容差 (mm)，吸收驱动上报尺寸的舍入误差
+const TOLERANCE_MM: f32 = 1.0;
+
+/// 常见纸张名称 (PPD / Windows 驱动) 与尺寸 (mm)
+const KNOWN_SIZES: &[(&str, f32, f32)] = &[
+    ("A3", 297.0, 420.0),
+    ("A4", 210.0, 297.0),
+    ("A5", 148.0, 210.0),
+    ("A6", 105.0, 148.0),
+    ("B5", 182.0, 257.0),
+    ("Letter", 215.9, 279.4),
+    ("Legal", 215.9, 355.6),
+    ("Executive", 184.15, 266.7),
+    ("Env10", 104.8, 241.3),
+    ("EnvDL", 110.0, 220.0),
+    ("4x6", 101.6, 152.4),
+];
+
+/// 页面放到纸张上的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaScaling {
+    /// 内容小于纸张时原尺寸居中，大于纸张时等比缩小
+    #[default]
+    Auto,
+    /// 等比缩放填满纸张 (可放大)
+    Fit,
+    /// 原尺寸居中，超出部分裁掉
+    Center,
+    /// 不匹配纸张，按模板尺寸输出，由驱动处理
+    Off,
+}
+
+impl MediaScaling {
+    /// 内容放到纸张上的缩放比例 (尺寸单位一致即可)
+    fn scale(self, content: (f32, f32), media: (f32, f32)) -> f32 {
+        let fit = (media.0 / content.0).min(media.1 / content.1);
+        match self {
+            MediaScaling::Auto => fit.min(1.0),
+            MediaScaling::Fit => fit,
+            MediaScaling::Center | MediaScaling::Off => 1.0,
+        }
+    }
+}
+
+/// 纸张尺寸
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaSize {
+    /// 提交作业时使用的纸张名称
+    pub name: String,
+    pub width_mm: f32,
+    pub height_mm: f32,
+}
+
+impl MediaSize {
+    fn new(name: &str, width_mm: f32, height_mm: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            width_mm,
+            height_mm,
+        }
+    }
+}
+
+/// 从纸张名称解析尺寸，支持：
+/// PWG 自描述名称 (如 "iso_a4_210x297mm"、"na_index-4x6_4x6in")、
+/// PPD 自定义尺寸 "w288h432" (pt)、"Custom.100x60mm" 以及常见名称 (A4、Letter 等)
+pub fn parse(name: &str) -> Option<MediaSize> {
+    static PWG: OnceLock<Regex> = OnceLock::new();
+    static POINTS: OnceLock<Regex> = OnceLock::new();
+    let pwg = PWG.get_or_init(|| {
+        Regex::new(r"^(?:[a-z0-9-]+_[a-z0-9.-]+_|custom\.)([0-9.]+)x([0-9.]+)(mm|in)$").unwrap()
+    });
+    let points = POINTS.get_or_init(|| Regex::new(r"^w([0-9.]+)h([0-9.]+)$").unwrap());
+
+    let lower = name.to_ascii_lowercase();
+    if let Some(caps) = pwg.captures(&lower) {
+        let unit = if &caps[3] == "in" { 25.4 } else { 1.0 };
+        let width: f32 = caps[1].parse().ok()?;
+        let height: f32 = caps[2].parse().ok()?;
+        return Some(MediaSize::new(name, width * unit, height * unit));
+    }
+    // PPD 名称可能带有 ".FullBleed" 等后缀
+    let base = lower.split('.').next().unwrap_or_default();
+    if let Some(caps) = points.captures(base) {
+        let width: f32 = caps[1].parse().ok()?;
+        let height: f32 = caps[2].parse().ok()?;
+        return Some(MediaSize::new(name, width / MM_TO_PT, height / MM_TO_PT));
+    }
+    KNOWN_SIZES
+        .iter()
+        .find(|(known, _, _)| known.eq_ignore_ascii_case(base))
+        .map(|(_, width, height)| MediaSize::new(name, *width, *height))
+}
+
+/// 横向 (宽大于高)
+fn landscape(width: f32, height: f32) -> bool {
+    width > height
+}
+
+/// 选择最接近的纸张：优先能完整容纳内容且面积最小的纸张，
+/// 都放不下时选择缩小比例最小的纸张；rotate 时内容按纸张方向旋转后比较
+pub fn nearest(
+    media: &[MediaSize],
+    width_mm: f32,
+    height_mm: f32,
+    rotate: bool,
+) -> Option<&MediaSize> {
+    let rank = |m: &MediaSize| {
+        let (width_mm, height_mm) =
+            if rotate && landscape(width_mm, height_mm) != landscape(m.width_mm, m.height_mm) {
+                (height_mm, width_mm)
+            } else {
+                (width_mm, height_mm)
+            };
+        let fits =
+            width_mm <= m.width_mm + TOLERANCE_MM && height_mm <= m.height_mm + TOLERANCE_MM;
+        let score = if fits {
+            -(m.width_mm * m.height_mm)
+        } else {
+            (m.width_mm / width_mm).min(m.height_mm / height_mm)
+        };
+        (fits, score)
+    };
+    media.iter().max_by(|a, b| {
+        rank(a)
+            .partial_cmp(&rank(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })
+}
+
+/// 将渲染好的页面匹配到打印机纸张，避免驱动把小标签裁切到默认的 A4 上：
+/// 请求未指定纸张时从打印机支持的纸张中选择最接近的一种并写回 options.media，
+/// 再按 mediaScaling 策略把每页缩放/居中到纸张上；
+/// 页面与纸张方向不一致时 (如横版标签配竖向纸张) 按 autoRotate 先旋转 90°
+pub fn fit_pages(
+    printer: &Printer,
+    pages: Vec<RenderedPage>,
+    options: &mut PrintOptions,
+) -> Result<Vec<RenderedPage>, RenderError> {
+    let scaling = options.media_scaling.unwrap_or_default();
+    let auto_rotate = options.auto_rotate.unwrap_or(true);
+    if scaling == MediaScaling::Off || pages.is_empty() {
+        return Ok(pages);
+    }
+
+    let media = match &options.media {
+        Some(name) => parse(name),
+        None => {
+            let width = pages.iter().map(|p| p.width).fold(0.0, f32::max) / MM_TO_PT;
+            let height = pages.iter().map(|p| p.height).fold(0.0, f32::max) / MM_TO_PT;
+            let selected = nearest(&supported(printer), width, height, auto_rotate).cloned();
+            if let Some(media) = &selected {
+                info!(
+                    "纸张匹配: {:.0}x{:.0}mm -> {} ({})",
+                    width, height, media.name, printer.name
+                );
+                options.media = Some(media.name.clone());
+            }
+            selected
+        }
+    };
+    // 纸张未知 (驱动未上报或名称无法解析) 时保持原样
+    let Some(media) = media else {
+        return Ok(pages);
+    };
+
+    let (media_w, media_h) = (media.width_mm * MM_TO_PT, media.height_mm * MM_TO_PT);
+    pages
+        .into_iter()
+        .map(|page| {
+            let page = if auto_rotate
+                && page.width != page.height
+                && landscape(page.width, page.height) != landscape(media_w, media_h)
+            {
+                output::rotate_page(page)?
+            } else {
+                page
+            };
+            let scale = scaling.scale((page.width, page.height), (media_w, media_h));
+            output::place_page(&page, media_w, media_h, scale)
+        })
+        .collect()
+}
+
+/// 打印机支持的纸张 (CUPS: lpoptions 的 PageSize 选项)
+#[cfg(all(unix, desktop))]
+pub fn supported(printer: &Printer) -> Vec<MediaSize> {
+    let output = match std::process::Command::new("lpoptions")
+        .arg("-p")
+        .arg(&printer.system_name)
+        .arg("-l")
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    // 形如 "PageSize/Media Size: Letter *A4 w288h432 Custom.WIDTHxHEIGHT"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| line.starts_with("PageSize/") || line.starts_with("media/"))
+        .filter_map(|line| line.split_once(':').map(|(_, values)| values.to_string()))
+        .flat_map(|values| {
+            values
+                .split_whitespace()
+                .filter_map(|name| parse(name.trim_start_matches('*')))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// 打印机支持的纸张 (Windows: DeviceCapabilities 的纸张名称与尺寸)
+#[cfg(windows)]
+pub fn supported(printer: &Printer) -> Vec<MediaSize> {
+    use std::ptr;
+    use windows_sys::Win32::Foundation::POINT;
+    use windows_sys::Win32::Storage::Xps::{DeviceCapabilitiesW, DC_PAPERNAMES, DC_PAPERSIZE};
+
+    /// DC_PAPERNAMES 每个名称固定 64 个 UTF-16 字符
+    const NAME_LEN: usize = 64;
+
+    let device: Vec<u16> = printer
+        .system_name
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let capability = |kind, output: *mut u16| unsafe {
+        DeviceCapabilitiesW(device.as_ptr(), ptr::null(), kind, output, ptr::null())
+    };
+    let count = capability(DC_PAPERNAMES, ptr::null_mut());
+    if count <= 0 {
+        return Vec::new();
+    }
+    let count = count as usize;
+
+    let mut names = vec![0u16; count * NAME_LEN];
+    let mut sizes = vec![POINT { x: 0, y: 0 }; count];
+    if capability(DC_PAPERNAMES, names.as_mut_ptr()) <= 0
+        || capability(DC_PAPERSIZE, sizes.as_mut_ptr() as *mut u16) <= 0
+    {
+        return Vec::new();
+    }
+
+    names
+        .chunks(NAME_LEN)
+        .zip(sizes)
+        .filter(|(_, size)| size.x > 0 && size.y > 0)
+        .map(|(name, size)| {
+            let end = name.iter().position(|c| *c == 0).unwrap_or(NAME_LEN);
+            // 尺寸单位为 0.1mm
+            MediaSize::new(
+                &String::from_utf16_lossy(&name[..end]),
+                size.x as f32 / 10.0,
+                size.y as f32 / 10.0,
+            )
+        })
+        .collect()
+}
+
+#[cfg(not(any(all(unix, desktop), windows)))]
+pub fn supported(_printer: &Printer) -> Vec<MediaSize> {
+    Vec::new()
+}
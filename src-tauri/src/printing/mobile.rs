@@ -0,0 +1,23 @@
+/// 移动端 (Android POS 终端等) 没有系统打印队列，printers crate 也无法编译：
+/// 这里提供与 printers crate 同名同字段的 Printer 类型，系统打印机枚举始终为空，
+/// 打印只能通过网络直连打印机 (TCP 9100 / IPP) 完成。
+/// 蓝牙打印机暂不支持：应用无法从 Rust 直接打开 RFCOMM 连接，需要原生 (Kotlin/Swift) 插件，
+/// 串口与 USB 直连在移动端同样返回不支持
+#[allow(dead_code)] // 移动端不会构造系统打印机
+#[derive(Debug, Clone)]
+pub struct Printer {
+    pub name: String,
+    pub system_name: String,
+    pub is_default: bool,
+    pub state: PrinterState,
+    pub state_reasons: Vec<String>,
+}
+
+#[allow(clippy::upper_case_acronyms, dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrinterState {
+    READY,
+    PAUSED,
+    PRINTING,
+    UNKNOWN,
+}
@@ -0,0 +1,261 @@
+use crate::deep_print_schema::CutMode;
+use crate::output::{GrayBitmap, MonoBitmap};
+use std::fmt::Write;
+
+/// 单条 GS v 0 指令的最大行数，超出时分段发送 (部分机型限制单次位图高度)
+const ESCPOS_BAND_ROWS: usize = 256;
+/// 走纸行高按 ESC/POS 默认行距 1/6 英寸换算
+const LINES_PER_INCH: f32 = 6.0;
+/// PWG Raster 页头长度 (PWG 5102.4)
+const PWG_HEADER_LEN: usize = 1796;
+/// PWG Raster 色彩空间 sGray
+const PWG_SGRAY: u32 = 18;
+
+/// 栅格输出的作业与设备参数 (来自打印选项与打印机档案)
+#[derive(Debug, Clone, Copy)]
+pub struct RasterSettings {
+    /// 份数，按整份文档重复输出
+    pub copies: u32,
+    pub cut: CutMode,
+    /// 每份之后都切纸，否则仅在最后一份之后切纸
+    pub cut_per_copy: bool,
+    /// 切纸前 (或打印结束后) 走纸行数
+    pub feed_lines: u8,
+    /// 打印浓度 0-30 (ZPL ~SD；TSPL 按比例换算为 0-15)
+    pub darkness: Option<u8>,
+    /// 打印头分辨率
+    pub dpi: u32,
+}
+
+impl Default for RasterSettings {
+    fn default() -> Self {
+        Self {
+            copies: 1,
+            cut: CutMode::None,
+            cut_per_copy: false,
+            feed_lines: 0,
+            darkness: None,
+            dpi: 203,
+        }
+    }
+}
+
+impl RasterSettings {
+    /// 第 copy 份 (从 0 开始) 之后是否走纸切纸
+    pub(super) fn finish_after(&self, copy: u32) -> bool {
+        self.cut_per_copy || copy + 1 >= self.copies.max(1)
+    }
+}
+
+/// 编码为 ESC/POS 光栅指令：初始化后逐页以 GS v 0 分段输出位图，
+/// 每份 (或最后一份) 之后以 ESC d 走纸并以 GS V 全切/半切
+pub fn to_escpos(pages: &[MonoBitmap], settings: &RasterSettings) -> Vec<u8> {
+    let mut out = vec![0x1B, 0x40]; // ESC @ 初始化
+    for copy in 0..settings.copies.max(1) {
+        for page in pages {
+            for band in page.data.chunks(page.bytes_per_row * ESCPOS_BAND_ROWS) {
+                let rows = band.len() / page.bytes_per_row;
+                out.extend_from_slice(&[0x1D, 0x76, 0x30, 0x00]); // GS v 0, 正常密度
+                out.extend_from_slice(&(page.bytes_per_row as u16).to_le_bytes());
+                out.extend_from_slice(&(rows as u16).to_le_bytes());
+                out.extend_from_slice(band);
+            }
+        }
+        if settings.finish_after(copy) {
+            escpos_finish(&mut out, settings);
+        }
+    }
+    out
+}
+
+/// ESC/POS 一份结束：以 ESC d 走纸并以 GS V 全切/半切
+pub(super) fn escpos_finish(out: &mut Vec<u8>, settings: &RasterSettings) {
+    if settings.feed_lines > 0 {
+        out.extend_from_slice(&[0x1B, 0x64, settings.feed_lines]); // ESC d n
+    }
+    match settings.cut {
+        CutMode::None => {}
+        CutMode::Full => out.extend_from_slice(&[0x1D, 0x56, 0x41, 0x00]), // GS V 65 0
+        CutMode::Partial => out.extend_from_slice(&[0x1D, 0x56, 0x42, 0x00]), // GS V 66 0
+    }
+}
+
+/// 编码为 ZPL：每页一个标签，位图以 ^GFA (ASCII 十六进制) 输出；
+/// 份数由打印机重复：单页时以 ^PQ 指定，多页时位图以 ~DG 存入打印机内存只发送一次，
+/// 每份按页序以 ^XG 调用 (逐份打印)，结束后删除
+pub fn to_zpl(pages: &[MonoBitmap], settings: &RasterSettings) -> Vec<u8> {
+    let copies = settings.copies.max(1);
+    let mut out = String::new();
+    if let Some(darkness) = settings.darkness {
+        let _ = write!(out, "~SD{:02}", darkness.min(30));
+    }
+    if let [page] = pages {
+        let total = page.data.len();
+        out.reserve(total * 2 + 64);
+        let _ = write!(
+            out,
+            "^XA^PW{}^LL{}^FO0,0^GFA,{},{},{},",
+            page.width, page.height, total, total, page.bytes_per_row
+        );
+        zpl_hex(&mut out, &page.data);
+        let _ = write!(out, "^FS^PQ{}^XZ\n", copies);
+        return out.into_bytes();
+    }
+
+    for (i, page) in pages.iter().enumerate() {
+        out.reserve(page.data.len() * 2 + 64);
+        let _ = write!(
+            out,
+            "~DGR:DP{}.GRF,{},{},",
+            i,
+            page.data.len(),
+            page.bytes_per_row
+        );
+        zpl_hex(&mut out, &page.data);
+        out.push('\n');
+    }
+    for _ in 0..copies {
+        for (i, page) in pages.iter().enumerate() {
+            let _ = write!(
+                out,
+                "^XA^PW{}^LL{}^FO0,0^XGR:DP{}.GRF,1,1^FS^XZ\n",
+                page.width, page.height, i
+            );
+        }
+    }
+    out.push_str("^XA^IDR:DP*.GRF^FS^XZ\n");
+    out.into_bytes()
+}
+
+fn zpl_hex(out: &mut String, data: &[u8]) {
+    for byte in data {
+        let _ = write!(out, "{:02X}", byte);
+    }
+}
+
+/// 编码为 TSPL (TSC、佳博等标签打印机)：每页一个标签，位图以 BITMAP 输出；
+/// 单页时份数由 PRINT 1,n 交给打印机重复，多页时按份重复发送 (逐份打印)；
+/// 切刀按标签计数触发，每份切纸时计数为每份页数，否则整个作业结束后切一次，
+/// 走纸行数换算为切纸前的 OFFSET (不切纸时在结束后 FEED)
+pub fn to_tspl(pages: &[MonoBitmap], settings: &RasterSettings) -> Vec<u8> {
+    let copies = settings.copies.max(1);
+    let dpi = settings.dpi.max(1) as f32;
+    let feed_mm = settings.feed_lines as f32 * 25.4 / LINES_PER_INCH;
+    let mut out = Vec::new();
+
+    if let Some(darkness) = settings.darkness {
+        out.extend_from_slice(format!("DENSITY {}\r\n", darkness.min(30) / 2).as_bytes());
+    }
+    let interval = if settings.cut_per_copy && settings.copies > 1 {
+        pages.len().max(1).to_string()
+    } else {
+        "BATCH".to_string()
+    };
+    let setup = match settings.cut {
+        CutMode::None => "SET CUTTER OFF\r\nSET PARTIAL_CUTTER OFF\r\n".to_string(),
+        CutMode::Full => format!("SET PARTIAL_CUTTER OFF\r\nSET CUTTER {}\r\n", interval),
+        CutMode::Partial => format!("SET CUTTER OFF\r\nSET PARTIAL_CUTTER {}\r\n", interval),
+    };
+    out.extend_from_slice(setup.as_bytes());
+    if settings.cut != CutMode::None {
+        out.extend_from_slice(format!("OFFSET {:.1} mm\r\n", feed_mm).as_bytes());
+    }
+
+    let (repeat, per_label) = if pages.len() == 1 { (1, copies) } else { (copies, 1) };
+    for _ in 0..repeat {
+        for page in pages {
+            let header = format!(
+                "SIZE {:.1} mm,{:.1} mm\r\nCLS\r\nBITMAP 0,0,{},{},0,",
+                page.width as f32 * 25.4 / dpi,
+                page.height as f32 * 25.4 / dpi,
+                page.bytes_per_row,
+                page.height
+            );
+            out.extend_from_slice(header.as_bytes());
+            // TSPL 位图中 0 为黑点
+            out.extend(page.data.iter().map(|byte| !byte));
+            out.extend_from_slice(format!("\r\nPRINT 1,{}\r\n", per_label).as_bytes());
+        }
+    }
+    if settings.cut == CutMode::None && settings.feed_lines > 0 {
+        let dots = (feed_mm * dpi / 25.4).round() as u32;
+        out.extend_from_slice(format!("FEED {}\r\n", dots).as_bytes());
+    }
+    out
+}
+
+/// 编码为 PWG Raster (PWG 5102.4，sgray_8)，用于不接受 PDF 的 IPP Everywhere 打印机；
+/// 份数与双面由 IPP 作业属性控制，页头中不设置
+pub fn to_pwg(pages: &[GrayBitmap], dpi: u32) -> Vec<u8> {
+    let dpi = dpi.max(1);
+    let mut out = b"RaS2".to_vec();
+    for page in pages {
+        let mut header = vec![0u8; PWG_HEADER_LEN];
+        let mut put = |offset: usize, value: u32| {
+            header[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+        };
+        put(276, dpi); // HWResolution
+        put(280, dpi);
+        put(340, 1); // NumCopies
+        put(352, page.width * 72 / dpi); // PageSize (points)
+        put(356, page.height * 72 / dpi);
+        put(372, page.width);
+        put(376, page.height);
+        put(384, 8); // BitsPerColor
+        put(388, 8); // BitsPerPixel
+        put(392, page.width); // BytesPerLine
+        put(400, PWG_SGRAY);
+        put(420, 1); // NumColors
+        put(452, pages.len() as u32); // TotalPageCount
+        put(456, 1); // CrossFeedTransform
+        put(460, 1); // FeedTransform
+        put(480, 0x00FF_FFFF); // AlternatePrimary
+        header[..9].copy_from_slice(b"PwgRaster");
+        out.extend_from_slice(&header);
+
+        let width = page.width as usize;
+        let rows: Vec<&[u8]> = page.data.chunks_exact(width.max(1)).collect();
+        let mut y = 0;
+        while y < rows.len() {
+            // 行重复计数：相同的行合并 (最多 256 行)
+            let mut repeat = 1;
+            while y + repeat < rows.len() && repeat < 256 && rows[y + repeat] == rows[y] {
+                repeat += 1;
+            }
+            out.push((repeat - 1) as u8);
+            pwg_encode_row(rows[y], &mut out);
+            y += repeat;
+        }
+    }
+    out
+}
+
+/// PackBits 变体：0-127 表示下一像素重复 n+1 次，129-255 表示其后 257-n 个像素原样输出
+fn pwg_encode_row(row: &[u8], out: &mut Vec<u8>) {
+    let mut x = 0;
+    while x < row.len() {
+        let mut run = 1;
+        while x + run < row.len() && run < 128 && row[x + run] == row[x] {
+            run += 1;
+        }
+        if run > 1 || x + 1 == row.len() {
+            out.push((run - 1) as u8);
+            out.push(row[x]);
+            x += run;
+            continue;
+        }
+        // 原样输出直到出现连续相同的像素
+        let start = x;
+        x += 1;
+        while x < row.len() && x - start < 128 && !(x + 1 < row.len() && row[x] == row[x + 1]) {
+            x += 1;
+        }
+        let count = x - start;
+        if count == 1 {
+            out.push(0);
+        } else {
+            out.push((257 - count) as u8);
+        }
+        out.extend_from_slice(&row[start..x]);
+    }
+}
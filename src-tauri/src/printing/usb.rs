@@ -0,0 +1,273 @@
+#[cfg(desktop)]
+use rusb::{Device, DeviceHandle, Direction, GlobalContext, TransferType, UsbContext};
+use serde::Serialize;
+#[cfg(desktop)]
+use std::time::Duration;
+
+/// 单次批量写入超时
+#[cfg(desktop)]
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 状态查询的读取超时 (打印机离线或不支持实时状态时不会应答)
+#[cfg(desktop)]
+const READ_TIMEOUT: Duration = Duration::from_millis(1000);
+/// USB 打印机接口类 (Printer Class)
+#[cfg(desktop)]
+const PRINTER_CLASS: u8 = 0x07;
+
+/// 常见热敏打印机厂商 ID，部分机型使用厂商自定义接口类，需按厂商识别
+/// 不含 Winbond (0x0416)、STMicro (0x0483)、GigaDevice (0x28E9) 等芯片厂商的 ID：
+/// 使用这些 ID 的还有键盘、调试器等设备，按厂商识别会误选并向其写入数据。
+/// 此类打印机只按打印机类接口识别，使用自定义接口类的机型需在配置中指定 vendorId 与 productId
+#[cfg(desktop)]
+const KNOWN_VENDORS: &[(u16, &str)] = &[
+    (0x04B8, "Epson"),
+    (0x0519, "Star"),
+    (0x1504, "Bixolon"),
+    (0x1D90, "Citizen"),
+    (0x154F, "SNBC"),
+    (0x0DD4, "Custom"),
+    (0x0A5F, "Zebra"),
+    (0x6868, "Hprt"),
+];
+
+/// 检测到的 USB 打印机
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbPrinterInfo {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// 厂商名 (已知厂商时)
+    pub vendor: Option<String>,
+    pub serial: Option<String>,
+    pub bus: u8,
+    pub address: u8,
+}
+
+/// 可写入的打印接口
+#[cfg(desktop)]
+struct PrinterEndpoint {
+    interface: u8,
+    endpoint: u8,
+    /// 同一接口上的批量 IN 端点 (用于读取实时状态，部分设备没有)
+    input: Option<u8>,
+    /// 标准打印机类接口 (否则为按厂商 ID 识别的自定义接口)
+    printer_class: bool,
+}
+
+/// 列出已连接的 USB 打印机 (打印机类接口或已知热敏厂商)
+#[cfg(desktop)]
+pub fn detect() -> Result<Vec<UsbPrinterInfo>, String> {
+    let devices = rusb::devices().map_err(|e| format!("List USB devices error: {}", e))?;
+    let mut printers = Vec::new();
+    for device in devices.iter() {
+        let Ok(desc) = device.device_descriptor() else {
+            continue;
+        };
+        if find_endpoint(&device, false).is_none() {
+            continue;
+        }
+        let serial = device
+            .open()
+            .ok()
+            .and_then(|handle| handle.read_serial_number_string_ascii(&desc).ok());
+        printers.push(UsbPrinterInfo {
+            vendor_id: desc.vendor_id(),
+            product_id: desc.product_id(),
+            vendor: vendor_name(desc.vendor_id()).map(str::to_string),
+            serial,
+            bus: device.bus_number(),
+            address: device.address(),
+        });
+    }
+    Ok(printers)
+}
+
+/// 将数据写入 USB 打印机的批量 OUT 端点
+/// 未指定 vendor/product 时自动选择 (优先标准打印机类接口)；serial 用于区分同型号的多台设备
+#[cfg(desktop)]
+pub fn send(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    serial: Option<&str>,
+    data: &[u8],
+) -> Result<(), String> {
+    let (handle, endpoint) = open(vendor_id, product_id, serial)?;
+    let mut written = 0;
+    let result = loop {
+        if written >= data.len() {
+            break Ok(());
+        }
+        match handle.write_bulk(endpoint.endpoint, &data[written..], WRITE_TIMEOUT) {
+            Ok(0) => break Err("USB printer accepted no data".to_string()),
+            Ok(n) => written += n,
+            Err(e) => break Err(format!("Write USB printer error: {}", e)),
+        }
+    };
+    let _ = handle.release_interface(endpoint.interface);
+    result
+}
+
+/// 写入查询指令并从批量 IN 端点读取应答 (如 ESC/POS 的 DLE EOT 实时状态)
+#[cfg(desktop)]
+pub fn query(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    serial: Option<&str>,
+    request: &[u8],
+) -> Result<Vec<u8>, String> {
+    let (handle, endpoint) = open(vendor_id, product_id, serial)?;
+    let result = exchange(&handle, &endpoint, request);
+    let _ = handle.release_interface(endpoint.interface);
+    result
+}
+
+#[cfg(desktop)]
+fn exchange(
+    handle: &DeviceHandle<GlobalContext>,
+    endpoint: &PrinterEndpoint,
+    request: &[u8],
+) -> Result<Vec<u8>, String> {
+    let input = endpoint
+        .input
+        .ok_or_else(|| "USB printer has no bulk IN endpoint".to_string())?;
+    handle
+        .write_bulk(endpoint.endpoint, request, WRITE_TIMEOUT)
+        .map_err(|e| format!("Write USB printer error: {}", e))?;
+    let mut buf = [0u8; 64];
+    let n = handle
+        .read_bulk(input, &mut buf, READ_TIMEOUT)
+        .map_err(|e| format!("Read USB printer error: {}", e))?;
+    Ok(buf[..n].to_vec())
+}
+
+/// 匹配的 USB 打印机是否已连接
+#[cfg(desktop)]
+pub fn connected(vendor_id: Option<u16>, product_id: Option<u16>, serial: Option<&str>) -> bool {
+    find(vendor_id, product_id, serial).is_ok()
+}
+
+/// 打开匹配的 USB 打印机并声明其打印接口
+#[cfg(desktop)]
+fn open(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    serial: Option<&str>,
+) -> Result<(DeviceHandle<GlobalContext>, PrinterEndpoint), String> {
+    let (device, endpoint) = find(vendor_id, product_id, serial)?;
+    let mut handle = device
+        .open()
+        .map_err(|e| format!("Open USB printer error: {}", e))?;
+    // Linux 上由 usblp 驱动占用时需先分离 (其他平台不支持，忽略错误)
+    let _ = handle.set_auto_detach_kernel_driver(true);
+    handle
+        .claim_interface(endpoint.interface)
+        .map_err(|e| format!("Claim USB interface error: {}", e))?;
+    Ok((handle, endpoint))
+}
+
+/// 查找匹配的 USB 打印机
+/// 未指定 vendor/product 时自动选择 (优先标准打印机类接口)；serial 用于区分同型号的多台设备
+/// 同时指定 vendor 与 product 时视为用户确认的打印机，接受任意接口类的批量 OUT 端点
+#[cfg(desktop)]
+fn find(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    serial: Option<&str>,
+) -> Result<(Device<GlobalContext>, PrinterEndpoint), String> {
+    let explicit = vendor_id.is_some() && product_id.is_some();
+    let devices = rusb::devices().map_err(|e| format!("List USB devices error: {}", e))?;
+    devices
+        .iter()
+        .filter_map(|device| {
+            let desc = device.device_descriptor().ok()?;
+            if vendor_id.is_some_and(|id| id != desc.vendor_id())
+                || product_id.is_some_and(|id| id != desc.product_id())
+            {
+                return None;
+            }
+            let endpoint = find_endpoint(&device, explicit)?;
+            if let Some(serial) = serial {
+                let handle = device.open().ok()?;
+                let actual = handle.read_serial_number_string_ascii(&desc).ok()?;
+                if actual != serial {
+                    return None;
+                }
+            }
+            Some((device, endpoint))
+        })
+        .min_by_key(|(_, endpoint)| !endpoint.printer_class)
+        .ok_or_else(|| "No matching USB printer found".to_string())
+}
+
+#[cfg(desktop)]
+fn vendor_name(vendor_id: u16) -> Option<&'static str> {
+    KNOWN_VENDORS
+        .iter()
+        .find(|(id, _)| *id == vendor_id)
+        .map(|(_, name)| *name)
+}
+
+/// 查找打印机类接口 (或已知厂商设备的任意接口) 上的批量 OUT 端点 (及同一接口上的批量 IN 端点)
+/// any_class 时不限接口类 (用户按 vendor/product 指定的设备)
+#[cfg(desktop)]
+fn find_endpoint<T: UsbContext>(device: &Device<T>, any_class: bool) -> Option<PrinterEndpoint> {
+    let desc = device.device_descriptor().ok()?;
+    let any_interface = any_class || vendor_name(desc.vendor_id()).is_some();
+    let config = device.active_config_descriptor().ok()?;
+    for interface in config.interfaces() {
+        for alt in interface.descriptors() {
+            if alt.class_code() != PRINTER_CLASS && !any_interface {
+                continue;
+            }
+            let endpoint = alt.endpoint_descriptors().find(|ep| {
+                ep.direction() == Direction::Out && ep.transfer_type() == TransferType::Bulk
+            });
+            if let Some(ep) = endpoint {
+                let input = alt
+                    .endpoint_descriptors()
+                    .find(|ep| {
+                        ep.direction() == Direction::In && ep.transfer_type() == TransferType::Bulk
+                    })
+                    .map(|ep| ep.address());
+                return Some(PrinterEndpoint {
+                    interface: alt.interface_number(),
+                    endpoint: ep.address(),
+                    input,
+                    printer_class: alt.class_code() == PRINTER_CLASS,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// 移动端没有 libusb，不检测 USB 打印机
+#[cfg(mobile)]
+pub fn detect() -> Result<Vec<UsbPrinterInfo>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(mobile)]
+pub fn send(
+    _vendor_id: Option<u16>,
+    _product_id: Option<u16>,
+    _serial: Option<&str>,
+    _data: &[u8],
+) -> Result<(), String> {
+    Err("USB printers are not supported on mobile".to_string())
+}
+
+#[cfg(mobile)]
+pub fn query(
+    _vendor_id: Option<u16>,
+    _product_id: Option<u16>,
+    _serial: Option<&str>,
+    _request: &[u8],
+) -> Result<Vec<u8>, String> {
+    Err("USB printers are not supported on mobile".to_string())
+}
+
+#[cfg(mobile)]
+pub fn connected(_vendor_id: Option<u16>, _product_id: Option<u16>, _serial: Option<&str>) -> bool {
+    false
+}
@@ -0,0 +1,117 @@
+use std::ffi::c_void;
+use std::ptr;
+use super::{SpoolerJobState, MAX_COPIES};
+use windows_sys::Win32::Graphics::Printing::{
+    ClosePrinter, EndDocPrinter, EndPagePrinter, GetJobW, OpenPrinterW, StartDocPrinterW,
+    StartPagePrinter, WritePrinter, DOC_INFO_1W, JOB_INFO_1W, JOB_STATUS_COMPLETE,
+    JOB_STATUS_DELETED, JOB_STATUS_DELETING, JOB_STATUS_PRINTED, JOB_STATUS_PRINTING,
+    PRINTER_HANDLE,
+};
+
+/// 通过 winspool 以 RAW 数据类型提交到打印队列，返回后台处理程序的作业 ID
+/// 数据不经过图形驱动 (GDI) 转换，避免热敏打印机驱动改写 ESC/POS、ZPL 指令；
+/// copies > 1 时在同一文档内重复写入数据 (不超过 MAX_COPIES 份)
+pub fn submit_raw(printer: &str, job_name: &str, data: &[u8], copies: u32) -> Result<u64, String> {
+    let handle = PrinterHandle::open(printer)?;
+
+    let mut doc_name = wide(job_name);
+    let mut datatype = wide("RAW");
+    let doc_info = DOC_INFO_1W {
+        pDocName: doc_name.as_mut_ptr(),
+        pOutputFile: ptr::null_mut(),
+        pDatatype: datatype.as_mut_ptr(),
+    };
+    let job_id = unsafe { StartDocPrinterW(handle.0, 1, &doc_info) };
+    if job_id == 0 {
+        return Err(format!("StartDocPrinter error: {}", last_error()));
+    }
+
+    let result = write_pages(&handle, data, copies.clamp(1, MAX_COPIES));
+    unsafe { EndDocPrinter(handle.0) };
+    result.map(|_| job_id as u64)
+}
+
+/// 查询后台处理程序中的作业状态；作业已从队列移除 (通常是打印完成) 时返回 Gone
+pub fn job_state(printer: &str, job_id: u64) -> Result<SpoolerJobState, String> {
+    let handle = PrinterHandle::open(printer)?;
+    let job_id = job_id as u32;
+
+    let mut needed = 0u32;
+    unsafe { GetJobW(handle.0, job_id, 1, ptr::null_mut(), 0, &mut needed) };
+    if needed == 0 {
+        return Ok(SpoolerJobState::Gone);
+    }
+    // JOB_INFO_1W 之后紧跟其字符串字段，按 8 字节对齐分配
+    let mut buffer = vec![0u64; (needed as usize).div_ceil(8)];
+    let ok = unsafe {
+        GetJobW(handle.0, job_id, 1, buffer.as_mut_ptr() as *mut u8, needed, &mut needed)
+    };
+    if ok == 0 {
+        return Ok(SpoolerJobState::Gone);
+    }
+    let status = unsafe { (*(buffer.as_ptr() as *const JOB_INFO_1W)).Status };
+
+    Ok(if status & (JOB_STATUS_DELETED | JOB_STATUS_DELETING) != 0 {
+        SpoolerJobState::Cancelled
+    } else if status & (JOB_STATUS_PRINTED | JOB_STATUS_COMPLETE) != 0 {
+        SpoolerJobState::Completed
+    } else if status & JOB_STATUS_PRINTING != 0 {
+        SpoolerJobState::Printing
+    } else {
+        SpoolerJobState::Pending
+    })
+}
+
+fn write_pages(handle: &PrinterHandle, data: &[u8], copies: u32) -> Result<(), String> {
+    if unsafe { StartPagePrinter(handle.0) } == 0 {
+        return Err(format!("StartPagePrinter error: {}", last_error()));
+    }
+    let result = (0..copies).try_for_each(|_| write_all(handle, data));
+    unsafe { EndPagePrinter(handle.0) };
+    result
+}
+
+/// WritePrinter 可能只写入部分数据，循环直至写完
+fn write_all(handle: &PrinterHandle, mut data: &[u8]) -> Result<(), String> {
+    while !data.is_empty() {
+        let chunk = data.len().min(u32::MAX as usize) as u32;
+        let mut written = 0u32;
+        let ok = unsafe {
+            WritePrinter(handle.0, data.as_ptr() as *const c_void, chunk, &mut written)
+        };
+        if ok == 0 || written == 0 {
+            return Err(format!("WritePrinter error: {}", last_error()));
+        }
+        data = &data[written as usize..];
+    }
+    Ok(())
+}
+
+/// 打印机句柄，离开作用域时自动关闭
+struct PrinterHandle(PRINTER_HANDLE);
+
+impl PrinterHandle {
+    fn open(printer: &str) -> Result<Self, String> {
+        let name = wide(printer);
+        let mut handle: PRINTER_HANDLE = ptr::null_mut();
+        if unsafe { OpenPrinterW(name.as_ptr(), &mut handle, ptr::null()) } == 0 {
+            return Err(format!("OpenPrinter '{}' error: {}", printer, last_error()));
+        }
+        Ok(Self(handle))
+    }
+}
+
+impl Drop for PrinterHandle {
+    fn drop(&mut self) {
+        unsafe { ClosePrinter(self.0) };
+    }
+}
+
+/// UTF-16 + 结尾 0
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn last_error() -> std::io::Error {
+    std::io::Error::last_os_error()
+}
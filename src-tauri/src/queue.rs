@@ -0,0 +1,1039 @@
+use crate::audit;
+use crate::config::{
+    AgentConfig, DuplicateAction, DuplicateConfig, OutputConfig, PrinterProfile, RemoteAssetConfig,
+};
+use crate::crash;
+use crate::deep_print_schema::DeepPrintTemplate;
+use crate::engine::{self, Engine};
+use crate::error::PrintError;
+use crate::jobs::{JobStatus, JobStore};
+use crate::output::{self, Composition, ImageLayout, Imposition, PdfOptions, RenderedPage};
+use crate::printing::direct::{self, DirectTarget, PrinterLanguage};
+use crate::printing::escpos_text::{self, CodePage};
+use crate::printing::media;
+use crate::printing::raster::{self, RasterSettings};
+use crate::printing::{self, Destination, PrintOptions, Printer};
+use crate::remote_assets;
+use crate::renderer::{CancelToken, RenderOptions};
+use crate::retention;
+use crate::tracker::JobTracker;
+use deepprint_core::plain_text;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, Semaphore};
+use tracing::{debug, info, warn};
+
+/// 任务内容 (序列化后随任务记录持久化，用于重启后恢复)
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum JobPayload {
+    /// 旧版资产标签布局 (engine.rs 固定模板)
+    Content {
+        content: String,
+        width_mm: Option<f32>,
+        height_mm: Option<f32>,
+    },
+    /// DeepPrint 模板 + 数据
+    Template {
+        template: Box<DeepPrintTemplate>,
+        data: Value,
+        render_options: RenderOptions,
+    },
+    /// DeepPrint 模板 + 多条数据，合并为一个多页文档
+    Records {
+        template: Box<DeepPrintTemplate>,
+        records: Vec<Value>,
+        render_options: RenderOptions,
+        /// 拼版：按网格把各条数据的标签排在同一张纸上
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        imposition: Option<Imposition>,
+    },
+    /// 客户端直接上传的 PDF，不经过渲染引擎
+    Pdf {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+    /// 客户端上传的 PNG/JPEG，按版式缩放/旋转后打印
+    Image {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+        layout: ImageLayout,
+    },
+    /// 打印机原始指令 (ESC/POS、ZPL 等)，原样透传给设备
+    Raw {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+}
+
+/// Vec<u8> 以 base64 字符串持久化
+mod base64_bytes {
+    use base64::Engine as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// 待处理的打印任务
+pub struct PrintJob {
+    pub task_id: String,
+    pub printer: Destination,
+    pub payload: JobPayload,
+    pub options: PrintOptions,
+    /// 目标打印机的档案 (纸宽、旋转、偏移、走纸/切纸)
+    pub profile: Option<PrinterProfile>,
+    /// 分配自打印机池时的池名：开始执行前该成员离线则改派到池中其他在线成员
+    pub pool: Option<String>,
+}
+
+/// 持久化的任务请求：系统打印机按名称保存，恢复时重新查找；直连设备保存连接参数
+#[derive(Serialize, Deserialize)]
+struct StoredJob {
+    #[serde(default)]
+    printer: String,
+    #[serde(default)]
+    direct: Option<DirectTarget>,
+    payload: JobPayload,
+    options: PrintOptions,
+    #[serde(default)]
+    profile: Option<PrinterProfile>,
+    #[serde(default)]
+    pool: Option<String>,
+}
+
+impl StoredJob {
+    /// 恢复输出目标：直连设备直接使用保存的参数，系统打印机按名称重新查找
+    fn destination(&self) -> Result<Destination, PrintError> {
+        match &self.direct {
+            Some(target) => Ok(Destination::Direct(target.clone())),
+            None => printing::find_printer(&self.printer)
+                .map(Destination::Spooler)
+                .map_err(|_| PrintError::PrinterNotFound(self.printer.clone())),
+        }
+    }
+}
+
+impl JobPayload {
+    /// 为渲染类任务设置取消令牌
+    fn set_cancel(&mut self, token: CancelToken) {
+        if let JobPayload::Template { render_options, .. }
+        | JobPayload::Records { render_options, .. } = self
+        {
+            render_options.cancel = Some(token);
+        }
+    }
+}
+
+/// 排队中与渲染中任务的取消令牌 {task_id: token}
+type Cancellations = Arc<Mutex<HashMap<String, CancelToken>>>;
+
+/// 入队失败：队列已满
+#[derive(Debug)]
+pub struct QueueFull;
+
+/// 打印队列
+/// 任务按打印机分发到各自的子队列：同一台打印机的任务严格按入队顺序逐个执行
+/// (如厨房与吧台小票交替下单时各自保持顺序)，不同打印机并行，
+/// 同时执行的任务数受 workers 限制
+#[derive(Clone)]
+pub struct PrintQueue {
+    sender: mpsc::UnboundedSender<PrintJob>,
+    /// 已入队、尚未开始执行的任务数
+    pending: Arc<AtomicUsize>,
+    capacity: usize,
+    /// 暂停时新任务照常入队，但不再开始执行 (执行中的任务不受影响)
+    paused: Arc<watch::Sender<bool>>,
+    /// 全局并发名额，全部空闲时没有执行中的任务
+    permits: Arc<Semaphore>,
+    workers: u32,
+    cancels: Cancellations,
+    jobs: JobStore,
+    tracker: JobTracker,
+}
+
+impl PrintQueue {
+    /// 创建队列并启动分发任务 (需在 tokio 运行时中调用)
+    /// 队列参数在启动时读取；输出目录等设置在每个任务执行时读取，修改后立即生效
+    pub fn start(settings: Arc<RwLock<AgentConfig>>, jobs: JobStore) -> Self {
+        let config = settings.read().unwrap().queue.clone();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let workers = config.workers.max(1);
+        let permits = Arc::new(Semaphore::new(workers));
+        let (paused, _) = watch::channel(false);
+        let tracker = JobTracker::start(Duration::from_secs(config.spooler_poll_secs), jobs.clone());
+        engine::init_render_pool(config.render_threads);
+        let cancels = Cancellations::default();
+
+        tokio::spawn(dispatch(
+            receiver,
+            Lane {
+                dispatch: sender.clone(),
+                permits: permits.clone(),
+                paused: paused.subscribe(),
+                pending: pending.clone(),
+                cancels: cancels.clone(),
+                settings,
+                jobs: jobs.clone(),
+                tracker: tracker.clone(),
+                recent: RecentDocuments::default(),
+            },
+        ));
+
+        let queue = Self {
+            sender,
+            pending,
+            capacity: config.capacity.max(1),
+            paused: Arc::new(paused),
+            permits,
+            workers: workers as u32,
+            cancels,
+            jobs,
+            tracker,
+        };
+        queue.resume_unfinished();
+        queue.resume_tracking();
+        queue
+    }
+
+    /// 任务入队，队列已满时立即返回错误 (不阻塞请求)
+    /// 入队前先持久化任务内容，保证崩溃后可以恢复
+    pub fn enqueue(&self, job: PrintJob) -> Result<(), QueueFull> {
+        self.persist(&job);
+        self.push(job)
+    }
+
+    /// 批量入队：一次占用全部队列位置，剩余容量不足时整批拒绝，不会只入队一部分
+    pub fn enqueue_all(&self, jobs: Vec<PrintJob>) -> Result<(), QueueFull> {
+        self.reserve(jobs.len())?;
+        let count = jobs.len();
+        for (i, job) in jobs.into_iter().enumerate() {
+            self.persist(&job);
+            if self.send(job).is_err() {
+                // 分发任务已退出：释放其余任务占用的位置
+                self.pending.fetch_sub(count - i - 1, Ordering::SeqCst);
+                return Err(QueueFull);
+            }
+        }
+        Ok(())
+    }
+
+    /// 当前排队中 (尚未开始执行) 的任务数
+    pub fn depth(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// 暂停出单
+    pub fn pause(&self) {
+        if !self.paused.send_replace(true) {
+            info!("打印队列已暂停");
+        }
+    }
+
+    /// 恢复出单，暂停期间积压的任务按顺序继续执行
+    pub fn resume(&self) {
+        if self.paused.send_replace(false) {
+            info!("打印队列已恢复");
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// 暂停出单并等待执行中的任务结束 (最多 timeout)，用于安装更新重启前；
+    /// 排队中的任务已持久化，重启后继续执行。返回执行中的任务是否均已结束
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.pause();
+        let all = self.permits.acquire_many(self.workers);
+        matches!(tokio::time::timeout(timeout, all).await, Ok(Ok(_)))
+    }
+
+    /// 取消尚未提交的任务 (任意类型)：排队中的任务不再执行，渲染中的任务在下一个元素/表格行处停止，
+    /// 提交前再检查一次。开始提交 (submitting) 后的任务无法取消，返回 false
+    pub fn cancel(&self, task_id: &str) -> bool {
+        match self.cancels.lock().unwrap().get(task_id) {
+            Some(token) => {
+                token.cancel();
+                info!("取消任务: {}", task_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 以新任务重打最近一个任务 (使用当时保存的内容与打印机)，返回新任务 ID
+    pub fn reprint_last(&self) -> Result<String, String> {
+        let (record, payload) = self
+            .jobs
+            .last_with_payload()
+            .ok_or_else(|| "No job to reprint".to_string())?;
+        let stored: StoredJob = serde_json::from_str(&payload)
+            .map_err(|e| format!("Cannot reprint job {}: {}", record.task_id, e))?;
+        let printer = stored
+            .destination()
+            .map_err(|e| format!("Cannot reprint job {}: {}", record.task_id, e))?;
+
+        let task_id = uuid::Uuid::new_v4().to_string();
+        self.jobs
+            .create(
+                &task_id,
+                &record.kind,
+                record.printer.clone(),
+                record.api_key.as_deref(),
+            )
+            .map_err(|_| format!("Task {} is already queued or printing", task_id))?;
+        let job = PrintJob {
+            task_id: task_id.clone(),
+            printer,
+            payload: stored.payload,
+            // 主动重打不做重复内容检测
+            options: PrintOptions {
+                force: Some(true),
+                ..stored.options
+            },
+            profile: stored.profile,
+            pool: stored.pool,
+        };
+        if self.enqueue(job).is_err() {
+            let message = "Print queue is full".to_string();
+            self.jobs.mark_failed_with_code(&task_id, "queue_full", message.clone());
+            return Err(message);
+        }
+        info!("重打任务 {} -> {}", record.task_id, task_id);
+        Ok(task_id)
+    }
+
+    /// 占用一个队列位置后交给分发任务
+    fn push(&self, job: PrintJob) -> Result<(), QueueFull> {
+        self.reserve(1)?;
+        self.send(job)
+    }
+
+    /// 占用 count 个队列位置
+    fn reserve(&self, count: usize) -> Result<(), QueueFull> {
+        self.pending
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n + count <= self.capacity).then_some(n + count)
+            })
+            .map(|_| ())
+            .map_err(|_| QueueFull)
+    }
+
+    /// 交给分发任务 (已占用队列位置)，失败时释放该位置
+    fn send(&self, job: PrintJob) -> Result<(), QueueFull> {
+        let task_id = job.task_id.clone();
+        self.cancels
+            .lock()
+            .unwrap()
+            .insert(task_id.clone(), CancelToken::new());
+        self.sender.send(job).map_err(|_| {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            self.cancels.lock().unwrap().remove(&task_id);
+            QueueFull
+        })
+    }
+
+    fn persist(&self, job: &PrintJob) {
+        persist(&self.jobs, job);
+    }
+
+    /// 恢复上次运行时未完成的任务 (崩溃或重启前仍在排队/渲染中的任务)
+    fn resume_unfinished(&self) {
+        for (record, payload) in self.jobs.unfinished() {
+            let stored: StoredJob = match serde_json::from_str(&payload) {
+                Ok(s) => s,
+                Err(e) => {
+                    self.jobs
+                        .mark_failed(&record.task_id, format!("Cannot resume job: {}", e));
+                    continue;
+                }
+            };
+            let printer = match stored.destination() {
+                Ok(printer) => printer,
+                Err(e) => {
+                    self.jobs.mark_failed_with_code(
+                        &record.task_id,
+                        e.code(),
+                        format!("Cannot resume job: {}", e),
+                    );
+                    continue;
+                }
+            };
+
+            // 提交途中退出的任务可能已经打出，不再重复提交
+            if record.status == JobStatus::Submitting {
+                self.jobs.mark_failed(
+                    &record.task_id,
+                    "Interrupted while submitting to the printer; not resubmitted to avoid a duplicate print".to_string(),
+                );
+                continue;
+            }
+            info!("恢复未完成的任务: {}", record.task_id);
+            self.jobs.set_status(&record.task_id, JobStatus::Queued);
+            let job = PrintJob {
+                task_id: record.task_id.clone(),
+                printer,
+                payload: stored.payload,
+                options: stored.options,
+                profile: stored.profile,
+                pool: stored.pool,
+            };
+            if self.push(job).is_err() {
+                self.jobs.mark_failed_with_code(
+                    &record.task_id,
+                    "queue_full",
+                    "Print queue is full".to_string(),
+                );
+            }
+        }
+    }
+
+    /// 继续跟踪上次运行时已提交、尚未确认打印结果的系统作业
+    fn resume_tracking(&self) {
+        for (record, payload) in self.jobs.in_spooler() {
+            let (Some(job_id), Ok(stored)) = (
+                record.spooler_job_id,
+                serde_json::from_str::<StoredJob>(&payload),
+            ) else {
+                continue;
+            };
+            match stored.destination() {
+                Ok(destination) => self.tracker.track(&record.task_id, destination, job_id),
+                Err(e) => warn!("无法继续跟踪任务 {}: {}", record.task_id, e),
+            }
+        }
+    }
+}
+
+/// 保存任务内容 (改派到其他打印机后重新保存)
+fn persist(jobs: &JobStore, job: &PrintJob) {
+    let stored = StoredJob {
+        printer: job.printer.name(),
+        direct: match &job.printer {
+            Destination::Direct(target) => Some(target.clone()),
+            Destination::Spooler(_) => None,
+        },
+        payload: job.payload.clone(),
+        options: job.options.clone(),
+        profile: job.profile.clone(),
+        pool: job.pool.clone(),
+    };
+    match serde_json::to_string(&stored) {
+        Ok(text) => jobs.set_payload(&job.task_id, &text),
+        Err(e) => warn!("任务内容序列化失败 ({}): {}", job.task_id, e),
+    }
+}
+
+/// 子队列共享的执行环境
+#[derive(Clone)]
+struct Lane {
+    /// 分发入口，池任务改派时重新分发
+    dispatch: mpsc::UnboundedSender<PrintJob>,
+    /// 全局并发上限
+    permits: Arc<Semaphore>,
+    paused: watch::Receiver<bool>,
+    pending: Arc<AtomicUsize>,
+    cancels: Cancellations,
+    settings: Arc<RwLock<AgentConfig>>,
+    jobs: JobStore,
+    tracker: JobTracker,
+    recent: RecentDocuments,
+}
+
+/// 近期发往各打印机的文档摘要 (打印机标识 + 内容哈希 → 发送时间)，用于重复内容检测
+#[derive(Clone, Default)]
+struct RecentDocuments(Arc<Mutex<HashMap<(String, u64), Instant>>>);
+
+impl RecentDocuments {
+    /// 登记一次发送；时间窗口内已发送过相同内容时返回距上次的秒数，
+    /// 此时仅在 record_duplicate 为 true (照常打印) 时刷新发送时间
+    fn check(
+        &self,
+        printer: &str,
+        document: &[u8],
+        window: Duration,
+        record_duplicate: bool,
+    ) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        document.hash(&mut hasher);
+        let key = (printer.to_string(), hasher.finish());
+
+        let now = Instant::now();
+        let mut recent = self.0.lock().unwrap();
+        recent.retain(|_, sent| now.duration_since(*sent) < window);
+        let previous = recent.get(&key).map(|sent| now.duration_since(*sent).as_secs());
+        if previous.is_none() || record_duplicate {
+            recent.insert(key, now);
+        }
+        previous
+    }
+}
+
+/// 按打印机分发任务：每台打印机 (按设备标识) 一个子队列，首次出现时创建
+async fn dispatch(mut receiver: mpsc::UnboundedReceiver<PrintJob>, lane: Lane) {
+    let mut lanes: HashMap<String, mpsc::UnboundedSender<PrintJob>> = HashMap::new();
+    while let Some(job) = receiver.recv().await {
+        let key = job.printer.key();
+        let sender = lanes
+            .entry(key.clone())
+            .or_insert_with(|| spawn_lane(key, lane.clone()));
+        // 子队列任务不会退出，发送不会失败
+        let _ = sender.send(job);
+    }
+}
+
+/// 启动单台打印机的子队列：逐个执行任务，上一个任务结束后才开始下一个
+fn spawn_lane(key: String, mut lane: Lane) -> mpsc::UnboundedSender<PrintJob> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<PrintJob>();
+    tokio::spawn(async move {
+        while let Some(mut job) = receiver.recv().await {
+            let _permit = loop {
+                // 暂停期间等待恢复 (发送端随队列存活)
+                let _ = lane.paused.wait_for(|paused| !paused).await;
+                // 信号量不会关闭
+                let Ok(permit) = lane.permits.clone().acquire_owned().await else {
+                    return;
+                };
+                // 等待名额期间被暂停 (如更新前排空队列) 时放回名额，继续等待恢复
+                if !*lane.paused.borrow() {
+                    break permit;
+                }
+            };
+            // 池成员离线时改派到池中其他在线成员 (排在其后的同池任务依次改派)，仍计为排队中
+            if let Some(pool) = &job.pool {
+                let (settings, pool, printer) =
+                    (lane.settings.clone(), pool.clone(), job.printer.clone());
+                let target =
+                    tokio::task::spawn_blocking(move || reroute(&settings, &pool, &printer))
+                        .await
+                        .ok()
+                        .flatten();
+                if let Some(target) = target {
+                    info!(
+                        "打印机池 {}: {} 离线，任务 {} 改派到 {}",
+                        job.pool.as_deref().unwrap_or_default(),
+                        job.printer.name(),
+                        job.task_id,
+                        target.name()
+                    );
+                    job.printer = target;
+                    persist(&lane.jobs, &job);
+                    let _ = lane.dispatch.send(job);
+                    continue;
+                }
+            }
+            lane.pending.fetch_sub(1, Ordering::SeqCst);
+            let (output, duplicates, remote_assets) = {
+                let settings = lane.settings.read().unwrap();
+                (
+                    settings.output.clone(),
+                    settings.queue.duplicates.clone(),
+                    settings.remote_assets.clone(),
+                )
+            };
+            let cancel = lane.cancels.lock().unwrap().get(&job.task_id).cloned();
+            let task_id = job.task_id.clone();
+            match cancel {
+                Some(token) if token.is_cancelled() => {
+                    fail(&lane.jobs, &task_id, PrintError::Cancelled);
+                }
+                token => {
+                    let env = JobEnv {
+                        output,
+                        duplicates,
+                        remote_assets,
+                        recent: lane.recent.clone(),
+                        jobs: lane.jobs.clone(),
+                        tracker: lane.tracker.clone(),
+                        cancels: lane.cancels.clone(),
+                    };
+                    run_job(&key, job, token, env).await;
+                }
+            }
+            lane.cancels.lock().unwrap().remove(&task_id);
+        }
+    });
+    sender
+}
+
+/// 池任务的当前成员离线时，按池中顺序查找在线的其他成员 (阻塞探测)；
+/// 当前成员在线、池已删除或其他成员均离线时返回 None，任务留在原打印机
+fn reroute(
+    settings: &RwLock<AgentConfig>,
+    pool: &str,
+    printer: &Destination,
+) -> Option<Destination> {
+    if printing::online(printer) {
+        return None;
+    }
+    // 成员名称按 已配置的直连打印机 → ipp:// / socket:// 地址 → 系统打印机 解析
+    let members: Vec<(String, Option<DirectTarget>)> = {
+        let config = settings.read().unwrap();
+        let pool = config.pools.get(pool)?;
+        pool.printers
+            .iter()
+            .map(|name| (name.clone(), config.direct_printers.get(name).cloned()))
+            .collect()
+    };
+    let current = printer.key();
+    members
+        .into_iter()
+        .filter_map(|(name, configured)| {
+            match configured.or_else(|| DirectTarget::from_name(&name)) {
+                Some(target) => Some(Destination::Direct(target)),
+                None => printing::find_printer(&name).ok().map(Destination::Spooler),
+            }
+        })
+        .filter(|destination| destination.key() != current)
+        .find(printing::online)
+}
+
+/// 单个任务的执行环境 (设置在任务开始时读取)
+struct JobEnv {
+    output: OutputConfig,
+    duplicates: DuplicateConfig,
+    remote_assets: RemoteAssetConfig,
+    recent: RecentDocuments,
+    jobs: JobStore,
+    tracker: JobTracker,
+    cancels: Cancellations,
+}
+
+async fn run_job(printer: &str, mut job: PrintJob, cancel: Option<CancelToken>, env: JobEnv) {
+    info!("[{}] 处理任务: {}", printer, job.task_id);
+    crash::set_current_job(&job.task_id);
+    if let Some(token) = cancel {
+        job.payload.set_cancel(token);
+    }
+    if let JobPayload::Template { template, .. } | JobPayload::Records { template, .. } =
+        &mut job.payload
+    {
+        let failures = remote_assets::resolve(template, &env.remote_assets).await;
+        env.jobs.add_warnings(&job.task_id, failures);
+    }
+    let task_id = job.task_id.clone();
+    let jobs = env.jobs.clone();
+    // 渲染与提交均为阻塞操作，放到阻塞线程池执行
+    let result = tokio::task::spawn_blocking(move || execute(job, &env)).await;
+    if let Err(e) = result {
+        fail(&jobs, &task_id, PrintError::Panicked(e.to_string()));
+    }
+}
+
+/// 执行单个任务：渲染 (直传 PDF/原始指令跳过，热敏/标签打印机栅格化) →
+/// 提交到系统打印队列或直连设备，并同步更新任务状态 → 保存打印内容副本；
+/// 提交成功的系统作业交由跟踪器确认打印结果
+fn execute(job: PrintJob, env: &JobEnv) {
+    let JobEnv {
+        output,
+        duplicates,
+        recent,
+        jobs,
+        tracker,
+        cancels,
+        ..
+    } = env;
+    jobs.set_status(&job.task_id, JobStatus::Rendering);
+
+    let engine = Engine::new();
+    let raw = matches!(job.payload, JobPayload::Raw { .. });
+    // 纸张匹配可能改写 media 选项
+    let mut options = job.options.clone();
+    let profile = job.profile.clone().unwrap_or_default();
+    let composition = Composition {
+        imposition: match &job.payload {
+            JobPayload::Records { imposition, .. } => imposition.clone(),
+            _ => None,
+        },
+        transform: job
+            .profile
+            .as_ref()
+            .map(|p| p.page_transform(options.auto_rotate.unwrap_or(true))),
+        ..Composition::default()
+    };
+    // 热敏/标签网络打印机：直接栅格化为打印机语言，不经过 PDF；档案中的 dpi 优先
+    let raster_language = match &job.printer {
+        Destination::Direct(target) if !raw => target
+            .raster_language()
+            .map(|(language, dpi)| (language, profile.dpi.unwrap_or(dpi))),
+        _ => None,
+    };
+    // IPP 打印机不接受 PDF 时改为 PWG 光栅
+    let pwg_dpi = match &job.printer {
+        Destination::Direct(target) if !raw && raster_language.is_none() => target.pwg_raster_dpi(),
+        _ => None,
+    };
+    let rendered = match (raster_language, pwg_dpi) {
+        (Some((language, dpi)), _) => {
+            // 模板中的切纸设置优先级最低
+            let cut_options = match &job.payload {
+                JobPayload::Template { template, .. } | JobPayload::Records { template, .. } => {
+                    match &template.canvas.cut {
+                        Some(cut) => options.clone().with_cut_defaults(cut),
+                        None => options.clone(),
+                    }
+                }
+                _ => options.clone(),
+            };
+            let settings = RasterSettings {
+                copies: cut_options.copies.unwrap_or(1).clamp(1, printing::MAX_COPIES),
+                cut: cut_options.cut.unwrap_or_default(),
+                cut_per_copy: cut_options.cut_per_copy.unwrap_or(false),
+                feed_lines: cut_options.feed_lines.unwrap_or(0),
+                darkness: profile.darkness,
+                dpi,
+            };
+            let text = match (language, profile.code_page) {
+                (PrinterLanguage::EscPos, Some(code_page)) => {
+                    render_escpos_text(&job.payload, code_page, profile.text_columns, &settings)
+                }
+                _ => None,
+            };
+            match text {
+                Some((document, text, pages)) => Ok((document, OutputCopy::Text(text, pages))),
+                None => render_raster(&engine, &job.payload, &composition, language, &settings)
+                    .map(|(document, pages)| (document, OutputCopy::Pages(pages, dpi))),
+            }
+        }
+        (None, Some(dpi)) => render_pwg(&engine, &job.payload, &composition, dpi)
+            .map(|(document, pages)| (document, OutputCopy::Pages(pages, dpi))),
+        (None, None) => {
+            let spooler = match &job.printer {
+                Destination::Spooler(printer) => Some(printer),
+                Destination::Direct(_) => None,
+            };
+            render_document(&engine, &job.payload, &composition, spooler, &mut options)
+                .map(|document| (document, OutputCopy::Document))
+        }
+    };
+    let (document, copy) = match rendered {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            fail(jobs, &job.task_id, e);
+            return;
+        }
+    };
+
+    if duplicates.window_secs > 0 && options.force != Some(true) {
+        let block = duplicates.action == DuplicateAction::Block;
+        let window = Duration::from_secs(duplicates.window_secs);
+        if let Some(seconds) = recent.check(&job.printer.key(), &document, window, !block) {
+            if block {
+                fail(jobs, &job.task_id, PrintError::Duplicate(seconds));
+                return;
+            }
+            warn!("任务 {} 与 {} 秒前发往同一打印机的内容相同", job.task_id, seconds);
+        }
+    }
+
+    if !raw {
+        // 审计日志所需的模板名称与页数
+        let template_name = match &job.payload {
+            JobPayload::Template { template, .. } | JobPayload::Records { template, .. } => {
+                Some(template.meta.name.clone())
+            }
+            _ => None,
+        };
+        let page_count = match &copy {
+            OutputCopy::Pages(pages, _) => Some(pages.len() as u32),
+            OutputCopy::Text(_, pages) => Some(*pages),
+            OutputCopy::Document => audit::pdf_page_count(&document),
+        };
+        jobs.set_rendered(&job.task_id, template_name, page_count);
+    }
+
+    // 提交前最后一次检查取消；令牌移除后 cancel 返回 false，取消与提交不会交错
+    let token = cancels.lock().unwrap().remove(&job.task_id);
+    if token.is_some_and(|token| token.is_cancelled()) {
+        fail(jobs, &job.task_id, PrintError::Cancelled);
+        return;
+    }
+    jobs.set_status(&job.task_id, JobStatus::Submitting);
+
+    match &job.printer {
+        Destination::Spooler(printer) => {
+            let result = if raw {
+                printing::submit_raw(printer, &job.task_id, &document, &options)
+            } else {
+                printing::submit(printer, &job.task_id, &document, &options)
+            };
+            match result {
+                Ok(spooler_job_id) => {
+                    jobs.mark_spooled(&job.task_id, spooler_job_id);
+                    tracker.track(&job.task_id, job.printer.clone(), spooler_job_id);
+                }
+                Err(e) => fail(jobs, &job.task_id, PrintError::Submit(e)),
+            }
+        }
+        // 端口/串口直连没有设备队列，数据写入成功即视为已打印；IPP 打印机返回其作业 ID
+        Destination::Direct(target) => {
+            let format = if raw {
+                "application/octet-stream"
+            } else if pwg_dpi.is_some() {
+                direct::PWG_FORMAT
+            } else {
+                direct::PDF_FORMAT
+            };
+            match direct::send(target, &job.task_id, format, &document, &options) {
+                Ok(Some(device_job_id)) => {
+                    jobs.mark_spooled(&job.task_id, device_job_id);
+                    tracker.track(&job.task_id, job.printer.clone(), device_job_id);
+                }
+                Ok(None) => jobs.set_status(&job.task_id, JobStatus::Printed),
+                Err(e) => fail(jobs, &job.task_id, PrintError::Submit(e)),
+            }
+        }
+    }
+
+    // 副本 (PNG 编码、写盘与清理) 在提交之后保存，不推迟出单
+    if !raw {
+        save_output(&job.task_id, &document, copy, output, jobs);
+    }
+}
+
+/// 保存打印内容的副本，可按任务 ID 下载 (GET /jobs/{id}/artifact)：
+/// PDF 原样保存，栅格化任务按打印机分辨率保存为 PNG；之后按保留策略删除旧文件
+/// 副本仅供排查，保存失败 (如无桌面目录的 Linux 终端机) 不影响打印
+fn save_output(
+    task_id: &str,
+    document: &[u8],
+    copy: OutputCopy,
+    output: &OutputConfig,
+    jobs: &JobStore,
+) {
+    let output_dir = output.dir();
+    let (extension, converted) = match copy {
+        OutputCopy::Pages(pages, dpi) => {
+            match output::stack_pages(pages).and_then(|page| output::encode_png(&page, dpi as f32 / 72.0)) {
+                Ok(image) => ("png", Some(image.bytes)),
+                Err(e) => {
+                    warn!("任务 {} 的 PNG 副本生成失败: {}", task_id, e);
+                    return;
+                }
+            }
+        }
+        OutputCopy::Text(text, _) => ("txt", Some(text.into_bytes())),
+        OutputCopy::Document => ("pdf", None),
+    };
+    let output_path = match retention::output_path(&output_dir, task_id, extension) {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("打印内容副本保存失败 ({}): {}", output_dir.display(), e);
+            return;
+        }
+    };
+    let bytes = converted.as_deref().unwrap_or(document);
+    match fs::create_dir_all(&output_dir).and_then(|_| fs::write(&output_path, bytes)) {
+        Ok(()) => jobs.set_output(task_id, output_path.to_string_lossy().to_string()),
+        Err(e) => warn!("打印内容副本保存失败 ({}): {}", output_path.display(), e),
+    }
+    if let Err(e) = retention::cleanup(output) {
+        warn!("打印内容副本清理失败 ({}): {}", output_dir.display(), e);
+    }
+}
+
+/// 记录任务失败 (含错误分类码)
+fn fail(jobs: &JobStore, task_id: &str, error: PrintError) {
+    warn!(code = error.code(), "任务 {} 失败: {}", task_id, error);
+    jobs.mark_error(task_id, &error);
+}
+
+/// 渲染为 PDF (直传 PDF/原始指令原样返回)
+fn render_document(
+    engine: &Engine,
+    payload: &JobPayload,
+    composition: &Composition,
+    printer: Option<&Printer>,
+    options: &mut PrintOptions,
+) -> Result<Vec<u8>, PrintError> {
+    match payload {
+        JobPayload::Content {
+            content,
+            width_mm,
+            height_mm,
+        } => Ok(engine.generate_pdf(content, *width_mm, *height_mm)),
+        JobPayload::Template {
+            template,
+            data,
+            render_options,
+        } => render_template(
+            engine,
+            template,
+            std::slice::from_ref(data),
+            render_options,
+            composition,
+            printer,
+            options,
+        ),
+        JobPayload::Records {
+            template,
+            records,
+            render_options,
+            ..
+        } => render_template(
+            engine,
+            template,
+            records,
+            render_options,
+            composition,
+            printer,
+            options,
+        ),
+        JobPayload::Pdf { data } | JobPayload::Raw { data } => Ok(data.clone()),
+        JobPayload::Image { data, layout } => {
+            Ok(output::render_image_pdf(data, layout, composition)?)
+        }
+    }
+}
+
+/// 打印内容副本的来源
+enum OutputCopy {
+    /// 文档本身 (PDF)
+    Document,
+    /// 栅格化前的页面及打印分辨率，保存为 PNG
+    Pages(Vec<RenderedPage>, u32),
+    /// 文本模式输出的各行及份数 (数据记录数)，保存为 TXT
+    Text(String, u32),
+}
+
+/// 小票打印机文本模式：模板按字符列排版并转码为档案指定的代码页，返回 (指令, 文本副本, 页数)；
+/// 模板不适合文本排版或含代码页无法表示的字符时返回 None，改用栅格输出
+fn render_escpos_text(
+    payload: &JobPayload,
+    code_page: CodePage,
+    columns: Option<usize>,
+    settings: &RasterSettings,
+) -> Option<(Vec<u8>, String, u32)> {
+    let (template, records) = match payload {
+        JobPayload::Template { template, data, .. } => (template, std::slice::from_ref(data)),
+        JobPayload::Records {
+            template, records, ..
+        } => (template, records.as_slice()),
+        _ => return None,
+    };
+    let columns = columns
+        .unwrap_or_else(|| escpos_text::columns(template.canvas.content_width(), settings.dpi));
+    let Some(documents) = records
+        .iter()
+        .map(|data| plain_text::layout(template, data, columns))
+        .collect::<Option<Vec<_>>>()
+    else {
+        debug!("模板 {} 含文本模式不支持的元素，改用栅格输出", template.meta.name);
+        return None;
+    };
+    let Some(document) = escpos_text::to_escpos_text(&documents, code_page, settings) else {
+        info!("模板 {} 的内容含 {:?} 无法表示的字符，改用栅格输出", template.meta.name, code_page);
+        return None;
+    };
+    let text = documents
+        .iter()
+        .flatten()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some((document, text, documents.len() as u32))
+}
+
+/// 渲染并栅格化为打印机语言 (ESC/POS 位图、ZPL/TSPL 图形)，份数与切纸由打印语言实现
+/// 旧版资产标签与直传 PDF 无法栅格化；同时返回栅格化前的页面 (用于保存 PNG 副本)
+fn render_raster(
+    engine: &Engine,
+    payload: &JobPayload,
+    composition: &Composition,
+    language: PrinterLanguage,
+    settings: &RasterSettings,
+) -> Result<(Vec<u8>, Vec<RenderedPage>), PrintError> {
+    let pages = raster_pages(engine, payload, composition, &format!("{:?}", language))?;
+    let bitmaps = engine.rasterize_pages(&pages, settings.dpi as f32)?;
+    let document = match language {
+        PrinterLanguage::EscPos => raster::to_escpos(&bitmaps, settings),
+        PrinterLanguage::Zpl => raster::to_zpl(&bitmaps, settings),
+        PrinterLanguage::Tspl => raster::to_tspl(&bitmaps, settings),
+        PrinterLanguage::Pdf => unreachable!("PDF printers are not rasterized"),
+    };
+    Ok((document, pages))
+}
+
+/// 栅格化为 PWG Raster (不接受 PDF 的 IPP 打印机)
+fn render_pwg(
+    engine: &Engine,
+    payload: &JobPayload,
+    composition: &Composition,
+    dpi: u32,
+) -> Result<(Vec<u8>, Vec<RenderedPage>), PrintError> {
+    let pages = raster_pages(engine, payload, composition, "PWG raster")?;
+    let bitmaps = engine.rasterize_gray_pages(&pages, dpi as f32)?;
+    Ok((raster::to_pwg(&bitmaps, dpi), pages))
+}
+
+/// 需要栅格化的页面：模板与图片任务 (PDF 直传等任务无法栅格化)
+fn raster_pages(
+    engine: &Engine,
+    payload: &JobPayload,
+    composition: &Composition,
+    mode: &str,
+) -> Result<Vec<RenderedPage>, PrintError> {
+    let pages = match payload {
+        JobPayload::Template {
+            template,
+            data,
+            render_options,
+        } => engine.generate_template_pages(
+            template,
+            std::slice::from_ref(data),
+            render_options,
+            composition,
+        ),
+        JobPayload::Records {
+            template,
+            records,
+            render_options,
+            ..
+        } => engine.generate_template_pages(template, records, render_options, composition),
+        JobPayload::Image { data, layout } => output::layout_image(data, layout)
+            .and_then(|page| output::compose(vec![page], composition)),
+        _ => {
+            return Err(PrintError::Unsupported(format!(
+                "This job type cannot be printed in {} mode",
+                mode
+            )))
+        }
+    }?;
+    Ok(pages)
+}
+
+/// 渲染模板，每条数据记录输出为文档中的一页 (或多页)
+/// 输出到系统打印机时先将页面匹配到打印机支持的纸张
+fn render_template(
+    engine: &Engine,
+    template: &DeepPrintTemplate,
+    records: &[Value],
+    render_options: &RenderOptions,
+    composition: &Composition,
+    printer: Option<&Printer>,
+    options: &mut PrintOptions,
+) -> Result<Vec<u8>, PrintError> {
+    let pages = engine.generate_template_pages(template, records, render_options, composition)?;
+    let pages = match printer {
+        Some(printer) => media::fit_pages(printer, pages, options)?,
+        None => pages,
+    };
+    Ok(output::write_pdf(&pages, &template.meta.name, &PdfOptions::default()))
+}
@@ -0,0 +1,83 @@
+use crate::api_error::ApiJson;
+use crate::auth::Access;
+use crate::jobs::{JobRecord, JobStatus};
+use crate::server::{self, AppState, TemplatePrintRequest};
+use axum::extract::{Extension, Json, State};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// 远程下发指令的受理结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteAck {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub accepted: bool,
+    pub message: String,
+}
+
+/// 执行远程下发的打印指令 (MQTT / 云端 WebSocket 共用)
+/// 消息体与 POST /print/template 的请求体相同，受理后的任务会被加入 tracker，
+/// 由调用方把其后续状态变化回传给云端
+pub async fn dispatch(state: &AppState, tracker: &RemoteTasks, payload: &[u8]) -> RemoteAck {
+    let req: TemplatePrintRequest = match serde_json::from_slice(payload) {
+        Ok(req) => req,
+        Err(e) => {
+            return RemoteAck {
+                task_id: None,
+                accepted: false,
+                message: format!("Invalid print command: {}", e),
+            }
+        }
+    };
+
+    let task_id = req.task_id.clone();
+    tracker.insert(&task_id);
+    let result = server::handle_print_template(
+        State(state.clone()),
+        Extension(Access::unrestricted()),
+        ApiJson(req),
+    )
+    .await;
+    let (accepted, message) = match result {
+        Ok((_, Json(body))) => (true, body.message),
+        Err(err) => (false, err.message),
+    };
+    if !accepted {
+        tracker.remove(&task_id);
+    }
+    RemoteAck {
+        task_id: Some(task_id),
+        accepted,
+        message,
+    }
+}
+
+/// 由远程下发、需要回传状态的任务集合
+#[derive(Clone, Default)]
+pub struct RemoteTasks {
+    ids: Arc<Mutex<HashSet<String>>>,
+}
+
+impl RemoteTasks {
+    pub fn insert(&self, task_id: &str) {
+        self.ids.lock().unwrap().insert(task_id.to_string());
+    }
+
+    pub fn remove(&self, task_id: &str) {
+        self.ids.lock().unwrap().remove(task_id);
+    }
+
+    /// 判断状态变化是否需要回传；任务结束 (printed / failed) 后不再跟踪
+    pub fn should_report(&self, record: &JobRecord) -> bool {
+        let mut ids = self.ids.lock().unwrap();
+        if !ids.contains(&record.task_id) {
+            return false;
+        }
+        if matches!(record.status, JobStatus::Printed | JobStatus::Failed) {
+            ids.remove(&record.task_id);
+        }
+        true
+    }
+}
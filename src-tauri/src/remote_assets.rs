@@ -0,0 +1,303 @@
+use crate::config::{ProxyMode, RemoteAssetConfig};
+use crate::deep_print_schema::{DeepPrintTemplate, Element, ElementData};
+use base64::Engine as _;
+use futures_util::future::join_all;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// 最多跟随的重定向次数
+const MAX_REDIRECTS: usize = 5;
+
+/// 渲染器不访问网络，只读取资源池中的图片。渲染前下载模板引用的远程图片并内嵌到资源池：
+/// 资源池中的 URL 替换为 base64 数据，图片元素的 URL src 改写为 "asset:{url}"。
+/// 下载失败或超时的图片保持原样，由渲染器按元素的 fallback 处理；含 {{}} 插值的 src 需按数据渲染，不做处理。
+/// 返回下载失败的说明，记入任务告警
+pub async fn resolve(template: &mut DeepPrintTemplate, config: &RemoteAssetConfig) -> Vec<String> {
+    let mut urls = HashSet::new();
+    if let Some(assets) = &template.assets {
+        urls.extend(assets.values().filter(|v| is_remote(v)).cloned());
+    }
+    for_each_element(template, &mut |element| {
+        if let ElementData::Image(props) = &element.data {
+            if is_remote(&props.src) {
+                urls.insert(props.src.clone());
+            }
+        }
+    });
+    if urls.is_empty() {
+        return Vec::new();
+    }
+
+    let client = match client(config) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("远程图片下载客户端创建失败: {}", e);
+            return vec![format!("Remote images not loaded: {}", e)];
+        }
+    };
+    let downloads = join_all(urls.into_iter().map(|url| {
+        let client = client.clone();
+        async move {
+            let result = download(&client, &url, config).await;
+            (url, result)
+        }
+    }))
+    .await;
+    let mut fetched = HashMap::new();
+    let mut failures = Vec::new();
+    for (url, result) in downloads {
+        match result {
+            Ok(bytes) => {
+                debug!("已下载远程图片: {} ({} 字节)", url, bytes.len());
+                fetched.insert(url, base64::engine::general_purpose::STANDARD.encode(bytes));
+            }
+            Err(e) => {
+                warn!("远程图片下载失败 ({}): {}", url, e);
+                failures.push(format!("Remote image {} not loaded: {}", url, e));
+            }
+        }
+    }
+
+    let assets = template.assets.get_or_insert_with(HashMap::new);
+    for value in assets.values_mut() {
+        if let Some(data) = fetched.get(value.as_str()) {
+            *value = data.clone();
+        }
+    }
+    for (url, data) in &fetched {
+        assets.insert(url.clone(), data.clone());
+    }
+    for_each_element_mut(template, &mut |element| {
+        if let ElementData::Image(props) = &mut element.data {
+            if fetched.contains_key(&props.src) {
+                props.src = format!("{}{}", deepprint_core::assets::ASSET_PREFIX, props.src);
+            }
+        }
+    });
+    failures
+}
+
+/// 按代理配置创建 HTTP 客户端
+fn client(config: &RemoteAssetConfig) -> reqwest::Result<reqwest::Client> {
+    builder(config)?.build()
+}
+
+/// 连接时直接使用已检查过的地址，不再解析域名 (防止 DNS 重绑定到内网地址)
+fn pinned_client(
+    config: &RemoteAssetConfig,
+    host: &str,
+    addrs: &[SocketAddr],
+) -> reqwest::Result<reqwest::Client> {
+    builder(config)?.resolve_to_addrs(host, addrs).build()
+}
+
+fn builder(config: &RemoteAssetConfig) -> reqwest::Result<reqwest::ClientBuilder> {
+    let timeout = Duration::from_millis(config.timeout_ms.max(1));
+    // 重定向由 download 逐跳检查后跟随
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(timeout)
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none());
+    builder = match config.proxy_mode {
+        // reqwest 默认读取 HTTP_PROXY / HTTPS_PROXY / NO_PROXY 环境变量与系统代理设置
+        ProxyMode::System => builder,
+        ProxyMode::None => builder.no_proxy(),
+        ProxyMode::Manual => match config.proxy.as_deref().filter(|p| !p.is_empty()) {
+            Some(url) => {
+                let mut proxy = reqwest::Proxy::all(url)?;
+                if let Some(username) = &config.proxy_username {
+                    proxy = proxy.basic_auth(username, config.proxy_password.as_deref().unwrap_or(""));
+                }
+                proxy = proxy.no_proxy(config.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string));
+                builder.no_proxy().proxy(proxy)
+            }
+            None => builder,
+        },
+    };
+    Ok(builder)
+}
+
+/// 下载图片：每一跳 (含重定向) 都检查主机白名单与目标地址，并以检查过的地址连接；
+/// 超过 maxBytes 时中止
+async fn download(client: &reqwest::Client, url: &str, config: &RemoteAssetConfig) -> Result<Vec<u8>, String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    let mut redirects = 0;
+    let mut response = loop {
+        let pinned = match check_target(&url, config).await? {
+            Some((host, addrs)) => Some(pinned_client(config, &host, &addrs).map_err(describe)?),
+            None => None,
+        };
+        let response = pinned
+            .as_ref()
+            .unwrap_or(client)
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(describe)?;
+        if !response.status().is_redirection() {
+            break response.error_for_status().map_err(describe)?;
+        }
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err("too many redirects".to_string());
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| format!("redirect without location ({})", response.status()))?;
+        url = url.join(location).map_err(|e| e.to_string())?;
+    };
+
+    let too_large = || format!("larger than {} bytes", config.max_bytes);
+    if response.content_length().is_some_and(|len| len > config.max_bytes) {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(describe)? {
+        if (bytes.len() + chunk.len()) as u64 > config.max_bytes {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// 只允许 http(s)、白名单内的主机；未开启 allowPrivate 时解析域名，任一地址不是公网地址即拒绝。
+/// 返回检查过的 (域名, 地址)，连接时固定使用这些地址；允许内网或主机为 IP 字面量时返回 None
+async fn check_target(
+    url: &reqwest::Url,
+    config: &RemoteAssetConfig,
+) -> Result<Option<(String, Vec<SocketAddr>)>, String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("scheme {} is not allowed", url.scheme()));
+    }
+    let host = url.host_str().ok_or("missing host")?;
+    if !config.allowed_hosts.is_empty() && !config.allowed_hosts.iter().any(|p| host_matches(p, host)) {
+        return Err(format!("host {} is not in allowedHosts", host));
+    }
+    if config.allow_private {
+        return Ok(None);
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("cannot resolve {}: {}", host, e))?
+        .collect();
+    for addr in &addrs {
+        if !is_public(addr.ip()) {
+            return Err(format!("{} resolves to non-public address {}", host, addr.ip()));
+        }
+    }
+    if host.parse::<IpAddr>().is_ok() {
+        return Ok(None);
+    }
+    Ok(Some((host.to_string(), addrs)))
+}
+
+/// "cdn.example.com" 精确匹配，"*.example.com" 匹配其子域名
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+        None => host == pattern.to_ascii_lowercase(),
+    }
+}
+
+/// 是否为公网单播地址 (排除回环、内网、链路本地、运营商 NAT、组播、保留与文档地址)
+fn is_public(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            let embedded = |hi: u16, lo: u16| Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo));
+            match segments {
+                // NAT64 (64:ff9b::/96) 与 6to4 (2002::/16) 地址内嵌 IPv4 地址，按内嵌地址判断
+                [0x64, 0xff9b, 0, 0, 0, 0, hi, lo] | [0x2002, hi, lo, ..] => {
+                    return is_public(IpAddr::V4(embedded(hi, lo)))
+                }
+                _ => {}
+            }
+            let first = segments[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first == 0x64 && segments[1] == 0xff9b) // 本地 NAT64 64:ff9b:1::/48
+                || (first & 0xfe00) == 0xfc00 // 唯一本地地址 fc00::/7
+                || (first & 0xffc0) == 0xfe80) // 链路本地地址 fe80::/10
+        }
+    }
+}
+
+fn describe(e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        "timed out".to_string()
+    } else {
+        e.to_string()
+    }
+}
+
+fn is_remote(value: &str) -> bool {
+    (value.starts_with("http://") || value.starts_with("https://")) && !value.contains("{{")
+}
+
+fn for_each_element(template: &DeepPrintTemplate, f: &mut impl FnMut(&Element)) {
+    fn visit(elements: &[Element], f: &mut impl FnMut(&Element)) {
+        for element in elements {
+            f(element);
+            if let ElementData::Switch(props) = &element.data {
+                for case in &props.cases {
+                    visit(&case.elements, f);
+                }
+                if let Some(fallback) = &props.fallback {
+                    visit(fallback, f);
+                }
+            }
+        }
+    }
+    visit(&template.canvas.elements, f);
+    for page in template.canvas.pages.iter().flatten() {
+        visit(&page.elements, f);
+    }
+    for partial in template.partials.iter().flat_map(|p| p.values()) {
+        visit(&partial.elements, f);
+    }
+}
+
+fn for_each_element_mut(template: &mut DeepPrintTemplate, f: &mut impl FnMut(&mut Element)) {
+    fn visit(elements: &mut [Element], f: &mut impl FnMut(&mut Element)) {
+        for element in elements {
+            f(element);
+            if let ElementData::Switch(props) = &mut element.data {
+                for case in &mut props.cases {
+                    visit(&mut case.elements, f);
+                }
+                if let Some(fallback) = &mut props.fallback {
+                    visit(fallback, f);
+                }
+            }
+        }
+    }
+    visit(&mut template.canvas.elements, f);
+    for page in template.canvas.pages.iter_mut().flatten() {
+        visit(&mut page.elements, f);
+    }
+    for partial in template.partials.iter_mut().flat_map(|p| p.values_mut()) {
+        visit(&mut partial.elements, f);
+    }
+}
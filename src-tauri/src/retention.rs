@@ -0,0 +1,112 @@
+use crate::config::OutputConfig;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+/// 打印内容副本的文件名前缀与扩展名；清理只涉及这类文件，保存目录 (如桌面) 中的其他文件不受影响
+const FILE_PREFIX: &str = "deepprint_";
+const FILE_EXTENSIONS: &[&str] = &["pdf", "png", "txt"];
+
+/// 文件名中任务 ID 部分的最大长度
+const MAX_ID_LEN: usize = 100;
+
+/// 打印内容副本的保存路径，extension 为 "pdf"、"png" 或 "txt"
+/// 任务 ID 由客户端提供：只保留 [A-Za-z0-9._-] (其余字符替换为 "_")，改写过的 ID 追加哈希避免重名，
+/// 文件名中不会出现路径分隔符，结果不在保存目录下时返回错误
+pub fn output_path(dir: &Path, task_id: &str, extension: &str) -> io::Result<PathBuf> {
+    let mut name: String = task_id
+        .chars()
+        .take(MAX_ID_LEN)
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+        .collect();
+    if name != task_id {
+        let mut hasher = DefaultHasher::new();
+        task_id.hash(&mut hasher);
+        name = format!("{}-{:016x}", name, hasher.finish());
+    }
+    let path = dir.join(format!("{}{}.{}", FILE_PREFIX, name, extension));
+    if path.parent() != Some(dir) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid output file name for task '{}'", task_id),
+        ));
+    }
+    Ok(path)
+}
+
+/// 按保留策略清理保存目录中的打印内容副本 (PDF/PNG)：超过保留天数的全部删除，
+/// 其余从新到旧累计，超出数量或总大小上限的删除。返回删除的文件数
+pub fn cleanup(config: &OutputConfig) -> io::Result<usize> {
+    let dir = config.dir();
+    let mut files = match list(&dir) {
+        Ok(files) => files,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    // 从新到旧
+    files.sort_by(|a, b| b.modified.cmp(&a.modified));
+
+    let now = SystemTime::now();
+    let max_age = (config.max_age_days > 0).then(|| Duration::from_secs(config.max_age_days * 24 * 3600));
+    let max_bytes = (config.max_total_mb > 0).then(|| config.max_total_mb * 1024 * 1024);
+    let mut kept_bytes = 0_u64;
+    let mut removed = 0;
+    for (i, file) in files.iter().enumerate() {
+        let expired = max_age.is_some_and(|max_age| {
+            now.duration_since(file.modified).is_ok_and(|age| age > max_age)
+        });
+        let too_many = config.max_files > 0 && i >= config.max_files;
+        let too_large = max_bytes.is_some_and(|max| kept_bytes + file.len > max);
+        if !(expired || too_many || too_large) {
+            kept_bytes += file.len;
+            continue;
+        }
+        match fs::remove_file(&file.path) {
+            Ok(()) => removed += 1,
+            Err(e) => warn!("打印内容副本删除失败 ({}): {}", file.path.display(), e),
+        }
+    }
+    if removed > 0 {
+        info!("已清理 {} 个过期的打印内容副本 ({})", removed, dir.display());
+    }
+    Ok(removed)
+}
+
+struct OutputFile {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+}
+
+fn list(dir: &Path) -> io::Result<Vec<OutputFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_output = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| FILE_EXTENSIONS.contains(&ext))
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX));
+        if !is_output {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        files.push(OutputFile {
+            path,
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            len: metadata.len(),
+        });
+    }
+    Ok(files)
+}
@@ -1,21 +1,134 @@
 
 use axum::{
-    extract::Json,
-    routing::{get, post},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Extension, FromRequest, Json, Multipart, Path, Query, Request, State,
+    },
+    http::{header, StatusCode},
+    middleware,
+    routing::{get, post, put},
     Router,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
-use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
-use crate::engine::Engine;
-use std::fs;
+use tower_http::compression::CompressionLayer;
+use base64::Engine as _;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+use axum_server::tls_rustls::RustlsConfig;
+use crate::allowlist;
+use crate::api_error::{ApiError, ApiJson};
+use crate::audit::{self, AuditQuery};
+use crate::auth::{self, Access};
+use crate::cloud;
+use crate::config::{
+    AgentConfig, ApiKey, DevPreviewConfig, PrinterAlias, PrinterDefaults, PrinterPool,
+    PrinterProfile, ServerConfig,
+};
+use std::collections::HashMap;
+use crate::cors;
+use crate::crash;
+use crate::discovery;
+use crate::hot_reload::{self, PreviewUpdate, PreviewWatcher, WatchRequest};
+use crate::mqtt;
+use crate::pools::{PoolBalancer, PoolStatus};
+use crate::deep_print_schema::DeepPrintTemplate;
+use crate::jobs::{BatchSummary, JobPage, JobQuery, JobRecord, JobStatus, JobStore};
+use crate::output::{self, EncodedImage, FitMode, ImageLayout, Imposition, PdfOptions};
+use crate::printing::direct::{self, DirectTarget};
+use crate::printing::media::{self, MediaSize};
+use crate::printing::usb::{self, UsbPrinterInfo};
+use crate::printing::{self, ColorMode, Destination, PrintOptions, Printer, PrinterStatus};
+use crate::queue::{JobPayload, PrintJob, PrintQueue};
+use crate::remote_assets;
+use crate::renderer::{CancelToken, DeepPrintRenderer, LumaWeights, RenderOptions};
+use crate::retention;
+use crate::supervisor::{self, ServerFailure};
+use crate::templates::{TemplateRecord, TemplateStore, TemplateSummary, TemplateVersion};
+use crate::tls;
+use crate::validator::{self, ValidationReport};
+use deepprint_core::{MigrationWarning, RenderDiagnostics, RenderLimits};
+use deepprint_core::data_schema::{self, SchemaViolation};
+use serde_json::Value;
+use std::borrow::Cow;
+use tracing::{error, info, warn};
 
 // --- 数据结构 ---
 
+/// 服务共享状态
+#[derive(Clone)]
+pub struct AppState {
+    pub jobs: JobStore,
+    pub queue: PrintQueue,
+    pub templates: TemplateStore,
+    /// 运行时配置 (可通过接口修改并持久化)
+    pub config: Arc<RwLock<AgentConfig>>,
+    /// HTTP 监听地址，修改后服务重新绑定
+    pub listen: Arc<watch::Sender<ServerConfig>>,
+    /// HTTP 服务实际监听的地址 (未在监听时为 None)
+    pub bound: Arc<watch::Sender<Option<SocketAddr>>>,
+    /// HTTP 服务最近一次异常退出的信息，重新开始监听后清除
+    pub server_failure: Arc<watch::Sender<Option<ServerFailure>>>,
+    /// 打印机池的轮询调度状态
+    pub pools: PoolBalancer,
+    /// 模板热重载 (开发模式)
+    pub preview: PreviewWatcher,
+    /// 服务启动时间
+    pub started_at: Instant,
+}
+
+/// 版本信息
 #[derive(Serialize)]
-struct PrinterInfo {
+#[serde(rename_all = "camelCase")]
+struct VersionInfo {
+    version: &'static str,
+    /// 构建时的 git commit
+    commit: &'static str,
+}
+
+/// 预览已注册模板时未指定 sample 所使用的示例数据名称
+const DEFAULT_SAMPLE: &str = "default";
+
+const VERSION_INFO: VersionInfo = VersionInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    commit: env!("DEEPPRINT_GIT_COMMIT"),
+};
+
+/// 健康状态
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HealthInfo {
+    status: &'static str,
+    version: &'static str,
+    commit: &'static str,
+    /// 运行时长 (秒)
+    uptime_secs: u64,
+    /// 排队中的任务数
+    queue_depth: usize,
+    /// 打印队列已暂停 (托盘菜单)
+    queue_paused: bool,
+    /// 系统打印机数量
+    printers: usize,
+    /// 最近一次失败任务的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<LastError>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LastError {
+    task_id: String,
+    message: String,
+    /// Unix 毫秒
+    at: u64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct PrinterInfo {
     name: String,
     system_name: String,
     is_default: bool,
@@ -28,14 +141,332 @@ pub struct PrintRequest {
     // 新增：宽和高 (单位 mm)，可选参数，默认 A4
     pub width_mm: Option<f32>,
     pub height_mm: Option<f32>,
+    /// 目标打印机 (显示名、系统名、别名或 ipp:// 地址)，为空时使用默认打印机
+    #[serde(default)]
+    pub printer: Option<String>,
+    /// 文档类型 (如 "receipt", "label")，用于选择该类型的默认打印机
+    #[serde(default)]
+    pub document_type: Option<String>,
+    /// 打印作业选项 (份数、双面、色彩、纸张、纸盒)，可选
+    #[serde(default)]
+    pub options: PrintOptions,
+}
+
+/// 模板打印请求：DeepPrint 模板 + 动态数据
+/// 模板可以随请求内联传入，也可以通过 templateId 引用已注册的模板
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplatePrintRequest {
+    pub task_id: String,
+    /// 内联模板，优先于 templateId
+    #[serde(default)]
+    pub template: Option<DeepPrintTemplate>,
+    /// 已注册模板的 ID (见 PUT /templates/{id})
+    #[serde(default)]
+    pub template_id: Option<String>,
+    /// 固定使用已注册模板的某个版本，为空时使用当前版本
+    #[serde(default)]
+    pub template_version: Option<u32>,
+    /// 模板插值数据
+    #[serde(default)]
+    pub data: Value,
+    /// 已注册模板保存的示例数据名称 (见 PUT /templates/{id}/samples/{name})，
+    /// 未提供 data 时使用，便于测试打印
+    #[serde(default)]
+    pub sample: Option<String>,
+    /// 目标打印机 (显示名、系统名、别名或 ipp:// 地址)，为空时使用默认打印机
+    #[serde(default)]
+    pub printer: Option<String>,
+    /// 文档类型，用于选择该类型的默认打印机
+    #[serde(default)]
+    pub document_type: Option<String>,
+    /// 打印作业选项
+    #[serde(default)]
+    pub options: PrintOptions,
+    /// 调试模式：入队前先试渲染一次，在响应中返回各阶段耗时、溢出警告与未解析的变量
+    #[serde(default)]
+    pub debug: bool,
+    /// 在响应中返回 base64 编码的 PDF (与打印内容使用相同的模板与数据)，
+    /// 客户端无需读取 Agent 所在机器的文件
+    #[serde(default)]
+    pub return_document: bool,
+    /// 返回文档的 PDF 选项；colorSpace 为 cmyk 时返回 400 (暂不支持输出 DeviceCMYK)
+    #[serde(default)]
+    pub pdf_options: PdfOptions,
+}
+
+/// 批量打印请求：同一模板 + 多条数据
+/// 默认每条数据生成一个独立任务；merge=true 时合并为一个多页 PDF 作为单个任务打印
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchPrintRequest {
+    /// 批次 ID，为空时自动生成；任务 ID 为 "{batchId}-{序号}"
+    #[serde(default)]
+    pub batch_id: Option<String>,
+    #[serde(default)]
+    pub template: Option<DeepPrintTemplate>,
+    #[serde(default)]
+    pub template_id: Option<String>,
+    #[serde(default)]
+    pub template_version: Option<u32>,
+    /// 数据记录，每条对应一个打印任务 (或合并文档中的一页)
+    pub records: Vec<Value>,
+    /// 合并为单个多页文档 (网络激光打印机上远快于数百个小任务)
+    #[serde(default)]
+    pub merge: bool,
+    /// 拼版参数 (如 3×8 地址标签纸)：各条数据的标签按网格排在同一张纸上，设置时按合并模式打印
+    #[serde(default)]
+    pub imposition: Option<Imposition>,
+    #[serde(default)]
+    pub printer: Option<String>,
+    #[serde(default)]
+    pub document_type: Option<String>,
+    #[serde(default)]
+    pub options: PrintOptions,
 }
 
 #[derive(Serialize)]
-struct ApiResponse {
+#[serde(rename_all = "camelCase")]
+struct BatchResponse {
     success: bool,
     message: String,
+    batch_id: String,
+    /// 已入队的任务 ID
+    task_ids: Vec<String>,
+    /// 模板提示 (同单个打印任务)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<MigrationWarning>,
+}
+
+/// 文档直传打印请求 (POST /print/pdf)
+/// JSON 请求中 data 为 base64 编码的文件内容；
+/// multipart 请求中 file 字段为文件内容，metadata 字段为本结构的 JSON (不含 data)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentPrintRequest {
+    pub task_id: String,
+    #[serde(default)]
+    pub printer: Option<String>,
+    #[serde(default)]
+    pub document_type: Option<String>,
+    #[serde(default)]
+    pub options: PrintOptions,
+}
+
+/// 图片直传打印请求 (POST /print/image)，上传方式同 DocumentPrintRequest
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagePrintRequest {
+    pub task_id: String,
+    #[serde(default)]
+    pub printer: Option<String>,
+    #[serde(default)]
+    pub document_type: Option<String>,
+    #[serde(default)]
+    pub options: PrintOptions,
+    /// 纸张尺寸 (mm)，默认 A4
+    pub paper_width_mm: Option<f32>,
+    pub paper_height_mm: Option<f32>,
+    /// 四周边距 (mm)，默认 0
+    #[serde(default)]
+    pub margin_mm: f32,
+    /// 适配方式 (Default: contain)
+    #[serde(default)]
+    pub fit: FitMode,
+    /// 方向不一致时自动旋转 (Default: true)
+    pub auto_rotate: Option<bool>,
+}
+
+/// 原始指令透传请求 (POST /print/raw)，上传方式同 DocumentPrintRequest
+/// 指定 target 时直接写入网络端口/串口 (仅限 admin Key)，否则按 printer 路由
+/// (已配置的直连打印机或系统打印队列，系统队列以 RAW 方式提交)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawPrintRequest {
+    pub task_id: String,
+    #[serde(default)]
+    pub printer: Option<String>,
+    #[serde(default)]
+    pub document_type: Option<String>,
+    #[serde(default)]
+    pub options: PrintOptions,
+    /// 直连设备，如 {"type": "socket", "host": "192.168.1.50", "port": 9100}；
+    /// 可写入任意主机、串口与 USB 设备，仅限 admin Key，其他调用方应使用已配置的直连打印机
+    #[serde(default)]
+    pub target: Option<DirectTarget>,
+}
+
+/// 预览请求：渲染为 PNG，不触碰任何打印机
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewRequest {
+    pub template: DeepPrintTemplate,
+    /// 模板插值数据；未提供时按模板的 dataSchema 生成示例数据
+    #[serde(default)]
+    pub data: Value,
+    /// 输出分辨率 (Default: 72，即 1pt = 1px)
+    pub dpi: Option<f32>,
+    /// 额外缩放倍率，与 dpi 叠加 (Default: 1)
+    pub scale: Option<f32>,
+    /// true: 返回 JSON (base64 编码的 PNG)；false: 直接返回 image/png
+    /// 与打印接口一致，也可写作 returnDocument
+    #[serde(default, alias = "returnDocument")]
+    pub base64: bool,
+    /// 以灰度预览 (模拟黑白打印机输出)
+    #[serde(default)]
+    pub grayscale: bool,
+    /// 调试模式：返回 JSON 并附带渲染诊断 (各阶段耗时、溢出警告、未解析的变量)
+    #[serde(default)]
+    pub debug: bool,
+    /// 渲染取消令牌 (客户端断开时取消)
+    #[serde(skip)]
+    pub cancel: Option<CancelToken>,
+}
+
+/// 已注册模板的预览参数 (POST /templates/{id}/preview?sample=default)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplatePreviewQuery {
+    /// 示例数据名称；为空时使用名为 "default" 的示例数据，没有时按模板的 dataSchema 生成
+    pub sample: Option<String>,
+    /// 模板版本，为空时使用当前版本
+    pub version: Option<u32>,
+    pub dpi: Option<f32>,
+    pub scale: Option<f32>,
+    #[serde(default)]
+    pub base64: bool,
+    #[serde(default)]
+    pub grayscale: bool,
+    #[serde(default)]
+    pub debug: bool,
+}
+
+/// 分页预览请求 (桌面端预览窗口)：data 为数组时每条记录渲染为一页
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PagedPreviewRequest {
+    #[serde(default)]
+    pub template: Option<DeepPrintTemplate>,
+    #[serde(default)]
+    pub template_id: Option<String>,
+    #[serde(default)]
+    pub template_version: Option<u32>,
+    /// 未提供时按模板的 dataSchema 生成示例数据
+    #[serde(default)]
+    pub data: Value,
+    /// 输出分辨率 (Default: 144，放大查看时仍保持清晰)
+    pub dpi: Option<f32>,
+    #[serde(default)]
+    pub grayscale: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PreviewResponse {
+    success: bool,
+    mime_type: &'static str,
+    /// 像素宽高
+    width: i32,
+    height: i32,
+    /// base64 编码的 PNG 数据
+    image: String,
+    /// 调试模式下的渲染诊断
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diagnostics: Option<RenderDiagnostics>,
+    /// 旧版本模板迁移为当前协议时的提示
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<MigrationWarning>,
+}
+
+impl PreviewResponse {
+    pub(crate) fn png(image: &EncodedImage) -> Self {
+        Self {
+            success: true,
+            mime_type: "image/png",
+            width: image.width,
+            height: image.height,
+            image: base64::engine::general_purpose::STANDARD.encode(&image.bytes),
+            diagnostics: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn with_diagnostics(mut self, diagnostics: RenderDiagnostics) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    fn with_warnings(mut self, warnings: &[MigrationWarning]) -> Self {
+        self.warnings = warnings.to_vec();
+        self
+    }
+}
+
+/// 新增/修改 API Key 请求，key 为空时沿用原 Key 或自动生成
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyRequest {
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub printers: Vec<String>,
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// API Key 概要，列表中不返回完整 Key
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiKeySummary {
+    name: String,
+    /// Key 末 4 位
+    key_hint: String,
+    printers: Vec<String>,
+    endpoints: Vec<String>,
+    admin: bool,
+}
+
+/// 模板回滚请求
+#[derive(Deserialize)]
+pub struct RollbackRequest {
+    pub version: u32,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ApiResponse {
+    pub(crate) success: bool,
+    pub(crate) message: String,
     // 调试用：返回 PDF 的路径方便查看
-    debug_path: Option<String>, 
+    pub(crate) debug_path: Option<String>, 
+    /// 调试模式 (debug: true) 下的渲染诊断
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) diagnostics: Option<RenderDiagnostics>,
+    /// 模板提示：旧版本模板迁移为当前协议时的改动、元素超出打印机可打印区域等
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) warnings: Vec<MigrationWarning>,
+    /// 请求 returnDocument 时返回的文档
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) document: Option<InlineDocument>,
+}
+
+/// 随响应返回的文档
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InlineDocument {
+    pub(crate) mime_type: &'static str,
+    /// base64 编码的文档内容
+    pub(crate) data: String,
+}
+
+impl InlineDocument {
+    fn pdf(bytes: &[u8]) -> Self {
+        Self {
+            mime_type: "application/pdf",
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
 }
 
 // --- 路由处理函数 ---
@@ -45,66 +476,2011 @@ async fn health_check() -> &'static str {
     "DeepPrint Agent is Running (Rust + Skia)"
 }
 
-/// 2. 获取打印机列表
-async fn get_printers() -> Json<Vec<PrinterInfo>> {
-    // 使用 printers crate 获取系统设备
-    // 注意：确保 Cargo.toml 中添加了 printers 依赖
-    let printers = printers::get_printers();
+/// 1.1 详细健康状态 (JSON)，供监控系统与云端控制台使用
+pub(crate) async fn health(State(state): State<AppState>) -> Json<HealthInfo> {
+    let last_failed = state
+        .jobs
+        .list(&JobQuery {
+            status: Some(JobStatus::Failed),
+            limit: Some(1),
+            ..Default::default()
+        })
+        .items
+        .pop();
+    // 枚举打印机可能较慢 (网络打印机)，不能阻塞异步运行时，否则健康检查本身会超时
+    let printers = tokio::task::spawn_blocking(|| printing::system_printers().len())
+        .await
+        .unwrap_or_default();
+
+    Json(HealthInfo {
+        status: "ok",
+        version: VERSION_INFO.version,
+        commit: VERSION_INFO.commit,
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        queue_depth: state.queue.depth(),
+        queue_paused: state.queue.is_paused(),
+        printers,
+        last_error: last_failed.map(|job| LastError {
+            task_id: job.task_id,
+            message: job.error.unwrap_or_default(),
+            at: job.updated_at,
+        }),
+    })
+}
+
+/// 1.2 版本信息
+async fn version() -> Json<VersionInfo> {
+    Json(VERSION_INFO)
+}
+
+/// 2. 获取打印机列表 (系统打印机 + 已配置的直连打印机)
+pub(crate) async fn get_printers(State(state): State<AppState>) -> Json<Vec<PrinterInfo>> {
+    // 系统打印队列中的设备 (移动端为空)
+    let printers = tokio::task::spawn_blocking(printing::system_printers)
+        .await
+        .unwrap_or_default();
     
-    let list = printers.iter().map(|p| PrinterInfo {
+    let mut list: Vec<PrinterInfo> = printers.iter().map(|p| PrinterInfo {
         name: p.name.clone(),
         system_name: p.system_name.clone(),
         is_default: p.is_default,
     }).collect();
 
+    let config = state.config.read().unwrap();
+    list.extend(config.direct_printers.iter().map(|(name, target)| PrinterInfo {
+        name: name.clone(),
+        system_name: target.name(),
+        is_default: false,
+    }));
+
     Json(list)
 }
 
-/// 3. 处理打印请求 (生成 PDF)
-async fn handle_print(Json(req): Json<PrintRequest>) -> Json<ApiResponse> {
-    println!("接收到打印任务: {}", req.task_id);
+/// 2.1 打印机实时状态 (在线、缺纸、开盖等)
+/// 直连打印机：ESC/POS 设备以 DLE EOT 查询实时状态，其余设备做连接探测
+async fn get_printer_status(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<PrinterStatus>, ApiError> {
+    blocking(move || match resolve_destination(&state, Some(&name))? {
+        Destination::Spooler(printer) => Ok(Json(printing::status(&printer))),
+        Destination::Direct(target) => Ok(Json(direct::status(&name, &target))),
+    })
+    .await
+}
+
+/// 2.2 检测已连接的 USB 打印机 (用于配置直连打印机)
+async fn get_usb_printers() -> Result<Json<Vec<UsbPrinterInfo>>, ApiError> {
+    tokio::task::spawn_blocking(usb::detect)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+/// 2.3 打印机支持的纸张 (模板尺寸与纸张不一致时据此自动选择纸张)
+async fn get_printer_media(Path(name): Path<String>) -> Result<Json<Vec<MediaSize>>, ApiError> {
+    blocking(move || {
+        let printer = resolve_printer(Some(&name))?;
+        Ok(Json(media::supported(&printer)))
+    })
+    .await
+}
+
+/// 3. 处理打印请求 (生成 PDF 并提交到打印机)
+/// 任务进入打印队列后立即返回 202，客户端通过 /jobs/{taskId} 查询结果
+async fn handle_print(
+    State(state): State<AppState>,
+    Extension(access): Extension<Access>,
+    ApiJson(req): ApiJson<PrintRequest>,
+) -> Result<(StatusCode, Json<ApiResponse>), ApiError> {
+    info!("接收到打印任务: {}", req.task_id);
+    state
+        .jobs
+        .create(&req.task_id, "content", req.printer.clone(), access.key_name.as_deref())
+        .map_err(|_| ApiError::task_in_progress(&req.task_id))?;
+
+    let Route {
+        printer,
+        options,
+        profile,
+        pool,
+    } = route_printer(
+        &state,
+        &access,
+        req.printer.as_deref(),
+        req.document_type.as_deref(),
+        req.options,
+    )
+    .await
+    .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+
+    enqueue_job(
+        &state,
+        PrintJob {
+            task_id: req.task_id,
+            printer,
+            payload: JobPayload::Content {
+                content: req.content,
+                width_mm: req.width_mm,
+                height_mm: req.height_mm,
+            },
+            options,
+            profile,
+            pool,
+        },
+    )
+}
+
+/// 4. 使用 DeepPrint 模板 + 数据渲染并打印
+pub(crate) async fn handle_print_template(
+    State(state): State<AppState>,
+    Extension(access): Extension<Access>,
+    ApiJson(mut req): ApiJson<TemplatePrintRequest>,
+) -> Result<(StatusCode, Json<ApiResponse>), ApiError> {
+    state
+        .jobs
+        .create(&req.task_id, "template", req.printer.clone(), access.key_name.as_deref())
+        .map_err(|_| ApiError::task_in_progress(&req.task_id))?;
+
+    let mut template = resolve_template(
+        &state.templates,
+        req.template,
+        req.template_id.as_deref(),
+        req.template_version,
+    )
+    .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+    if let Some(sample) = req.sample.as_deref().filter(|_| req.data.is_null()) {
+        req.data = template_sample(&state.templates, req.template_id.as_deref(), sample)
+            .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+    }
+    check_data(&template, &req.data).map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+    if req.return_document {
+        req.pdf_options.check().map_err(|e| {
+            fail_job(&state.jobs, &req.task_id, ApiError::bad_request(e.to_string()))
+        })?;
+    }
+    info!("接收到模板打印任务: {} ({})", req.task_id, template.meta.name);
+
+    let Route {
+        printer,
+        options,
+        profile,
+        pool,
+    } = route_printer(
+        &state,
+        &access,
+        req.printer.as_deref(),
+        req.document_type.as_deref(),
+        req.options,
+    )
+    .await
+    .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+
+    // 黑白打印时同步以灰度渲染，避免彩色元素在单色设备上产生半色调
+    let render_options = RenderOptions {
+        grayscale: (options.color_mode == Some(ColorMode::Monochrome))
+            .then(LumaWeights::default),
+        ..Default::default()
+    };
+
+    // 调试模式 (收集诊断) 或需要返回文档时：先试渲染一次，渲染失败时直接返回错误，不再入队
+    let (diagnostics, document) = if req.debug || req.return_document {
+        // 试渲染同样需要先下载远程图片；任务执行时不会重复下载已内嵌的图片
+        resolve_remote_assets(&state, &mut template).await;
+        let cancel = CancelToken::new();
+        let _guard = cancel.drop_guard();
+        let (template, data, debug) = (template.clone(), req.data.clone(), req.debug);
+        let pdf_options = req.pdf_options.clone();
+        let render_options = RenderOptions {
+            cancel: Some(cancel),
+            ..render_options.clone()
+        };
+        let (diagnostics, pdf) = blocking(move || {
+            let renderer = DeepPrintRenderer::new();
+            if debug {
+                output::render_pdf_traced(&renderer, &template, &data, &render_options, &pdf_options)
+                    .map(|(pdf, diagnostics)| (Some(diagnostics), pdf))
+            } else {
+                output::render_pdf(&renderer, &template, &data, &render_options, &pdf_options)
+                    .map(|pdf| (None, pdf))
+            }
+            .map_err(ApiError::from)
+        })
+        .await
+        .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+        (diagnostics, req.return_document.then(|| InlineDocument::pdf(&pdf)))
+    } else {
+        (None, None)
+    };
+
+    let warnings = template_warnings(&template, profile.as_ref());
+    for w in &warnings[template.migration_warnings.len()..] {
+        warn!("任务 {}: {}", req.task_id, w.message);
+    }
+    let (status, Json(mut response)) = enqueue_job(
+        &state,
+        PrintJob {
+            task_id: req.task_id,
+            printer,
+            payload: JobPayload::Template {
+                template: Box::new(template),
+                data: req.data,
+                render_options,
+            },
+            options,
+            profile,
+            pool,
+        },
+    )?;
+    response.diagnostics = diagnostics;
+    response.warnings = warnings;
+    response.document = document;
+    Ok((status, Json(response)))
+}
+
+/// 4.1 直传 PDF 打印：不经过渲染引擎，直接提交到打印机
+async fn handle_print_pdf(
+    State(state): State<AppState>,
+    Extension(access): Extension<Access>,
+    req: Request,
+) -> Result<(StatusCode, Json<ApiResponse>), ApiError> {
+    let (req, data) = read_upload::<DocumentPrintRequest>(req).await?;
+    info!("接收到 PDF 打印任务: {} ({} bytes)", req.task_id, data.len());
+    state
+        .jobs
+        .create(&req.task_id, "pdf", req.printer.clone(), access.key_name.as_deref())
+        .map_err(|_| ApiError::task_in_progress(&req.task_id))?;
+
+    if !data.starts_with(b"%PDF-") {
+        let err = ApiError::bad_request("Uploaded file is not a PDF");
+        return Err(fail_job(&state.jobs, &req.task_id, err));
+    }
+
+    let Route {
+        printer,
+        options,
+        profile,
+        pool,
+    } = route_printer(
+        &state,
+        &access,
+        req.printer.as_deref(),
+        req.document_type.as_deref(),
+        req.options,
+    )
+    .await
+    .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+
+    enqueue_job(
+        &state,
+        PrintJob {
+            task_id: req.task_id,
+            printer,
+            payload: JobPayload::Pdf { data },
+            options,
+            profile,
+            pool,
+        },
+    )
+}
+
+/// 4.2 直传图片打印：自动缩放/旋转到纸张尺寸后打印
+async fn handle_print_image(
+    State(state): State<AppState>,
+    Extension(access): Extension<Access>,
+    req: Request,
+) -> Result<(StatusCode, Json<ApiResponse>), ApiError> {
+    let (req, data) = read_upload::<ImagePrintRequest>(req).await?;
+    info!("接收到图片打印任务: {} ({} bytes)", req.task_id, data.len());
+    state
+        .jobs
+        .create(&req.task_id, "image", req.printer.clone(), access.key_name.as_deref())
+        .map_err(|_| ApiError::task_in_progress(&req.task_id))?;
+
+    let is_png = data.starts_with(b"\x89PNG\r\n\x1a\n");
+    let is_jpeg = data.starts_with(&[0xFF, 0xD8, 0xFF]);
+    if !is_png && !is_jpeg {
+        let err = ApiError::bad_request("Uploaded file is not a PNG or JPEG image");
+        return Err(fail_job(&state.jobs, &req.task_id, err));
+    }
+
+    // mm -> pt
+    const MM_TO_PT: f32 = 72.0 / 25.4;
+    let layout = ImageLayout {
+        page_width: req.paper_width_mm.unwrap_or(210.0) * MM_TO_PT,
+        page_height: req.paper_height_mm.unwrap_or(297.0) * MM_TO_PT,
+        fit: req.fit,
+        margin: req.margin_mm.max(0.0) * MM_TO_PT,
+        auto_rotate: req.auto_rotate.unwrap_or(true),
+    };
 
-    let engine = Engine::new();
+    let Route {
+        printer,
+        options,
+        profile,
+        pool,
+    } = route_printer(
+        &state,
+        &access,
+        req.printer.as_deref(),
+        req.document_type.as_deref(),
+        req.options,
+    )
+    .await
+    .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
 
-    // 1. 获取 PDF 数据 (现在是 Vec<u8> 类型)
-    let pdf_bytes = engine.generate_pdf(&req.content, req.width_mm, req.height_mm);
+    enqueue_job(
+        &state,
+        PrintJob {
+            task_id: req.task_id,
+            printer,
+            payload: JobPayload::Image { data, layout },
+            options,
+            profile,
+            pool,
+        },
+    )
+}
 
-    let output_path = dirs::desktop_dir()
-        .unwrap_or(PathBuf::from("."))
-        .join(format!("deepprint_{}.pdf", req.task_id));
+/// 4.3 原始指令透传 (ESC/POS、ZPL 等)，不做任何渲染或转换
+async fn handle_print_raw(
+    State(state): State<AppState>,
+    Extension(access): Extension<Access>,
+    req: Request,
+) -> Result<(StatusCode, Json<ApiResponse>), ApiError> {
+    let (req, data) = read_upload::<RawPrintRequest>(req).await?;
+    info!("接收到原始指令任务: {} ({} bytes)", req.task_id, data.len());
+    if req.target.is_some() && !access.is_admin() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "target_forbidden",
+            "Inline direct targets require an admin API key; configure a direct printer and pass its name as 'printer' instead",
+        ));
+    }
 
-    // 2. 写入文件
-    // 之前的 pdf_data.as_bytes() 删掉，因为 Vec<u8> 可以直接作为引用传给 fs::write
-    match fs::write(&output_path, &pdf_bytes) {
-        Ok(_) => Json(ApiResponse {
+    let Route {
+        printer,
+        options,
+        profile,
+        pool,
+    } = match req.target {
+        Some(target) => {
+            state
+                .jobs
+                .create(&req.task_id, "raw", Some(target.name()), access.key_name.as_deref())
+                .map_err(|_| ApiError::task_in_progress(&req.task_id))?;
+            access
+                .check_printer(&[&target.name()])
+                .and_then(|_| req.options.validate().map_err(ApiError::bad_request))
+                .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?;
+            Route {
+                printer: Destination::Direct(target),
+                options: req.options,
+                profile: None,
+                pool: None,
+            }
+        }
+        None => {
+            state
+                .jobs
+                .create(&req.task_id, "raw", req.printer.clone(), access.key_name.as_deref())
+                .map_err(|_| ApiError::task_in_progress(&req.task_id))?;
+            route_printer(
+                &state,
+                &access,
+                req.printer.as_deref(),
+                req.document_type.as_deref(),
+                req.options,
+            )
+            .await
+            .map_err(|e| fail_job(&state.jobs, &req.task_id, e))?
+        }
+    };
+
+    enqueue_job(
+        &state,
+        PrintJob {
+            task_id: req.task_id,
+            printer,
+            payload: JobPayload::Raw { data },
+            options,
+            profile,
+            pool,
+        },
+    )
+}
+
+/// 4.4 批量打印：逐条数据入队，通过 GET /print/batch/{batchId} 查询进度
+async fn handle_print_batch(
+    State(state): State<AppState>,
+    Extension(access): Extension<Access>,
+    ApiJson(req): ApiJson<BatchPrintRequest>,
+) -> Result<(StatusCode, Json<BatchResponse>), ApiError> {
+    if req.records.is_empty() {
+        return Err(ApiError::bad_request("records must not be empty"));
+    }
+    let template = resolve_template(
+        &state.templates,
+        req.template,
+        req.template_id.as_deref(),
+        req.template_version,
+    )?;
+    // 任一记录不符合 dataSchema 时整批拒绝，不创建任何任务
+    if let Some(schema) = &template.data_schema {
+        let violations = req
+            .records
+            .iter()
+            .enumerate()
+            .flat_map(|(i, data)| {
+                data_schema::validate(schema, data)
+                    .into_iter()
+                    .map(move |v| SchemaViolation {
+                        path: format!("records[{}]{}{}", i, if v.path.is_empty() { "" } else { "." }, v.path),
+                        ..v
+                    })
+            })
+            .collect();
+        invalid_data(violations)?;
+    }
+    let Route {
+        printer,
+        options,
+        profile,
+        pool,
+    } = route_printer(
+        &state,
+        &access,
+        req.printer.as_deref(),
+        req.document_type.as_deref(),
+        req.options,
+    )
+    .await?;
+    let warnings = template_warnings(&template, profile.as_ref());
+    let batch_id = req
+        .batch_id
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    info!(
+        "接收到批量打印任务: {} ({}, {} 条)",
+        batch_id,
+        template.meta.name,
+        req.records.len()
+    );
+
+    let render_options = RenderOptions {
+        grayscale: (options.color_mode == Some(ColorMode::Monochrome))
+            .then(LumaWeights::default),
+        ..Default::default()
+    };
+
+    if req.merge || req.imposition.is_some() {
+        // 合并模式：整个批次是一个任务，任务 ID 与批次 ID 相同
+        let record_count = req.records.len();
+        state
+            .jobs
+            .create_in_batch(
+                &batch_id,
+                "batch",
+                Some(printer.name()),
+                Some(&batch_id),
+                access.key_name.as_deref(),
+            )
+            .map_err(|_| ApiError::task_in_progress(&batch_id))?;
+        let job = PrintJob {
+            task_id: batch_id.clone(),
+            printer,
+            payload: JobPayload::Records {
+                template: Box::new(template),
+                records: req.records,
+                render_options,
+                imposition: req.imposition,
+            },
+            options,
+            profile,
+            pool,
+        };
+        let printer_name = job.printer.name();
+        if state.queue.enqueue(job).is_err() {
+            return Err(fail_job(&state.jobs, &batch_id, ApiError::queue_full()));
+        }
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(BatchResponse {
+                success: true,
+                message: format!(
+                    "{} records merged into one job for {}",
+                    record_count, printer_name
+                ),
+                task_ids: vec![batch_id.clone()],
+                batch_id,
+                warnings,
+            }),
+        ));
+    }
+
+    // 整批一次占用队列位置：容量不足时全部拒绝，不会只打出一部分
+    let mut task_ids = Vec::new();
+    let mut jobs = Vec::new();
+    for (i, data) in req.records.into_iter().enumerate() {
+        let task_id = format!("{}-{}", batch_id, i + 1);
+        let created = state.jobs.create_in_batch(
+            &task_id,
+            "template",
+            Some(printer.name()),
+            Some(&batch_id),
+            access.key_name.as_deref(),
+        );
+        if created.is_err() {
+            // 批次中已登记的任务不会入队，标记为失败
+            let conflict = ApiError::task_in_progress(&task_id);
+            for task_id in &task_ids {
+                fail_job(&state.jobs, task_id, conflict.clone());
+            }
+            return Err(conflict);
+        }
+        let job = PrintJob {
+            task_id: task_id.clone(),
+            printer: printer.clone(),
+            payload: JobPayload::Template {
+                template: Box::new(template.clone()),
+                data,
+                render_options: render_options.clone(),
+            },
+            options: options.clone(),
+            profile: profile.clone(),
+            pool: pool.clone(),
+        };
+        task_ids.push(task_id);
+        jobs.push(job);
+    }
+
+    if state.queue.enqueue_all(jobs).is_err() {
+        for task_id in &task_ids {
+            fail_job(&state.jobs, task_id, ApiError::queue_full());
+        }
+        return Err(ApiError::queue_full());
+    }
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(BatchResponse {
             success: true,
-            message: "PDF Rendered & Saved successfully".to_string(),
-            debug_path: Some(output_path.to_string_lossy().to_string()),
+            message: format!("{} jobs queued for {}", task_ids.len(), printer.name()),
+            batch_id,
+            task_ids,
+            warnings,
         }),
-        Err(e) => Json(ApiResponse {
-            success: false,
-            message: format!("File save error: {}", e),
+    ))
+}
+
+/// 4.5 批次进度
+async fn get_batch(
+    State(state): State<AppState>,
+    Path(batch_id): Path<String>,
+) -> Result<Json<BatchSummary>, ApiError> {
+    state.jobs.batch(&batch_id).map(Json).ok_or_else(|| {
+        ApiError::not_found("batch_not_found", format!("Batch '{}' not found", batch_id))
+    })
+}
+
+/// 5. 预览：渲染模板为 PNG
+async fn handle_preview(
+    State(state): State<AppState>,
+    ApiJson(req): ApiJson<PreviewRequest>,
+) -> Result<Response, ApiError> {
+    preview(&state, req).await
+}
+
+/// 渲染预览并按请求返回 PNG 或 JSON
+async fn preview(state: &AppState, mut req: PreviewRequest) -> Result<Response, ApiError> {
+    prepare_preview(state, &mut req).await?;
+    // 客户端断开时请求 future 被丢弃，守卫随之取消仍在阻塞线程中进行的渲染
+    let cancel = CancelToken::new();
+    let _guard = cancel.drop_guard();
+    req.cancel = Some(cancel);
+    // 渲染与 PNG/Base64 编码都是 CPU 密集操作，放到阻塞线程池中执行
+    blocking(move || {
+        if req.debug {
+            let (image, diagnostics) = render_preview_traced(&req)?;
+            let response = PreviewResponse::png(&image)
+                .with_diagnostics(diagnostics)
+                .with_warnings(&req.template.migration_warnings);
+            return Ok(Json(response).into_response());
+        }
+        let image = render_preview(&req)?;
+        if req.base64 {
+            let response = PreviewResponse::png(&image).with_warnings(&req.template.migration_warnings);
+            Ok(Json(response).into_response())
+        } else {
+            Ok(([(header::CONTENT_TYPE, "image/png")], image.bytes).into_response())
+        }
+    })
+    .await
+}
+
+/// 下载模板引用的远程图片并内嵌 (渲染器不访问网络)，返回下载失败的说明
+pub(crate) async fn resolve_remote_assets(state: &AppState, template: &mut DeepPrintTemplate) -> Vec<String> {
+    let remote_assets = state.config.read().unwrap().remote_assets.clone();
+    remote_assets::resolve(template, &remote_assets).await
+}
+
+/// 渲染预览前的准备 (同打印)：展开模板库中的片段，下载远程图片
+pub(crate) async fn prepare_preview(state: &AppState, req: &mut PreviewRequest) -> Result<(), ApiError> {
+    req.template = state.templates.expand_partials(req.template.clone())?;
+    resolve_remote_assets(state, &mut req.template).await;
+    Ok(())
+}
+
+/// 按预览请求的分辨率渲染 PNG (HTTP 预览接口与桌面端共用)，调用前需先 prepare_preview
+pub(crate) fn render_preview(req: &PreviewRequest) -> Result<EncodedImage, ApiError> {
+    let (scale, render_options) = preview_options(req)?;
+    let data = preview_data(&req.template, &req.data)?;
+    let renderer = DeepPrintRenderer::new();
+    output::render_png(&renderer, &req.template, &data, &render_options, scale)
+        .map_err(ApiError::from)
+}
+
+/// 渲染预览并收集渲染诊断 (调试模式)
+fn render_preview_traced(req: &PreviewRequest) -> Result<(EncodedImage, RenderDiagnostics), ApiError> {
+    let (scale, render_options) = preview_options(req)?;
+    let data = preview_data(&req.template, &req.data)?;
+    let renderer = DeepPrintRenderer::new();
+    output::render_png_traced(&renderer, &req.template, &data, &render_options, scale)
+        .map_err(ApiError::from)
+}
+
+/// 预览数据：未提供数据时按 dataSchema 生成示例数据，否则按 dataSchema 校验
+pub(crate) fn preview_data<'a>(template: &DeepPrintTemplate, data: &'a Value) -> Result<Cow<'a, Value>, ApiError> {
+    match &template.data_schema {
+        Some(schema) if data.is_null() => Ok(Cow::Owned(data_schema::sample(schema))),
+        _ => {
+            check_data(template, data)?;
+            Ok(Cow::Borrowed(data))
+        }
+    }
+}
+
+fn preview_options(req: &PreviewRequest) -> Result<(f32, RenderOptions), ApiError> {
+    let scale = req.dpi.unwrap_or(72.0) / 72.0 * req.scale.unwrap_or(1.0);
+    if !(scale > 0.0 && scale.is_finite()) {
+        return Err(ApiError::bad_request("dpi and scale must be positive"));
+    }
+    let render_options = RenderOptions {
+        grayscale: req.grayscale.then(LumaWeights::default),
+        cancel: req.cancel.clone(),
+    };
+    Ok((scale, render_options))
+}
+
+/// 按记录逐页渲染 PNG (预览窗口翻页查看，每页对应一条数据记录)
+pub(crate) async fn render_preview_pages(
+    state: &AppState,
+    req: PagedPreviewRequest,
+) -> Result<Vec<EncodedImage>, ApiError> {
+    let scale = req.dpi.unwrap_or(144.0) / 72.0;
+    if !(scale > 0.0 && scale.is_finite()) {
+        return Err(ApiError::bad_request("dpi must be positive"));
+    }
+    let mut template = resolve_template(
+        &state.templates,
+        req.template,
+        req.template_id.as_deref(),
+        req.template_version,
+    )?;
+    resolve_remote_assets(state, &mut template).await;
+    let records = match req.data {
+        Value::Array(records) if !records.is_empty() => records,
+        Value::Array(_) => return Err(ApiError::bad_request("data must not be empty")),
+        data => vec![preview_data(&template, &data)?.into_owned()],
+    };
+
+    let render_options = RenderOptions {
+        grayscale: req.grayscale.then(LumaWeights::default),
+        ..Default::default()
+    };
+    blocking(move || {
+        let renderer = DeepPrintRenderer::new();
+        records
+            .iter()
+            .map(|data| output::render_png(&renderer, &template, data, &render_options, scale))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ApiError::from)
+    })
+    .await
+}
+
+/// 5.1 模板热重载 (开发模式)：开始监视模板文件或已注册模板，保存后自动重新渲染
+async fn put_preview_watch(
+    State(state): State<AppState>,
+    ApiJson(mut req): ApiJson<WatchRequest>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    let dev_preview = dev_preview_config(&state)?;
+    if let Some(path) = &req.path {
+        // 相对路径按模板目录解析
+        req.path = Some(
+            hot_reload::confine_path(dev_preview.templates_dir.as_deref(), path)
+                .map_err(|e| ApiError::new(StatusCode::FORBIDDEN, "path_forbidden", e))?,
+        );
+    }
+    state
+        .preview
+        .start(state.clone(), req)
+        .map_err(ApiError::bad_request)?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Watching template".to_string(),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+/// 5.2 停止模板热重载
+async fn delete_preview_watch(State(state): State<AppState>) -> Result<StatusCode, ApiError> {
+    dev_preview_config(&state)?;
+    state.preview.stop();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 5.3 以 WebSocket 推送热重载的预览结果 (每次重新渲染一条 JSON 文本消息)
+/// 浏览器发起的握手必须来自跨域白名单内的网页 (由 cors::reject_foreign_origins 检查)
+async fn dev_preview_socket(
+    State(state): State<AppState>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    dev_preview_config(&state)?;
+    let updates = state.preview.subscribe();
+    Ok(ws.on_upgrade(move |socket| stream_preview(socket, updates)))
+}
+
+/// 热重载的 HTTP 接口需在配置中开启 (devPreview.enabled)
+fn dev_preview_config(state: &AppState) -> Result<DevPreviewConfig, ApiError> {
+    let config = state.config.read().unwrap().dev_preview.clone();
+    if !config.enabled {
+        return Err(ApiError::not_found("dev_preview_disabled", "Dev preview is disabled"));
+    }
+    Ok(config)
+}
+
+async fn stream_preview(
+    mut socket: WebSocket,
+    mut updates: tokio::sync::broadcast::Receiver<PreviewUpdate>,
+) {
+    loop {
+        match updates.recv().await {
+            Ok(update) => {
+                let Ok(text) = serde_json::to_string(&update) else {
+                    continue;
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            // 只关心最新的预览，跳过积压的旧结果
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ValidateQuery {
+    /// 严格模式：未识别的字段 (如拼错的 fontsize) 按错误处理
+    #[serde(default)]
+    strict: bool,
+    /// 打印机档案名：检查元素是否落入该设备的不可打印边距
+    profile: Option<String>,
+}
+
+/// 6. 模板校验：返回结构化的错误/警告列表
+async fn handle_validate(
+    State(state): State<AppState>,
+    Query(query): Query<ValidateQuery>,
+    ApiJson(raw): ApiJson<Value>,
+) -> Result<Json<ValidationReport>, ApiError> {
+    let profile = match &query.profile {
+        Some(name) => Some(
+            state
+                .config
+                .read()
+                .unwrap()
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| {
+                    ApiError::not_found(
+                        "profile_not_found",
+                        format!("Printer profile '{}' not found", name),
+                    )
+                })?,
+        ),
+        None => None,
+    };
+    Ok(Json(validator::validate(&raw, query.strict, profile.as_ref())))
+}
+
+/// 7. 查询任务状态
+pub(crate) async fn get_job(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Json<JobRecord>, ApiError> {
+    state.jobs.get(&task_id).map(Json).ok_or_else(|| {
+        ApiError::not_found("job_not_found", format!("Job '{}' not found", task_id))
+    })
+}
+
+/// 7.1 取消任务：排队中的任务不再执行，渲染中的任务尽快停止；已提交到打印机的任务无法取消 (409)
+async fn cancel_job(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    if state.jobs.get(&task_id).is_none() {
+        return Err(ApiError::not_found(
+            "job_not_found",
+            format!("Job '{}' not found", task_id),
+        ));
+    }
+    if !state.queue.cancel(&task_id) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "job_not_cancellable",
+            format!("Job '{}' has already been submitted or finished", task_id),
+        ));
+    }
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Job {} cancelled", task_id),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+/// 7.2 下载任务实际打印的内容 (PDF，栅格化任务为 PNG，文本模式任务为 TXT)
+/// 原始指令任务没有副本；副本按保留策略删除后返回 404
+async fn get_job_artifact(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Response, ApiError> {
+    let job = state.jobs.get(&task_id).ok_or_else(|| {
+        ApiError::not_found("job_not_found", format!("Job '{}' not found", task_id))
+    })?;
+    let not_found = || {
+        ApiError::not_found(
+            "artifact_not_found",
+            format!("No rendered output is stored for job '{}'", task_id),
+        )
+    };
+    let path = PathBuf::from(job.output_path.ok_or_else(not_found)?);
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => return Err(ApiError::internal(format!("Cannot read {}: {}", path.display(), e))),
+    };
+    let (content_type, extension) = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => ("image/png", "png"),
+        Some("txt") => ("text/plain; charset=utf-8", "txt"),
+        _ => ("application/pdf", "pdf"),
+    };
+    let disposition = format!("attachment; filename=\"deepprint_{}.{}\"", task_id, extension);
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+/// 8. 任务列表 (支持按状态/打印机/时间过滤与分页)
+async fn list_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<JobQuery>,
+) -> Json<JobPage> {
+    Json(state.jobs.list(&query))
+}
+
+/// 8.1 审计日志 (按时间顺序，仅限 admin Key)，format=csv 时以 CSV 文件下载
+async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Response, ApiError> {
+    let csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => {
+            return Err(ApiError::bad_request(format!(
+                "Unsupported audit format '{}', expected json or csv",
+                other
+            )))
+        }
+    };
+    let entries = state.jobs.audit_log(&query);
+    if !csv {
+        return Ok(Json(entries).into_response());
+    }
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"deepprint_audit.csv\""),
+        ],
+        audit::to_csv(&entries),
+    )
+        .into_response())
+}
+
+/// 9. 已注册模板列表
+pub(crate) async fn list_templates(State(state): State<AppState>) -> Json<Vec<TemplateSummary>> {
+    Json(state.templates.list())
+}
+
+/// 10. 获取已注册模板
+async fn get_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<TemplateRecord>, ApiError> {
+    state.templates.get(&id).map(Json).ok_or_else(|| template_not_found(&id))
+}
+
+/// 11. 注册/更新模板，保存前先校验，未通过时返回 400，details 为校验报告
+async fn put_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(raw): ApiJson<Value>,
+) -> Result<Json<TemplateRecord>, ApiError> {
+    let report = validator::validate(&raw, false, None);
+    if !report.valid {
+        return Err(ApiError::invalid_template("Template validation failed").with_details(report));
+    }
+    let template: DeepPrintTemplate =
+        serde_json::from_value(raw).map_err(|e| ApiError::invalid_template(e.to_string()))?;
+
+    let record = state.templates.put(&id, template).map_err(ApiError::internal)?;
+    info!("模板已保存: {} ({})", record.id, record.name);
+    Ok(Json(record))
+}
+
+/// 12. 删除已注册模板
+async fn delete_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    match state.templates.delete(&id) {
+        Ok(true) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("Template '{}' deleted", id),
             debug_path: None,
+            diagnostics: None,
+            warnings: Vec::new(),
+            document: None,
+        })),
+        Ok(false) => Err(template_not_found(&id)),
+        Err(e) => Err(ApiError::internal(e)),
+    }
+}
+
+/// 13. 模板历史版本
+async fn list_template_versions(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<TemplateVersion>>, ApiError> {
+    state.templates.versions(&id).map(Json).ok_or_else(|| template_not_found(&id))
+}
+
+/// 14. 获取模板的指定版本
+async fn get_template_version(
+    State(state): State<AppState>,
+    Path((id, version)): Path<(String, u32)>,
+) -> Result<Json<TemplateRecord>, ApiError> {
+    state
+        .templates
+        .get_version(&id, version)
+        .map(Json)
+        .ok_or_else(|| template_version_not_found(&id, version))
+}
+
+/// 15. 回滚：将指定历史版本设为当前版本
+async fn rollback_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ApiJson(req): ApiJson<RollbackRequest>,
+) -> Result<Json<TemplateRecord>, ApiError> {
+    match state.templates.rollback(&id, req.version) {
+        Ok(Some(record)) => {
+            info!("模板已回滚: {} -> v{}", id, req.version);
+            Ok(Json(record))
+        }
+        Ok(None) => Err(template_version_not_found(&id, req.version)),
+        Err(e) => Err(ApiError::internal(e)),
+    }
+}
+
+/// 15.1 预览已注册模板，数据取自保存的示例数据，调用方无需构造数据
+async fn preview_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<TemplatePreviewQuery>,
+) -> Result<Response, ApiError> {
+    let template = resolve_template(&state.templates, None, Some(&id), query.version)?;
+    let data = match query.sample.as_deref() {
+        Some(name) => template_sample(&state.templates, Some(&id), name)?,
+        None => state.templates.sample(&id, DEFAULT_SAMPLE).unwrap_or(Value::Null),
+    };
+    let req = PreviewRequest {
+        template,
+        data,
+        dpi: query.dpi,
+        scale: query.scale,
+        base64: query.base64,
+        grayscale: query.grayscale,
+        debug: query.debug,
+        cancel: None,
+    };
+    preview(&state, req).await
+}
+
+/// 15.2 模板的示例数据 (名称 -> 数据)
+async fn list_template_samples(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<HashMap<String, Value>>, ApiError> {
+    if state.templates.get(&id).is_none() {
+        return Err(template_not_found(&id));
+    }
+    Ok(Json(state.templates.samples(&id)))
+}
+
+/// 15.3 保存示例数据 (整体替换)，按模板当前版本的 dataSchema 校验
+async fn put_template_sample(
+    State(state): State<AppState>,
+    Path((id, name)): Path<(String, String)>,
+    ApiJson(data): ApiJson<Value>,
+) -> Result<Json<Value>, ApiError> {
+    let record = state.templates.get(&id).ok_or_else(|| template_not_found(&id))?;
+    check_data(&record.template, &data)?;
+    state.templates.put_sample(&id, &name, &data).map_err(ApiError::internal)?;
+    info!("示例数据已保存: {} / {}", id, name);
+    Ok(Json(data))
+}
+
+/// 15.4 删除示例数据
+async fn delete_template_sample(
+    State(state): State<AppState>,
+    Path((id, name)): Path<(String, String)>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    match state.templates.delete_sample(&id, &name) {
+        Ok(true) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("Sample '{}' of template '{}' deleted", name, id),
+            debug_path: None,
+            diagnostics: None,
+            warnings: Vec::new(),
+            document: None,
+        })),
+        Ok(false) => Err(sample_not_found(&id, &name)),
+        Err(e) => Err(ApiError::internal(e)),
+    }
+}
+
+/// 16. 查询 Agent 默认打印机配置
+async fn get_default_printers(State(state): State<AppState>) -> Json<PrinterDefaults> {
+    Json(state.config.read().unwrap().printers.clone())
+}
+
+/// 17. 设置 Agent 默认打印机 (整体替换)，打印机必须存在、为已配置的别名、打印机池或 IPP 地址
+async fn put_default_printers(
+    State(state): State<AppState>,
+    ApiJson(defaults): ApiJson<PrinterDefaults>,
+) -> Result<Json<PrinterDefaults>, ApiError> {
+    for name in defaults
+        .default_printer
+        .iter()
+        .chain(defaults.document_types.values())
+    {
+        check_printer_name(&state, name)?;
+    }
+
+    update_config(&state, |config| config.printers = defaults.clone())?;
+    info!("默认打印机已更新: {:?}", defaults);
+    Ok(Json(defaults))
+}
+
+/// 18. 打印机别名列表
+async fn list_aliases(State(state): State<AppState>) -> Json<HashMap<String, PrinterAlias>> {
+    Json(state.config.read().unwrap().aliases.clone())
+}
+
+/// 19. 新增/修改打印机别名，物理打印机必须存在 (或为打印机池、IPP 地址)
+async fn put_alias(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(alias): ApiJson<PrinterAlias>,
+) -> Result<Json<PrinterAlias>, ApiError> {
+    if !state.config.read().unwrap().pools.contains_key(&alias.printer) {
+        resolve_destination(&state, Some(&alias.printer))?;
+    }
+    update_config(&state, |config| {
+        config.aliases.insert(name.clone(), alias.clone());
+    })?;
+    info!("打印机别名已更新: {} -> {}", name, alias.printer);
+    Ok(Json(alias))
+}
+
+/// 20. 删除打印机别名
+async fn delete_alias(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    if !state.config.read().unwrap().aliases.contains_key(&name) {
+        return Err(ApiError::not_found(
+            "alias_not_found",
+            format!("Alias '{}' not found", name),
+        ));
+    }
+    update_config(&state, |config| {
+        config.aliases.remove(&name);
+    })?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Alias '{}' deleted", name),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+/// 21. API Key 列表 (仅 admin)
+async fn list_api_keys(State(state): State<AppState>) -> Json<Vec<ApiKeySummary>> {
+    let config = state.config.read().unwrap();
+    let mut keys: Vec<ApiKeySummary> = config
+        .api_keys
+        .iter()
+        .map(|(name, key)| {
+            let hint_start = key.key.char_indices().rev().nth(3).map_or(0, |(i, _)| i);
+            ApiKeySummary {
+                name: name.clone(),
+                key_hint: key.key[hint_start..].to_string(),
+                printers: key.printers.clone(),
+                endpoints: key.endpoints.clone(),
+                admin: key.admin,
+            }
         })
+        .collect();
+    keys.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(keys)
+}
+
+/// 22. 新增/修改 API Key，响应中包含完整 Key (仅此一次)
+async fn put_api_key(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(req): ApiJson<ApiKeyRequest>,
+) -> Result<Json<ApiKey>, ApiError> {
+    let existing = state.config.read().unwrap().api_keys.get(&name).map(|k| k.key.clone());
+    let key = ApiKey {
+        key: req
+            .key
+            .filter(|k| !k.is_empty())
+            .or(existing)
+            .unwrap_or_else(|| format!("dp_{}", uuid::Uuid::new_v4().simple())),
+        printers: req.printers,
+        endpoints: req.endpoints,
+        admin: req.admin,
+    };
+    update_config(&state, |config| {
+        config.api_keys.insert(name.clone(), key.clone());
+    })?;
+    info!("API Key 已更新: {}", name);
+    Ok(Json(key))
+}
+
+/// 23. 删除 API Key
+async fn delete_api_key(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    if !state.config.read().unwrap().api_keys.contains_key(&name) {
+        return Err(ApiError::not_found(
+            "api_key_not_found",
+            format!("API key '{}' not found", name),
+        ));
+    }
+    update_config(&state, |config| {
+        config.api_keys.remove(&name);
+    })?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("API key '{}' deleted", name),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+/// 24. 直连打印机列表
+async fn list_direct_printers(
+    State(state): State<AppState>,
+) -> Json<HashMap<String, DirectTarget>> {
+    Json(state.config.read().unwrap().direct_printers.clone())
+}
+
+/// 25. 新增/修改直连打印机，如 {"type": "socket", "host": "10.0.0.5", "language": "zpl"}
+async fn put_direct_printer(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(target): ApiJson<DirectTarget>,
+) -> Result<Json<DirectTarget>, ApiError> {
+    update_config(&state, |config| {
+        config.direct_printers.insert(name.clone(), target.clone());
+    })?;
+    info!("直连打印机已更新: {} -> {}", name, target.name());
+    Ok(Json(target))
+}
+
+/// 26. 删除直连打印机
+async fn delete_direct_printer(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    if !state.config.read().unwrap().direct_printers.contains_key(&name) {
+        return Err(ApiError::not_found(
+            "printer_not_found",
+            format!("Direct printer '{}' not found", name),
+        ));
+    }
+    update_config(&state, |config| {
+        config.direct_printers.remove(&name);
+    })?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Direct printer '{}' deleted", name),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+/// 27. 打印机档案列表
+async fn list_profiles(State(state): State<AppState>) -> Json<HashMap<String, PrinterProfile>> {
+    Json(state.config.read().unwrap().profiles.clone())
+}
+
+/// 28. 新增/修改打印机档案 (整体替换)，名称为别名、直连打印机名或物理打印机名，
+/// 如 {"paperWidthMm": 72, "rotation": 90, "options": {"cut": "partial", "feedLines": 4}}
+async fn put_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(mut profile): ApiJson<PrinterProfile>,
+) -> Result<Json<PrinterProfile>, ApiError> {
+    profile.migrate_legacy();
+    if profile.rotation % 90 != 0 || profile.rotation >= 360 {
+        return Err(ApiError::bad_request("rotation must be 0, 90, 180 or 270"));
+    }
+    if profile.darkness.is_some_and(|d| d > 30) {
+        return Err(ApiError::bad_request("darkness must be between 0 and 30"));
+    }
+    if profile.paper_width_mm.is_some_and(|w| w <= 0.0) {
+        return Err(ApiError::bad_request("paperWidthMm must be greater than 0"));
+    }
+    if profile.unprintable_margin_mm < 0.0 {
+        return Err(ApiError::bad_request("unprintableMarginMm must not be negative"));
+    }
+    if profile.text_columns == Some(0) {
+        return Err(ApiError::bad_request("textColumns must be greater than 0"));
+    }
+    update_config(&state, |config| {
+        config.profiles.insert(name.clone(), profile.clone());
+    })?;
+    info!("打印机档案已更新: {}", name);
+    Ok(Json(profile))
+}
+
+/// 29. 删除打印机档案
+async fn delete_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    if !state.config.read().unwrap().profiles.contains_key(&name) {
+        return Err(ApiError::not_found(
+            "profile_not_found",
+            format!("Printer profile '{}' not found", name),
+        ));
+    }
+    update_config(&state, |config| {
+        config.profiles.remove(&name);
+    })?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Printer profile '{}' deleted", name),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+/// 30. 打印机池列表，含各成员的在线状态与已分配任务数
+async fn list_pools(State(state): State<AppState>) -> Result<Json<Vec<PoolStatus>>, ApiError> {
+    let pools = state.config.read().unwrap().pools.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut status: Vec<PoolStatus> = pools
+            .iter()
+            .map(|(name, pool)| {
+                state
+                    .pools
+                    .status(name, pool, |printer| member_online(&state, printer))
+            })
+            .collect();
+        status.sort_by(|a, b| a.name.cmp(&b.name));
+        Json(status)
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))
+}
+
+/// 31. 新增/修改打印机池，如 {"printers": ["label-1", "label-2"], "options": {...}}
+/// 成员必须为可解析的打印机 (不能是别名或其他池)
+async fn put_pool(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(pool): ApiJson<PrinterPool>,
+) -> Result<Json<PrinterPool>, ApiError> {
+    if pool.printers.is_empty() {
+        return Err(ApiError::bad_request("A pool needs at least one printer"));
+    }
+    for printer in &pool.printers {
+        resolve_destination(&state, Some(printer))?;
+    }
+    update_config(&state, |config| {
+        config.pools.insert(name.clone(), pool.clone());
+    })?;
+    info!("打印机池已更新: {} -> {:?}", name, pool.printers);
+    Ok(Json(pool))
+}
+
+/// 32. 删除打印机池
+async fn delete_pool(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    if !state.config.read().unwrap().pools.contains_key(&name) {
+        return Err(ApiError::not_found(
+            "pool_not_found",
+            format!("Printer pool '{}' not found", name),
+        ));
+    }
+    update_config(&state, |config| {
+        config.pools.remove(&name);
+    })?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Printer pool '{}' deleted", name),
+        debug_path: None,
+        diagnostics: None,
+        warnings: Vec::new(),
+        document: None,
+    }))
+}
+
+// --- 辅助函数 ---
+
+/// 将错误记录到任务状态后原样返回
+fn fail_job(jobs: &JobStore, task_id: &str, err: ApiError) -> ApiError {
+    jobs.mark_failed_with_code(task_id, err.code, err.message.clone());
+    err
+}
+
+/// 在阻塞线程池中执行渲染、编码、打印机枚举等耗时操作，
+/// 避免占用 Tokio 工作线程导致健康检查等其他请求超时
+async fn blocking<T, F>(f: F) -> Result<T, ApiError>
+where
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+}
+
+/// 确定目标打印机：指定名称时校验是否存在 (不存在返回 404，details 中列出可用打印机)，
+/// 未指定时回退到系统默认打印机
+fn resolve_printer(name: Option<&str>) -> Result<Printer, ApiError> {
+    match name {
+        Some(name) => printing::find_printer(name).map_err(|available| {
+            ApiError::not_found(
+                "printer_not_found",
+                format!(
+                    "Printer '{}' not found. Available printers: [{}]",
+                    name,
+                    available.join(", ")
+                ),
+            )
+            .with_details(serde_json::json!({ "availablePrinters": available }))
+        }),
+        None => printing::default_printer().ok_or_else(|| {
+            ApiError::not_found(
+                "printer_not_found",
+                "No printer specified and no system default printer",
+            )
+        }),
+    }
+}
+
+fn template_not_found(id: &str) -> ApiError {
+    ApiError::not_found("template_not_found", format!("Template '{}' not found", id))
+}
+
+fn sample_not_found(id: &str, name: &str) -> ApiError {
+    ApiError::not_found(
+        "sample_not_found",
+        format!("Sample '{}' of template '{}' not found", name, id),
+    )
+}
+
+/// 已注册模板保存的示例数据
+fn template_sample(store: &TemplateStore, template_id: Option<&str>, name: &str) -> Result<Value, ApiError> {
+    let id = template_id.ok_or_else(|| ApiError::bad_request("sample requires templateId"))?;
+    store.sample(id, name).ok_or_else(|| sample_not_found(id, name))
+}
+
+fn template_version_not_found(id: &str, version: u32) -> ApiError {
+    ApiError::not_found(
+        "template_version_not_found",
+        format!("Template '{}' version {} not found", id, version),
+    )
+}
+
+/// 确定打印使用的模板：内联模板优先，否则从模板仓库按 ID (及可选的版本号) 读取，
+/// 然后展开其中引用的片段 (模板内定义或已注册的模板)
+pub(crate) fn resolve_template(
+    store: &TemplateStore,
+    inline: Option<DeepPrintTemplate>,
+    template_id: Option<&str>,
+    version: Option<u32>,
+) -> Result<DeepPrintTemplate, ApiError> {
+    let template = match (inline, template_id) {
+        (Some(template), _) => template,
+        (None, Some(id)) => match version {
+            Some(version) => store
+                .get_version(id, version)
+                .map(|record| record.template)
+                .ok_or_else(|| template_version_not_found(id, version))?,
+            None => store
+                .get(id)
+                .map(|record| record.template)
+                .ok_or_else(|| template_not_found(id))?,
+        },
+        (None, None) => {
+            return Err(ApiError::bad_request("Either template or templateId is required"))
+        }
+    };
+    if !template.migration_warnings.is_empty() {
+        warn!(
+            "模板 {} 为旧版本协议，已自动迁移 ({} 处改动)",
+            template.meta.name,
+            template.migration_warnings.len()
+        );
+    }
+    Ok(store.expand_partials(template)?)
+}
+
+/// 随打印响应返回的模板提示：旧版本协议的迁移改动，以及元素落入打印机档案不可打印边距的警告
+pub(crate) fn template_warnings(template: &DeepPrintTemplate, profile: Option<&PrinterProfile>) -> Vec<MigrationWarning> {
+    let mut warnings = template.migration_warnings.clone();
+    if let Some(profile) = profile {
+        warnings.extend(
+            validator::check_printable_area(template, profile)
+                .into_iter()
+                .map(|d| MigrationWarning {
+                    path: d.path,
+                    message: d.message,
+                }),
+        );
+    }
+    warnings
+}
+
+/// 按模板的 dataSchema 校验打印数据，不符合时返回 400 invalid_data (details.violations 列出各处问题)
+pub(crate) fn check_data(template: &DeepPrintTemplate, data: &Value) -> Result<(), ApiError> {
+    match &template.data_schema {
+        Some(schema) => invalid_data(data_schema::validate(schema, data)),
+        None => Ok(()),
+    }
+}
+
+fn invalid_data(violations: Vec<SchemaViolation>) -> Result<(), ApiError> {
+    let Some(first) = violations.first() else {
+        return Ok(());
+    };
+    let location = if first.path.is_empty() {
+        String::new()
+    } else {
+        format!(" at {}", first.path)
+    };
+    let message = format!("Data does not match dataSchema{}: {}", location, first.message);
+    Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_data", message)
+        .with_details(serde_json::json!({ "violations": violations })))
+}
+
+/// 修改运行时配置并写入配置文件，写入失败时不改变运行时配置
+pub(crate) fn update_config<F: FnOnce(&mut AgentConfig)>(
+    state: &AppState,
+    f: F,
+) -> Result<(), ApiError> {
+    let mut config = state.config.write().unwrap();
+    let mut updated = config.clone();
+    f(&mut updated);
+    // 局域网监听时不允许删除最后一个 API Key 或关闭 HTTPS
+    if config.check_bind().is_ok() {
+        updated
+            .check_bind()
+            .map_err(|e| ApiError::new(StatusCode::CONFLICT, "insecure_bind", e))?;
+    }
+    updated.save().map_err(ApiError::internal)?;
+    RenderLimits::set_global(updated.limits);
+    *config = updated;
+    Ok(())
+}
+
+/// 读取上传的文件及其元数据，支持 JSON (data 字段为 base64) 与 multipart (file + metadata)
+async fn read_upload<T: DeserializeOwned>(
+    req: Request,
+) -> Result<(T, Vec<u8>), ApiError> {
+    // 超出大小限制时保留 413，其余读取错误按 400 处理
+    let bad_request =
+        |message: String| ApiError::new(StatusCode::BAD_REQUEST, "invalid_upload", message);
+    let rejected = |status: StatusCode, message: String| {
+        if status == StatusCode::PAYLOAD_TOO_LARGE {
+            ApiError::payload_too_large()
+        } else {
+            bad_request(message)
+        }
+    };
+    let is_multipart = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("multipart/form-data"));
+
+    let (metadata, data) = if is_multipart {
+        let mut multipart = Multipart::from_request(req, &())
+            .await
+            .map_err(|e| rejected(e.status(), e.body_text()))?;
+        let mut metadata = None;
+        let mut data = None;
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| rejected(e.status(), e.body_text()))?
+        {
+            match field.name() {
+                Some("file") => {
+                    let bytes = field
+                        .bytes()
+                        .await
+                        .map_err(|e| rejected(e.status(), e.body_text()))?;
+                    data = Some(bytes.to_vec());
+                }
+                Some("metadata") => {
+                    let text = field
+                        .text()
+                        .await
+                        .map_err(|e| rejected(e.status(), e.body_text()))?;
+                    metadata = Some(
+                        serde_json::from_str::<Value>(&text)
+                            .map_err(|e| bad_request(format!("Invalid metadata: {}", e)))?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        (
+            metadata.ok_or_else(|| bad_request("Missing 'metadata' field".to_string()))?,
+            data.ok_or_else(|| bad_request("Missing 'file' field".to_string()))?,
+        )
+    } else {
+        let Json(mut body) = Json::<Value>::from_request(req, &())
+            .await
+            .map_err(|e| rejected(e.status(), e.body_text()))?;
+        let encoded = body
+            .as_object_mut()
+            .and_then(|o| o.remove("data"))
+            .and_then(|v| v.as_str().map(str::to_string))
+            .ok_or_else(|| bad_request("Missing base64 'data' field".to_string()))?;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| bad_request(format!("Invalid base64 data: {}", e)))?;
+        (body, data)
+    };
+
+    let meta = serde_json::from_value(metadata).map_err(|e| bad_request(e.to_string()))?;
+    Ok((meta, data))
+}
+
+/// 请求体超限时 axum 返回纯文本的 413，统一改写为 JSON 错误体
+async fn payload_too_large_as_json(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return ApiError::payload_too_large().into_response();
+    }
+    response
+}
+
+/// 确定输出目标：已配置的直连打印机 → ipp:// / socket:// 地址 → 系统打印机
+fn resolve_destination(state: &AppState, name: Option<&str>) -> Result<Destination, ApiError> {
+    let configured = name.and_then(|n| {
+        let config = state.config.read().unwrap();
+        config.direct_printers.get(n).cloned()
+    });
+    match configured.or_else(|| name.and_then(DirectTarget::from_name)) {
+        Some(target) => Ok(Destination::Direct(target)),
+        None => resolve_printer(name).map(Destination::Spooler),
     }
 }
 
+/// 检查打印机名称可用：已配置的别名、打印机池，或可解析的打印机
+pub(crate) fn check_printer_name(state: &AppState, name: &str) -> Result<(), ApiError> {
+    {
+        let config = state.config.read().unwrap();
+        if config.aliases.contains_key(name) || config.pools.contains_key(name) {
+            return Ok(());
+        }
+    }
+    resolve_destination(state, Some(name)).map(|_| ())
+}
+
+/// 池成员是否在线 (阻塞探测)
+fn member_online(state: &AppState, printer: &str) -> bool {
+    resolve_destination(state, Some(printer)).is_ok_and(|d| printing::online(&d))
+}
+
+/// 打印机路由结果
+pub(crate) struct Route {
+    pub printer: Destination,
+    pub options: PrintOptions,
+    /// 目标打印机的档案
+    pub profile: Option<PrinterProfile>,
+    /// 分配自打印机池时的池名
+    pub pool: Option<String>,
+}
+
+/// 按路由规则确定打印机：请求指定 → 文档类型默认 → Agent 默认 → 系统默认
+/// 目标名称为逻辑别名时映射到物理打印机，并以别名的默认选项补全请求未设置的选项；
+/// 映射结果为打印机池时按轮询选择在线的成员 (探测在阻塞线程池中执行)，池的默认选项次之
+/// 最终打印机须在调用方 API Key 的授权范围内 (别名、池名或物理打印机名均可)
+/// 打印机档案按 别名 → 池名 → 显示名 → 系统名 查找，其默认选项的优先级最低
+pub(crate) async fn route_printer(
+    state: &AppState,
+    access: &Access,
+    requested: Option<&str>,
+    document_type: Option<&str>,
+    options: PrintOptions,
+) -> Result<Route, ApiError> {
+    let (target, physical, options, pool) = {
+        let config = state.config.read().unwrap();
+        let target = requested
+            .map(str::to_string)
+            .or_else(|| document_type.and_then(|t| config.printers.document_types.get(t).cloned()))
+            .or_else(|| config.printers.default_printer.clone());
+        let (physical, options) = match target.as_deref().and_then(|t| config.aliases.get(t)) {
+            Some(alias) => (Some(alias.printer.clone()), options.with_defaults(&alias.options)),
+            None => (target.clone(), options),
+        };
+        let pool = physical
+            .as_deref()
+            .and_then(|p| config.pools.get(p).map(|pool| (p.to_string(), pool.clone())));
+        (target, physical, options, pool)
+    };
+    let (physical, options) = match &pool {
+        Some((pool_name, pool)) => {
+            let options = options.with_defaults(&pool.options);
+            let (state, pool_name, pool) = (state.clone(), pool_name.clone(), pool.clone());
+            let selected = blocking(move || {
+                Ok(state
+                    .pools
+                    .select(&pool_name, &pool, |printer| member_online(&state, printer)))
+            })
+            .await?;
+            (selected, options)
+        }
+        None => (physical, options),
+    };
+    let destination = resolve_destination(state, physical.as_deref())?;
+    let (name, key) = (destination.name(), destination.key());
+    let names: Vec<&str> = target
+        .as_deref()
+        .into_iter()
+        .chain(pool.as_ref().map(|(pool_name, _)| pool_name.as_str()))
+        .chain([name.as_str(), key.as_str()])
+        .collect();
+    access.check_printer(&names)?;
+
+    let profile = {
+        let config = state.config.read().unwrap();
+        names.iter().find_map(|n| config.profiles.get(*n).cloned())
+    };
+    let options = match &profile {
+        Some(profile) => options.with_defaults(&profile.options),
+        None => options,
+    };
+    options.validate().map_err(ApiError::bad_request)?;
+    Ok(Route {
+        printer: destination,
+        options,
+        profile,
+        pool: pool.map(|(pool_name, _)| pool_name),
+    })
+}
+
+/// 任务入队；队列已满时返回 503 并将任务标记为失败
+fn enqueue_job(
+    state: &AppState,
+    job: PrintJob,
+) -> Result<(StatusCode, Json<ApiResponse>), ApiError> {
+    let task_id = job.task_id.clone();
+    let printer_name = job.printer.name();
+
+    state
+        .queue
+        .enqueue(job)
+        .map_err(|_| fail_job(&state.jobs, &task_id, ApiError::queue_full()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ApiResponse {
+            success: true,
+            message: format!("Job {} queued for {}", task_id, printer_name),
+            debug_path: None,
+            diagnostics: None,
+            warnings: Vec::new(),
+            document: None,
+        }),
+    ))
+}
+
 // --- 服务启动入口 ---
 
-pub async fn start_server() {
-    // 允许跨域 (CORS)，否则 Web 端无法调用 localhost
-    let cors = CorsLayer::permissive();
+/// 加载配置并创建共享状态 (启动打印队列，需在 tokio 运行时中调用)
+/// 桌面端 (托盘、窗口) 与 HTTP 服务共用同一份状态
+pub fn init_state() -> AppState {
+    let config = AgentConfig::load();
+    RenderLimits::set_global(config.limits);
+    let jobs = JobStore::open(&AgentConfig::database_path()).unwrap_or_else(|e| {
+        warn!("{}，任务历史将不会被持久化", e);
+        JobStore::in_memory()
+    });
+    let templates = TemplateStore::open(&AgentConfig::database_path()).unwrap_or_else(|e| {
+        warn!("{}，已注册模板将不会被持久化", e);
+        TemplateStore::in_memory()
+    });
+    let (listen, _) = watch::channel(config.server.clone());
+    let config = Arc::new(RwLock::new(config));
+    let queue = PrintQueue::start(config.clone(), jobs.clone());
+    AppState {
+        jobs,
+        queue,
+        templates,
+        config,
+        listen: Arc::new(listen),
+        bound: Arc::new(watch::channel(None).0),
+        server_failure: Arc::new(watch::channel(None).0),
+        pools: PoolBalancer::default(),
+        preview: PreviewWatcher::default(),
+        started_at: Instant::now(),
+    }
+}
+
+pub async fn start_server(state: AppState) {
+    let config = state.config.read().unwrap().clone();
+
+    // 跨域 (CORS)：仅允许白名单内的网页调用 localhost
+    let cors = cors::layer(&config.cors);
+
+    // 上传接口的请求体上限单独设置，覆盖全局上限
+    let upload_limit = DefaultBodyLimit::max(config.http.max_upload_bytes);
+    // 响应压缩：预览图 base64、任务列表等大体积 JSON 收益明显
+    let compression = CompressionLayer::new()
+        .gzip(config.http.compression)
+        .deflate(config.http.compression);
 
     let app = Router::new()
         .route("/", get(health_check))
+        .route("/health", get(health))
+        .route("/version", get(version))
         .route("/printers", get(get_printers))
+        .route("/printers/{name}/status", get(get_printer_status))
+        .route("/printers/{name}/media", get(get_printer_media))
+        .route("/usb-printers", get(get_usb_printers))
+        .route(
+            "/settings/default-printer",
+            get(get_default_printers).put(put_default_printers),
+        )
+        .route("/aliases", get(list_aliases))
+        .route("/aliases/{name}", put(put_alias).delete(delete_alias))
+        .route("/direct-printers", get(list_direct_printers))
+        .route(
+            "/direct-printers/{name}",
+            put(put_direct_printer).delete(delete_direct_printer),
+        )
+        .route("/profiles", get(list_profiles))
+        .route("/profiles/{name}", put(put_profile).delete(delete_profile))
+        .route("/pools", get(list_pools))
+        .route("/pools/{name}", put(put_pool).delete(delete_pool))
         .route("/print", post(handle_print))
-        .layer(cors);
+        .route("/print/template", post(handle_print_template))
+        .route(
+            "/print/pdf",
+            post(handle_print_pdf).layer(upload_limit.clone()),
+        )
+        .route(
+            "/print/image",
+            post(handle_print_image).layer(upload_limit.clone()),
+        )
+        .route(
+            "/print/raw",
+            post(handle_print_raw).layer(upload_limit.clone()),
+        )
+        .route("/print/batch", post(handle_print_batch))
+        .route("/print/batch/{batch_id}", get(get_batch))
+        .route("/preview", post(handle_preview))
+        .route("/validate", post(handle_validate))
+        .route("/jobs", get(list_jobs))
+        .route("/admin/audit", get(get_audit_log))
+        .route("/jobs/{task_id}", get(get_job).delete(cancel_job))
+        .route("/jobs/{task_id}/artifact", get(get_job_artifact))
+        .route("/templates", get(list_templates))
+        .route(
+            "/templates/{id}",
+            get(get_template).put(put_template).delete(delete_template),
+        )
+        .route("/templates/{id}/versions", get(list_template_versions))
+        .route("/templates/{id}/versions/{version}", get(get_template_version))
+        .route("/templates/{id}/rollback", post(rollback_template))
+        .route("/templates/{id}/preview", post(preview_template))
+        .route("/templates/{id}/samples", get(list_template_samples))
+        .route(
+            "/templates/{id}/samples/{name}",
+            put(put_template_sample).delete(delete_template_sample),
+        )
+        .route("/dev/preview", get(dev_preview_socket))
+        .route(
+            "/dev/preview/watch",
+            put(put_preview_watch).delete(delete_preview_watch),
+        )
+        .route("/admin/api-keys", get(list_api_keys))
+        .route("/admin/api-keys/{name}", put(put_api_key).delete(delete_api_key))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::require_api_key))
+        .layer(middleware::from_fn_with_state(state.clone(), allowlist::restrict_clients))
+        .layer(middleware::from_fn_with_state(state.clone(), cors::reject_foreign_origins))
+        .layer(middleware::map_response(payload_too_large_as_json))
+        .layer(DefaultBodyLimit::max(config.http.max_body_bytes))
+        .layer(compression)
+        .layer(cors)
+        .with_state(state.clone());
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 18088));
-    println!("DeepPrint Agent listening on http://{}", addr);
+    // HTTPS (可选)：与 HTTP 共用同一套路由
+    if config.tls.enabled {
+        let https_addr = SocketAddr::new(config.server.ip(), config.tls.port);
+        match tls::resolve_cert(&config.tls) {
+            Ok((cert, key)) => match RustlsConfig::from_pem_file(&cert, &key).await {
+                Ok(rustls) => {
+                    let app = app.clone();
+                    info!("DeepPrint Agent listening on https://{}", https_addr);
+                    tokio::spawn(async move {
+                        if let Err(e) = axum_server::bind_rustls(https_addr, rustls)
+                            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                            .await
+                        {
+                            error!("HTTPS 服务异常退出: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("证书加载失败 ({}): {}", cert.display(), e),
+            },
+            Err(e) => warn!("HTTPS 未启动: {}", e),
+        }
+    }
+
+    allowlist::check(&config.server.allowed_clients);
+
+    // 启动时按保留策略清理旧的打印内容副本，之后每个任务保存副本后清理
+    let output = config.output.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = retention::cleanup(&output) {
+            warn!("打印内容副本清理失败 ({}): {}", output.dir().display(), e);
+        }
+    });
 
-    // 启动服务
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // 上传上次运行留下的崩溃报告 (配置了上传地址时)
+    tokio::spawn(crash::upload_pending(config.crash_reports.clone()));
+
+    // MQTT 云端派单 (可选)
+    if config.mqtt.enabled {
+        tokio::spawn(mqtt::run(state.clone(), config.mqtt.clone()));
+    }
+
+    // 云端 WebSocket 长连接 (可选)
+    if config.cloud.enabled {
+        tokio::spawn(cloud::run(state.clone(), config.cloud.clone()));
+    }
+
+    // gRPC (可选)：与 REST 共享同一份状态
+    if config.grpc.enabled {
+        #[cfg(feature = "grpc")]
+        {
+            let grpc_state = state.clone();
+            let grpc_addr = SocketAddr::from(([127, 0, 0, 1], config.grpc.port));
+            tokio::spawn(async move {
+                if let Err(e) = crate::grpc::serve(grpc_state, grpc_addr).await {
+                    error!("gRPC 服务异常退出: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        warn!("配置启用了 gRPC，但当前版本未包含 grpc 功能，已忽略");
+    }
+
+    // HTTP 服务由监督任务运行：panic 或异常退出时通知界面并按退避间隔重启
+    let http_state = state.clone();
+    supervisor::supervise(state, move || {
+        serve_http(http_state.clone(), app.clone(), config.clone())
+    })
+    .await;
+}
+
+/// 监听 HTTP；监听地址修改后停止接受新连接，在新地址重新绑定。
+/// 监听地址通道关闭时返回 Ok，服务异常退出时返回错误
+async fn serve_http(state: AppState, app: Router, config: AgentConfig) -> Result<(), String> {
+    let mut listen = state.listen.subscribe();
+    loop {
+        let mut addr = listen.borrow_and_update().addr();
+        // 旧版配置可能在未配置 API Key/HTTPS 时监听局域网，此时只监听本机
+        if let Err(e) = state.config.read().unwrap().check_bind() {
+            error!("{}，HTTP 服务只监听本机", e);
+            addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port());
+        }
+        let listener = match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("HTTP 服务无法监听 {}: {}", addr, e);
+                state.bound.send_replace(None);
+                // 等待地址修改后重试
+                if listen.changed().await.is_err() {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        info!("DeepPrint Agent listening on http://{}", addr);
+        state.bound.send_replace(listener.local_addr().ok());
+        state.server_failure.send_if_modified(|failure| failure.take().is_some());
+
+        // 局域网服务发现 (daemon 需在服务运行期间保持存活)
+        let _mdns = if config.discovery.enabled {
+            discovery::advertise(&config, addr.port())
+                .map_err(|e| warn!("mDNS 广播失败: {}", e))
+                .ok()
+        } else {
+            None
+        };
+
+        let mut changed = listen.clone();
+        let service = app.clone().into_make_service_with_connect_info::<SocketAddr>();
+        let result = axum::serve(listener, service)
+            .with_graceful_shutdown(async move {
+                let _ = changed.changed().await;
+            })
+            .await;
+        state.bound.send_replace(None);
+        result.map_err(|e| e.to_string())?;
+        info!("监听地址已修改，HTTP 服务重新绑定");
+    }
 }
\ No newline at end of file
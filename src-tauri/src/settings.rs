@@ -0,0 +1,98 @@
+use crate::config::{ApiKey, BindMode, LogLevel, ServerConfig};
+use crate::logging;
+use crate::server::{self, AppState};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::info;
+
+/// 设置窗口使用的 API Key 名称 (管理员 Key)
+const SETTINGS_KEY_NAME: &str = "default";
+
+/// 设置窗口中的常用配置，保存后写入配置文件并立即生效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    /// HTTP 端口
+    pub port: u16,
+    pub bind: BindMode,
+    /// 管理员 API Key，为空时删除 (未配置其他 Key 时不启用鉴权)
+    pub api_key: Option<String>,
+    /// Agent 默认打印机
+    pub default_printer: Option<String>,
+    /// 调试 PDF 保存目录，为空时保存到桌面
+    pub output_dir: Option<PathBuf>,
+    pub log_level: LogLevel,
+    /// 启动时只显示托盘图标 (下次启动生效)
+    pub start_in_tray: bool,
+}
+
+/// 读取当前设置
+pub fn get(state: &AppState) -> Settings {
+    let config = state.config.read().unwrap();
+    Settings {
+        port: config.server.port,
+        bind: config.server.bind,
+        api_key: config.api_keys.get(SETTINGS_KEY_NAME).map(|k| k.key.clone()),
+        default_printer: config.printers.default_printer.clone(),
+        output_dir: config.output.dir.clone(),
+        log_level: config.log_level,
+        start_in_tray: config.ui.start_in_tray,
+    }
+}
+
+/// 校验并保存设置：日志级别、输出目录、API Key 立即生效，端口/监听范围变化时服务重新绑定；
+/// 局域网监听需同时设置 API Key (或已启用 HTTPS)
+pub fn update(state: &AppState, settings: Settings) -> Result<Settings, String> {
+    if settings.port == 0 {
+        return Err("port must be between 1 and 65535".to_string());
+    }
+    let api_key = settings.api_key.clone().filter(|k| !k.trim().is_empty());
+    let default_printer = settings.default_printer.clone().filter(|p| !p.is_empty());
+    let output_dir = settings.output_dir.clone().filter(|d| !d.as_os_str().is_empty());
+    if let Some(printer) = &default_printer {
+        server::check_printer_name(state, printer).map_err(|e| e.message)?;
+    }
+    if let Some(dir) = &output_dir {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+
+    let listen = ServerConfig {
+        port: settings.port,
+        bind: settings.bind,
+        allowed_clients: state.config.read().unwrap().server.allowed_clients.clone(),
+    };
+    server::update_config(state, |config| {
+        config.server = listen.clone();
+        config.log_level = settings.log_level;
+        config.ui.start_in_tray = settings.start_in_tray;
+        config.printers.default_printer = default_printer;
+        config.output.dir = output_dir;
+        match &api_key {
+            Some(key) => {
+                config.api_keys.insert(
+                    SETTINGS_KEY_NAME.to_string(),
+                    ApiKey {
+                        key: key.trim().to_string(),
+                        printers: Vec::new(),
+                        endpoints: Vec::new(),
+                        admin: true,
+                    },
+                );
+            }
+            None => {
+                config.api_keys.remove(SETTINGS_KEY_NAME);
+            }
+        }
+    })
+    .map_err(|e| e.message)?;
+
+    logging::set_level(settings.log_level);
+    state.listen.send_if_modified(|current| {
+        let modified = *current != listen;
+        *current = listen;
+        modified
+    });
+    info!("设置已更新");
+    Ok(get(state))
+}
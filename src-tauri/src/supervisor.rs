@@ -0,0 +1,69 @@
+use crate::server::AppState;
+use serde::Serialize;
+use std::any::Any;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// 重启间隔：从 1 秒开始每次翻倍，最长 60 秒
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// 连续运行超过该时长后再退出，重启间隔从头计算
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// HTTP 服务异常退出的信息，以 server:failed 事件推送给前端，并显示在托盘提示中
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerFailure {
+    /// 退出原因 (错误信息或 panic 内容)
+    pub message: String,
+    /// 此前已重启的次数
+    pub restarts: u32,
+    /// 距下次重启的秒数
+    pub retry_in_secs: u64,
+}
+
+/// 在独立任务中运行 HTTP 服务，panic 或返回错误时记录原因、通知界面，按退避间隔重启；
+/// run 正常返回 (监听地址通道关闭) 时结束监督
+pub async fn supervise<F, Fut>(state: AppState, mut run: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let mut restarts = 0;
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let started = Instant::now();
+        let message = match tokio::spawn(run()).await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e,
+            Err(e) if e.is_panic() => panic_message(e.into_panic()),
+            // 运行时关闭，任务被取消
+            Err(_) => return,
+        };
+        state.bound.send_replace(None);
+        if started.elapsed() >= STABLE_AFTER {
+            backoff = INITIAL_BACKOFF;
+        }
+        error!("HTTP 服务异常退出，{} 秒后重启: {}", backoff.as_secs(), message);
+        state.server_failure.send_replace(Some(ServerFailure {
+            message,
+            restarts,
+            retry_in_secs: backoff.as_secs(),
+        }));
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        restarts += 1;
+        info!("重启 HTTP 服务 (第 {} 次)", restarts);
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    format!("panicked: {}", message)
+}
@@ -0,0 +1,375 @@
+use crate::deep_print_schema::{DeepPrintTemplate, Partial};
+use crate::jobs::now_millis;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use deepprint_core::{partials, RenderError};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+/// templates 保存每个模板的当前版本；template_versions 保存全部历史版本；
+/// template_samples 保存模板的具名示例数据 (不随版本变化)
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS templates (
+    id         TEXT PRIMARY KEY,
+    name       TEXT NOT NULL,
+    version    INTEGER NOT NULL DEFAULT 1,
+    template   TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS template_versions (
+    id         TEXT NOT NULL,
+    version    INTEGER NOT NULL,
+    name       TEXT NOT NULL,
+    template   TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (id, version)
+);
+CREATE TABLE IF NOT EXISTS template_samples (
+    id         TEXT NOT NULL,
+    name       TEXT NOT NULL,
+    data       TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (id, name)
+);
+";
+
+/// 已注册的模板
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateRecord {
+    pub id: String,
+    /// 模板名称 (meta.name)
+    pub name: String,
+    /// 当前生效的版本号
+    pub version: u32,
+    pub template: DeepPrintTemplate,
+    /// Unix 时间戳 (毫秒)
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// 模板列表项 (不含模板正文)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSummary {
+    pub id: String,
+    pub name: String,
+    pub version: u32,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// 模板的一个历史版本
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVersion {
+    pub version: u32,
+    pub name: String,
+    /// 保存时间 (毫秒)
+    pub created_at: u64,
+    /// 是否为当前生效的版本
+    pub current: bool,
+}
+
+const COLUMNS: &str = "id, name, version, template, created_at, updated_at";
+
+fn record_from_row(row: &Row) -> rusqlite::Result<TemplateRecord> {
+    let text: String = row.get(3)?;
+    let template = serde_json::from_str(&text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(TemplateRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        version: row.get(2)?,
+        template,
+        created_at: row.get::<_, i64>(4)? as u64,
+        updated_at: row.get::<_, i64>(5)? as u64,
+    })
+}
+
+/// 模板仓库：业务系统预先上传模板，打印时只需传 templateId + data
+/// 每次保存都会生成新版本，可以按版本打印或回滚到历史版本
+#[derive(Clone)]
+pub struct TemplateStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl TemplateStore {
+    /// 打开 (或创建) 模板数据库
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Create data dir error: {}", e))?;
+        }
+        let conn =
+            Connection::open(path).map_err(|e| format!("Open template database error: {}", e))?;
+        Self::init(conn)
+    }
+
+    /// 内存数据库 (数据库文件无法打开时的兜底)
+    pub fn in_memory() -> Self {
+        let conn = Connection::open_in_memory().expect("Failed to open in-memory database");
+        Self::init(conn).expect("Failed to initialize in-memory database")
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("Initialize template database error: {}", e))?;
+        Self::migrate(&conn).map_err(|e| format!("Migrate template database error: {}", e))?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// 旧版数据库的 templates 表没有 version 列：补上该列，并把现有模板登记为版本 1
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        let has_version = conn
+            .prepare("SELECT 1 FROM pragma_table_info('templates') WHERE name = 'version'")?
+            .exists([])?;
+        if !has_version {
+            conn.execute_batch(
+                "ALTER TABLE templates ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+                 INSERT OR IGNORE INTO template_versions (id, version, name, template, created_at)
+                    SELECT id, 1, name, template, updated_at FROM templates;",
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 保存模板为新版本并设为当前版本
+    pub fn put(&self, id: &str, template: DeepPrintTemplate) -> Result<TemplateRecord, String> {
+        let text = serde_json::to_string(&template).map_err(|e| e.to_string())?;
+        let now = now_millis() as i64;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Save template error: {}", e))?;
+        let version: u32 = tx
+            .query_row(
+                "SELECT COALESCE(MAX(version), 0) + 1 FROM template_versions WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Save template error: {}", e))?;
+        tx.execute(
+            "INSERT INTO template_versions (id, version, name, template, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, version, template.meta.name, text, now],
+        )
+        .and_then(|_| {
+            tx.execute(
+                "INSERT INTO templates (id, name, version, template, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+                 ON CONFLICT (id) DO UPDATE SET
+                    name = excluded.name, version = excluded.version,
+                    template = excluded.template, updated_at = excluded.updated_at",
+                params![id, template.meta.name, version, text, now],
+            )
+        })
+        .map_err(|e| format!("Save template error: {}", e))?;
+        let record = Self::get_locked(&tx, id);
+        tx.commit().map_err(|e| format!("Save template error: {}", e))?;
+
+        record.ok_or_else(|| format!("Template '{}' disappeared after save", id))
+    }
+
+    /// 当前版本
+    pub fn get(&self, id: &str) -> Option<TemplateRecord> {
+        let conn = self.conn.lock().unwrap();
+        Self::get_locked(&conn, id)
+    }
+
+    fn get_locked(conn: &Connection, id: &str) -> Option<TemplateRecord> {
+        conn.query_row(
+            &format!("SELECT {} FROM templates WHERE id = ?1", COLUMNS),
+            params![id],
+            record_from_row,
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            error!("模板读取失败 ({}): {}", id, e);
+            None
+        })
+    }
+
+    /// 展开模板中的 include 元素：模板自身未定义的片段按 ID 取已注册模板的当前版本，
+    /// 以其画布元素作为片段内容，多个门店模板可以共用同一个页眉/页脚模板
+    pub fn expand_partials(&self, template: DeepPrintTemplate) -> Result<DeepPrintTemplate, RenderError> {
+        let resolve = |id: &str| {
+            self.get(id).map(|record| Partial {
+                params: HashMap::new(),
+                elements: record.template.canvas.elements,
+            })
+        };
+        Ok(partials::expand(&template, &resolve)?.unwrap_or(template))
+    }
+
+    /// 指定版本 (created_at/updated_at 均为该版本的保存时间)
+    pub fn get_version(&self, id: &str, version: u32) -> Option<TemplateRecord> {
+        let conn = self.conn.lock().unwrap();
+        Self::get_version_locked(&conn, id, version)
+    }
+
+    fn get_version_locked(conn: &Connection, id: &str, version: u32) -> Option<TemplateRecord> {
+        conn.query_row(
+            "SELECT id, name, version, template, created_at, created_at
+             FROM template_versions WHERE id = ?1 AND version = ?2",
+            params![id, version],
+            record_from_row,
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            error!("模板版本读取失败 ({} v{}): {}", id, version, e);
+            None
+        })
+    }
+
+    /// 全部历史版本，按版本号倒序；模板不存在时返回 None
+    pub fn versions(&self, id: &str) -> Option<Vec<TemplateVersion>> {
+        let conn = self.conn.lock().unwrap();
+        let current = Self::get_locked(&conn, id)?.version;
+        let versions = conn
+            .prepare(
+                "SELECT version, name, created_at FROM template_versions
+                 WHERE id = ?1 ORDER BY version DESC",
+            )
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map(params![id], |row| {
+                        let version: u32 = row.get(0)?;
+                        Ok(TemplateVersion {
+                            version,
+                            name: row.get(1)?,
+                            created_at: row.get::<_, i64>(2)? as u64,
+                            current: version == current,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("模板版本查询失败 ({}): {}", id, e);
+                Vec::new()
+            });
+        Some(versions)
+    }
+
+    /// 回滚：把指定历史版本设为当前版本 (不产生新版本，之后仍可再切回)
+    /// 模板或版本不存在时返回 Ok(None)
+    pub fn rollback(&self, id: &str, version: u32) -> Result<Option<TemplateRecord>, String> {
+        let conn = self.conn.lock().unwrap();
+        if Self::get_version_locked(&conn, id, version).is_none() {
+            return Ok(None);
+        }
+        conn.execute(
+            "UPDATE templates SET
+                (name, version, template) =
+                    (SELECT name, version, template FROM template_versions
+                     WHERE id = ?1 AND version = ?2),
+                updated_at = ?3
+             WHERE id = ?1",
+            params![id, version, now_millis() as i64],
+        )
+        .map_err(|e| format!("Rollback template error: {}", e))?;
+        Ok(Self::get_locked(&conn, id))
+    }
+
+    /// 删除模板及其全部历史版本与示例数据，返回是否存在
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM template_samples WHERE id = ?1", params![id])
+            .and_then(|_| conn.execute("DELETE FROM template_versions WHERE id = ?1", params![id]))
+            .and_then(|_| conn.execute("DELETE FROM templates WHERE id = ?1", params![id]))
+            .map(|n| n > 0)
+            .map_err(|e| format!("Delete template error: {}", e))
+    }
+
+    /// 模板的全部示例数据 (名称 -> 数据)
+    pub fn samples(&self, id: &str) -> HashMap<String, Value> {
+        let conn = self.conn.lock().unwrap();
+        conn.prepare("SELECT name, data FROM template_samples WHERE id = ?1")
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map(params![id], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("示例数据查询失败 ({}): {}", id, e);
+                Vec::new()
+            })
+            .into_iter()
+            .filter_map(|(name, data)| Some((name, serde_json::from_str(&data).ok()?)))
+            .collect()
+    }
+
+    /// 指定名称的示例数据
+    pub fn sample(&self, id: &str, name: &str) -> Option<Value> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT data FROM template_samples WHERE id = ?1 AND name = ?2",
+            params![id, name],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            error!("示例数据读取失败 ({}/{}): {}", id, name, e);
+            None
+        })
+        .and_then(|data| serde_json::from_str(&data).ok())
+    }
+
+    /// 保存 (覆盖) 示例数据
+    pub fn put_sample(&self, id: &str, name: &str, data: &Value) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO template_samples (id, name, data, updated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, name, data.to_string(), now_millis() as i64],
+        )
+        .map(|_| ())
+        .map_err(|e| format!("Save sample data error: {}", e))
+    }
+
+    /// 删除示例数据，返回是否存在
+    pub fn delete_sample(&self, id: &str, name: &str) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM template_samples WHERE id = ?1 AND name = ?2",
+            params![id, name],
+        )
+        .map(|n| n > 0)
+        .map_err(|e| format!("Delete sample data error: {}", e))
+    }
+
+    /// 全部模板，按 id 排序
+    pub fn list(&self) -> Vec<TemplateSummary> {
+        let conn = self.conn.lock().unwrap();
+        conn.prepare("SELECT id, name, version, created_at, updated_at FROM templates ORDER BY id")
+            .and_then(|mut stmt| {
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok(TemplateSummary {
+                            id: row.get(0)?,
+                            name: row.get(1)?,
+                            version: row.get(2)?,
+                            created_at: row.get::<_, i64>(3)? as u64,
+                            updated_at: row.get::<_, i64>(4)? as u64,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                rows
+            })
+            .unwrap_or_else(|e| {
+                error!("模板列表查询失败: {}", e);
+                Vec::new()
+            })
+    }
+}
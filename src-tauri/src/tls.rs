@@ -0,0 +1,35 @@
+use crate::config::{AgentConfig, TlsConfig};
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+/// 确定 HTTPS 使用的证书与私钥 (PEM 文件路径)
+/// 用户在配置中指定了 certPath/keyPath 时直接使用；
+/// 否则使用 {data_dir}/tls 下的自签名证书，首次运行时自动生成
+pub fn resolve_cert(config: &TlsConfig) -> Result<(PathBuf, PathBuf), String> {
+    if let (Some(cert), Some(key)) = (&config.cert_path, &config.key_path) {
+        return Ok((cert.clone(), key.clone()));
+    }
+
+    let dir = AgentConfig::data_dir().join("tls");
+    let cert_path = dir.join("localhost.crt");
+    let key_path = dir.join("localhost.key");
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    info!("生成自签名证书: {}", cert_path.display());
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ])
+    .map_err(|e| format!("Certificate generation error: {}", e))?;
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    fs::write(&cert_path, cert.pem()).map_err(|e| format!("Cannot write certificate: {}", e))?;
+    fs::write(&key_path, key_pair.serialize_pem())
+        .map_err(|e| format!("Cannot write private key: {}", e))?;
+
+    Ok((cert_path, key_path))
+}
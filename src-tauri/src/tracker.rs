@@ -0,0 +1,124 @@
+use crate::jobs::{JobStatus, JobStore};
+use crate::printing::{self, Destination, SpoolerJobState};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// 单个作业的最长跟踪时间，超时后保持当前状态不再查询 (如打印机长期离线)
+const MAX_TRACKING: Duration = Duration::from_secs(24 * 60 * 60);
+/// 连续查询失败达到该次数后放弃跟踪
+const MAX_ERRORS: u32 = 30;
+
+/// 正在跟踪的系统作业
+struct Tracked {
+    destination: Destination,
+    job_id: u64,
+    since: Instant,
+    printing: bool,
+    errors: u32,
+}
+
+/// 系统打印队列作业跟踪：任务提交到系统队列 (或 IPP 打印机) 后定时查询作业状态，
+/// 将 printing / printed / failed 回写到任务记录，使 printed 表示纸张已实际打出
+#[derive(Clone)]
+pub struct JobTracker {
+    tracked: Arc<Mutex<HashMap<String, Tracked>>>,
+    enabled: bool,
+}
+
+impl JobTracker {
+    /// 启动后台轮询 (需在 tokio 运行时中调用)；interval 为 0 时不跟踪，任务停留在 spooled
+    pub fn start(interval: Duration, jobs: JobStore) -> Self {
+        let tracker = Self {
+            tracked: Arc::new(Mutex::new(HashMap::new())),
+            enabled: !interval.is_zero(),
+        };
+        if tracker.enabled {
+            let poller = tracker.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    let poller = poller.clone();
+                    let jobs = jobs.clone();
+                    // 查询系统队列为阻塞操作
+                    let _ = tokio::task::spawn_blocking(move || poller.poll(&jobs)).await;
+                }
+            });
+        }
+        tracker
+    }
+
+    /// 开始跟踪已提交的作业
+    pub fn track(&self, task_id: &str, destination: Destination, job_id: u64) {
+        if !self.enabled {
+            return;
+        }
+        self.tracked.lock().unwrap().insert(
+            task_id.to_string(),
+            Tracked {
+                destination,
+                job_id,
+                since: Instant::now(),
+                printing: false,
+                errors: 0,
+            },
+        );
+    }
+
+    /// 查询所有跟踪中的作业并更新任务状态，作业结束后停止跟踪
+    fn poll(&self, jobs: &JobStore) {
+        let snapshot: Vec<(String, Destination, u64)> = self
+            .tracked
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(task_id, t)| (task_id.clone(), t.destination.clone(), t.job_id))
+            .collect();
+
+        for (task_id, destination, job_id) in snapshot {
+            let state = printing::job_state(&destination, job_id);
+
+            let mut tracked = self.tracked.lock().unwrap();
+            let Some(entry) = tracked.get_mut(&task_id) else {
+                continue;
+            };
+            if state.is_ok() {
+                entry.errors = 0;
+            }
+            let finished = match state {
+                Ok(SpoolerJobState::Pending) => false,
+                Ok(SpoolerJobState::Printing) => {
+                    if !entry.printing {
+                        entry.printing = true;
+                        jobs.set_status(&task_id, JobStatus::Printing);
+                    }
+                    false
+                }
+                Ok(SpoolerJobState::Completed | SpoolerJobState::Gone) => {
+                    jobs.set_status(&task_id, JobStatus::Printed);
+                    true
+                }
+                Ok(SpoolerJobState::Cancelled) => {
+                    jobs.mark_failed(&task_id, "Job was cancelled in the print queue".to_string());
+                    true
+                }
+                Ok(SpoolerJobState::Aborted(reason)) => {
+                    jobs.mark_failed(&task_id, format!("Printer aborted the job: {}", reason));
+                    true
+                }
+                Err(e) => {
+                    entry.errors += 1;
+                    if entry.errors == MAX_ERRORS {
+                        warn!("任务 {} 的系统作业状态查询失败，停止跟踪: {}", task_id, e);
+                    }
+                    entry.errors >= MAX_ERRORS
+                }
+            };
+            if finished || entry.since.elapsed() > MAX_TRACKING {
+                tracked.remove(&task_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::server::AppState;
+use crate::supervisor::ServerFailure;
+use crate::updater::{self, PendingUpdate};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Runtime};
+use tracing::warn;
+
+const PAUSE_TEXT: &str = "暂停打印";
+const RESUME_TEXT: &str = "恢复打印";
+const CHECK_UPDATE_TEXT: &str = "检查更新";
+
+/// 托盘中的更新菜单项，发现新版本后显示为"安装更新"
+struct UpdateMenuItem<R: Runtime>(MenuItem<R>);
+
+/// 创建系统托盘：Agent 作为后台工具常驻托盘，关闭窗口不会退出
+/// 菜单：打开窗口、暂停/恢复打印队列、重打上一单、设置、检查/安装更新、退出
+pub fn create<R: Runtime>(app: &AppHandle<R>, state: AppState) -> tauri::Result<()> {
+    let open = MenuItem::with_id(app, "open", "打开窗口", true, None::<&str>)?;
+    let pause = MenuItem::with_id(app, "pause", PAUSE_TEXT, true, None::<&str>)?;
+    let reprint = MenuItem::with_id(app, "reprint", "重打上一单", true, None::<&str>)?;
+    let settings = MenuItem::with_id(app, "settings", "设置...", true, None::<&str>)?;
+    let update = MenuItem::with_id(app, "update", CHECK_UPDATE_TEXT, true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let menu = Menu::with_items(
+        app,
+        &[&open, &pause, &reprint, &settings, &update, &separator, &quit],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("DeepPrint Agent")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(move |app, event| match event.id().as_ref() {
+            "open" => show_main_window(app),
+            "pause" => {
+                let text = if state.queue.is_paused() {
+                    state.queue.resume();
+                    PAUSE_TEXT
+                } else {
+                    state.queue.pause();
+                    RESUME_TEXT
+                };
+                let _ = pause.set_text(text);
+            }
+            "reprint" => {
+                if let Err(e) = state.queue.reprint_last() {
+                    warn!("重打失败: {}", e);
+                }
+            }
+            "settings" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = crate::commands::open_settings(app).await {
+                        warn!("设置窗口打开失败: {}", e);
+                    }
+                });
+            }
+            "update" => {
+                let app = app.clone();
+                let config = state.config.read().unwrap().updater.clone();
+                tauri::async_runtime::spawn(async move {
+                    let pending = app
+                        .try_state::<PendingUpdate>()
+                        .is_some_and(|pending| pending.is_some());
+                    let result = if pending {
+                        updater::install(&app).await
+                    } else {
+                        updater::check(&app, &config).await.map(|_| ())
+                    };
+                    if let Err(e) = result {
+                        warn!("更新失败: {}", e);
+                    }
+                });
+            }
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        // 左键单击托盘图标打开窗口，右键弹出菜单
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    app.manage(UpdateMenuItem(update));
+    Ok(())
+}
+
+/// 发现新版本后在托盘菜单和提示中显示
+pub fn show_update<R: Runtime>(app: &AppHandle<R>, version: &str) {
+    if let Some(item) = app.try_state::<UpdateMenuItem<R>>() {
+        let _ = item.0.set_text(format!("安装更新 v{}", version));
+    }
+    if let Some(tray) = app.tray_by_id("main") {
+        let _ = tray.set_tooltip(Some(format!("DeepPrint Agent (新版本 v{} 可用)", version)));
+    }
+}
+
+/// HTTP 服务异常退出时在托盘提示中显示原因，恢复后还原
+pub fn show_server_failure<R: Runtime>(app: &AppHandle<R>, failure: Option<&ServerFailure>) {
+    let Some(tray) = app.tray_by_id("main") else {
+        return;
+    };
+    let tooltip = match failure {
+        Some(failure) => format!(
+            "DeepPrint Agent (HTTP 服务异常，{} 秒后重启: {})",
+            failure.retry_in_secs, failure.message
+        ),
+        None => "DeepPrint Agent".to_string(),
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+/// 显示并聚焦主窗口 (窗口关闭时只是隐藏)
+pub fn show_main_window<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
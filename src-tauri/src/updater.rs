@@ -0,0 +1,151 @@
+use crate::config::UpdaterConfig;
+use crate::server::AppState;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::{info, warn};
+
+/// 发现新版本，载荷为 UpdateInfo
+pub const UPDATE_AVAILABLE: &str = "update:available";
+/// 开始下载安装，载荷为新版本号
+pub const UPDATE_INSTALLING: &str = "update:installing";
+/// 检查或安装失败，载荷为错误信息
+pub const UPDATE_ERROR: &str = "update:error";
+
+/// 安装前等待执行中的打印任务结束的最长时间
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 已发现、尚未安装的更新
+#[derive(Default)]
+pub struct PendingUpdate(Mutex<Option<Update>>);
+
+impl PendingUpdate {
+    pub fn is_some(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateInfo {
+    current_version: String,
+    version: String,
+    /// 更新说明
+    notes: Option<String>,
+}
+
+/// 按配置定期从发布渠道检查更新，门店机器无需逐台手动升级
+pub fn start<R: Runtime>(app: &AppHandle<R>, config: UpdaterConfig) {
+    app.manage(PendingUpdate::default());
+    if !config.enabled {
+        info!("自动更新已关闭");
+        return;
+    }
+    if !pubkey_configured(app) {
+        warn!("未配置更新签名公钥 (tauri.conf.json plugins.updater.pubkey)，不检查更新");
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let interval = Duration::from_secs(config.check_interval_hours.max(1) * 3600);
+        loop {
+            if let Err(e) = check(&app, &config).await {
+                warn!("检查更新失败: {}", e);
+                let _ = app.emit(UPDATE_ERROR, &e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// 检查更新；发现新版本时通知界面和托盘，auto_install 时直接安装。返回是否有新版本
+pub async fn check<R: Runtime>(app: &AppHandle<R>, config: &UpdaterConfig) -> Result<bool, String> {
+    if !pubkey_configured(app) {
+        return Err("Update signing key (pubkey) is not configured".to_string());
+    }
+    let mut builder = app.updater_builder();
+    if !config.endpoints.is_empty() {
+        let endpoints = config
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.parse::<Url>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+        builder = builder.endpoints(endpoints).map_err(|e| e.to_string())?;
+    }
+    let update = builder
+        .build()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(update) = update else {
+        info!("当前已是最新版本");
+        return Ok(false);
+    };
+
+    info!("发现新版本 {} (当前 {})", update.version, update.current_version);
+    let _ = app.emit(
+        UPDATE_AVAILABLE,
+        UpdateInfo {
+            current_version: update.current_version.clone(),
+            version: update.version.clone(),
+            notes: update.body.clone(),
+        },
+    );
+    crate::tray::show_update(app, &update.version);
+    if let Some(pending) = app.try_state::<PendingUpdate>() {
+        *pending.0.lock().unwrap() = Some(update);
+    }
+    if config.auto_install {
+        install(app).await?;
+    }
+    Ok(true)
+}
+
+/// 下载并安装已发现的更新，完成后重启 Agent
+/// 安装前暂停出单并等待执行中的任务结束 (Windows 上安装程序会直接结束当前进程)，
+/// 排队中的任务重启后继续执行；安装失败时恢复出单
+pub async fn install<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let update = app
+        .try_state::<PendingUpdate>()
+        .and_then(|pending| pending.0.lock().unwrap().take())
+        .ok_or("No update available")?;
+    info!("下载更新 {}", update.version);
+    let _ = app.emit(UPDATE_INSTALLING, &update.version);
+    let bytes = update
+        .download(|_, _| {}, || {})
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let queue = app.try_state::<AppState>().map(|state| state.queue.clone());
+    // 原本已手动暂停的队列在安装失败后保持暂停
+    let was_paused = queue.as_ref().is_some_and(|queue| queue.is_paused());
+    if let Some(queue) = &queue {
+        info!("暂停出单，等待执行中的任务结束");
+        if !queue.drain(DRAIN_TIMEOUT).await {
+            warn!("等待 {} 秒后仍有任务在执行，继续安装更新", DRAIN_TIMEOUT.as_secs());
+        }
+    }
+    if let Err(e) = update.install(bytes) {
+        if let Some(queue) = queue.filter(|_| !was_paused) {
+            queue.resume();
+        }
+        return Err(e.to_string());
+    }
+    info!("更新 {} 已安装，重启 Agent", update.version);
+    app.restart()
+}
+
+/// tauri.conf.json 中是否配置了更新签名公钥；未配置时无法校验更新包
+fn pubkey_configured<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .is_some_and(|pubkey| !pubkey.trim().is_empty())
+}
@@ -0,0 +1,401 @@
+use crate::config::PrinterProfile;
+use crate::deep_print_schema::*;
+use deepprint_core::assets::{ASSET_PREFIX, HASH_PREFIX};
+use deepprint_core::{migration, strict, TemplateError};
+use crate::renderer::try_parse_color;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// 诊断级别
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 单条诊断信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 机器可读的诊断代码，如 "duplicate_id"
+    pub code: &'static str,
+    pub message: String,
+    /// 相关元素 ID (如有)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub element_id: Option<String>,
+    /// 出错位置的 JSON 路径，如 "canvas.elements[2].fontColor"
+    pub path: String,
+}
+
+/// 校验报告
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    /// 不存在 Error 级别诊断时为 true
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        let valid = !diagnostics.iter().any(|d| d.severity == Severity::Error);
+        Self { valid, diagnostics }
+    }
+}
+
+/// 校验原始模板 JSON
+/// 旧版本模板的迁移改动以 legacy_template 警告给出
+/// 依次检查：能否解析、未知字段、dataSchema、重复 ID、linkedTo 目标缺失、循环依赖、资源池引用、颜色格式、高度约束
+/// strict 为 true 时未知字段按错误处理 (deny_unknown_fields 语义)；
+/// 指定打印机档案时额外检查元素是否落入该设备的不可打印边距
+pub fn validate(raw: &Value, strict: bool, profile: Option<&PrinterProfile>) -> ValidationReport {
+    // 旧版本模板先迁移，之后的检查都针对迁移后的 JSON
+    let mut migrated = raw.clone();
+    let mut diagnostics: Vec<Diagnostic> = migration::migrate(&mut migrated)
+        .into_iter()
+        .map(|w| Diagnostic {
+            severity: Severity::Warning,
+            code: "legacy_template",
+            message: w.message,
+            element_id: None,
+            path: w.path,
+        })
+        .collect();
+    let raw = &migrated;
+
+    let template = match deepprint_core::parse_template_value(raw.clone()) {
+        Ok(t) => t,
+        Err(e) => {
+            let (path, message) = match e {
+                TemplateError::Parse { path, message } => (path, message),
+                e => (String::new(), e.to_string()),
+            };
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "parse_error",
+                message,
+                element_id: None,
+                path,
+            });
+            return ValidationReport::new(diagnostics);
+        }
+    };
+
+    check_unknown_fields(raw, strict, &mut diagnostics);
+    check_data_schema(raw, &template, &mut diagnostics);
+    check_elements(&template, &mut diagnostics);
+    if let Some(profile) = profile {
+        diagnostics.extend(check_printable_area(&template, profile));
+    }
+    ValidationReport::new(diagnostics)
+}
+
+/// 未知字段 (默认解析会忽略它们)：严格模式下为 Error，否则为 Warning
+fn check_unknown_fields(raw: &Value, strict: bool, out: &mut Vec<Diagnostic>) {
+    let severity = if strict { Severity::Error } else { Severity::Warning };
+    for unknown in strict::unknown_fields(raw) {
+        out.push(Diagnostic {
+            severity,
+            code: "unknown_field",
+            message: unknown.message(),
+            element_id: None,
+            path: unknown.path,
+        });
+    }
+}
+
+/// dataSchema：旧版字符串形式给出提示，解析后必须是 JSON Schema 对象
+fn check_data_schema(raw: &Value, template: &DeepPrintTemplate, out: &mut Vec<Diagnostic>) {
+    if raw.get("dataSchema").is_some_and(Value::is_string) {
+        out.push(Diagnostic {
+            severity: Severity::Warning,
+            code: "legacy_data_schema",
+            message: "dataSchema as a string is deprecated, use a JSON Schema object".to_string(),
+            element_id: None,
+            path: "dataSchema".to_string(),
+        });
+    }
+    if template.data_schema.as_ref().is_some_and(|schema| !schema.is_object()) {
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            code: "invalid_data_schema",
+            message: "dataSchema must be a JSON Schema object".to_string(),
+            element_id: None,
+            path: "dataSchema".to_string(),
+        });
+    }
+}
+
+fn check_elements(template: &DeepPrintTemplate, out: &mut Vec<Diagnostic>) {
+    let elements = &template.canvas.elements;
+
+    if let Some(styles) = &template.canvas.styles {
+        check_color(styles.font_color.as_deref(), "canvas.styles.fontColor", None, out);
+    }
+
+    // 画布与各分页的元素分别检查 (ID 与 linkedTo 在各自的元素数组内解析)
+    check_element_list(elements, "canvas.elements", template, out);
+    for (i, page) in template.canvas.pages.iter().flatten().enumerate() {
+        check_element_list(&page.elements, &format!("canvas.pages[{}].elements", i), template, out);
+    }
+
+    // 资源池中的 "sha256:..." 引用宿主注册的资源，Agent 没有资源注册接口，渲染时必然找不到
+    let mut hashed: Vec<_> = template
+        .assets
+        .iter()
+        .flatten()
+        .filter(|(_, value)| value.starts_with(HASH_PREFIX))
+        .collect();
+    hashed.sort_by_key(|(name, _)| name.as_str());
+    for (name, value) in hashed {
+        out.push(Diagnostic {
+            severity: Severity::Error,
+            code: "unregistered_asset",
+            message: format!(
+                "Asset '{}' references {}, but the agent has no asset registry; embed the image as base64 or a URL",
+                name, value
+            ),
+            element_id: None,
+            path: format!("assets.{}", name),
+        });
+    }
+
+    // 颜色格式 (含分页、片段与条件分支中的元素)
+    check_element_colors(elements, "canvas.elements", out);
+    for (i, page) in template.canvas.pages.iter().flatten().enumerate() {
+        check_element_colors(&page.elements, &format!("canvas.pages[{}].elements", i), out);
+    }
+    let mut partials: Vec<_> = template.partials.iter().flatten().collect();
+    partials.sort_by_key(|(name, _)| name.as_str());
+    for (name, partial) in partials {
+        check_element_colors(&partial.elements, &format!("partials.{}.elements", name), out);
+    }
+}
+
+/// 一个元素数组内的检查：重复 ID、linkedTo 目标缺失、循环依赖、资源池引用、高度约束；
+/// array_path 为元素数组的 JSON 路径
+fn check_element_list(
+    elements: &[Element],
+    array_path: &str,
+    template: &DeepPrintTemplate,
+    out: &mut Vec<Diagnostic>,
+) {
+    // 重复 ID
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (i, elem) in elements.iter().enumerate() {
+        if let Some(first) = seen.insert(elem.id.as_str(), i) {
+            out.push(Diagnostic {
+                severity: Severity::Error,
+                code: "duplicate_id",
+                message: format!(
+                    "Duplicate element id '{}' (first defined at {}[{}])",
+                    elem.id, array_path, first
+                ),
+                element_id: Some(elem.id.clone()),
+                path: format!("{}[{}].id", array_path, i),
+            });
+            // 保留首次出现的位置
+            seen.insert(elem.id.as_str(), first);
+        }
+    }
+
+    // linkedTo 目标缺失
+    for (i, elem) in elements.iter().enumerate() {
+        if let Some(target) = &elem.linked_to {
+            if !seen.contains_key(target.as_str()) {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "missing_link_target",
+                    message: format!("linkedTo target '{}' does not exist", target),
+                    element_id: Some(elem.id.clone()),
+                    path: format!("{}[{}].linkedTo", array_path, i),
+                });
+            }
+        }
+    }
+
+    // 循环依赖：沿 linkedTo 链前进，若回到自身则该元素处于环中
+    let links: HashMap<&str, &str> = elements
+        .iter()
+        .filter_map(|e| e.linked_to.as_deref().map(|t| (e.id.as_str(), t)))
+        .collect();
+    for (i, elem) in elements.iter().enumerate() {
+        let mut visited = HashSet::new();
+        let mut current = elem.id.as_str();
+        while let Some(&next) = links.get(current) {
+            if next == elem.id {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "circular_dependency",
+                    message: format!("Element '{}' is part of a linkedTo cycle", elem.id),
+                    element_id: Some(elem.id.clone()),
+                    path: format!("{}[{}].linkedTo", array_path, i),
+                });
+                break;
+            }
+            if !visited.insert(next) {
+                break;
+            }
+            current = next;
+        }
+    }
+
+    // 资源池引用 (src 与 fallback)
+    for (i, elem) in elements.iter().enumerate() {
+        let ElementData::Image(p) = &elem.data else {
+            continue;
+        };
+        if let Some(fallback) = p.fallback.as_deref() {
+            if !matches!(fallback, "placeholder" | "skip") && !fallback.starts_with(ASSET_PREFIX) {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "invalid_fallback",
+                    message: format!(
+                        "Image fallback '{}' must be \"placeholder\", \"skip\" or \"asset:<name>\"",
+                        fallback
+                    ),
+                    element_id: Some(elem.id.clone()),
+                    path: format!("{}[{}].fallback", array_path, i),
+                });
+            }
+        }
+        for (field, value) in [("src", Some(p.src.as_str())), ("fallback", p.fallback.as_deref())] {
+            let Some(name) = value.and_then(|v| v.strip_prefix(ASSET_PREFIX)) else {
+                continue;
+            };
+            if !template.assets.as_ref().is_some_and(|a| a.contains_key(name)) {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "unknown_asset",
+                    message: format!("Asset '{}' is not defined in assets", name),
+                    element_id: Some(elem.id.clone()),
+                    path: format!("{}[{}].{}", array_path, i, field),
+                });
+            }
+        }
+    }
+
+    // 高度约束
+    for (i, elem) in elements.iter().enumerate() {
+        if let (Some(min), Some(max)) = (elem.min_height, elem.max_height) {
+            if min > max {
+                out.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "invalid_height_range",
+                    message: format!("minHeight {} is greater than maxHeight {}", min, max),
+                    element_id: Some(elem.id.clone()),
+                    path: format!("{}[{}].minHeight", array_path, i),
+                });
+            }
+        }
+    }
+}
+
+/// 元素的颜色格式，array_path 为元素数组的 JSON 路径
+fn check_element_colors(elements: &[Element], array_path: &str, out: &mut Vec<Diagnostic>) {
+    for (i, elem) in elements.iter().enumerate() {
+        let base = format!("{}[{}]", array_path, i);
+        let id = Some(elem.id.as_str());
+        match &elem.data {
+            ElementData::Text(p) => {
+                check_color(p.font_color.as_deref(), &format!("{}.fontColor", base), id, out);
+            }
+            ElementData::Table(p) => {
+                check_color(p.border_color.as_deref(), &format!("{}.borderColor", base), id, out);
+            }
+            ElementData::Line(p) => {
+                check_color(p.stroke_color.as_deref(), &format!("{}.strokeColor", base), id, out);
+            }
+            ElementData::Rect(p) => {
+                check_color(p.stroke_color.as_deref(), &format!("{}.strokeColor", base), id, out);
+                check_color(p.fill_color.as_deref(), &format!("{}.fillColor", base), id, out);
+            }
+            ElementData::Ellipse(p) => {
+                check_color(p.stroke_color.as_deref(), &format!("{}.strokeColor", base), id, out);
+                check_color(p.fill_color.as_deref(), &format!("{}.fillColor", base), id, out);
+            }
+            ElementData::Checkbox(p) => {
+                check_color(p.stroke_color.as_deref(), &format!("{}.strokeColor", base), id, out);
+                check_color(p.mark_color.as_deref(), &format!("{}.markColor", base), id, out);
+            }
+            ElementData::Signature(p) => {
+                check_color(p.stroke_color.as_deref(), &format!("{}.strokeColor", base), id, out);
+            }
+            ElementData::Chart(p) => {
+                for (j, color) in p.colors.iter().flatten().enumerate() {
+                    check_color(Some(color), &format!("{}.colors[{}]", base, j), id, out);
+                }
+            }
+            ElementData::Image(_)
+            | ElementData::Barcode(_)
+            | ElementData::Qrcode(_)
+            | ElementData::Spacer(_)
+            | ElementData::Include(_) => {}
+            ElementData::Switch(p) => {
+                for (j, case) in p.cases.iter().enumerate() {
+                    check_element_colors(&case.elements, &format!("{}.cases[{}].elements", base, j), out);
+                }
+                if let Some(fallback) = &p.fallback {
+                    check_element_colors(fallback, &format!("{}.default", base), out);
+                }
+            }
+        }
+    }
+}
+
+/// 可打印区域：元素 (含画布页边距与内边距的偏移) 落入打印机四周的不可打印边距时给出警告
+/// linkedTo 元素的纵向位置取决于数据，只检查水平方向
+pub fn check_printable_area(template: &DeepPrintTemplate, profile: &PrinterProfile) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let margin = profile.unprintable_margin();
+    if margin <= 0.0 {
+        return out;
+    }
+    let canvas = &template.canvas;
+    let insets = canvas.content_insets();
+    let auto_height = canvas.orientation == Some(3);
+    for (i, elem) in canvas.elements.iter().enumerate() {
+        let left = insets.left + elem.x;
+        let mut outside = left < margin || left + elem.w > canvas.width - margin;
+        if elem.linked_to.is_none() {
+            let top = insets.top + elem.y;
+            outside |= top < margin || (!auto_height && top + elem.h > canvas.height - margin);
+        }
+        if outside {
+            out.push(Diagnostic {
+                severity: Severity::Warning,
+                code: "outside_printable_area",
+                message: format!(
+                    "Element '{}' extends into the printer's unprintable margin ({:.1}pt)",
+                    elem.id, margin
+                ),
+                element_id: Some(elem.id.clone()),
+                path: format!("canvas.elements[{}]", i),
+            });
+        }
+    }
+    out
+}
+
+fn check_color(value: Option<&str>, path: &str, element_id: Option<&str>, out: &mut Vec<Diagnostic>) {
+    let Some(value) = value else {
+        return;
+    };
+    // 空字符串表示不填充
+    if value.is_empty() || try_parse_color(value).is_some() {
+        return;
+    }
+    out.push(Diagnostic {
+        severity: Severity::Warning,
+        code: "invalid_color",
+        message: format!(
+            "Unrecognized color '{}' (expected #RRGGBB, #RRGGBBAA, rgb(), rgba(), cmyk() or a CSS color name), black will be used",
+            value
+        ),
+        element_id: element_id.map(str::to_string),
+        path: path.to_string(),
+    });
+}
@@ -1,4 +1,6 @@
 import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 
 interface Printer {
@@ -6,30 +8,43 @@ interface Printer {
   is_default: boolean;
 }
 
+interface Job {
+  taskId: string;
+  kind: string;
+  printer?: string;
+  status: "queued" | "rendering" | "submitting" | "spooled" | "printing" | "printed" | "failed";
+  error?: string;
+  updatedAt: number;
+}
+
+interface PrinterStatus {
+  name: string;
+  state: string;
+  online: boolean;
+}
+
+// 界面只保留最近的任务
+const MAX_JOBS = 50;
+
 function App() {
   const [status, setStatus] = useState("Checking...");
   const [printers, setPrinters] = useState<Printer[]>([]);
   const [loading, setLoading] = useState(false);
+  const [agentUrl, setAgentUrl] = useState<string | null>(null);
+  const [jobs, setJobs] = useState<Job[]>([]);
+  const [printerStatus, setPrinterStatus] = useState<Record<string, PrinterStatus>>({});
 
-  const AGENT_URL = "http://localhost:18088";
-
-  // 1. 检查 Agent 是否存活
+  // 1. 服务状态：启动时查询一次，之后由 server:started 事件推送
   const checkHealth = async () => {
-    try {
-      const res = await fetch(`${AGENT_URL}/`);
-      const text = await res.text();
-      setStatus(text);
-    } catch (e) {
-      setStatus("Agent offline (Connection Failed)");
-    }
+    const url = await invoke<string | null>("get_server_address");
+    setAgentUrl(url);
+    setStatus(url ? `Running on ${url}` : "Agent offline (not listening)");
   };
 
   // 2. 获取打印机列表
   const fetchPrinters = async () => {
     try {
-      const res = await fetch(`${AGENT_URL}/printers`);
-      const data = await res.json();
-      setPrinters(data);
+      setPrinters(await invoke<Printer[]>("list_printers"));
     } catch (e) {
       console.error(e);
     }
@@ -39,7 +54,7 @@ function App() {
   const testPrint = async () => {
     setLoading(true);
     try {
-      const res = await fetch(`${AGENT_URL}/print`, {
+      const res = await fetch(`${agentUrl}/print`, {
         method: "POST",
         headers: { "Content-Type": "application/json" },
         body: JSON.stringify({
@@ -58,6 +73,28 @@ function App() {
 
   useEffect(() => {
     checkHealth();
+    const unlisten = [
+      listen<{ url: string }>("server:started", (event) => {
+        setAgentUrl(event.payload.url);
+        setStatus(`Running on ${event.payload.url}`);
+      }),
+      listen<{ message: string; retryInSecs: number }>("server:failed", (event) => {
+        setAgentUrl(null);
+        setStatus(`Agent crashed, restarting in ${event.payload.retryInSecs}s: ${event.payload.message}`);
+      }),
+      listen<Job>("job:update", (event) => {
+        const job = event.payload;
+        setJobs((current) =>
+          [job, ...current.filter((j) => j.taskId !== job.taskId)].slice(0, MAX_JOBS)
+        );
+      }),
+      listen<PrinterStatus>("printer:status", (event) => {
+        setPrinterStatus((current) => ({ ...current, [event.payload.name]: event.payload }));
+      }),
+    ];
+    return () => {
+      unlisten.forEach((p) => p.then((off) => off()));
+    };
   }, []);
 
   return (
@@ -67,6 +104,9 @@ function App() {
       <div className="card">
         <h3>Status: <span style={{color: status.includes("Running") ? "green" : "red"}}>{status}</span></h3>
         <button onClick={fetchPrinters}>Refresh Printers</button>
+        <button onClick={() => invoke("open_preview")}>Preview</button>
+        <button onClick={() => invoke("open_settings")}>Settings</button>
+        <button onClick={() => invoke("open_logs")}>Logs</button>
       </div>
 
       <div className="card">
@@ -75,7 +115,26 @@ function App() {
           <ul style={{textAlign: 'left'}}>
             {printers.map((p) => (
               <li key={p.name}>
-                {p.name} {p.is_default && <strong>(Default)</strong>}
+                {p.name} {p.is_default && <strong>(Default)</strong>}{" "}
+                {printerStatus[p.name] && (
+                  <span style={{color: printerStatus[p.name].online ? "green" : "red"}}>
+                    {printerStatus[p.name].online ? printerStatus[p.name].state : "offline"}
+                  </span>
+                )}
+              </li>
+            ))}
+          </ul>
+        )}
+      </div>
+
+      <div className="card">
+        <h3>Recent Jobs:</h3>
+        {jobs.length === 0 ? <p>No jobs yet.</p> : (
+          <ul style={{textAlign: 'left'}}>
+            {jobs.map((job) => (
+              <li key={job.taskId}>
+                {job.taskId} ({job.kind}{job.printer && ` → ${job.printer}`}): <strong>{job.status}</strong>
+                {job.error && <span style={{color: "red"}}> {job.error}</span>}
               </li>
             ))}
           </ul>
@@ -85,7 +144,7 @@ function App() {
       <div className="card">
         <h3>Test Engine</h3>
         <p>This will generate a PDF on your desktop via Skia.</p>
-        <button onClick={testPrint} disabled={loading}>
+        <button onClick={testPrint} disabled={loading || !agentUrl}>
           {loading ? "Rendering..." : "Generate Test PDF"}
         </button>
       </div>
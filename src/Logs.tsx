@@ -0,0 +1,93 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./App.css";
+
+interface LogEntry {
+  timestamp: number;
+  level: string;
+  target: string;
+  message: string;
+  fields?: Record<string, string>;
+}
+
+type Level = "error" | "warn" | "info" | "debug" | "trace";
+
+// 自动刷新间隔 (毫秒)
+const REFRESH_INTERVAL = 2000;
+
+function Logs() {
+  const [entries, setEntries] = useState<LogEntry[]>([]);
+  const [level, setLevel] = useState<Level>("info");
+  const [autoRefresh, setAutoRefresh] = useState(true);
+  const [message, setMessage] = useState("");
+  const [exporting, setExporting] = useState(false);
+
+  const load = () =>
+    invoke<LogEntry[]>("get_logs", { limit: 500, level })
+      .then(setEntries)
+      .catch((e) => setMessage(`Load failed: ${e}`));
+
+  useEffect(() => {
+    load();
+    if (!autoRefresh) return;
+    const timer = setInterval(load, REFRESH_INTERVAL);
+    return () => clearInterval(timer);
+  }, [level, autoRefresh]);
+
+  const exportDiagnostics = async () => {
+    setExporting(true);
+    try {
+      const path = await invoke<string>("export_diagnostics");
+      setMessage(`Diagnostics saved to ${path}`);
+    } catch (e) {
+      setMessage(`Export failed: ${e}`);
+    } finally {
+      setExporting(false);
+    }
+  };
+
+  return (
+    <div className="logs">
+      <div className="logs-toolbar">
+        <select value={level} onChange={(e) => setLevel(e.target.value as Level)}>
+          {["error", "warn", "info", "debug", "trace"].map((l) => (
+            <option key={l} value={l}>
+              {l}
+            </option>
+          ))}
+        </select>
+        <label>
+          <input
+            type="checkbox"
+            checked={autoRefresh}
+            onChange={(e) => setAutoRefresh(e.target.checked)}
+          />
+          Auto refresh
+        </label>
+        <button onClick={load}>Refresh</button>
+        <button onClick={exportDiagnostics} disabled={exporting}>
+          {exporting ? "Exporting..." : "Export diagnostics"}
+        </button>
+      </div>
+      {message && <p>{message}</p>}
+      <table className="logs-table">
+        <tbody>
+          {entries.map((entry, i) => (
+            <tr key={i} className={`log-${entry.level.toLowerCase()}`}>
+              <td>{new Date(entry.timestamp).toLocaleTimeString()}</td>
+              <td>{entry.level}</td>
+              <td>{entry.target}</td>
+              <td>
+                {entry.message}
+                {entry.fields &&
+                  Object.entries(entry.fields).map(([k, v]) => ` ${k}=${v}`).join("")}
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  );
+}
+
+export default Logs;
@@ -0,0 +1,228 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import "./App.css";
+
+interface PreviewPage {
+  width: number;
+  height: number;
+  image: string;
+}
+
+interface PreviewUpdate {
+  source: string;
+  pages: PreviewPage[];
+  error?: string;
+}
+
+interface TemplateSummary {
+  id: string;
+  name: string;
+  version: number;
+}
+
+interface Printer {
+  name: string;
+  is_default: boolean;
+}
+
+// 渲染分辨率，页面按 zoom 相对 72dpi (1pt = 1px) 显示
+const PREVIEW_DPI = 144;
+const ZOOM_STEPS = [0.25, 0.5, 0.75, 1, 1.5, 2, 3, 4];
+
+function Preview() {
+  const [templates, setTemplates] = useState<TemplateSummary[]>([]);
+  const [printers, setPrinters] = useState<Printer[]>([]);
+  const [templateId, setTemplateId] = useState("");
+  const [templateJson, setTemplateJson] = useState("");
+  const [dataJson, setDataJson] = useState("{}");
+  const [printer, setPrinter] = useState("");
+  const [pages, setPages] = useState<PreviewPage[]>([]);
+  const [records, setRecords] = useState<unknown[]>([]);
+  const [page, setPage] = useState(0);
+  const [zoom, setZoom] = useState(1);
+  const [message, setMessage] = useState("");
+  const [busy, setBusy] = useState(false);
+  const [watchPath, setWatchPath] = useState("");
+  const [watching, setWatching] = useState(false);
+
+  useEffect(() => {
+    invoke<TemplateSummary[]>("list_templates").then(setTemplates).catch(() => {});
+    invoke<Printer[]>("list_printers").then(setPrinters).catch(() => {});
+    // 热重载：模板保存后自动刷新预览，保留当前页
+    const unlisten = listen<PreviewUpdate>("preview:update", (event) => {
+      const update = event.payload;
+      if (update.error) {
+        setMessage(`${update.source}: ${update.error}`);
+        return;
+      }
+      setPages(update.pages);
+      setPage((current) => Math.min(current, Math.max(update.pages.length - 1, 0)));
+      setMessage(`Reloaded ${update.source} at ${new Date().toLocaleTimeString()}`);
+    });
+    return () => {
+      unlisten.then((off) => off());
+      invoke("unwatch_preview");
+    };
+  }, []);
+
+  // 内联模板优先于已注册模板
+  const templateSource = () =>
+    templateJson.trim() !== ""
+      ? { template: JSON.parse(templateJson) }
+      : { templateId: templateId || undefined };
+
+  const render = async () => {
+    setBusy(true);
+    setMessage("");
+    try {
+      const data = JSON.parse(dataJson);
+      const rendered = await invoke<PreviewPage[]>("preview_pages", {
+        request: { ...templateSource(), data, dpi: PREVIEW_DPI },
+      });
+      setPages(rendered);
+      setRecords(Array.isArray(data) ? data : [data]);
+      setPage(0);
+    } catch (e) {
+      setPages([]);
+      setMessage(`Render failed: ${e}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  // 监视模板文件 (优先) 或所选已注册模板
+  const toggleWatch = async () => {
+    try {
+      if (watching) {
+        await invoke("unwatch_preview");
+        setWatching(false);
+        return;
+      }
+      const data = JSON.parse(dataJson);
+      await invoke("watch_preview", {
+        request: {
+          path: watchPath.trim() || undefined,
+          templateId: templateId || undefined,
+          data,
+          dpi: PREVIEW_DPI,
+        },
+      });
+      setRecords(Array.isArray(data) ? data : [data]);
+      setWatching(true);
+    } catch (e) {
+      setMessage(`Watch failed: ${e}`);
+    }
+  };
+
+  // 只打印当前页对应的数据记录
+  const printPage = async () => {
+    setBusy(true);
+    try {
+      const taskId = crypto.randomUUID();
+      const result = await invoke<{ message: string }>("print_template", {
+        request: {
+          taskId,
+          ...templateSource(),
+          data: records[page],
+          printer: printer || undefined,
+        },
+      });
+      setMessage(`${result.message} (${taskId})`);
+    } catch (e) {
+      setMessage(`Print failed: ${e}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const zoomBy = (step: number) => {
+    const index = ZOOM_STEPS.indexOf(zoom) + step;
+    if (index >= 0 && index < ZOOM_STEPS.length) setZoom(ZOOM_STEPS[index]);
+  };
+
+  const current = pages[page];
+  const scale = (zoom * 72) / PREVIEW_DPI;
+
+  return (
+    <div className="preview">
+      <div className="preview-form">
+        <label>
+          Template
+          <select value={templateId} onChange={(e) => setTemplateId(e.target.value)}>
+            <option value="">(inline JSON below)</option>
+            {templates.map((t) => (
+              <option key={t.id} value={t.id}>
+                {t.name} (v{t.version})
+              </option>
+            ))}
+          </select>
+        </label>
+        <textarea
+          placeholder="Inline template JSON (overrides selected template)"
+          value={templateJson}
+          onChange={(e) => setTemplateJson(e.target.value)}
+        />
+        <textarea
+          placeholder="Data JSON (an array renders one page per record)"
+          value={dataJson}
+          onChange={(e) => setDataJson(e.target.value)}
+        />
+        <button onClick={render} disabled={busy}>
+          {busy ? "Rendering..." : "Render"}
+        </button>
+        <label>
+          Watch file
+          <input
+            type="text"
+            placeholder="Template JSON path (or the selected template)"
+            value={watchPath}
+            onChange={(e) => setWatchPath(e.target.value)}
+            disabled={watching}
+          />
+          <button onClick={toggleWatch}>{watching ? "Stop watching" : "Watch"}</button>
+        </label>
+      </div>
+
+      {current && (
+        <>
+          <div className="preview-toolbar">
+            <button onClick={() => setPage(page - 1)} disabled={page === 0}>
+              ‹
+            </button>
+            <span>
+              Page {page + 1} / {pages.length}
+            </span>
+            <button onClick={() => setPage(page + 1)} disabled={page >= pages.length - 1}>
+              ›
+            </button>
+            <button onClick={() => zoomBy(-1)}>−</button>
+            <span>{Math.round(zoom * 100)}%</span>
+            <button onClick={() => zoomBy(1)}>+</button>
+            <select value={printer} onChange={(e) => setPrinter(e.target.value)}>
+              <option value="">Default printer</option>
+              {printers.map((p) => (
+                <option key={p.name} value={p.name}>
+                  {p.name}
+                </option>
+              ))}
+            </select>
+            <button onClick={printPage} disabled={busy}>
+              Print this page
+            </button>
+          </div>
+          <div className="preview-canvas">
+            <img
+              src={`data:image/png;base64,${current.image}`}
+              width={current.width * scale}
+              height={current.height * scale}
+            />
+          </div>
+        </>
+      )}
+      {message && <p>{message}</p>}
+    </div>
+  );
+}
+
+export default Preview;
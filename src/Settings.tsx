@@ -0,0 +1,138 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import "./App.css";
+
+interface AgentSettings {
+  port: number;
+  bind: "localhost" | "lan" | "lanIpv6";
+  apiKey: string | null;
+  defaultPrinter: string | null;
+  outputDir: string | null;
+  logLevel: "error" | "warn" | "info" | "debug" | "trace";
+  startInTray: boolean;
+}
+
+function Settings() {
+  const [settings, setSettings] = useState<AgentSettings | null>(null);
+  const [message, setMessage] = useState("");
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    invoke<AgentSettings>("get_settings")
+      .then(setSettings)
+      .catch((e) => setMessage(`Load failed: ${e}`));
+  }, []);
+
+  const update = (patch: Partial<AgentSettings>) =>
+    setSettings((current) => (current ? { ...current, ...patch } : current));
+
+  // 空字符串按未设置保存
+  const orNull = (value: string) => (value.trim() === "" ? null : value);
+
+  const save = async () => {
+    if (!settings) return;
+    setSaving(true);
+    try {
+      const saved = await invoke<AgentSettings>("update_settings", { settings });
+      setSettings(saved);
+      setMessage("Saved");
+    } catch (e) {
+      setMessage(`Save failed: ${e}`);
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  if (!settings) {
+    return <div className="container">{message || "Loading..."}</div>;
+  }
+
+  return (
+    <div className="container settings">
+      <h2>Settings</h2>
+
+      <label>
+        Port
+        <input
+          type="number"
+          min={1}
+          max={65535}
+          value={settings.port}
+          onChange={(e) => update({ port: Number(e.target.value) })}
+        />
+      </label>
+
+      <label>
+        Listen on
+        <select
+          value={settings.bind}
+          onChange={(e) => update({ bind: e.target.value as AgentSettings["bind"] })}
+        >
+          <option value="localhost">This computer only</option>
+          <option value="lan">Local network</option>
+          <option value="lanIpv6">Local network (IPv6)</option>
+        </select>
+      </label>
+
+      <label>
+        API key
+        <input
+          type="text"
+          placeholder="Not required"
+          value={settings.apiKey ?? ""}
+          onChange={(e) => update({ apiKey: orNull(e.target.value) })}
+        />
+      </label>
+
+      <label>
+        Default printer
+        <input
+          type="text"
+          placeholder="System default"
+          value={settings.defaultPrinter ?? ""}
+          onChange={(e) => update({ defaultPrinter: orNull(e.target.value) })}
+        />
+      </label>
+
+      <label>
+        Save directory
+        <input
+          type="text"
+          placeholder="Desktop"
+          value={settings.outputDir ?? ""}
+          onChange={(e) => update({ outputDir: orNull(e.target.value) })}
+        />
+      </label>
+
+      <label>
+        Log level
+        <select
+          value={settings.logLevel}
+          onChange={(e) => update({ logLevel: e.target.value as AgentSettings["logLevel"] })}
+        >
+          {["error", "warn", "info", "debug", "trace"].map((level) => (
+            <option key={level} value={level}>
+              {level}
+            </option>
+          ))}
+        </select>
+      </label>
+
+      <label>
+        Start hidden in tray
+        <input
+          type="checkbox"
+          checked={settings.startInTray}
+          onChange={(e) => update({ startInTray: e.target.checked })}
+        />
+      </label>
+
+      <button onClick={save} disabled={saving}>
+        {saving ? "Saving..." : "Save"}
+      </button>
+      {message && <p>{message}</p>}
+    </div>
+  );
+}
+
+export default Settings;
@@ -1,9 +1,23 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import Logs from "./Logs";
+import Preview from "./Preview";
+import Settings from "./Settings";
+
+// 同一前端按 ?view= 渲染不同窗口
+const view = new URLSearchParams(window.location.search).get("view");
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {view === "settings" ? (
+      <Settings />
+    ) : view === "preview" ? (
+      <Preview />
+    ) : view === "logs" ? (
+      <Logs />
+    ) : (
+      <App />
+    )}
   </React.StrictMode>,
 );